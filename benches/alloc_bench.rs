@@ -1,7 +1,13 @@
+#[cfg(feature = "realloc")]
+use criterion::BatchSize;
 use criterion::{Criterion, criterion_group, criterion_main};
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
 use lite_alloc::single_threaded::{
-    BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator,
+    BuddyAllocator, BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator,
+    TlsfAllocator,
 };
+use lite_alloc::{GrowthPolicy, PAGE_SIZE, ResettableAllocator};
 use std::alloc::{GlobalAlloc, Layout};
 use std::vec::Vec;
 
@@ -33,48 +39,26 @@ impl SimpleRng {
     }
 }
 
-trait BenchmarkAllocator: GlobalAlloc {
-    unsafe fn reset_env();
-    fn create() -> Self;
-}
+trait BenchmarkAllocator: GlobalAlloc + ResettableAllocator + Default {}
 
-impl BenchmarkAllocator for FreeListAllocator {
-    unsafe fn reset_env() {
-        lite_alloc::reset_heap();
-        unsafe { FreeListAllocator::reset() };
-    }
-    fn create() -> Self {
-        FreeListAllocator::new()
-    }
-}
+impl<A: GlobalAlloc + ResettableAllocator + Default> BenchmarkAllocator for A {}
 
-impl BenchmarkAllocator for BumpFreeListAllocator {
-    unsafe fn reset_env() {
-        lite_alloc::reset_heap();
-        unsafe {
-            BumpFreeListAllocator::reset();
-        };
-    }
-    fn create() -> Self {
-        BumpFreeListAllocator::new()
-    }
+// Fresh mock heap, pinned so that address-dependent paths (e.g. the order of free
+// blocks in the fragmentation workload) are the same in every run
+fn fresh_heap() {
+    lite_alloc::pin_mock_heap();
 }
 
-impl BenchmarkAllocator for SegregatedBumpAllocator {
-    unsafe fn reset_env() {
-        lite_alloc::reset_heap();
-        unsafe { SegregatedBumpAllocator::reset() };
-    }
-    fn create() -> Self {
-        SegregatedBumpAllocator::new()
-    }
+// Fresh mock heap and a freshly reset allocator
+fn setup<A: BenchmarkAllocator>() -> A {
+    fresh_heap();
+    let allocator = A::default();
+    unsafe { allocator.reset() };
+    allocator
 }
 
 fn bench_fn_simple_cycle<A: BenchmarkAllocator>(b: &mut criterion::Bencher) {
-    unsafe {
-        A::reset_env();
-    }
-    let allocator = A::create();
+    let allocator = setup::<A>();
     b.iter(|| {
         let layout = Layout::new::<u64>();
         unsafe {
@@ -85,10 +69,7 @@ fn bench_fn_simple_cycle<A: BenchmarkAllocator>(b: &mut criterion::Bencher) {
 }
 
 fn bench_fn_fragmentation<A: BenchmarkAllocator>(b: &mut criterion::Bencher) {
-    unsafe {
-        A::reset_env();
-    }
-    let allocator = A::create();
+    let allocator = setup::<A>();
     b.iter(|| {
         let mut rng = SimpleRng::new(0xDEADBEEF);
         let mut ptrs = Vec::with_capacity(1000);
@@ -137,10 +118,7 @@ fn bench_fn_fragmentation<A: BenchmarkAllocator>(b: &mut criterion::Bencher) {
 }
 
 fn bench_fn_sequential<A: BenchmarkAllocator>(b: &mut criterion::Bencher) {
-    unsafe {
-        A::reset_env();
-    }
-    let allocator = A::create();
+    let allocator = setup::<A>();
     b.iter(|| {
         let count = 1000;
         let layout = Layout::new::<u64>();
@@ -162,6 +140,91 @@ fn bench_fn_sequential<A: BenchmarkAllocator>(b: &mut criterion::Bencher) {
     })
 }
 
+// Many live small objects (<= 128 bytes) freed and reallocated in random order.
+// Compare FreeList with and without the `small-bins` feature.
+fn bench_fn_small_objects<A: BenchmarkAllocator>(b: &mut criterion::Bencher) {
+    let allocator = setup::<A>();
+    b.iter(|| {
+        let mut rng = SimpleRng::new(0x5EED);
+        let mut live = Vec::with_capacity(512);
+
+        for _ in 0..512 {
+            let layout = Layout::from_size_align(rng.range(8, 128), 8).unwrap();
+            unsafe { live.push((allocator.alloc(layout), layout)) };
+        }
+
+        // Churn: replace a random live object with a new one of a random small size
+        for _ in 0..2000 {
+            let i = rng.range(0, live.len());
+            let layout = Layout::from_size_align(rng.range(8, 128), 8).unwrap();
+            unsafe {
+                let (ptr, old) = live[i];
+                allocator.dealloc(ptr, old);
+                live[i] = (allocator.alloc(layout), layout);
+            }
+        }
+
+        for (ptr, layout) in live {
+            unsafe { allocator.dealloc(ptr, layout) };
+        }
+    })
+}
+
+// Fill a fresh heap with 256 page-sized blocks; returns how many times memory grew.
+// Compares exact growth with `GrowthPolicy::DOUBLING`; the counts themselves are asserted in
+// `test_heap_growth_bench_counts`.
+fn heap_growth_run(allocator: &dyn GlobalAlloc) -> usize {
+    let layout = Layout::from_size_align(PAGE_SIZE - 256, 16).unwrap();
+    let mut growths = 0;
+    let mut grown = lite_alloc::heap_pages_grown();
+    for _ in 0..256 {
+        unsafe { allocator.alloc(layout) };
+        let now = lite_alloc::heap_pages_grown();
+        if now != grown {
+            growths += 1;
+            grown = now;
+        }
+    }
+    growths
+}
+
+fn bench_fn_heap_growth(b: &mut criterion::Bencher, policy: GrowthPolicy) {
+    b.iter(|| {
+        fresh_heap();
+        heap_growth_run(&FreeListAllocator::new().with_growth(policy))
+    })
+}
+
+// Vec-style doubling from 16 bytes to 64KB; returns how many of the reallocs kept the
+// pointer, i.e. grew in place. The counts are asserted in `test_realloc_growth_bench_counts`.
+#[cfg(feature = "realloc")]
+fn realloc_growth_run(allocator: &dyn GlobalAlloc) -> usize {
+    let mut layout = Layout::from_size_align(16, 8).unwrap();
+    let mut ptr = unsafe { allocator.alloc(layout) };
+    let mut in_place = 0;
+    while layout.size() < 64 * 1024 {
+        let new_size = layout.size() * 2;
+        let new_ptr = unsafe { allocator.realloc(ptr, layout, new_size) };
+        if new_ptr == ptr {
+            in_place += 1;
+        }
+        ptr = new_ptr;
+        layout = Layout::from_size_align(new_size, 8).unwrap();
+    }
+    unsafe { allocator.dealloc(ptr, layout) };
+    in_place
+}
+
+// Each run gets a fresh heap, so allocators that leak large blocks do not run out
+#[cfg(feature = "realloc")]
+fn bench_fn_realloc_growth<A: BenchmarkAllocator>(b: &mut criterion::Bencher) {
+    b.iter_batched(
+        setup::<A>,
+        |allocator| realloc_growth_run(&allocator),
+        BatchSize::PerIteration,
+    )
+}
+
 // ============================================================================
 // Benchmark Groups
 // ============================================================================
@@ -191,6 +254,8 @@ fn bench_group_fragmentation(c: &mut Criterion) {
         "SegregatedBump",
         bench_fn_fragmentation::<SegregatedBumpAllocator>,
     );
+    group.bench_function("Tlsf", bench_fn_fragmentation::<TlsfAllocator>);
+    group.bench_function("Buddy", bench_fn_fragmentation::<BuddyAllocator>);
     group.finish();
 }
 
@@ -202,6 +267,48 @@ fn bench_group_sequential(c: &mut Criterion) {
         "SegregatedBump",
         bench_fn_sequential::<SegregatedBumpAllocator>,
     );
+    group.bench_function("Buddy", bench_fn_sequential::<BuddyAllocator>);
+    group.finish();
+}
+
+fn bench_group_small_objects(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small_object_churn");
+    group.bench_function("FreeList", bench_fn_small_objects::<FreeListAllocator>);
+    group.bench_function(
+        "BumpFreeList",
+        bench_fn_small_objects::<BumpFreeListAllocator>,
+    );
+    group.bench_function(
+        "SegregatedBump",
+        bench_fn_small_objects::<SegregatedBumpAllocator>,
+    );
+    group.bench_function("Buddy", bench_fn_small_objects::<BuddyAllocator>);
+    group.finish();
+}
+
+fn bench_group_heap_growth(c: &mut Criterion) {
+    let mut group = c.benchmark_group("heap_growth");
+    group.bench_function("FreeList/exact", |b| {
+        bench_fn_heap_growth(b, GrowthPolicy::EXACT)
+    });
+    group.bench_function("FreeList/doubling", |b| {
+        bench_fn_heap_growth(b, GrowthPolicy::DOUBLING)
+    });
+    group.finish();
+}
+
+#[cfg(feature = "realloc")]
+fn bench_group_realloc_growth(c: &mut Criterion) {
+    let mut group = c.benchmark_group("realloc_growth");
+    group.bench_function("FreeList", bench_fn_realloc_growth::<FreeListAllocator>);
+    group.bench_function(
+        "BumpFreeList",
+        bench_fn_realloc_growth::<BumpFreeListAllocator>,
+    );
+    group.bench_function(
+        "SegregatedBump",
+        bench_fn_realloc_growth::<SegregatedBumpAllocator>,
+    );
     group.finish();
 }
 
@@ -209,6 +316,14 @@ criterion_group!(
     benches,
     bench_group_simple_cycle,
     bench_group_fragmentation,
-    bench_group_sequential
+    bench_group_sequential,
+    bench_group_small_objects,
+    bench_group_heap_growth
 );
+#[cfg(feature = "realloc")]
+criterion_group!(realloc_benches, bench_group_realloc_growth);
+
+#[cfg(feature = "realloc")]
+criterion_main!(benches, realloc_benches);
+#[cfg(not(feature = "realloc"))]
 criterion_main!(benches);
@@ -1,89 +1,26 @@
 #![no_main]
 use libfuzzer_sys::fuzz_target;
-use lite_alloc::{reset_heap, single_threaded::FreeListAllocator};
-use std::alloc::{GlobalAlloc, Layout};
+use lite_alloc::{
+    reset_heap,
+    replay::{AllocOp, replay_with},
+    single_threaded::FreeListAllocator,
+};
 
 fuzz_target!(|data: &[u8]| {
-    // Reset mock heap memory before each Fuzz iteration to ensure a clean environment
-    // 每次 Fuzz 迭代开始前，重置模拟堆内存，确保环境干净
+    // Reset mock heap memory before each Fuzz iteration; the allocator itself is fresh
+    // 每次 Fuzz 迭代开始前重置模拟堆内存；分配器本身是新建的
     reset_heap();
-    unsafe {
-        FreeListAllocator::reset();
-    }
 
+    // Alloc, dealloc and realloc as decoded from the input; every block is filled and
+    // checked, and whatever is still live is freed at the end
+    // 按输入解码出的 alloc、dealloc 与 realloc 操作执行；每个块都会被填充并检查，
+    // 最后释放仍存活的块
     let allocator = FreeListAllocator::new();
-    // Record allocated blocks: (pointer, layout)
-    // 记录已分配的块：(pointer, layout)
-    let mut ptrs: Vec<(*mut u8, Layout)> = Vec::new();
-
-    let mut cursor = 0;
-    while cursor < data.len() {
-        // Read opcode (1 byte)
-        // 读取操作码 (1 byte)
-        let op = data[cursor];
-        cursor += 1;
-
-        if op % 2 == 0 {
-            // --- Alloc ---
-            // Need 2 bytes for size
-            // 需要 2 bytes 作为 size
-            if cursor + 2 > data.len() {
-                break;
-            }
-            let s1 = data[cursor] as usize;
-            let s2 = data[cursor + 1] as usize;
-            cursor += 2;
-
-            // Combine into usize, max 65535, min 1
-            // 组合成 usize, 最大 65535, 最小 1
-            let size = ((s2 << 8) | s1).max(1);
-
-            // Fixed Alignment = 8 (common alignment)
-            // 固定 Alignment = 8 (常见的对齐)
-            let align = 8;
-
-            if let Ok(layout) = Layout::from_size_align(size, align) {
-                let ptr = unsafe { allocator.alloc(layout) };
-                if !ptr.is_null() {
-                    // Optional: Write some data to trigger potential out-of-bounds write detection (if used with ASAN)
-                    // In pure Rust fuzz, this mainly verifies that write does not panic
-                    // 可选：写入一些数据以触发潜在的越界写入检测（如果配合 ASAN）
-                    // 在纯 Rust fuzz 中，这主要验证 write 没有 panic
-                    unsafe {
-                        std::ptr::write_bytes(ptr, 0xCC, size);
-                    }
-                    ptrs.push((ptr, layout));
-                }
-            }
-        } else {
-            // --- Dealloc ---
-            if ptrs.is_empty() {
-                continue;
-            }
-
-            // Need 1 byte for index
-            // 需要 1 byte 作为索引
-            if cursor + 1 > data.len() {
-                break;
-            }
-            let idx_byte = data[cursor] as usize;
-            cursor += 1;
-
-            // Map random byte to ptrs range
-            // 将随机 byte 映射到 ptrs 范围
-            let idx = idx_byte % ptrs.len();
-            let (ptr, layout) = ptrs.swap_remove(idx);
-            unsafe {
-                allocator.dealloc(ptr, layout);
-            }
-        }
-    }
-
-    // Iteration ends, free remaining objects (simulate program exit or scope end)
-    // 迭代结束，释放剩余的对象 (模拟程序退出或 Scope 结束)
-    for (ptr, layout) in ptrs {
-        unsafe {
-            allocator.dealloc(ptr, layout);
-        }
-    }
+    unsafe {
+        replay_with(&allocator, &AllocOp::decode(data), |allocator| {
+            // The free list must stay sorted, disjoint and fully merged after every operation
+            // 每次操作之后，空闲链表都必须保持有序、互不重叠且完全合并
+            allocator.check_integrity().expect("free list corrupted");
+        })
+    };
 });
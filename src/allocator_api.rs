@@ -0,0 +1,115 @@
+//! `core::alloc::Allocator` implementations (nightly, `allocator_api` feature).
+//!
+//! Every method maps onto the existing `GlobalAlloc` implementation; the returned
+//! slice length is the block's `usable_size`, so collections can use the slack.
+//!
+//! `core::alloc::Allocator` 实现（nightly，`allocator_api` feature）。
+//!
+//! 所有方法都映射到现有的 `GlobalAlloc` 实现；返回切片的长度为块的 `usable_size`，
+//! 因此集合类型可以直接使用多余空间。
+
+use crate::MemorySource;
+use crate::layout_math::copy_span;
+use crate::single_threaded::{
+    BitmapAllocator, BuddyAllocator, BumpFreeListAllocator, FreeListAllocator,
+    SegregatedBumpAllocator, SlabAllocator, TlsfAllocator,
+};
+use core::alloc::{AllocError, Allocator, GlobalAlloc, Layout};
+use core::ptr::{self, NonNull};
+
+macro_rules! impl_allocator {
+    ([$($generics:tt)*] $ty:ty) => {
+        unsafe impl<$($generics)*> Allocator for $ty {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                let ptr = unsafe { GlobalAlloc::alloc(self, layout) };
+                unsafe { self.slice_of(ptr, layout) }
+            }
+
+            fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                let ptr = unsafe { GlobalAlloc::alloc_zeroed(self, layout) };
+                unsafe { self.slice_of(ptr, layout) }
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                unsafe { GlobalAlloc::dealloc(self, ptr.as_ptr(), layout) }
+            }
+
+            unsafe fn grow(
+                &self,
+                ptr: NonNull<u8>,
+                old_layout: Layout,
+                new_layout: Layout,
+            ) -> Result<NonNull<[u8]>, AllocError> {
+                unsafe { self.resize(ptr, old_layout, new_layout) }
+            }
+
+            unsafe fn shrink(
+                &self,
+                ptr: NonNull<u8>,
+                old_layout: Layout,
+                new_layout: Layout,
+            ) -> Result<NonNull<[u8]>, AllocError> {
+                unsafe { self.resize(ptr, old_layout, new_layout) }
+            }
+        }
+
+        impl<$($generics)*> $ty {
+            /// Wrap a raw allocation result, mapping null to `AllocError`.
+            ///
+            /// 包装原始分配结果，将 null 映射为 `AllocError`。
+            unsafe fn slice_of(
+                &self,
+                ptr: *mut u8,
+                layout: Layout,
+            ) -> Result<NonNull<[u8]>, AllocError> {
+                let ptr = NonNull::new(ptr).ok_or(AllocError)?;
+                let len = unsafe { self.usable_size(ptr.as_ptr(), layout) };
+                Ok(NonNull::slice_from_raw_parts(ptr, len))
+            }
+
+            /// Shared `grow`/`shrink` path: `realloc` keeps the alignment, so it is only
+            /// used when the alignment is unchanged; otherwise allocate, copy and free.
+            ///
+            /// `grow`/`shrink` 的共用路径：`realloc` 保持对齐不变，因此仅在对齐不变时使用；
+            /// 否则分配、复制并释放。
+            unsafe fn resize(
+                &self,
+                ptr: NonNull<u8>,
+                old_layout: Layout,
+                new_layout: Layout,
+            ) -> Result<NonNull<[u8]>, AllocError> {
+                if old_layout.align() == new_layout.align() {
+                    let new_ptr = unsafe {
+                        GlobalAlloc::realloc(self, ptr.as_ptr(), old_layout, new_layout.size())
+                    };
+                    return unsafe { self.slice_of(new_ptr, new_layout) };
+                }
+
+                let new_ptr = self.allocate(new_layout)?;
+                unsafe {
+                    ptr::copy_nonoverlapping(
+                        ptr.as_ptr(),
+                        new_ptr.as_ptr() as *mut u8,
+                        copy_span(old_layout, new_layout.size()),
+                    );
+                    self.deallocate(ptr, old_layout);
+                }
+                Ok(new_ptr)
+            }
+        }
+    };
+}
+
+impl_allocator!(
+    [S: MemorySource, const MIN_ALIGN: usize, const MIN_BLOCK: usize]
+    FreeListAllocator<S, MIN_ALIGN, MIN_BLOCK>
+);
+impl_allocator!([S: MemorySource, const MIN_ALIGN: usize] BumpFreeListAllocator<S, MIN_ALIGN>);
+impl_allocator!(
+    [S: MemorySource, const N: usize, const MIN_ALIGN: usize]
+    SegregatedBumpAllocator<S, N, MIN_ALIGN>
+);
+impl_allocator!([const BLOCK: usize, S: MemorySource] SlabAllocator<BLOCK, S>);
+impl_allocator!([const CELL: usize, S: MemorySource] BitmapAllocator<CELL, S>);
+impl_allocator!([S: MemorySource] TlsfAllocator<S>);
+impl_allocator!([S: MemorySource] BuddyAllocator<S>);
@@ -0,0 +1,144 @@
+//! Block-size arithmetic used by the allocators, for heap-inspection tools and
+//! wrappers that need to predict exact block sizes.
+//!
+//! 分配器使用的块大小计算，供需要预测确切块大小的堆检查工具和包装器使用。
+
+use core::alloc::Layout;
+
+/// Size of a `FreeListAllocator` free-list node header (`next` pointer and `size`, plus
+/// the two size-index links with the `size-index` feature), which is also the smallest
+/// block it hands out. With the `compact-header` feature a one-word block keeps only a
+/// tagged `next` pointer, so this is a single word and `size` is stored from two words on.
+/// `size-index` takes priority: with both features, `compact-header` has no effect.
+///
+/// The value therefore depends on the enabled features, and Cargo unifies features across
+/// the dependency graph, so any crate turning on `size-index` or `compact-header` changes
+/// it for everyone: read it at compile time rather than hard-coding or persisting it.
+///
+/// | Features | `NODE_SIZE` |
+/// | --- | --- |
+/// | neither | two words |
+/// | `compact-header` | one word |
+/// | `size-index` (with or without `compact-header`) | four words |
+///
+/// `FreeListAllocator` 空闲链表节点头（`next` 指针与 `size`，开启 `size-index` feature
+/// 时再加上两个大小索引链接）的大小，也是它分配出去的最小块。开启 `compact-header`
+/// feature 时，单字块只保留一个带标记的 `next` 指针，因此该值为一个字，
+/// `size` 仅在两个字及以上的块中存储。`size-index` 优先：同时开启两者时，`compact-header` 不生效。
+///
+/// 因此该值取决于已开启的 feature，而 Cargo 会在整个依赖图中统一 feature，
+/// 任何 crate 开启 `size-index` 或 `compact-header` 都会改变所有使用者看到的值：
+/// 请在编译期读取它，而不要硬编码或持久化保存。
+///
+/// | Feature | `NODE_SIZE` |
+/// | --- | --- |
+/// | 均未开启 | 两个字 |
+/// | `compact-header` | 一个字 |
+/// | `size-index`（无论是否开启 `compact-header`） | 四个字 |
+pub const NODE_SIZE: usize = core::mem::size_of::<usize>()
+    * if cfg!(feature = "size-index") {
+        4
+    } else if cfg!(feature = "compact-header") {
+        1
+    } else {
+        2
+    };
+
+/// Block size a `FreeListAllocator` with the default 16-byte `MIN_ALIGN` reserves for
+/// `layout` on its sorted list: at least [`NODE_SIZE`], rounded up to a multiple of 16
+/// (of [`NODE_SIZE`] when the `size-index` feature makes it larger).
+/// With the `small-bins` feature, requests of up to 128 bytes use the class size instead.
+/// With `compact-header` the one-word minimum is still rounded up to 16 here; only a
+/// smaller `MIN_ALIGN` hands out one-word blocks.
+///
+/// 默认 16 字节 `MIN_ALIGN` 的 `FreeListAllocator` 在其有序链表上为 `layout` 预留的块大小：
+/// 至少为 [`NODE_SIZE`]，并向上取整到 16 的倍数（当 `size-index` feature 使 [`NODE_SIZE`]
+/// 更大时为其倍数）。
+/// 启用 `small-bins` feature 时，不超过 128 字节的请求改用所属大小等级的大小。
+/// 开启 `compact-header` 时，单字的最小值在这里仍会向上取整到 16；
+/// 只有更小的 `MIN_ALIGN` 才会分配出单字块。
+///
+/// ```
+/// use core::alloc::Layout;
+/// use lite_alloc::layout_math::{NODE_SIZE, full_size, round_up};
+///
+/// assert_eq!(full_size(Layout::from_size_align(1, 1).unwrap()), round_up(NODE_SIZE, 16));
+/// assert_eq!(full_size(Layout::from_size_align(120, 8).unwrap()), 128);
+/// ```
+pub const fn full_size(layout: Layout) -> usize {
+    let size = if layout.size() > NODE_SIZE {
+        layout.size()
+    } else {
+        NODE_SIZE
+    };
+    round_up(size, if NODE_SIZE > 16 { NODE_SIZE } else { 16 })
+}
+
+/// Bytes a moving `realloc` copies from a block allocated with `old_layout` to its
+/// `new_size`-byte replacement: only the bytes that are valid in both, so a shrink never
+/// reads past `new_size` (or writes past the end of the new block).
+///
+/// 移动式 `realloc` 从以 `old_layout` 分配的块复制到 `new_size` 字节的新块时复制的字节数：
+/// 只复制两者中都有效的字节，因此缩小时永远不会读取超过 `new_size` 的部分（也不会写出新块的末尾）。
+///
+/// ```
+/// use core::alloc::Layout;
+/// use lite_alloc::layout_math::copy_span;
+///
+/// let layout = Layout::from_size_align(100, 8).unwrap();
+/// assert_eq!(copy_span(layout, 200), 100);
+/// assert_eq!(copy_span(layout, 40), 40);
+/// ```
+#[inline(always)]
+pub const fn copy_span(old_layout: Layout, new_size: usize) -> usize {
+    if old_layout.size() < new_size {
+        old_layout.size()
+    } else {
+        new_size
+    }
+}
+
+/// Round up value to the nearest multiple of increment, where increment must be a power of 2.
+/// If `value` is already a multiple of increment, it remains unchanged.
+///
+/// 将值向上取整到增量的最接近倍数，增量必须是 2 的幂。
+/// 如果 `value` 是增量的倍数，则保持不变。
+///
+/// ```
+/// use lite_alloc::{PAGE_SIZE, layout_math::round_up};
+///
+/// assert_eq!(round_up(1, PAGE_SIZE), PAGE_SIZE);
+/// assert_eq!(round_up(32, 16), 32);
+/// ```
+pub const fn round_up(value: usize, increment: usize) -> usize {
+    debug_assert!(increment.is_power_of_two());
+    // Calculate `value.div_ceil(increment) * increment`,
+    // utilizing the fact that `increment` is always a power of 2 to avoid integer division,
+    // as it is not always optimized away.
+    // 计算 `value.div_ceil(increment) * increment`，
+    // 利用 `increment` 总是 2 的幂这一事实避免使用整数除法，
+    // 因为它并不总是会被优化掉。
+    multiple_below(value + (increment - 1), increment)
+}
+
+/// Round down value to the nearest multiple of increment, where increment must be a power of 2.
+/// If `value` is a multiple of `increment`, it remains unchanged.
+///
+/// 将值向下取整到增量的最接近倍数，增量必须是 2 的幂。
+/// 如果 `value` 是 `increment` 的倍数，则保持不变。
+///
+/// ```
+/// use lite_alloc::{PAGE_SIZE, layout_math::multiple_below};
+///
+/// assert_eq!(multiple_below(PAGE_SIZE + 1, PAGE_SIZE), PAGE_SIZE);
+/// ```
+pub const fn multiple_below(value: usize, increment: usize) -> usize {
+    debug_assert!(increment.is_power_of_two());
+    // Calculate `value / increment * increment`,
+    // utilizing the fact that `increment` is always a power of 2 to avoid integer division,
+    // as it is not always optimized away.
+    // 计算 `value / increment * increment`，
+    // 利用 `increment` 总是 2 的幂这一事实避免使用整数除法，
+    // 因为它并不总是会被优化掉。
+    value & increment.wrapping_neg()
+}
@@ -1,11 +1,29 @@
 #![no_std]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+#![cfg_attr(target_arch = "wasm64", feature(simd_wasm64))]
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
 extern crate std;
 
-#[cfg(not(target_arch = "wasm32"))]
 extern crate alloc;
 
+// The unit tests link `defmt` too, so they need the test logger
+// 单元测试同样链接 `defmt`，因此需要测试日志器
+#[cfg(all(test, feature = "defmt"))]
+use defmt_capture as _;
+
+use core::alloc::Layout;
+use core::cell::Cell;
+
+// Emit a `defmt` trace event; expands to nothing without the `defmt` feature
+// 发出 `defmt` 跟踪事件；未开启 `defmt` feature 时展开为空
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "defmt")]
+        defmt::trace!($($arg)*);
+    };
+}
+
 /// WebAssembly memory page count.
 ///
 /// WebAssembly 内存页数量。
@@ -16,6 +34,151 @@ impl PageCount {
     pub fn size_in_bytes(self) -> usize {
         self.0 * PAGE_SIZE
     }
+
+    /// Size in bytes, or `None` if it does not fit in the address space.
+    ///
+    /// 字节大小；如果超出地址空间则返回 `None`。
+    pub const fn checked_size_in_bytes(self) -> Option<usize> {
+        self.0.checked_mul(PAGE_SIZE)
+    }
+
+    /// Pages needed to hold `bytes`, rounding up: `from_bytes(1)` is one page.
+    ///
+    /// 容纳 `bytes` 字节所需的页数，向上取整：`from_bytes(1)` 为一页。
+    pub const fn from_bytes(bytes: usize) -> PageCount {
+        PageCount(bytes.div_ceil(PAGE_SIZE))
+    }
+
+    /// Sum of both counts, or `None` on overflow.
+    ///
+    /// 两者之和；溢出时返回 `None`。
+    pub const fn checked_add(self, other: PageCount) -> Option<PageCount> {
+        match self.0.checked_add(other.0) {
+            Some(pages) => Some(PageCount(pages)),
+            None => None,
+        }
+    }
+
+    /// The number of pages.
+    ///
+    /// 页数。
+    pub const fn as_usize(self) -> usize {
+        self.0
+    }
+}
+
+impl core::ops::Add for PageCount {
+    type Output = PageCount;
+
+    fn add(self, other: PageCount) -> PageCount {
+        PageCount(self.0 + other.0)
+    }
+}
+
+impl core::ops::Sub for PageCount {
+    type Output = PageCount;
+
+    fn sub(self, other: PageCount) -> PageCount {
+        PageCount(self.0 - other.0)
+    }
+}
+
+/// Grow `source` by `pages` pages and return the start address of the new region, or `None`
+/// if the growth failed or the page index does not fit in the address space.
+///
+/// 将 `source` 增长 `pages` 页并返回新区域的起始地址；如果增长失败或页索引超出地址空间，
+/// 则返回 `None`。
+///
+/// # Safety
+/// Same as [`MemorySource::grow`].
+///
+/// # Safety
+/// 与 [`MemorySource::grow`] 相同。
+#[inline(always)]
+pub(crate) unsafe fn grow_region<S: MemorySource>(source: &S, pages: usize) -> Option<usize> {
+    match unsafe { source.grow(pages) } {
+        usize::MAX => None,
+        page_index => {
+            trace!("grow_memory pages={=usize} at={=usize}", pages, page_index);
+            PageCount(page_index).checked_size_in_bytes()
+        }
+    }
+}
+
+/// Pointer handed out for zero-sized layouts: non-null, aligned to `layout`, and not
+/// backed by any heap memory. Freeing it is a no-op.
+///
+/// 为零大小布局返回的指针：非空、按 `layout` 对齐，且不对应任何堆内存。释放它是空操作。
+#[inline(always)]
+pub(crate) fn dangling(layout: Layout) -> *mut u8 {
+    core::ptr::without_provenance_mut(layout.align())
+}
+
+/// `realloc` from or to a zero-sized layout: allocate the new block (there is nothing to
+/// copy) or free the old one and hand out a dangling pointer.
+///
+/// 从零大小布局或向零大小布局 `realloc`：分配新块（无需复制），
+/// 或释放旧块并返回悬垂指针。
+#[cfg(feature = "realloc")]
+pub(crate) unsafe fn realloc_zero_sized<A: core::alloc::GlobalAlloc>(
+    allocator: &A,
+    ptr: *mut u8,
+    layout: Layout,
+    new_size: usize,
+) -> *mut u8 {
+    let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+    unsafe {
+        if layout.size() == 0 {
+            return allocator.alloc(new_layout);
+        }
+        allocator.dealloc(ptr, layout);
+    }
+    dangling(new_layout)
+}
+
+/// `realloc`, then zero the bytes in `[layout.size(), new_size)`, whether the block grew in
+/// place or moved.
+///
+/// 执行 `realloc`，然后将 `[layout.size(), new_size)` 范围内的字节清零，无论块是原地扩容还是被移动。
+#[cfg(feature = "realloc")]
+pub(crate) unsafe fn realloc_zero_tail<A: core::alloc::GlobalAlloc>(
+    allocator: &A,
+    ptr: *mut u8,
+    layout: Layout,
+    new_size: usize,
+) -> *mut u8 {
+    let new_ptr = unsafe { allocator.realloc(ptr, layout, new_size) };
+    if !new_ptr.is_null() && new_size > layout.size() {
+        unsafe {
+            new_ptr
+                .add(layout.size())
+                .write_bytes(0, new_size - layout.size())
+        };
+    }
+    new_ptr
+}
+
+/// Start and end address of a `size`-byte block bumped from `top` at `align`, or `None`
+/// when the block would reach past `usize::MAX`.
+///
+/// 从 `top` 按 `align` Bump 出的 `size` 字节块的起止地址；块会超出 `usize::MAX` 时返回 `None`。
+#[inline(always)]
+pub(crate) fn bump_range(top: usize, size: usize, align: usize) -> Option<(usize, usize)> {
+    let start = top.checked_next_multiple_of(align)?;
+    Some((start, start.checked_add(size)?))
+}
+
+/// Pages a bump region ending at `end` must grow by before `bytes` more bytes fit above
+/// `top`. A block that could never fit in the address space asks for all of `bytes`.
+///
+/// 在 `top` 之上再放入 `bytes` 字节之前，结束于 `end` 的 Bump 区域需要增长的页数。
+/// 在地址空间中永远放不下的块按完整的 `bytes` 计算。
+#[inline(always)]
+pub(crate) fn bump_pages_needed(top: usize, end: usize, bytes: usize) -> PageCount {
+    match top.checked_add(bytes) {
+        Some(needed_end) => PageCount::from_bytes(needed_end.saturating_sub(end)),
+        None => PageCount::from_bytes(bytes),
+    }
 }
 
 /// WebAssembly page size, in bytes (64KB).
@@ -23,57 +186,539 @@ impl PageCount {
 /// WebAssembly 页大小，单位字节（64KB）。
 pub const PAGE_SIZE: usize = 65536;
 
-// Remove MemoryGrower trait, use function directly
-// 移除 trait MemoryGrower，直接写成函数
+/// How the allocators size their `grow_memory` calls.
+///
+/// [`EXACT`](Self::EXACT) (the default) grows by exactly the pages a request needs.
+/// A known large working set is better served by one big first growth
+/// (`initial_pages`) and amortized later growths (`divisor`).
+///
+/// 分配器决定每次 `grow_memory` 调用大小的策略。
+///
+/// [`EXACT`](Self::EXACT)（默认）恰好增长请求所需的页数。
+/// 已知工作集较大时，更适合一次较大的首次增长（`initial_pages`）
+/// 以及摊销的后续增长（`divisor`）。
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct GrowthPolicy {
+    /// Pages requested by the first growth of an empty allocator (or more if the
+    /// request needs them).
+    ///
+    /// 空分配器首次增长时申请的页数（如果请求需要更多则申请更多）。
+    pub initial_pages: PageCount,
+    /// Later growths request at least `region_pages / divisor` pages, where `region_pages`
+    /// is the size of the current contiguous heap region, e.g. 2 for "grow by half".
+    /// 0 grows by exactly the pages needed.
+    ///
+    /// 后续增长至少申请 `region_pages / divisor` 页，其中 `region_pages` 为当前连续堆
+    /// 区域的大小，例如 2 表示“增长一半”。0 表示恰好增长所需的页数。
+    pub divisor: usize,
+}
+
+impl GrowthPolicy {
+    /// Grow by exactly the pages each request needs.
+    ///
+    /// 恰好增长每次请求所需的页数。
+    pub const EXACT: Self = GrowthPolicy {
+        initial_pages: PageCount(0),
+        divisor: 0,
+    };
+
+    /// Request at least as many pages as the current region already spans, so capacity
+    /// roughly doubles with each growth: fewer `grow_memory` calls for up to half the
+    /// memory as slack.
+    ///
+    /// 至少申请与当前区域已有页数相同的页数，使容量每次增长大致翻倍：
+    /// `grow_memory` 调用更少，代价是最多一半的内存作为余量。
+    pub const DOUBLING: Self = GrowthPolicy {
+        initial_pages: PageCount(0),
+        divisor: 1,
+    };
+
+    /// Pages to request when `needed` more are required and the current region spans
+    /// `region_pages` (0 before the first growth).
+    ///
+    /// 当还需要 `needed` 页、当前区域为 `region_pages` 页（首次增长前为 0）时应申请的页数。
+    pub(crate) const fn pages_for(self, needed: usize, region_pages: usize) -> usize {
+        let wanted = if region_pages == 0 {
+            self.initial_pages.0
+        } else {
+            // A zero divisor means no amortization
+            // 除数为 0 表示不摊销
+            match region_pages.checked_div(self.divisor) {
+                Some(pages) => pages,
+                None => 0,
+            }
+        };
+        if wanted > needed { wanted } else { needed }
+    }
+}
+
+impl Default for GrowthPolicy {
+    fn default() -> Self {
+        Self::EXACT
+    }
+}
+
+/// [`grow_region`] by `wanted` pages, falling back to the `needed` pages if the source
+/// cannot provide that many. Returns the start address and the number of pages obtained.
+///
+/// # Safety
+/// Same as [`MemorySource::grow`].
+///
+/// 以 `wanted` 页调用 [`grow_region`]，如果内存源无法提供这么多，则退回到所需的 `needed` 页。
+/// 返回起始地址与实际获得的页数。
+///
+/// # Safety
+/// 与 [`MemorySource::grow`] 相同。
+pub(crate) unsafe fn grow_amortized<S: MemorySource>(
+    source: &S,
+    needed: usize,
+    wanted: usize,
+) -> Option<(usize, usize)> {
+    if wanted > needed
+        && let Some(start) = unsafe { grow_region(source, wanted) }
+    {
+        return Some((start, wanted));
+    }
+    unsafe { grow_region(source, needed) }.map(|start| (start, needed))
+}
+
+/// Source of linear memory pages for the allocators.
+///
+/// `grow` follows the semantics of `memory.grow`: it returns the index of the first newly
+/// added page (so the new region starts at `index * PAGE_SIZE`), or `usize::MAX` on failure.
+///
+/// 分配器的线性内存页来源。
+///
+/// `grow` 遵循 `memory.grow` 的语义：返回新增第一页的索引
+/// （因此新区域起始于 `index * PAGE_SIZE`），失败时返回 `usize::MAX`。
+pub trait MemorySource {
+    /// Whether newly grown pages are guaranteed to be zeroed (true for WASM `memory.grow`
+    /// and the host mock). Sources that recycle dirty memory set this to `false`, and the
+    /// allocators then zero freshly bumped or grown blocks themselves.
+    ///
+    /// 新增长的页面是否保证为零（WASM `memory.grow` 与宿主模拟均为 true）。
+    /// 回收脏内存的来源应将其设为 `false`，此时分配器会自行将新 Bump 或新增长的块清零。
+    const ZEROED: bool = true;
+
+    /// Grow the memory by `pages` pages.
+    ///
+    /// # Safety
+    /// The returned region is handed over to the caller, which becomes responsible for it.
+    ///
+    /// 将内存增长 `pages` 页。
+    ///
+    /// # Safety
+    /// 返回的区域交由调用者管理。
+    unsafe fn grow(&self, pages: usize) -> usize;
+
+    /// Give back the `pages` pages starting at page index `page_index`, returning whether
+    /// they were released. Sources may refuse, e.g. when the range is not at the end of
+    /// memory; the default refuses everything, as WASM linear memory cannot shrink.
+    /// A `ZEROED` source must hand released pages out zeroed if they are grown again.
+    ///
+    /// # Safety
+    /// The range must have been obtained from `grow` and must no longer be in use.
+    ///
+    /// 归还从页索引 `page_index` 开始的 `pages` 页，返回是否已释放。
+    /// 内存来源可以拒绝，例如该范围不在内存末尾时；默认实现拒绝一切，因为 WASM 线性内存无法收缩。
+    /// `ZEROED` 的内存来源在这些页被再次增长时必须提供清零的页面。
+    ///
+    /// # Safety
+    /// 该范围必须通过 `grow` 获得，且不再被使用。
+    unsafe fn shrink(&self, page_index: usize, pages: usize) -> bool {
+        let _ = (page_index, pages);
+        false
+    }
+}
+
+/// A [`MemorySource`] whose pages stay valid for the rest of the program, whichever thread
+/// grew them, so a block may be freed on another thread than the one that allocated it
+/// (see `ThreadLocalAlloc`). The host mock behind [`DefaultMemory`] is per thread and is
+/// released when its thread exits, so it is not one.
+///
+/// # Safety
+/// Pages returned by `grow` must stay valid, and must not be handed out again unless they
+/// were given back with `shrink`, after the thread that grew them exits.
+///
+/// 页面在程序剩余时间内始终有效的 [`MemorySource`]，无论由哪个线程增长，
+/// 因此块可以在分配它的线程以外的线程上释放（见 `ThreadLocalAlloc`）。
+/// [`DefaultMemory`] 背后的宿主模拟是每线程的，并在其线程退出时释放，因此不属于此类。
+///
+/// # Safety
+/// 增长页面的线程退出后，`grow` 返回的页面必须保持有效，
+/// 且除非已通过 `shrink` 归还，否则不得再次分配出去。
+pub unsafe trait SharedMemorySource: MemorySource {}
+
+/// Default memory source, forwarding to [`grow_memory`].
+///
+/// 默认内存来源，直接转发到 [`grow_memory`]。
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Default)]
+pub struct DefaultMemory;
+
+impl MemorySource for DefaultMemory {
+    #[inline(always)]
+    unsafe fn grow(&self, pages: usize) -> usize {
+        unsafe { grow_memory(pages) }
+    }
+
+    // The host mock can give back the pages at its end
+    // 宿主模拟可以归还其末尾的页面
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    unsafe fn shrink(&self, page_index: usize, pages: usize) -> bool {
+        unsafe { host_memory::shrink_impl(page_index, pages) }
+    }
+}
+
+/// Allocators whose internal state can be wiped, e.g. between tests or benchmark runs.
+/// Host memory is reset separately with `reset_heap`.
+///
+/// 可以清空内部状态的分配器，例如在测试或基准测试之间使用。
+/// 宿主内存需另外通过 `reset_heap` 重置。
+pub trait ResettableAllocator {
+    /// Forget all allocations and free blocks.
+    ///
+    /// # Safety
+    /// All pointers handed out before the reset become dangling.
+    ///
+    /// 忘记所有分配和空闲块。
+    ///
+    /// # Safety
+    /// 重置之前分配出去的所有指针都将失效。
+    unsafe fn reset(&self);
+}
+
+/// Error returned by the fallible `try_alloc` methods when the allocator is out of memory.
+///
+/// 当分配器内存不足时，可失败的 `try_alloc` 方法返回的错误。
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Default)]
+pub struct AllocError;
+
+impl core::fmt::Display for AllocError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+impl core::error::Error for AllocError {}
+
+/// Optional callback fired right before an allocator returns null for a failed allocation,
+/// letting the host log the failing layout before the allocation error handler runs.
+/// With the `panic-on-oom` feature, firing panics after the callback instead.
+///
+/// 可选回调，在分配器因分配失败返回 null 之前触发，
+/// 让宿主可以在分配错误处理程序运行之前记录失败的布局。
+/// 启用 `panic-on-oom` feature 时，触发后会在回调之后直接 panic。
+pub(crate) struct OomHook(Cell<Option<fn(Layout)>>);
+
+impl OomHook {
+    pub(crate) const fn new() -> Self {
+        OomHook(Cell::new(None))
+    }
+
+    pub(crate) fn set(&self, hook: Option<fn(Layout)>) {
+        self.0.set(hook);
+    }
+
+    #[cold]
+    pub(crate) fn fire(&self, layout: Layout) {
+        #[cfg(feature = "defmt")]
+        defmt::error!(
+            "oom size={=usize} align={=usize}",
+            layout.size(),
+            layout.align()
+        );
+        if let Some(hook) = self.0.get() {
+            hook(layout);
+        }
+        #[cfg(feature = "panic-on-oom")]
+        panic!("lite-alloc OOM: {layout:?}");
+    }
+}
+
+/// Cap on the live bytes an allocator hands out (`quota` feature). Bytes are counted
+/// as requested by the caller; blocks an allocator leaks on `dealloc` stay counted.
+///
+/// 分配器分配出去的存活字节数上限（`quota` feature）。按调用者请求的字节数计数；
+/// 分配器在 `dealloc` 时泄漏的块仍计入其中。
+#[cfg(feature = "quota")]
+pub(crate) struct Quota {
+    limit: Cell<usize>,
+    live: Cell<usize>,
+}
+
+#[cfg(feature = "quota")]
+impl Quota {
+    pub(crate) const fn new() -> Self {
+        Quota {
+            limit: Cell::new(usize::MAX),
+            live: Cell::new(0),
+        }
+    }
+
+    pub(crate) fn set_limit(&self, limit: Option<usize>) {
+        self.limit.set(limit.unwrap_or(usize::MAX));
+    }
+
+    pub(crate) fn live(&self) -> usize {
+        self.live.get()
+    }
+
+    /// Whether `bytes` more can be handed out without exceeding the limit.
+    ///
+    /// 再分配出 `bytes` 字节是否不会超出限制。
+    pub(crate) fn fits(&self, bytes: usize) -> bool {
+        bytes <= self.limit.get().saturating_sub(self.live.get())
+    }
+
+    pub(crate) fn charge(&self, bytes: usize) {
+        self.live.set(self.live.get() + bytes);
+    }
+
+    pub(crate) fn release(&self, bytes: usize) {
+        self.live.set(self.live.get().saturating_sub(bytes));
+    }
+
+    /// Account for a block resized in place from `old` to `new` bytes.
+    ///
+    /// 记录一个块从 `old` 字节原地调整为 `new` 字节。
+    pub(crate) fn resize(&self, old: usize, new: usize) {
+        self.live.set((self.live.get() + new).saturating_sub(old));
+    }
+
+    pub(crate) fn clear(&self) {
+        self.live.set(0);
+    }
+}
+
+/// High-water mark of the heap an allocator uses (`stats` feature): the bytes grown from
+/// the memory source minus the tail no block has reached yet, at its largest.
+///
+/// 分配器所用堆的高水位线（`stats` feature）：从内存来源增长的字节数减去尚无块触及的尾部，
+/// 取其最大值。
+#[cfg(feature = "stats")]
+pub(crate) struct HighWater {
+    grown: Cell<usize>,
+    peak: Cell<usize>,
+}
+
+#[cfg(feature = "stats")]
+impl HighWater {
+    pub(crate) const fn new() -> Self {
+        HighWater {
+            grown: Cell::new(0),
+            peak: Cell::new(0),
+        }
+    }
+
+    pub(crate) fn grew(&self, bytes: usize) {
+        self.grown.set(self.grown.get() + bytes);
+    }
+
+    pub(crate) fn shrank(&self, bytes: usize) {
+        self.grown.set(self.grown.get().saturating_sub(bytes));
+    }
+
+    /// Raise the mark to everything grown but the `untouched` bytes at the top.
+    ///
+    /// 将水位线提升至已增长的全部字节减去顶部 `untouched` 字节。
+    pub(crate) fn reach(&self, untouched: usize) {
+        let used = self.grown.get().saturating_sub(untouched);
+        self.peak.set(self.peak.get().max(used));
+    }
+
+    pub(crate) fn peak(&self) -> usize {
+        self.peak.get()
+    }
+
+    pub(crate) fn clear(&self) {
+        self.grown.set(0);
+        self.peak.set(0);
+    }
+}
+
+/// Freed-memory poisoning (`poison` feature): freed blocks are filled with [`POISON`],
+/// and debug builds check that recycled blocks still hold it before handing them out.
+///
+/// 释放内存投毒（`poison` feature）：释放的块被填充为 [`POISON`]，
+/// 调试构建会在分配回收的块之前检查其内容是否仍为该值。
+#[cfg(feature = "poison")]
+pub(crate) mod poison {
+    /// Byte pattern written over freed memory.
+    ///
+    /// 写入已释放内存的字节模式。
+    pub(crate) const POISON: u8 = 0xDE;
+
+    #[inline(always)]
+    pub(crate) unsafe fn fill(ptr: *mut u8, len: usize) {
+        unsafe { core::ptr::write_bytes(ptr, POISON, len) }
+    }
+
+    /// Assert that `[ptr + from, ptr + to)` is still poisoned. Free-list headers live
+    /// before `from` and are skipped by the caller. No-op in release builds.
+    ///
+    /// 断言 `[ptr + from, ptr + to)` 仍然是毒化状态。空闲链表头部位于 `from` 之前，
+    /// 由调用者跳过。在 release 构建中为空操作。
+    #[inline(always)]
+    pub(crate) unsafe fn check(ptr: *const u8, from: usize, to: usize) {
+        #[cfg(debug_assertions)]
+        for i in from..to {
+            debug_assert!(
+                unsafe { *ptr.add(i) } == POISON,
+                "use after free: freed block {ptr:p} was written at offset {i}"
+            );
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = (ptr, from, to);
+    }
+}
+
+/// Index of the WASM linear memory the heap lives in, used by [`grow_memory`] and
+/// [`heap_pages_grown`]. Defaults to 0; multi-memory modules whose heap is another memory
+/// set `LITE_ALLOC_HEAP_MEMORY_INDEX` at build time, e.g.
+/// `LITE_ALLOC_HEAP_MEMORY_INDEX=1 cargo build`. Has no effect on the host mock heap.
+///
+/// 堆所在的 WASM 线性内存索引，由 [`grow_memory`] 和 [`heap_pages_grown`] 使用。
+/// 默认为 0；堆位于其他内存的多内存模块可在构建时设置 `LITE_ALLOC_HEAP_MEMORY_INDEX`，
+/// 例如 `LITE_ALLOC_HEAP_MEMORY_INDEX=1 cargo build`。对宿主模拟堆无效。
+pub const HEAP_MEMORY_INDEX: u32 = match option_env!("LITE_ALLOC_HEAP_MEMORY_INDEX") {
+    Some(index) => parse_memory_index(index),
+    None => 0,
+};
+
+// Const parser for the build-time index: an invalid value fails the build
+// 构建时索引的常量解析器：非法的值会导致构建失败
+const fn parse_memory_index(index: &str) -> u32 {
+    let bytes = index.as_bytes();
+    assert!(
+        !bytes.is_empty(),
+        "LITE_ALLOC_HEAP_MEMORY_INDEX must not be empty"
+    );
+    let mut value: u32 = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let digit = bytes[i];
+        assert!(
+            digit.is_ascii_digit(),
+            "LITE_ALLOC_HEAP_MEMORY_INDEX must be a decimal number"
+        );
+        let next = match value.checked_mul(10) {
+            Some(v) => v.checked_add((digit - b'0') as u32),
+            None => None,
+        };
+        value = match next {
+            Some(v) => v,
+            None => panic!("LITE_ALLOC_HEAP_MEMORY_INDEX does not fit in u32"),
+        };
+        i += 1;
+    }
+    value
+}
+
+// Raw page growth function, wrapped by `DefaultMemory`
+// 原始的页增长函数，由 `DefaultMemory` 封装
 #[cfg(target_arch = "wasm32")]
 #[inline(always)]
 pub unsafe fn grow_memory(pages: usize) -> usize {
-    core::arch::wasm32::memory_grow(0, pages)
+    core::arch::wasm32::memory_grow::<HEAP_MEMORY_INDEX>(pages)
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+// memory64: page indices are 64-bit, so the page-to-address math is done with checked arithmetic
+// memory64：页索引为 64 位，因此页到地址的换算使用带检查的算术
+#[cfg(target_arch = "wasm64")]
+#[inline(always)]
+pub unsafe fn grow_memory(pages: usize) -> usize {
+    core::arch::wasm64::memory_grow::<HEAP_MEMORY_INDEX>(pages)
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
 mod host_memory {
     use super::PAGE_SIZE;
-    use std::alloc::{Layout, alloc, dealloc};
+    use std::alloc::{Layout, alloc_zeroed, dealloc};
     use std::cell::RefCell;
     use std::ptr;
 
-    // Simulate 128MB of addressable WASM memory space per thread
-    // 模拟每线程 128MB 的可寻址 WASM 内存空间
-    const MOCK_MEMORY_SIZE: usize = 128 * 1024 * 1024;
+    // Simulate 128MB of addressable WASM memory space per thread by default
+    // 默认模拟每线程 128MB 的可寻址 WASM 内存空间
+    pub const MOCK_MEMORY_SIZE: usize = 128 * 1024 * 1024;
+
+    // Alignment small enough for the system allocator to use `calloc`, which maps large
+    // blocks lazily instead of zeroing them up front
+    // 足够小的对齐，使系统分配器使用 `calloc`，它会延迟映射大块内存，而不是预先清零
+    const RESERVE_ALIGN: usize = 16;
 
     struct MockMemory {
+        // Host allocation backing the mock heap; `base_ptr` is aligned inside it
+        // 支撑模拟堆的宿主分配；`base_ptr` 在其内部对齐
+        reservation: *mut u8,
         base_ptr: *mut u8,
         current_pages: usize,
+        // Most pages ever grown at once: the only part of the reservation ever written
+        // 曾经同时增长的最大页数：预留区域中唯一被写入过的部分
+        touched_pages: usize,
+        size: usize,
+        // Base aligned to the size rounded up to a power of two (see `pin_mock_heap`)
+        // 基址按大小向上取整到 2 的幂对齐（见 `pin_mock_heap`）
+        pinned: bool,
     }
 
     impl MockMemory {
         fn new() -> Self {
-            unsafe {
-                let layout = Layout::from_size_align(MOCK_MEMORY_SIZE, PAGE_SIZE).unwrap();
-                let ptr = alloc(layout);
-                if ptr.is_null() {
-                    // Panic immediately if we can't allocate the mock heap
-                    // 如果无法分配模拟堆，立即 Panic
-                    panic!("Failed to allocate mock WASM memory");
+            Self::with_size(MOCK_MEMORY_SIZE, false)
+        }
+
+        fn base_align(size: usize, pinned: bool) -> usize {
+            if pinned {
+                size.next_power_of_two()
+            } else {
+                PAGE_SIZE
+            }
+        }
+
+        fn layout(size: usize, pinned: bool) -> Option<Layout> {
+            let slack = Self::base_align(size, pinned) - 1;
+            Layout::from_size_align(size.checked_add(slack)?, RESERVE_ALIGN).ok()
+        }
+
+        fn with_size(mut size: usize, pinned: bool) -> Self {
+            // Back off to smaller heaps on hosts that cannot reserve `size`
+            // 在无法预留 `size` 的宿主上退而使用更小的堆
+            loop {
+                if let Some(mem) = Self::reserve(size, pinned) {
+                    return mem;
                 }
-                // Initialize memory to zero, similar to WASM behavior
-                // 将内存初始化为零，类似于 WASM 行为
-                ptr::write_bytes(ptr, 0, MOCK_MEMORY_SIZE);
-                Self {
-                    base_ptr: ptr,
-                    current_pages: 0,
+                if size <= PAGE_SIZE {
+                    panic!("Failed to allocate mock WASM memory");
                 }
+                size = (size / 2).next_multiple_of(PAGE_SIZE);
             }
         }
+
+        // Reserve zeroed memory for `size` bytes plus alignment slack. Nothing is written
+        // here, so the host only backs the pages that are actually grown
+        // 为 `size` 字节加上对齐余量预留清零的内存。此处不写入任何内容，
+        // 因此宿主只需为实际增长的页面提供物理内存
+        fn reserve(size: usize, pinned: bool) -> Option<Self> {
+            let reservation = unsafe { alloc_zeroed(Self::layout(size, pinned)?) };
+            if reservation.is_null() {
+                return None;
+            }
+            let align = Self::base_align(size, pinned);
+            let offset = (reservation as usize).next_multiple_of(align) - reservation as usize;
+            Some(Self {
+                reservation,
+                base_ptr: unsafe { reservation.add(offset) },
+                current_pages: 0,
+                touched_pages: 0,
+                size,
+                pinned,
+            })
+        }
     }
 
     impl Drop for MockMemory {
         fn drop(&mut self) {
-            unsafe {
-                let layout = Layout::from_size_align(MOCK_MEMORY_SIZE, PAGE_SIZE).unwrap();
-                dealloc(self.base_ptr, layout);
-            }
+            let layout = Self::layout(self.size, self.pinned).unwrap();
+            unsafe { dealloc(self.reservation, layout) };
         }
     }
 
@@ -89,7 +734,7 @@ mod host_memory {
 
             // Check if we have enough space in our pre-allocated buffer
             // 检查预分配缓冲区中是否有足够的空间
-            if (mem.current_pages + pages) * PAGE_SIZE > MOCK_MEMORY_SIZE {
+            if (mem.current_pages + pages) * PAGE_SIZE > mem.size {
                 return usize::MAX;
             }
 
@@ -108,57 +753,395 @@ mod host_memory {
             // Advance the usage counter
             // 增加使用计数
             mem.current_pages += pages;
+            mem.touched_pages = mem.touched_pages.max(mem.current_pages);
 
-            // Zero out the newly allocated pages (emulate WASM grow behavior)
-            // Note: We zeroed the whole block on init, but reset_memory might not zero everything
-            // if we optimize it. Let's ensure it's zeroed here if we change reset policy.
-            // For now, init zeros everything and reset zeros used, so it's fine.
-            // 将新分配的页面清零（模拟 WASM 增长行为）
-            // 注意：我们在初始化时将整个块清零，但如果进行优化，reset_memory 可能不会清零所有内容。
-            // 如果我们要更改重置策略，请确保在此处清零。
-            // 目前，初始化会清零所有内容，重置会清零已使用的内容，所以没问题。
+            // The new pages are already zero (emulating WASM grow behavior): the
+            // reservation starts zeroed, and reset and shrink zero the pages they take back
+            // 新页面已经为零（模拟 WASM 增长行为）：预留区域初始即为零，
+            // 而重置与收缩会将收回的页面清零
 
             ret_page_index
         })
     }
 
-    pub unsafe fn reset_memory() {
+    pub unsafe fn shrink_impl(page_index: usize, pages: usize) -> bool {
         MEMORY.with(|mem| {
             let mut mem = mem.borrow_mut();
-            // Zero out the used memory so the next test starts clean
-            // 将已使用的内存清零，以便下一个测试从干净的状态开始
-            let used_bytes = mem.current_pages * PAGE_SIZE;
-            if used_bytes > 0 {
-                unsafe {
-                    ptr::write_bytes(mem.base_ptr, 0, used_bytes);
-                }
+            // Only the pages at the end of the mock heap can be given back
+            // 只能归还位于模拟堆末尾的页面
+            let end_page = mem.base_ptr as usize / PAGE_SIZE + mem.current_pages;
+            if pages > mem.current_pages || page_index.checked_add(pages) != Some(end_page) {
+                return false;
+            }
+            mem.current_pages -= pages;
+            // Zero them again so a later grow hands out clean pages
+            // 重新清零，使之后的增长得到干净的页面
+            unsafe {
+                ptr::write_bytes((page_index * PAGE_SIZE) as *mut u8, 0, pages * PAGE_SIZE);
+            }
+            true
+        })
+    }
+
+    pub fn pages_grown() -> usize {
+        MEMORY.with(|mem| mem.borrow().current_pages)
+    }
+
+    pub fn pages_touched() -> usize {
+        MEMORY.with(|mem| mem.borrow().touched_pages)
+    }
+
+    pub fn size() -> usize {
+        MEMORY.with(|mem| mem.borrow().size)
+    }
+
+    pub fn offset_of(addr: usize) -> Option<usize> {
+        MEMORY.with(|mem| {
+            let mem = mem.borrow();
+            let offset = addr.checked_sub(mem.base_ptr as usize)?;
+            (offset < mem.size).then_some(offset)
+        })
+    }
+
+    pub fn set_size(bytes: usize) {
+        let size = bytes.div_ceil(PAGE_SIZE).max(1) * PAGE_SIZE;
+        MEMORY.with(|mem| {
+            // The old region is freed when the previous `MockMemory` is dropped
+            // 旧区域在之前的 `MockMemory` 被丢弃时释放
+            let pinned = mem.borrow().pinned;
+            *mem.borrow_mut() = MockMemory::with_size(size, pinned);
+        });
+    }
+
+    pub fn pin() {
+        MEMORY.with(|mem| {
+            let mut mem = mem.borrow_mut();
+            if mem.pinned {
+                unsafe { reset_locked(&mut mem) };
+            } else {
+                *mem = MockMemory::with_size(mem.size, true);
             }
-            mem.current_pages = 0;
         });
     }
+
+    pub unsafe fn reset_memory() {
+        MEMORY.with(|mem| unsafe { reset_locked(&mut mem.borrow_mut()) });
+    }
+
+    unsafe fn reset_locked(mem: &mut MockMemory) {
+        // Zero out the used memory so the next test starts clean
+        // 将已使用的内存清零，以便下一个测试从干净的状态开始
+        let used_bytes = mem.current_pages * PAGE_SIZE;
+        if used_bytes > 0 {
+            unsafe {
+                ptr::write_bytes(mem.base_ptr, 0, used_bytes);
+            }
+        }
+        mem.current_pages = 0;
+    }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
+/// Host shim of `grow_memory`, backed by a per-thread mock heap.
+///
+/// # Safety
+/// Mirrors the WASM intrinsic: the returned pages belong to the caller.
+///
+/// `grow_memory` 的宿主实现，由每线程的模拟堆支持。
+///
+/// # Safety
+/// 与 WASM 内部函数一致：返回的页面归调用者所有。
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
 pub unsafe fn grow_memory(pages: usize) -> usize {
     unsafe { host_memory::grow_memory_impl(pages) }
 }
 
+/// Size of the heap's linear memory ([`HEAP_MEMORY_INDEX`]) in pages, including pages the
+/// module started with.
+///
+/// 堆所在线性内存（[`HEAP_MEMORY_INDEX`]）的页数，包括模块初始拥有的页。
+#[cfg(target_arch = "wasm32")]
+pub fn heap_pages_grown() -> PageCount {
+    PageCount(core::arch::wasm32::memory_size::<HEAP_MEMORY_INDEX>())
+}
+
+/// Size of the heap's linear memory ([`HEAP_MEMORY_INDEX`]) in pages, including pages the
+/// module started with.
+///
+/// 堆所在线性内存（[`HEAP_MEMORY_INDEX`]）的页数，包括模块初始拥有的页。
+#[cfg(target_arch = "wasm64")]
+pub fn heap_pages_grown() -> PageCount {
+    PageCount(core::arch::wasm64::memory_size::<HEAP_MEMORY_INDEX>())
+}
+
+/// Pages successfully grown from the current thread's mock heap since the last `reset_heap`.
+///
+/// 自上次 `reset_heap` 以来，从当前线程模拟堆成功增长的页数。
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub fn heap_pages_grown() -> PageCount {
+    PageCount(host_memory::pages_grown())
+}
+
+/// Pages of the current thread's mock heap that have ever been handed out, the most that
+/// were grown at once. The rest of the reservation is never written, so on hosts whose
+/// allocator maps large zeroed blocks lazily it takes no physical memory.
+///
+/// 当前线程模拟堆中曾被分配出去的页数，即同时增长过的最大页数。预留区域的其余部分
+/// 从不被写入，因此在会延迟映射大块清零内存的宿主上，它不占用物理内存。
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub fn mock_heap_footprint() -> PageCount {
+    PageCount(host_memory::pages_touched())
+}
+
+/// Byte offset of `ptr` from the start of the current thread's mock heap, or `None` if it
+/// points elsewhere. The mock heap itself lives wherever the system allocator put it, so
+/// absolute addresses change between runs; offsets (and `offset / PAGE_SIZE` page indices)
+/// do not, which lets tests assert exact positions.
+///
+/// 返回 `ptr` 相对当前线程模拟堆起始处的字节偏移；如果指向其他位置则返回 `None`。
+/// 模拟堆本身位于系统分配器给出的任意位置，因此绝对地址在每次运行之间都会变化；
+/// 而偏移（以及 `offset / PAGE_SIZE` 页索引）不会变化，使测试可以断言确切的位置。
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub fn mock_heap_offset(ptr: *const u8) -> Option<usize> {
+    host_memory::offset_of(ptr as usize)
+}
+
+/// Checked [`grow_memory`]: `Some(previous_page_count)`, or `None` instead of the
+/// `usize::MAX` failure sentinel.
+///
+/// # Safety
+/// Same as [`grow_memory`]: the returned pages belong to the caller.
+///
+/// 带检查的 [`grow_memory`]：返回 `Some(previous_page_count)`，
+/// 失败时返回 `None` 而不是哨兵值 `usize::MAX`。
+///
+/// # Safety
+/// 与 [`grow_memory`] 相同：返回的页面归调用者所有。
+#[inline(always)]
+pub unsafe fn try_grow_memory(pages: usize) -> Option<usize> {
+    match unsafe { grow_memory(pages) } {
+        usize::MAX => None,
+        previous_page_count => Some(previous_page_count),
+    }
+}
+
 /// For Test/Bench only: Reset the mock heap memory of the current thread
 ///
 /// 仅用于测试/Bench：重置当前线程的模拟堆内存
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
 pub fn reset_heap() {
     unsafe {
         host_memory::reset_memory();
     }
 }
 
+/// For Test/Bench only: Replace the mock heap of the current thread with a fresh,
+/// zeroed region of `bytes` (rounded up to whole pages). The default is 128MB.
+/// Pointers into the previous mock heap become dangling.
+///
+/// The region is only reserved: pages are written once they are grown, so a large
+/// capacity costs little as long as few pages are used (see [`mock_heap_footprint`]).
+/// Hosts that cannot reserve `bytes` get the largest half, quarter, ... of it they can;
+/// [`mock_memory_size`] reports the capacity actually set.
+///
+/// 仅用于测试/Bench：将当前线程的模拟堆替换为新的、大小为 `bytes`（向上取整到整页）的清零区域。
+/// 默认为 128MB。指向之前模拟堆的指针都将失效。
+///
+/// 该区域只是被预留：页面在增长后才会被写入，因此只要使用的页面不多，
+/// 较大的容量也几乎没有开销（见 [`mock_heap_footprint`]）。
+/// 无法预留 `bytes` 的宿主会得到其能预留的最大的一半、四分之一……；
+/// [`mock_memory_size`] 返回实际设置的容量。
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub fn set_mock_memory_size(bytes: usize) {
+    host_memory::set_size(bytes);
+}
+
+/// For Bench only: Reset the mock heap of the current thread and align its base to the
+/// heap size rounded up to a power of two. Addresses then agree across runs in every bit
+/// below that alignment, so address-dependent paths (alignment padding, buddy merges,
+/// address-ordered lists) take the same branches every run. The first call replaces the
+/// mock heap, so pointers into the previous one become dangling; later calls only reset
+/// it. `set_mock_memory_size` keeps the pin.
+///
+/// 仅用于 Bench：重置当前线程的模拟堆，并将其基址按“堆大小向上取整到 2 的幂”对齐。
+/// 此后各次运行的地址在低于该对齐的所有位上都一致，因此依赖地址的路径
+/// （对齐填充、伙伴合并、按地址排序的链表）每次运行都走相同的分支。
+/// 首次调用会替换模拟堆，指向之前模拟堆的指针都将失效；之后的调用只会重置它。
+/// `set_mock_memory_size` 会保留固定状态。
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub fn pin_mock_heap() {
+    host_memory::pin();
+}
+
+/// Default size of the per-thread mock heap, in bytes. A host that cannot reserve it
+/// gets the largest halving of it that fits; see [`mock_memory_size`].
+///
+/// 每线程模拟堆的默认大小，单位字节。无法预留该大小的宿主会得到
+/// 能够容纳的最大折半大小；见 [`mock_memory_size`]。
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub const MOCK_MEMORY_SIZE: usize = host_memory::MOCK_MEMORY_SIZE;
+
+/// Capacity of the current thread's mock heap, in bytes.
+///
+/// 当前线程模拟堆的容量，单位字节。
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub fn mock_memory_size() -> usize {
+    host_memory::size()
+}
+
+/// Alignment of the buffers handed out by [`alloc_js_buffer`], enough for any JS typed
+/// array view over them.
+///
+/// [`alloc_js_buffer`] 分配的缓冲区的对齐，足以在其上创建任意 JS 类型化数组视图。
+#[cfg(not(target_arch = "wasm64"))]
+pub const JS_BUFFER_ALIGN: usize = 16;
+
+/// Allocate a [`JS_BUFFER_ALIGN`]-aligned buffer of `len` bytes from the global allocator,
+/// for handing to JS (e.g. as the offset of a `Uint8Array` view over the WASM memory) in
+/// the manual-memory wasm-bindgen pattern. Returns null if the allocation fails, and a
+/// dangling, aligned pointer that must still be passed to [`free_js_buffer`] for
+/// `len == 0`.
+///
+/// # Safety
+/// The buffer must be freed with [`free_js_buffer`] and the same `len`.
+///
+/// 从全局分配器分配一个按 [`JS_BUFFER_ALIGN`] 对齐、长度为 `len` 字节的缓冲区，
+/// 用于在手动管理内存的 wasm-bindgen 模式中交给 JS（例如作为 WASM 内存上
+/// `Uint8Array` 视图的偏移）。分配失败时返回 null；`len == 0` 时返回一个悬垂但对齐的指针，
+/// 它仍应传给 [`free_js_buffer`]。
+///
+/// # Safety
+/// 该缓冲区必须以相同的 `len` 通过 [`free_js_buffer`] 释放。
+#[cfg(target_arch = "wasm32")]
+pub unsafe fn alloc_js_buffer(len: usize) -> *mut u8 {
+    unsafe { js_buffer::alloc(len) }
+}
+
+/// Host shim of `alloc_js_buffer`, backed by the host's global allocator.
+///
+/// # Safety
+/// The buffer must be freed with [`free_js_buffer`] and the same `len`.
+///
+/// `alloc_js_buffer` 的宿主实现，由宿主的全局分配器支持。
+///
+/// # Safety
+/// 该缓冲区必须以相同的 `len` 通过 [`free_js_buffer`] 释放。
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub unsafe fn alloc_js_buffer(len: usize) -> *mut u8 {
+    unsafe { js_buffer::alloc(len) }
+}
+
+/// Free a buffer from [`alloc_js_buffer`], e.g. once JS has copied its contents out.
+///
+/// # Safety
+/// `ptr` must come from [`alloc_js_buffer`] called with `len`, and must not be used
+/// afterwards, from Rust or from JS.
+///
+/// 释放由 [`alloc_js_buffer`] 分配的缓冲区，例如在 JS 复制出其内容之后。
+///
+/// # Safety
+/// `ptr` 必须来自以 `len` 调用的 [`alloc_js_buffer`]，且之后不得再被 Rust 或 JS 使用。
+#[cfg(target_arch = "wasm32")]
+pub unsafe fn free_js_buffer(ptr: *mut u8, len: usize) {
+    unsafe { js_buffer::free(ptr, len) }
+}
+
+/// Host shim of `free_js_buffer`, backed by the host's global allocator.
+///
+/// # Safety
+/// `ptr` must come from [`alloc_js_buffer`] called with `len`, and must not be used
+/// afterwards.
+///
+/// `free_js_buffer` 的宿主实现，由宿主的全局分配器支持。
+///
+/// # Safety
+/// `ptr` 必须来自以 `len` 调用的 [`alloc_js_buffer`]，且之后不得再被使用。
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub unsafe fn free_js_buffer(ptr: *mut u8, len: usize) {
+    unsafe { js_buffer::free(ptr, len) }
+}
+
+#[cfg(not(target_arch = "wasm64"))]
+mod js_buffer {
+    use super::{JS_BUFFER_ALIGN, dangling};
+    use core::alloc::Layout;
+    use core::ptr::null_mut;
+
+    pub unsafe fn alloc(len: usize) -> *mut u8 {
+        // A length no `Layout` can describe cannot be allocated either
+        // 无法用 `Layout` 描述的长度也无法被分配
+        let Ok(layout) = Layout::from_size_align(len, JS_BUFFER_ALIGN) else {
+            return null_mut();
+        };
+        if len == 0 {
+            return dangling(layout);
+        }
+        unsafe { alloc::alloc::alloc(layout) }
+    }
+
+    pub unsafe fn free(ptr: *mut u8, len: usize) {
+        if len == 0 {
+            return;
+        }
+        unsafe {
+            let layout = Layout::from_size_align_unchecked(len, JS_BUFFER_ALIGN);
+            alloc::alloc::dealloc(ptr, layout)
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+mod allocator_api;
+
+pub mod layout_math;
+
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub mod replay;
+
+// Update an allocator's `stats` counters; expands to nothing without the feature
+// 更新分配器的 `stats` 计数器；未开启 feature 时展开为空
+macro_rules! record {
+    ($self:ident, $s:ident => $body:expr) => {
+        #[cfg(feature = "stats")]
+        {
+            let mut $s = $self.stats.get();
+            $body;
+            $self.stats.set($s);
+        }
+    };
+}
+
 pub mod single_threaded {
+    mod any;
+    mod bitmap;
+    mod buddy;
     mod bump_freelist;
+    mod fallback;
     mod freelist;
+    mod global_wrapper;
+    mod ring_arena;
+    mod scope;
     mod segregated_bump;
+    mod slab;
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    mod thread_local;
+    mod tlsf;
 
+    pub use any::AnyAllocator;
+    pub use bitmap::BitmapAllocator;
+    pub use buddy::BuddyAllocator;
     pub use bump_freelist::BumpFreeListAllocator;
-    pub use freelist::FreeListAllocator;
+    pub use fallback::Fallback;
+    #[cfg(feature = "stats")]
+    pub use freelist::AllocStats;
+    pub use freelist::{FreeListAllocator, IntegrityError};
+    pub use global_wrapper::GlobalWrapper;
+    pub use ring_arena::RingArena;
+    pub use scope::{Scope, ScopedAllocator};
+    #[cfg(feature = "stats")]
+    pub use segregated_bump::BinStats;
     pub use segregated_bump::SegregatedBumpAllocator;
+    pub use slab::SlabAllocator;
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    pub use thread_local::{SharedMemoryAllocator, ThreadLocalAlloc};
+    pub use tlsf::TlsfAllocator;
 }
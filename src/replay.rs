@@ -0,0 +1,240 @@
+//! Deterministic replay of allocation sequences on the host, to reproduce fuzzer findings
+//! outside libfuzzer and to pin them down as regression tests. The fuzz targets drive
+//! their allocators through [`replay_with`] as well.
+//!
+//! 在宿主上确定性地重放分配序列，用于在 libfuzzer 之外复现模糊测试发现的问题，
+//! 并将其固定为回归测试。模糊测试目标同样通过 [`replay_with`] 驱动其分配器。
+
+use crate::ResettableAllocator;
+use core::alloc::{GlobalAlloc, Layout};
+use std::vec::Vec;
+
+/// One step of a replayed sequence. Indices pick a live block modulo the number of live
+/// blocks, so every sequence is valid; steps that find no live block are skipped.
+///
+/// 重放序列中的一步。索引对存活块数取模来选择存活块，因此任何序列都是合法的；
+/// 找不到存活块的步骤会被跳过。
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum AllocOp {
+    /// Allocate `size` bytes (at least 1) aligned to `align`; invalid layouts are skipped.
+    ///
+    /// 分配按 `align` 对齐的 `size` 字节（至少为 1）；非法的布局会被跳过。
+    Alloc { size: usize, align: usize },
+    /// Free a live block.
+    ///
+    /// 释放一个存活块。
+    Dealloc { index: usize },
+    /// Resize a live block to `new_size` bytes (at least 1).
+    ///
+    /// 将一个存活块调整为 `new_size` 字节（至少为 1）。
+    Realloc { index: usize, new_size: usize },
+}
+
+impl AllocOp {
+    /// Decode a fuzzer input: an opcode byte, then for `op % 3 == 0` a little-endian `u16`
+    /// size (alignment 8), for `op % 3 == 1` an index byte, and otherwise an index byte and
+    /// a little-endian `u16` new size. A truncated last step is dropped.
+    ///
+    /// 解码模糊测试输入：一个操作码字节，之后 `op % 3 == 0` 时为小端 `u16` 大小（对齐为 8），
+    /// `op % 3 == 1` 时为一个索引字节，否则为一个索引字节和小端 `u16` 新大小。
+    /// 被截断的最后一步会被丢弃。
+    pub fn decode(data: &[u8]) -> Vec<AllocOp> {
+        let mut ops = Vec::new();
+        let mut rest = data;
+        while let Some((&op, args)) = rest.split_first() {
+            let (step, len) = match (op % 3, args) {
+                (0, [s1, s2, ..]) => {
+                    let size = u16::from_le_bytes([*s1, *s2]) as usize;
+                    (AllocOp::Alloc { size, align: 8 }, 2)
+                }
+                (1, [index, ..]) => (
+                    AllocOp::Dealloc {
+                        index: *index as usize,
+                    },
+                    1,
+                ),
+                (2, [index, s1, s2, ..]) => {
+                    let new_size = u16::from_le_bytes([*s1, *s2]) as usize;
+                    let index = *index as usize;
+                    (AllocOp::Realloc { index, new_size }, 3)
+                }
+                _ => break,
+            };
+            ops.push(step);
+            rest = &args[len..];
+        }
+        ops
+    }
+
+    /// A pseudo-random sequence of `len` steps, the same for the same `seed`: sizes up to
+    /// 4 KiB, alignments up to 64, about half of the steps allocating.
+    ///
+    /// 由 `len` 步组成的伪随机序列，相同的 `seed` 得到相同的序列：大小不超过 4 KiB，
+    /// 对齐不超过 64，约一半的步骤为分配。
+    pub fn seeded(seed: u64, len: usize) -> Vec<AllocOp> {
+        // SplitMix64: tiny, and good enough to spread sizes and choices
+        // SplitMix64：足够小巧，也足以使大小和选择分散开
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            (z ^ (z >> 31)) as usize
+        };
+        (0..len)
+            .map(|_| {
+                let choice = next();
+                // Mostly small sizes, with the occasional large one
+                // 大多为小尺寸，偶尔出现大尺寸
+                let size = next() % (1 << (4 + next() % 9)) + 1;
+                match choice % 10 {
+                    0..5 => AllocOp::Alloc {
+                        size,
+                        align: 1 << (next() % 7),
+                    },
+                    5..8 => AllocOp::Dealloc { index: next() },
+                    _ => AllocOp::Realloc {
+                        index: next(),
+                        new_size: size,
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+/// [`replay_with`] without an allocator-specific check.
+///
+/// # Safety
+/// Same as [`replay_with`].
+///
+/// 不带分配器特定检查的 [`replay_with`]。
+///
+/// # Safety
+/// 与 [`replay_with`] 相同。
+pub unsafe fn replay<A: GlobalAlloc + ResettableAllocator>(allocator: &A, ops: &[AllocOp]) {
+    unsafe { replay_with(allocator, ops, |_| {}) }
+}
+
+/// Reset `allocator`, run `ops` on it, then free every block still live. Each block is
+/// filled with its own byte, and the run panics, naming the step, as soon as a block comes
+/// back misaligned, overlaps another live block, or has lost its contents (checked when it
+/// is freed or resized). `check` runs after every step and after the final frees, e.g. to
+/// assert `FreeListAllocator::check_integrity`.
+///
+/// # Safety
+/// The allocator is reset first, so no block it handed out before may still be in use.
+///
+/// 重置 `allocator`，在其上执行 `ops`，然后释放所有仍存活的块。每个块都以其专属的字节填充；
+/// 一旦某个块返回时未对齐、与其他存活块重叠，或丢失了内容（在释放或调整大小时检查），
+/// 就会 panic 并指出是哪一步。`check` 在每一步之后以及最后的释放之后运行，
+/// 例如用于断言 `FreeListAllocator::check_integrity`。
+///
+/// # Safety
+/// 分配器会先被重置，因此它之前分配出去的块都不得仍在使用中。
+pub unsafe fn replay_with<A: GlobalAlloc + ResettableAllocator>(
+    allocator: &A,
+    ops: &[AllocOp],
+    mut check: impl FnMut(&A),
+) {
+    unsafe { allocator.reset() };
+    let mut live = Live::default();
+    for (step, &op) in ops.iter().enumerate() {
+        match op {
+            AllocOp::Alloc { size, align } => {
+                let Ok(layout) = Layout::from_size_align(size.max(1), align) else {
+                    continue;
+                };
+                let ptr = unsafe { allocator.alloc(layout) };
+                if !ptr.is_null() {
+                    live.insert(step, ptr, layout);
+                }
+            }
+            AllocOp::Dealloc { index } => {
+                if let Some((ptr, layout, _)) = live.remove(step, index) {
+                    unsafe { allocator.dealloc(ptr, layout) };
+                }
+            }
+            AllocOp::Realloc { index, new_size } => {
+                let Some((ptr, layout, fill)) = live.remove(step, index) else {
+                    continue;
+                };
+                let Ok(new_layout) = Layout::from_size_align(new_size.max(1), layout.align())
+                else {
+                    live.blocks.push((ptr, layout, fill));
+                    continue;
+                };
+                let new_ptr = unsafe { allocator.realloc(ptr, layout, new_layout.size()) };
+                if new_ptr.is_null() {
+                    // A failed realloc leaves the old block untouched
+                    // 失败的 realloc 不会改动旧块
+                    live.blocks.push((ptr, layout, fill));
+                } else {
+                    let kept = layout.size().min(new_layout.size());
+                    assert!(
+                        (0..kept).all(|i| unsafe { *new_ptr.add(i) } == fill),
+                        "step {step}: realloc to {} lost the contents",
+                        new_layout.size()
+                    );
+                    live.insert(step, new_ptr, new_layout);
+                }
+            }
+        }
+        check(allocator);
+    }
+    while let Some((ptr, layout, _)) = live.remove(ops.len(), 0) {
+        unsafe { allocator.dealloc(ptr, layout) };
+    }
+    check(allocator);
+}
+
+// Blocks handed out during a replay, each with the byte it was filled with
+// 重放期间分配出去的块，以及各自填充的字节
+#[derive(Default)]
+struct Live {
+    blocks: Vec<(*mut u8, Layout, u8)>,
+    fills: u8,
+}
+
+impl Live {
+    // Check a new block against the live ones and fill it with the next byte
+    // 将新块与存活块对照检查，并用下一个字节填充它
+    fn insert(&mut self, step: usize, ptr: *mut u8, layout: Layout) {
+        let (start, end) = (ptr as usize, ptr as usize + layout.size());
+        assert!(
+            start.is_multiple_of(layout.align()),
+            "step {step}: {ptr:p} is not aligned to {}",
+            layout.align()
+        );
+        for &(other, other_layout, _) in &self.blocks {
+            let (other_start, other_end) = (other as usize, other as usize + other_layout.size());
+            assert!(
+                end <= other_start || other_end <= start,
+                "step {step}: {ptr:p} ({} bytes) overlaps live block {other:p} ({} bytes)",
+                layout.size(),
+                other_layout.size()
+            );
+        }
+        // Never zero, so a block that was wiped shows up too
+        // 从不为零，因此被清零的块也能被发现
+        self.fills = self.fills % 255 + 1;
+        unsafe { ptr.write_bytes(self.fills, layout.size()) };
+        self.blocks.push((ptr, layout, self.fills));
+    }
+
+    // Take the live block `index` picks, checking its contents
+    // 取出 `index` 选中的存活块，并检查其内容
+    fn remove(&mut self, step: usize, index: usize) -> Option<(*mut u8, Layout, u8)> {
+        if self.blocks.is_empty() {
+            return None;
+        }
+        let (ptr, layout, fill) = self.blocks.swap_remove(index % self.blocks.len());
+        assert!(
+            (0..layout.size()).all(|i| unsafe { *ptr.add(i) } == fill),
+            "step {step}: block {ptr:p} ({} bytes) lost its contents",
+            layout.size()
+        );
+        Some((ptr, layout, fill))
+    }
+}
@@ -0,0 +1,61 @@
+use super::{BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator};
+use crate::{DefaultMemory, MemorySource, ResettableAllocator};
+use core::alloc::{GlobalAlloc, Layout};
+
+/// One of the general-purpose allocators, picked at runtime, e.g. from a Wasm import
+/// describing the workload. Every call is dispatched to the active variant, so the enum
+/// can stand in wherever a concrete allocator or a `&dyn GlobalAlloc` is expected.
+///
+/// 在运行时选定的某个通用分配器，例如根据描述工作负载的 Wasm 导入来选择。
+/// 每次调用都分派给当前变体，因此该枚举可以用在任何需要具体分配器或
+/// `&dyn GlobalAlloc` 的地方。
+pub enum AnyAllocator<S = DefaultMemory> {
+    /// A coalescing [`FreeListAllocator`].
+    ///
+    /// 支持合并的 [`FreeListAllocator`]。
+    FreeList(FreeListAllocator<S>),
+    /// A [`BumpFreeListAllocator`].
+    ///
+    /// [`BumpFreeListAllocator`]。
+    BumpFreeList(BumpFreeListAllocator<S>),
+    /// A [`SegregatedBumpAllocator`] with the default bins.
+    ///
+    /// 使用默认 Bin 的 [`SegregatedBumpAllocator`]。
+    SegregatedBump(SegregatedBumpAllocator<S>),
+}
+
+// Forward one call to whichever allocator is active
+// 将一次调用转发给当前活动的分配器
+macro_rules! dispatch {
+    ($self:ident, $alloc:ident => $call:expr) => {
+        match $self {
+            AnyAllocator::FreeList($alloc) => $call,
+            AnyAllocator::BumpFreeList($alloc) => $call,
+            AnyAllocator::SegregatedBump($alloc) => $call,
+        }
+    };
+}
+
+unsafe impl<S: MemorySource> GlobalAlloc for AnyAllocator<S> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        dispatch!(self, a => unsafe { a.alloc(layout) })
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        dispatch!(self, a => unsafe { a.alloc_zeroed(layout) })
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        dispatch!(self, a => unsafe { a.dealloc(ptr, layout) })
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        dispatch!(self, a => unsafe { a.realloc(ptr, layout, new_size) })
+    }
+}
+
+impl<S> ResettableAllocator for AnyAllocator<S> {
+    unsafe fn reset(&self) {
+        dispatch!(self, a => unsafe { a.reset() })
+    }
+}
@@ -0,0 +1,402 @@
+use crate::{DefaultMemory, MemorySource, OomHook, PAGE_SIZE, ResettableAllocator, grow_region};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::UnsafeCell,
+    ptr::{self, null_mut},
+};
+
+/// Safety Warning:
+/// Allocators in this module are designed for [Single Threaded] environments.
+/// `Sync` is implemented only to satisfy `GlobalAlloc` trait requirements.
+/// Using this allocator in a multi-threaded environment will lead to Undefined Behavior (UB).
+/// Please ensure it is used only in single-threaded environments (e.g., WASM or single-threaded embedded).
+///
+/// 安全性警示 (Safety Warning):
+/// 本模块中的分配器均为【单线程】设计。
+/// 实现了 `Sync` 仅为了满足 `GlobalAlloc` trait 的要求。
+/// 在多线程环境中使用此分配器会导致未定义行为 (UB)。
+/// 请确保只在单线程环境（如 WASM 或单线程嵌入式环境）中使用。
+///
+/// With `strict-single-thread` this impl is left out: wrap the allocator in
+/// [`GlobalWrapper`](super::GlobalWrapper) at the `#[global_allocator]` site instead.
+///
+/// 开启 `strict-single-thread` 时不提供此实现：
+/// 请改为在 `#[global_allocator]` 处用 [`GlobalWrapper`](super::GlobalWrapper) 包装分配器。
+#[cfg(not(feature = "strict-single-thread"))]
+unsafe impl<const CELL: usize, S> Sync for BitmapAllocator<CELL, S> {}
+
+// Safety: No one else owns the raw pointers (conceptually), logic is same.
+unsafe impl<const CELL: usize, S: Send> Send for BitmapAllocator<CELL, S> {}
+
+const WORD_BITS: usize = usize::BITS as usize;
+
+/// Allocator handing out fixed `CELL`-byte cells, tracked by one bit each.
+///
+/// Every page starts with a small header holding a bitmap of its cells; the cells fill
+/// the rest of the page. Nothing is ever written into a cell, allocated or free, so the
+/// whole cell is usable and freed memory is left untouched. Allocation scans the page
+/// under a cursor from its lowest possibly-free word (O(1) while the page has room);
+/// deallocation clears one bit in O(1) and points the cursor at that page, so freed
+/// cells are reused first. Requests larger than `CELL`, or aligned beyond what the cell
+/// spacing guarantees, get a null pointer.
+///
+/// 分配固定 `CELL` 字节单元的分配器，每个单元由一个位跟踪。
+///
+/// 每页以一个保存其单元位图的小头部开始，单元填满页面的其余部分。
+/// 无论单元已分配还是空闲，都不会向其中写入任何内容，因此整个单元都可用，且释放的内存保持不变。
+/// 分配时从游标所在页面中可能空闲的最低字开始扫描（页面有空位时为 O(1)）；
+/// 释放时以 O(1) 清除一个位，并将游标指向该页，使释放的单元被优先复用。
+/// 大于 `CELL` 的请求，或对齐要求超过单元间距所能保证的对齐的请求，将得到空指针。
+pub struct BitmapAllocator<const CELL: usize, S = DefaultMemory> {
+    // Every page obtained so far, linked through their headers
+    // 目前获取的所有页面，通过其头部链接
+    pages: UnsafeCell<*mut PageHeader>,
+    // Page tried first by the next allocation
+    // 下一次分配首先尝试的页面
+    cursor: UnsafeCell<*mut PageHeader>,
+    source: S,
+    oom_hook: OomHook,
+}
+
+// Start of every page; the bitmap words follow it (set bits are allocated cells)
+// 每页的起始处；位图字紧随其后（置位表示已分配的单元）
+struct PageHeader {
+    next: *mut PageHeader,
+    free: usize,
+    // No word below this one has a clear bit
+    // 此字之前的字都没有清零的位
+    hint: usize,
+}
+
+impl<const CELL: usize> BitmapAllocator<CELL> {
+    pub const fn new() -> Self {
+        Self::with_source(DefaultMemory)
+    }
+}
+
+impl<const CELL: usize, S> BitmapAllocator<CELL, S> {
+    /// Alignment every cell is guaranteed to have.
+    ///
+    /// 每个单元保证具有的对齐。
+    pub const CELL_ALIGN: usize = {
+        // Lowest set bit of CELL
+        // CELL 的最低置位
+        let align = CELL & CELL.wrapping_neg();
+        if align > PAGE_SIZE { PAGE_SIZE } else { align }
+    };
+
+    // Bitmap words needed for a page made only of cells, an upper bound
+    // 整页都是单元时所需的位图字数，作为上界
+    const WORDS: usize = (PAGE_SIZE / CELL).div_ceil(WORD_BITS);
+
+    // Offset of the first cell: past the header and bitmap, on a cell boundary
+    // 第一个单元的偏移：位于头部与位图之后，并落在单元边界上
+    const FIRST_CELL: usize = (core::mem::size_of::<PageHeader>()
+        + Self::WORDS * core::mem::size_of::<usize>())
+    .next_multiple_of(CELL);
+
+    /// Number of cells in each page.
+    ///
+    /// 每页中的单元数。
+    pub const CELLS_PER_PAGE: usize = (PAGE_SIZE - Self::FIRST_CELL) / CELL;
+
+    /// Create an allocator that obtains its pages from `source`.
+    ///
+    /// # Panics
+    /// If `CELL` is zero or a page cannot hold its header and at least one cell.
+    ///
+    /// 创建一个从 `source` 获取页面的分配器。
+    ///
+    /// # Panics
+    /// 如果 `CELL` 为零，或一页无法容纳其头部与至少一个单元。
+    pub const fn with_source(source: S) -> Self {
+        assert!(
+            CELL > 0 && CELL < PAGE_SIZE && Self::FIRST_CELL < PAGE_SIZE,
+            "CELL must leave room for the page header and at least one cell"
+        );
+        BitmapAllocator {
+            pages: UnsafeCell::new(null_mut()),
+            cursor: UnsafeCell::new(null_mut()),
+            source,
+            oom_hook: OomHook::new(),
+        }
+    }
+
+    /// Register a hook called with the failing `Layout` right before `alloc` returns null.
+    /// Pass `None` to remove it.
+    ///
+    /// 注册一个回调，在 `alloc` 返回 null 之前以失败的 `Layout` 调用。
+    /// 传入 `None` 以移除。
+    pub fn set_oom_hook(&self, hook: Option<fn(Layout)>) {
+        self.oom_hook.set(hook);
+    }
+
+    /// Testing only: Reset the internal state.
+    /// Pages already obtained from the memory source are not returned.
+    ///
+    /// # Safety
+    /// Usage is inherently unsafe if allocator is in use.
+    ///
+    /// 仅测试用：重置内部状态。
+    /// 已从内存源获取的页面不会被归还。
+    ///
+    /// # Safety
+    /// 如果分配器正在使用，未定义的行为。
+    pub unsafe fn reset(&self) {
+        unsafe {
+            *self.pages.get() = null_mut();
+            *self.cursor.get() = null_mut();
+        }
+    }
+
+    /// Number of usable bytes in the block at `ptr`: always the whole cell.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`.
+    ///
+    /// `ptr` 处的块中的可用字节数：总是整个单元。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配。
+    pub unsafe fn usable_size(&self, _ptr: *mut u8, _layout: Layout) -> usize {
+        CELL
+    }
+
+    /// Number of cells currently allocated, over all pages. O(pages).
+    ///
+    /// # Safety
+    /// Must not race with an allocation on this allocator.
+    ///
+    /// 所有页面中当前已分配的单元数。O(页数)。
+    ///
+    /// # Safety
+    /// 不得与此分配器上的分配同时进行。
+    pub unsafe fn live_cells(&self) -> usize {
+        let mut live = 0;
+        let mut page = unsafe { *self.pages.get() };
+        while !page.is_null() {
+            unsafe {
+                live += Self::CELLS_PER_PAGE - (*page).free;
+                page = (*page).next;
+            }
+        }
+        live
+    }
+
+    /// Whether a cell can serve `layout`.
+    ///
+    /// 单元能否服务 `layout`。
+    #[inline(always)]
+    fn fits(layout: Layout) -> bool {
+        layout.size() <= CELL && layout.align() <= Self::CELL_ALIGN
+    }
+
+    /// Bitmap words of `page`.
+    ///
+    /// `page` 的位图字。
+    #[inline(always)]
+    unsafe fn bits(page: *mut PageHeader) -> *mut usize {
+        unsafe { page.add(1).cast() }
+    }
+
+    /// Take the lowest free cell of `page`, which must have one.
+    ///
+    /// 取出 `page` 中最低的空闲单元；该页必须有空闲单元。
+    unsafe fn take_cell(page: *mut PageHeader) -> *mut u8 {
+        unsafe {
+            let bits = Self::bits(page);
+            let mut word = (*page).hint;
+            while *bits.add(word) == usize::MAX {
+                word += 1;
+            }
+            let bit = (*bits.add(word)).trailing_ones() as usize;
+            *bits.add(word) |= 1 << bit;
+            (*page).free -= 1;
+            (*page).hint = word;
+            (page as *mut u8).add(Self::FIRST_CELL + (word * WORD_BITS + bit) * CELL)
+        }
+    }
+}
+
+impl<const CELL: usize> Default for BitmapAllocator<CELL> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const CELL: usize, S: MemorySource> BitmapAllocator<CELL, S> {
+    /// Allocate like `GlobalAlloc::alloc`, also returning the capacity of the block:
+    /// the whole cell, always `>= layout.size()`.
+    /// The caller may use all of it, and may later free or resize the block with the
+    /// same `layout`. On failure, returns `(null, 0)`.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::alloc`.
+    ///
+    /// 与 `GlobalAlloc::alloc` 一样分配，并同时返回块的容量：
+    /// 整个单元，总是 `>= layout.size()`。
+    /// 调用者可以使用全部容量，之后仍以同一 `layout` 释放或调整该块。失败时返回 `(null, 0)`。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::alloc` 的约定相同。
+    pub unsafe fn alloc_with_capacity(&self, layout: Layout) -> (*mut u8, usize) {
+        let ptr = unsafe { self.alloc(layout) };
+        if ptr.is_null() {
+            return (ptr, 0);
+        }
+        (ptr, unsafe { self.usable_size(ptr, layout) })
+    }
+
+    /// Grow the heap up front so that free cells totalling at least `bytes` bytes are
+    /// available, e.g. before a latency-sensitive phase. Nothing is grown if enough cells
+    /// are already free. Returns `false` on OOM.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 预先增长堆，使空闲单元合计至少 `bytes` 字节，例如在对延迟敏感的阶段之前。
+    /// 如果已有足够的空闲单元，则不会增长。OOM 时返回 `false`。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn reserve(&self, bytes: usize) -> bool {
+        let mut free = 0;
+        let mut page = unsafe { *self.pages.get() };
+        while !page.is_null() {
+            unsafe {
+                free += (*page).free;
+                page = (*page).next;
+            }
+        }
+        let cells = bytes.div_ceil(CELL);
+        if free >= cells {
+            return true;
+        }
+        let pages = (cells - free).div_ceil(Self::CELLS_PER_PAGE);
+        let Some(start) = (unsafe { grow_region(&self.source, pages) }) else {
+            return false;
+        };
+        for index in 0..pages {
+            unsafe { self.add_page((start + index * PAGE_SIZE) as *mut PageHeader) };
+        }
+        true
+    }
+
+    /// Find a page with a free cell: the cursor, any other page, or a freshly grown one.
+    ///
+    /// 查找一个有空闲单元的页面：游标所在页、其他任一页面，或新增长的页面。
+    unsafe fn page_with_room(&self) -> *mut PageHeader {
+        unsafe {
+            let cursor = *self.cursor.get();
+            if !cursor.is_null() && (*cursor).free > 0 {
+                return cursor;
+            }
+            let mut page = *self.pages.get();
+            while !page.is_null() && (*page).free == 0 {
+                page = (*page).next;
+            }
+            if page.is_null() {
+                let Some(start) = grow_region(&self.source, 1) else {
+                    return null_mut();
+                };
+                page = start as *mut PageHeader;
+                self.add_page(page);
+            }
+            *self.cursor.get() = page;
+            page
+        }
+    }
+
+    /// Set up the freshly grown page at `page`, with all its cells free, and link it in.
+    ///
+    /// 初始化位于 `page` 的新增长页面（其所有单元均空闲），并将其链接进来。
+    unsafe fn add_page(&self, page: *mut PageHeader) {
+        unsafe {
+            // Bits past the last cell stay set, so scans never hand them out
+            // 最后一个单元之后的位保持置位，使扫描永远不会将其分配出去
+            let bits = Self::bits(page);
+            ptr::write_bytes(bits, 0, Self::WORDS);
+            for index in Self::CELLS_PER_PAGE..Self::WORDS * WORD_BITS {
+                *bits.add(index / WORD_BITS) |= 1 << (index % WORD_BITS);
+            }
+            page.write(PageHeader {
+                next: *self.pages.get(),
+                free: Self::CELLS_PER_PAGE,
+                hint: 0,
+            });
+            *self.pages.get() = page;
+        }
+    }
+}
+
+unsafe impl<const CELL: usize, S: MemorySource> GlobalAlloc for BitmapAllocator<CELL, S> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if Self::fits(layout) {
+            let page = unsafe { self.page_with_room() };
+            if !page.is_null() {
+                return unsafe { Self::take_cell(page) };
+            }
+        }
+        self.oom_hook.fire(layout);
+        null_mut()
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, _layout: Layout) {
+        unsafe {
+            // Pages are page-aligned: the header sits at the start of the cell's page
+            // 页面按页对齐：头部位于单元所在页的起始处
+            let page = (ptr as usize & !(PAGE_SIZE - 1)) as *mut PageHeader;
+            let index = (ptr as usize - page as usize - Self::FIRST_CELL) / CELL;
+            let (word, bit) = (index / WORD_BITS, index % WORD_BITS);
+            let bits = Self::bits(page);
+            #[cfg(feature = "dealloc-check")]
+            debug_assert!(
+                *bits.add(word) & (1 << bit) != 0,
+                "dealloc of a free cell (double free?): {ptr:p}"
+            );
+            *bits.add(word) &= !(1 << bit);
+            (*page).free += 1;
+            (*page).hint = (*page).hint.min(word);
+            *self.cursor.get() = page;
+        }
+    }
+
+    // Every cell already spans CELL bytes: resizing within it never moves the block
+    // 每个单元都已占据 CELL 字节：在其范围内调整大小永远不需要移动块
+    #[cfg(feature = "realloc")]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size <= CELL {
+            ptr
+        } else {
+            self.oom_hook
+                .fire(unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) });
+            null_mut()
+        }
+    }
+}
+
+impl<const CELL: usize, S> ResettableAllocator for BitmapAllocator<CELL, S> {
+    unsafe fn reset(&self) {
+        unsafe { BitmapAllocator::reset(self) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_layout() {
+        type Cells16 = BitmapAllocator<16>;
+        // 4096 cells fit a bare page: 64 bitmap words behind a 3-word header
+        assert_eq!(Cells16::WORDS, PAGE_SIZE / 16 / WORD_BITS);
+        assert_eq!(Cells16::FIRST_CELL % 16, 0);
+        assert_eq!(
+            Cells16::CELLS_PER_PAGE,
+            (PAGE_SIZE - Cells16::FIRST_CELL) / 16
+        );
+        // Odd cell sizes still leave the cells aligned to their lowest set bit
+        assert_eq!(BitmapAllocator::<24>::CELL_ALIGN, 8);
+        assert_eq!(BitmapAllocator::<24>::FIRST_CELL % 24, 0);
+    }
+}
@@ -0,0 +1,388 @@
+#[cfg(feature = "realloc")]
+use crate::layout_math::copy_span;
+use crate::{
+    DefaultMemory, MemorySource, OomHook, PAGE_SIZE, PageCount, ResettableAllocator, grow_region,
+};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::UnsafeCell,
+    ptr::null_mut,
+};
+
+/// Safety Warning:
+/// Allocators in this module are designed for [Single Threaded] environments.
+/// `Sync` is implemented only to satisfy `GlobalAlloc` trait requirements.
+/// Using this allocator in a multi-threaded environment will lead to Undefined Behavior (UB).
+/// Please ensure it is used only in single-threaded environments (e.g., WASM or single-threaded embedded).
+///
+/// 安全性警示 (Safety Warning):
+/// 本模块中的分配器均为【单线程】设计。
+/// 实现了 `Sync` 仅为了满足 `GlobalAlloc` trait 的要求。
+/// 在多线程环境中使用此分配器会导致未定义行为 (UB)。
+/// 请确保只在单线程环境（如 WASM 或单线程嵌入式环境）中使用。
+///
+/// With `strict-single-thread` this impl is left out: wrap the allocator in
+/// [`GlobalWrapper`](super::GlobalWrapper) at the `#[global_allocator]` site instead.
+///
+/// 开启 `strict-single-thread` 时不提供此实现：
+/// 请改为在 `#[global_allocator]` 处用 [`GlobalWrapper`](super::GlobalWrapper) 包装分配器。
+#[cfg(not(feature = "strict-single-thread"))]
+unsafe impl<S> Sync for BuddyAllocator<S> {}
+
+// Safety: No one else owns the raw pointers (conceptually), logic is same.
+unsafe impl<S: Send> Send for BuddyAllocator<S> {}
+
+// Smallest block: 16 bytes, enough for a free-list link and the usual 16-byte alignment
+// 最小块：16 字节，足以容纳空闲链表链接，并满足常规的 16 字节对齐
+const MIN_ORDER: u32 = 4;
+const ORDERS: usize = usize::BITS as usize;
+
+/// Binary buddy allocator: every block is a power of two in size and aligned to its size.
+///
+/// The order of a block is derived from its `Layout` alone, so blocks carry no header.
+/// Allocation splits a larger free block in halves down to the requested order;
+/// deallocation merges a block with its buddy (the other half of the same parent) for
+/// as long as the buddy is free. Any alignment up to the block size is satisfied for
+/// free, at the cost of rounding every request up to the next power of two.
+///
+/// Free blocks of each order sit in a LIFO list; finding the buddy on `dealloc` walks
+/// the list of that order.
+///
+/// 二进制伙伴分配器：每个块的大小都是 2 的幂，并按其大小对齐。
+///
+/// 块的阶数仅由其 `Layout` 推出，因此块不带头部。分配时将更大的空闲块逐次对半切分到
+/// 所需的阶数；释放时只要伙伴（同一父块的另一半）空闲，就不断与其合并。
+/// 不超过块大小的任意对齐都可免费满足，代价是每个请求都要向上取整到下一个 2 的幂。
+///
+/// 每个阶数的空闲块位于一个 LIFO 链表中；`dealloc` 时查找伙伴需要遍历该阶数的链表。
+pub struct BuddyAllocator<S = DefaultMemory> {
+    // Free blocks of size `1 << order`, indexed by order
+    // 大小为 `1 << order` 的空闲块，按阶数索引
+    free_lists: UnsafeCell<[*mut Node; ORDERS]>,
+    source: S,
+    oom_hook: OomHook,
+}
+
+struct Node {
+    next: *mut Node,
+}
+
+impl BuddyAllocator {
+    pub const fn new() -> Self {
+        Self::with_source(DefaultMemory)
+    }
+}
+
+impl Default for BuddyAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> BuddyAllocator<S> {
+    /// Create an allocator that obtains its pages from `source`.
+    ///
+    /// 创建一个从 `source` 获取页面的分配器。
+    pub const fn with_source(source: S) -> Self {
+        BuddyAllocator {
+            free_lists: UnsafeCell::new([null_mut(); ORDERS]),
+            source,
+            oom_hook: OomHook::new(),
+        }
+    }
+
+    /// Register a hook called with the failing `Layout` right before `alloc` returns null.
+    /// Pass `None` to remove it.
+    ///
+    /// 注册一个回调，在 `alloc` 返回 null 之前以失败的 `Layout` 调用。
+    /// 传入 `None` 以移除。
+    pub fn set_oom_hook(&self, hook: Option<fn(Layout)>) {
+        self.oom_hook.set(hook);
+    }
+
+    /// Testing only: Reset the internal state.
+    /// Pages already obtained from the memory source are not returned.
+    ///
+    /// # Safety
+    /// Usage is inherently unsafe if allocator is in use.
+    ///
+    /// 仅测试用：重置内部状态。
+    /// 已从内存源获取的页面不会被归还。
+    ///
+    /// # Safety
+    /// 如果分配器正在使用，未定义的行为。
+    pub unsafe fn reset(&self) {
+        unsafe { *self.free_lists.get() = [null_mut(); ORDERS] };
+    }
+
+    /// Number of usable bytes in the block at `ptr`: the whole power-of-two block.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`.
+    ///
+    /// `ptr` 处的块中的可用字节数：整个 2 的幂大小的块。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配。
+    pub unsafe fn usable_size(&self, _ptr: *mut u8, layout: Layout) -> usize {
+        order_of(layout).map_or(0, |order| 1 << order)
+    }
+
+    /// Push the block at `addr` onto the list of `order`.
+    ///
+    /// 将 `addr` 处的块压入 `order` 的链表。
+    unsafe fn push(&self, addr: usize, order: u32) {
+        unsafe {
+            let head = &mut (*self.free_lists.get())[order as usize];
+            let node = addr as *mut Node;
+            (*node).next = *head;
+            *head = node;
+        }
+    }
+
+    /// Unlink the block at `addr` from the list of `order`, if it is there.
+    ///
+    /// 如果 `addr` 处的块在 `order` 的链表中，则将其移除。
+    unsafe fn take(&self, addr: usize, order: u32) -> bool {
+        unsafe {
+            let mut link: *mut *mut Node = &mut (*self.free_lists.get())[order as usize];
+            while !(*link).is_null() {
+                if *link as usize == addr {
+                    *link = (**link).next;
+                    return true;
+                }
+                link = &mut (**link).next;
+            }
+            false
+        }
+    }
+
+    /// Free the block at `addr`, merging it with its buddy as far up as possible.
+    ///
+    /// 释放 `addr` 处的块，并尽可能向上与其伙伴合并。
+    unsafe fn release(&self, mut addr: usize, mut order: u32) {
+        unsafe {
+            while (order as usize) < ORDERS - 1 && self.take(addr ^ (1 << order), order) {
+                addr &= !(1 << order);
+                order += 1;
+            }
+            self.push(addr, order);
+        }
+    }
+
+    /// Free `[start, end)` as the largest naturally aligned blocks covering it.
+    ///
+    /// 将 `[start, end)` 作为覆盖它的最大自然对齐块释放。
+    unsafe fn release_range(&self, mut start: usize, end: usize) {
+        while start < end {
+            let order = start
+                .trailing_zeros()
+                .min(usize::BITS - 1 - (end - start).leading_zeros());
+            unsafe { self.release(start, order) };
+            start += 1 << order;
+        }
+    }
+}
+
+impl<S: MemorySource> BuddyAllocator<S> {
+    /// Allocate like `GlobalAlloc::alloc`, also returning the capacity of the block:
+    /// the whole power-of-two block, always `>= layout.size()`.
+    /// The caller may use all of it, and may later free or resize the block with the
+    /// same `layout`. On failure, returns `(null, 0)`.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::alloc`.
+    ///
+    /// 与 `GlobalAlloc::alloc` 一样分配，并同时返回块的容量：
+    /// 整个 2 的幂大小的块，总是 `>= layout.size()`。
+    /// 调用者可以使用全部容量，之后仍以同一 `layout` 释放或调整该块。失败时返回 `(null, 0)`。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::alloc` 的约定相同。
+    pub unsafe fn alloc_with_capacity(&self, layout: Layout) -> (*mut u8, usize) {
+        let ptr = unsafe { self.alloc(layout) };
+        if ptr.is_null() {
+            return (ptr, 0);
+        }
+        (ptr, unsafe { self.usable_size(ptr, layout) })
+    }
+
+    /// Grow the heap up front so that a free block of at least `bytes` bytes is available,
+    /// e.g. before a latency-sensitive phase. Nothing is grown if such a block is already
+    /// free. Returns `false` on OOM.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 预先增长堆，使至少有一个 `bytes` 字节的空闲块可用，例如在对延迟敏感的阶段之前。
+    /// 如果已有这样的空闲块，则不会增长。OOM 时返回 `false`。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn reserve(&self, bytes: usize) -> bool {
+        let Some(order) = Layout::from_size_align(bytes, 1).ok().and_then(order_of) else {
+            return false;
+        };
+        unsafe {
+            loop {
+                let lists = &*self.free_lists.get();
+                if (order as usize..ORDERS).any(|o| !lists[o].is_null()) {
+                    return true;
+                }
+                if !self.grow(order) {
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Grow the source so that a free block of `order` exists.
+    /// Pages that do not line up with that size are freed as smaller blocks.
+    ///
+    /// 增长内存源，使一个 `order` 阶的空闲块存在。
+    /// 未能按该大小对齐的页面会作为更小的块被释放。
+    unsafe fn grow(&self, order: u32) -> bool {
+        let size = 1usize << order;
+        let pages = PageCount::from_bytes(size).as_usize();
+        let Some(start) = (unsafe { grow_region(&self.source, pages) }) else {
+            return false;
+        };
+        let mut end = start + pages * PAGE_SIZE;
+        unsafe { self.release_range(start, end) };
+
+        // Pages only come page-aligned: extend the range until an aligned block fits
+        // 页面只保证按页对齐：扩展区域直到能容纳一个对齐的块
+        let aligned = start.next_multiple_of(size);
+        if aligned + size > end {
+            let extra = (aligned + size - end) / PAGE_SIZE;
+            let Some(more) = (unsafe { grow_region(&self.source, extra) }) else {
+                return false;
+            };
+            // Pages not following ours are still kept; the caller simply tries again
+            // 不紧接在我们之后的页面同样保留；调用方只需再试一次
+            if more == end {
+                end += extra * PAGE_SIZE;
+                unsafe { self.release_range(more, end) };
+            } else {
+                unsafe { self.release_range(more, more + extra * PAGE_SIZE) };
+            }
+        }
+        true
+    }
+
+    /// Allocation logic behind `GlobalAlloc::alloc`, without the OOM hook.
+    ///
+    /// `GlobalAlloc::alloc` 背后的分配逻辑，不包含 OOM 回调。
+    unsafe fn alloc_block(&self, layout: Layout) -> *mut u8 {
+        let Some(order) = order_of(layout) else {
+            return null_mut();
+        };
+        unsafe {
+            loop {
+                // Smallest non-empty list at or above the requested order
+                // 不低于所需阶数的最小非空链表
+                let lists = &mut *self.free_lists.get();
+                if let Some(found) = (order as usize..ORDERS).find(|&o| !lists[o].is_null()) {
+                    let block = lists[found];
+                    lists[found] = (*block).next;
+                    // Split down, returning the upper halves
+                    // 向下切分，归还上半部分
+                    for o in (order..found as u32).rev() {
+                        self.push(block as usize + (1 << o), o);
+                    }
+                    return block as *mut u8;
+                }
+                if !self.grow(order) {
+                    return null_mut();
+                }
+            }
+        }
+    }
+}
+
+unsafe impl<S: MemorySource> GlobalAlloc for BuddyAllocator<S> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.alloc_block(layout) };
+        if ptr.is_null() {
+            self.oom_hook.fire(layout);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let Some(order) = order_of(layout) else {
+            return;
+        };
+        #[cfg(feature = "dealloc-check")]
+        debug_assert!(
+            (ptr as usize).is_multiple_of(1 << order),
+            "dealloc of a misaligned pointer: {ptr:p}"
+        );
+        unsafe { self.release(ptr as usize, order) };
+    }
+
+    #[cfg(feature = "realloc")]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+        let (Some(old), Some(new)) = (order_of(layout), order_of(new_layout)) else {
+            return null_mut();
+        };
+
+        // 1. Same or smaller block: keep it, freeing the upper halves it no longer needs
+        // 1. 块大小相同或更小：保留它，释放不再需要的上半部分
+        if new <= old {
+            for o in new..old {
+                unsafe { self.push(ptr as usize + (1 << o), o) };
+            }
+            return ptr;
+        }
+
+        // 2. Fallback: Alloc + Copy + Dealloc
+        // 2. 回退：Alloc + Copy + Dealloc
+        unsafe {
+            let new_ptr = self.alloc(new_layout);
+            if !new_ptr.is_null() {
+                core::ptr::copy_nonoverlapping(ptr, new_ptr, copy_span(layout, new_size));
+                self.dealloc(ptr, layout);
+            }
+            new_ptr
+        }
+    }
+}
+
+impl<S> ResettableAllocator for BuddyAllocator<S> {
+    unsafe fn reset(&self) {
+        unsafe { BuddyAllocator::reset(self) }
+    }
+}
+
+/// Order of the block serving `layout`: its size and alignment rounded up to a power of
+/// two, at least `1 << MIN_ORDER`. `None` if that overflows.
+///
+/// 服务 `layout` 的块的阶数：其大小与对齐向上取整到 2 的幂，至少为 `1 << MIN_ORDER`。
+/// 溢出时返回 `None`。
+#[inline(always)]
+fn order_of(layout: Layout) -> Option<u32> {
+    let size = layout
+        .size()
+        .max(layout.align())
+        .checked_next_power_of_two()?;
+    Some(size.trailing_zeros().max(MIN_ORDER))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_of_layout() {
+        let order = |size, align| order_of(Layout::from_size_align(size, align).unwrap());
+        assert_eq!(order(0, 1), Some(MIN_ORDER));
+        assert_eq!(order(16, 8), Some(4));
+        assert_eq!(order(17, 8), Some(5));
+        assert_eq!(order(100, 16), Some(7));
+        // Alignment larger than the size decides the block size
+        assert_eq!(order(8, 4096), Some(12));
+        assert_eq!(order(isize::MAX as usize, 1), Some(usize::BITS - 1));
+    }
+}
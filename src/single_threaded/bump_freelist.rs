@@ -1,7 +1,23 @@
-use crate::{PAGE_SIZE, grow_memory};
+use super::scope::{ScopedAllocator, rewound, sealed};
+#[cfg(feature = "stats")]
+use crate::HighWater;
+#[cfg(feature = "quota")]
+use crate::Quota;
+#[cfg(feature = "realloc")]
+use crate::layout_math::copy_span;
+#[cfg(feature = "poison")]
+use crate::poison;
+use crate::{
+    AllocError, DefaultMemory, GrowthPolicy, MemorySource, OomHook, PAGE_SIZE, PageCount,
+    ResettableAllocator, bump_pages_needed, bump_range, dangling, grow_amortized, grow_region,
+};
+#[cfg(feature = "realloc")]
+use crate::{realloc_zero_sized, realloc_zero_tail};
 use core::{
     alloc::{GlobalAlloc, Layout},
-    ptr::{self, null_mut},
+    cell::UnsafeCell,
+    fmt,
+    ptr::{self, NonNull, null_mut},
 };
 
 /// Safety Warning:
@@ -15,7 +31,17 @@ use core::{
 /// 实现了 `Sync` 仅为了满足 `GlobalAlloc` trait 的要求。
 /// 在多线程环境中使用此分配器会导致未定义行为 (UB)。
 /// 请确保只在单线程环境（如 WASM 或单线程嵌入式环境）中使用。
-unsafe impl Sync for BumpFreeListAllocator {}
+///
+/// With `strict-single-thread` this impl is left out: wrap the allocator in
+/// [`GlobalWrapper`](super::GlobalWrapper) at the `#[global_allocator]` site instead.
+///
+/// 开启 `strict-single-thread` 时不提供此实现：
+/// 请改为在 `#[global_allocator]` 处用 [`GlobalWrapper`](super::GlobalWrapper) 包装分配器。
+#[cfg(not(feature = "strict-single-thread"))]
+unsafe impl<S, const MIN_ALIGN: usize> Sync for BumpFreeListAllocator<S, MIN_ALIGN> {}
+
+// Safety: No one else owns the raw pointers (conceptually), logic is same.
+unsafe impl<S: Send, const MIN_ALIGN: usize> Send for BumpFreeListAllocator<S, MIN_ALIGN> {}
 
 /// Minimal Bump Pointer + Unordered Free List Allocator.
 ///
@@ -25,19 +51,350 @@ unsafe impl Sync for BumpFreeListAllocator {}
 /// - **Extreme Size**: Removes binning and merging logic to minimize code size.
 /// - **Fast Startup**: No initialization overhead.
 /// - **Fragmentation**: Does not merge memory, long-running processes will cause OOM. Only suitable for short-lived tasks.
+///   With the `coalesce` feature, freeing the block at the heap top rolls the bump pointer back.
+/// - **Canary**: With the `canary` feature, a magic value is written right after each allocation
+///   and checked on `dealloc`/`realloc`, catching one-past-the-end writes in debug builds.
+/// - **Address-ordered reuse**: With the `address-ordered` feature, the free list is kept sorted
+///   by address (O(N) insert), so reuse picks the lowest suitable block and layouts are reproducible.
 ///
 /// # 特性
 /// - **极致体积**：移除分箱和合并逻辑，代码量最小化。
 /// - **快速启动**：无初始化开销。
 /// - **碎片化**：不合并内存，长期运行会导致 OOM。仅适用于短生命周期任务。
-pub struct BumpFreeListAllocator;
+///   开启 `coalesce` feature 后，释放位于堆顶的块会回退 bump 指针。
+/// - **金丝雀**：开启 `canary` feature 后，每次分配之后紧接着写入一个魔数，
+///   并在 `dealloc`/`realloc` 时检查，从而在调试构建中捕获越界一字节的写入。
+/// - **按地址复用**：开启 `address-ordered` feature 后，空闲链表按地址排序（插入为 O(N)），
+///   复用时选择地址最低的合适块，使内存布局可复现。
+pub struct BumpFreeListAllocator<S = DefaultMemory, const MIN_ALIGN: usize = 16> {
+    // Single free list head, unordered (sorted by ascending address with `address-ordered`)
+    // 单个空闲链表头，无序（开启 `address-ordered` 时按地址升序排列）
+    free_list: UnsafeCell<*mut Node>,
+    // Bump Pointer State
+    // Bump Pointer 状态
+    heap_top: UnsafeCell<usize>,
+    heap_end: UnsafeCell<usize>,
+    // Start of the current bump region, used by `Scope` to rewind
+    // 当前 Bump 区域的起始地址，供 `Scope` 回退使用
+    heap_start: UnsafeCell<usize>,
+    // Start of the first region ever grown: the lower bound of `heap_extent` and of the
+    // `dealloc-check` checks
+    // 首个增长区域的起始地址：`heap_extent` 以及 `dealloc-check` 检查的下界
+    heap_base: UnsafeCell<usize>,
+    source: S,
+    // How many pages each `grow_memory` call requests
+    // 每次 `grow_memory` 调用申请的页数策略
+    growth: GrowthPolicy,
+    oom_hook: OomHook,
+    #[cfg(feature = "quota")]
+    quota: Quota,
+    #[cfg(feature = "stats")]
+    high_water: HighWater,
+}
 
 impl BumpFreeListAllocator {
     pub const fn new() -> Self {
-        Self
+        Self::with_source(DefaultMemory)
+    }
+
+    /// Create an allocator whose first allocation grows memory by `pages` pages at once
+    /// (or more if the request needs them), instead of page by page.
+    ///
+    /// 创建一个分配器，其首次分配一次性增长 `pages` 页内存（如果请求需要更多则增长更多），
+    /// 而不是逐页增长。
+    pub const fn with_initial_pages(pages: PageCount) -> Self {
+        Self::new().with_growth(GrowthPolicy {
+            initial_pages: pages,
+            divisor: 0,
+        })
+    }
+}
+
+impl<S> BumpFreeListAllocator<S> {
+    /// Create an allocator that obtains its pages from `source`.
+    ///
+    /// 创建一个从 `source` 获取页面的分配器。
+    pub const fn with_source(source: S) -> Self {
+        Self::with_min_align(source)
     }
 }
 
+impl<S, const MIN_ALIGN: usize> BumpFreeListAllocator<S, MIN_ALIGN> {
+    /// Create an allocator whose blocks are aligned to, and sized in multiples of,
+    /// `MIN_ALIGN` (16 by default): e.g.
+    /// `BumpFreeListAllocator::<DefaultMemory, 8>::with_min_align(DefaultMemory)`.
+    ///
+    /// Blocks are never smaller than their two-word free-list node, so `MIN_ALIGN` only has
+    /// to keep that node's words aligned: the alignment of `usize` is enough.
+    ///
+    /// # Panics
+    /// If `MIN_ALIGN` is not a power of two or is smaller than the alignment of `usize`.
+    ///
+    /// 创建一个块按 `MIN_ALIGN`（默认 16）对齐且大小为其倍数的分配器，例如
+    /// `BumpFreeListAllocator::<DefaultMemory, 8>::with_min_align(DefaultMemory)`。
+    ///
+    /// 块永远不会小于其两个字的空闲链表节点，因此 `MIN_ALIGN` 只需保证该节点的各个字对齐：
+    /// `usize` 的对齐就足够了。
+    ///
+    /// # Panics
+    /// 如果 `MIN_ALIGN` 不是 2 的幂，或小于 `usize` 的对齐。
+    pub const fn with_min_align(source: S) -> Self {
+        assert!(
+            MIN_ALIGN.is_power_of_two() && MIN_ALIGN >= core::mem::align_of::<usize>(),
+            "MIN_ALIGN must be a power of two >= align_of::<usize>()"
+        );
+        Self {
+            free_list: UnsafeCell::new(null_mut()),
+            heap_top: UnsafeCell::new(0),
+            heap_end: UnsafeCell::new(0),
+            heap_start: UnsafeCell::new(0),
+            heap_base: UnsafeCell::new(0),
+            source,
+            growth: GrowthPolicy::EXACT,
+            oom_hook: OomHook::new(),
+            #[cfg(feature = "quota")]
+            quota: Quota::new(),
+            #[cfg(feature = "stats")]
+            high_water: HighWater::new(),
+        }
+    }
+
+    /// Testing only: Reset the internal state.
+    /// Pages already obtained from the memory source are not returned.
+    ///
+    /// # Safety
+    /// Usage is inherently unsafe if allocator is in use.
+    ///
+    /// 仅测试用：重置内部状态。
+    /// 已从内存源获取的页面不会被归还。
+    ///
+    /// # Safety
+    /// 如果分配器正在使用，未定义的行为。
+    pub unsafe fn reset(&self) {
+        unsafe {
+            *self.free_list.get() = null_mut();
+            *self.heap_top.get() = 0;
+            *self.heap_end.get() = 0;
+            *self.heap_start.get() = 0;
+            *self.heap_base.get() = 0;
+        }
+        #[cfg(feature = "quota")]
+        self.quota.clear();
+        #[cfg(feature = "stats")]
+        self.high_water.clear();
+    }
+
+    /// Deallocate everything at once while keeping the pages: the bump pointer goes back to
+    /// the start of the current region and free blocks inside it are dropped, so the next
+    /// allocations reuse the same memory. Host memory is not touched.
+    ///
+    /// Only the current region is reclaimed; if the memory source ever returned pages that
+    /// do not follow the previous ones, free blocks in earlier regions stay on the free list
+    /// and their bump tails are not reclaimed.
+    ///
+    /// # Safety
+    /// All outstanding pointers from this allocator are invalidated.
+    ///
+    /// 保留页面的同时一次性释放所有内存：Bump 指针回到当前区域的起始位置，并丢弃其中的空闲块，
+    /// 使之后的分配复用同一块内存。不会触碰宿主内存。
+    ///
+    /// 只回收当前区域；如果内存来源曾返回不紧接前一段的页面，更早区域中的空闲块仍留在
+    /// 空闲链表上，而它们未使用的 Bump 尾部不会被回收。
+    ///
+    /// # Safety
+    /// 此分配器分配出去的所有指针都将失效。
+    pub unsafe fn clear(&self) {
+        // A mark taken before the first allocation rolls back to the start of any region
+        // 在首次分配之前获取的标记会回退到任一区域的起始位置
+        unsafe { sealed::Sealed::rewind(self, (0, 0)) };
+        #[cfg(feature = "quota")]
+        self.quota.clear();
+    }
+
+    /// Use `policy` to size this allocator's memory growth, e.g.
+    /// `GrowthPolicy { initial_pages: PageCount(64), divisor: 2 }` reserves 4 MiB up front
+    /// and then grows by at least half of the current region.
+    ///
+    /// 使用 `policy` 决定此分配器的内存增长大小，例如
+    /// `GrowthPolicy { initial_pages: PageCount(64), divisor: 2 }` 预先保留 4 MiB，
+    /// 之后每次至少增长当前区域的一半。
+    pub const fn with_growth(mut self, policy: GrowthPolicy) -> Self {
+        self.growth = policy;
+        self
+    }
+
+    /// Register a hook called with the failing `Layout` right before `alloc` returns null.
+    /// Pass `None` to remove it.
+    ///
+    /// 注册一个回调，在 `alloc` 返回 null 之前以失败的 `Layout` 调用。
+    /// 传入 `None` 以移除。
+    pub fn set_oom_hook(&self, hook: Option<fn(Layout)>) {
+        self.oom_hook.set(hook);
+    }
+
+    /// Cap the bytes this allocator has handed out and not yet taken back (`quota` feature).
+    /// Once a request would exceed `limit`, `alloc` returns null without growing memory.
+    /// Pass `None` to remove the cap.
+    ///
+    /// 限制此分配器已分配出去且尚未收回的字节数（`quota` feature）。
+    /// 一旦请求会超出 `limit`，`alloc` 将返回 null 且不会增长内存。
+    /// 传入 `None` 以移除限制。
+    #[cfg(feature = "quota")]
+    pub fn set_quota(&self, limit: Option<usize>) {
+        self.quota.set_limit(limit);
+    }
+
+    /// Bytes currently counted against the quota (`quota` feature).
+    ///
+    /// 当前计入配额的字节数（`quota` feature）。
+    #[cfg(feature = "quota")]
+    pub fn quota_used(&self) -> usize {
+        self.quota.live()
+    }
+
+    /// Peak heap usage so far (`stats` feature): the bytes grown from the memory source
+    /// minus the part of the current region above the highest heap top. Kept after the
+    /// memory is freed, so it can size the initial memory of a workload. Cleared by
+    /// [`reset`](Self::reset).
+    ///
+    /// 迄今为止的堆使用峰值（`stats` feature）：从内存来源增长的字节数，减去当前区域中
+    /// 高于最高堆顶的部分。内存被释放后仍保留，因此可用于确定某个工作负载的初始内存大小。
+    /// 由 [`reset`](Self::reset) 清零。
+    #[cfg(feature = "stats")]
+    pub fn peak_bytes(&self) -> usize {
+        self.high_water.peak()
+    }
+
+    /// Number of usable bytes in the block at `ptr`, allocated with `layout`.
+    /// Always `>= layout.size()`; callers may use the slack without reallocating.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`.
+    ///
+    /// `ptr` 处以 `layout` 分配的块中的可用字节数。
+    /// 总是 `>= layout.size()`；调用者可以直接使用多余空间而无需重新分配。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配。
+    pub unsafe fn usable_size(&self, _ptr: *mut u8, layout: Layout) -> usize {
+        if layout.size() == 0 {
+            return 0;
+        }
+        // The canary sits right after the requested bytes, so there is no usable slack
+        // 金丝雀紧跟在请求的字节之后，因此没有可用的多余空间
+        if cfg!(feature = "canary") {
+            layout.size()
+        } else {
+            Self::block_size_for(layout.size())
+        }
+    }
+
+    /// Size of the block an allocation with `layout` would carve from the heap, without
+    /// allocating: the request plus the canary, rounded up to a multiple of MIN_ALIGN.
+    /// `0` for zero-sized layouts, which use no heap memory.
+    ///
+    /// 不进行分配，返回以 `layout` 分配时将从堆中切出的块大小：请求大小加上金丝雀，
+    /// 向上取整到 MIN_ALIGN 的倍数。零大小的布局不占用堆内存，返回 `0`。
+    pub fn block_size(&self, layout: Layout) -> usize {
+        if layout.size() == 0 {
+            return 0;
+        }
+        Self::block_size_for(layout.size())
+    }
+
+    /// Address range `[base, end)` this allocator has obtained so far: from the first page
+    /// it grew to the end of its current region. Every live block lies inside it, so a
+    /// host can copy this span to checkpoint the heap. `(0, 0)` before the first allocation.
+    ///
+    /// If the memory source ever returned pages that do not follow the previous ones, the
+    /// span also covers the gap between them.
+    ///
+    /// # Safety
+    /// Must not race with an allocation on this allocator.
+    ///
+    /// 此分配器目前已获取的地址范围 `[base, end)`：从其增长的第一页到当前区域的末尾。
+    /// 所有存活块都位于其中，因此宿主可以复制这段内存为堆建立检查点。
+    /// 首次分配之前为 `(0, 0)`。
+    ///
+    /// 如果内存来源曾返回不紧接前一段的页面，该范围也会覆盖它们之间的空隙。
+    ///
+    /// # Safety
+    /// 不得与此分配器上的分配同时进行。
+    pub unsafe fn heap_extent(&self) -> (usize, usize) {
+        unsafe { (*self.heap_base.get(), *self.heap_end.get()) }
+    }
+
+    /// Block size for a request of `size` bytes: room for a free-list node and the canary,
+    /// rounded up to a multiple of MIN_ALIGN.
+    ///
+    /// 请求 `size` 字节时的块大小：可容纳空闲链表节点与金丝雀，并向上取整到 MIN_ALIGN 的倍数。
+    #[inline(always)]
+    fn block_size_for(size: usize) -> usize {
+        let size = (size + CANARY_SIZE).max(core::mem::size_of::<Node>());
+        (size + MIN_ALIGN - 1) & !(MIN_ALIGN - 1)
+    }
+}
+
+impl Default for BumpFreeListAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Prints the bump pointer, the end of the heap and the free-list length without
+/// allocating. Walks the free list, so it must not run during another operation on the
+/// allocator.
+///
+/// 不进行分配地打印 Bump 指针、堆末尾和空闲链表长度。它会遍历空闲链表，
+/// 因此不得在分配器的其他操作期间运行。
+impl<S, const MIN_ALIGN: usize> fmt::Debug for BumpFreeListAllocator<S, MIN_ALIGN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut free_blocks = 0;
+        let mut node = unsafe { *self.free_list.get() };
+        while !node.is_null() {
+            free_blocks += 1;
+            node = unsafe { (*node).next };
+        }
+        f.debug_struct("BumpFreeListAllocator")
+            .field("heap_top", unsafe { &*self.heap_top.get() })
+            .field("heap_end", unsafe { &*self.heap_end.get() })
+            .field("free_blocks", &free_blocks)
+            .finish()
+    }
+}
+
+/// Magic value written right after each allocation with the `canary` feature.
+///
+/// 开启 `canary` feature 时，紧跟在每次分配之后写入的魔数。
+#[cfg(feature = "canary")]
+const CANARY: u64 = 0xC0DE_CAFE_F00D_BEEF;
+
+#[cfg(feature = "canary")]
+const CANARY_SIZE: usize = core::mem::size_of::<u64>();
+#[cfg(not(feature = "canary"))]
+const CANARY_SIZE: usize = 0;
+
+/// Write the canary right after the `size` requested bytes.
+///
+/// 在请求的 `size` 字节之后写入金丝雀。
+#[cfg(feature = "canary")]
+#[inline(always)]
+unsafe fn write_canary(ptr: *mut u8, size: usize) {
+    unsafe { ptr.add(size).cast::<u64>().write_unaligned(CANARY) }
+}
+
+/// Check that the canary after the `size` requested bytes is intact (debug builds only).
+///
+/// 检查请求的 `size` 字节之后的金丝雀是否完好（仅调试构建）。
+#[cfg(feature = "canary")]
+#[inline(always)]
+unsafe fn check_canary(ptr: *mut u8, size: usize) {
+    debug_assert!(
+        unsafe { ptr.add(size).cast::<u64>().read_unaligned() } == CANARY,
+        "canary overwritten: write past the end of the {size}-byte block at {ptr:p}"
+    );
+}
+
 // Linked list node: must store size because we have only one mixed list
 // 链表节点：必须存储大小，因为我们只有一个混杂的链表
 struct Node {
@@ -45,32 +402,431 @@ struct Node {
     size: usize,
 }
 
-// --------------------------------------------------------------------------
-// Global State
-// 全局状态
-// --------------------------------------------------------------------------
+unsafe impl<S: MemorySource, const MIN_ALIGN: usize> GlobalAlloc
+    for BumpFreeListAllocator<S, MIN_ALIGN>
+{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.try_alloc(layout) }.map_or(null_mut(), NonNull::as_ptr);
+        trace!(
+            "alloc size={=usize} align={=usize} ptr={=usize:#x}",
+            layout.size(),
+            layout.align(),
+            ptr as usize
+        );
+        ptr
+    }
 
-// Single unordered free list head
-// 单个无序空闲链表头
-static mut FREE_LIST: *mut Node = null_mut();
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        trace!(
+            "dealloc size={=usize} ptr={=usize:#x}",
+            layout.size(),
+            ptr as usize
+        );
+        if layout.size() == 0 {
+            return;
+        }
+        #[cfg(feature = "dealloc-check")]
+        self.debug_check_dealloc(ptr);
+        #[cfg(feature = "canary")]
+        unsafe {
+            check_canary(ptr, layout.size());
+        }
+        #[cfg(feature = "quota")]
+        self.quota.release(layout.size());
 
-// Bump Pointer State
-// Bump Pointer 状态
-static mut HEAP_TOP: usize = 0;
-static mut HEAP_END: usize = 0;
+        // 1. Calculate size (must be consistent with calculation in alloc)
+        // 1. 计算大小 (必须与 alloc 中的计算方式一致)
+        let size = Self::block_size_for(layout.size());
 
-unsafe impl GlobalAlloc for BumpFreeListAllocator {
-    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        // 1. Unify alignment to 16 bytes.
+        // 2. Hand it back to the heap top or the free list
+        // 2. 归还给堆顶或空闲链表
+        unsafe { self.free_block(ptr, size) };
+    }
+
+    #[cfg(feature = "realloc")]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if layout.size() == 0 || new_size == 0 {
+            return unsafe { realloc_zero_sized(self, ptr, layout, new_size) };
+        }
+
+        #[cfg(feature = "canary")]
+        unsafe {
+            check_canary(ptr, layout.size());
+        }
+
+        // Growing must fit in the quota whether it happens in place or by moving
+        // 无论原地扩容还是移动，增长都必须在配额之内
+        #[cfg(feature = "quota")]
+        if new_size > layout.size() && !self.quota.fits(new_size - layout.size()) {
+            self.oom_hook
+                .fire(unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) });
+            return null_mut();
+        }
+
+        if unsafe { self.resize_in_place(ptr, layout, new_size) } {
+            return ptr;
+        }
+
+        // Default fallback
+        // 默认回退
+        unsafe {
+            let new_ptr = self.alloc(Layout::from_size_align_unchecked(new_size, layout.align()));
+            if !new_ptr.is_null() {
+                ptr::copy_nonoverlapping(ptr, new_ptr, copy_span(layout, new_size));
+                self.dealloc(ptr, layout);
+            }
+            new_ptr
+        }
+    }
+}
+
+impl<S: MemorySource, const MIN_ALIGN: usize> BumpFreeListAllocator<S, MIN_ALIGN> {
+    /// Grow the heap up front so that the next `bytes` bytes can be bumped without growing,
+    /// e.g. before a latency-sensitive phase. Advances the heap end only; nothing is grown
+    /// if the current region already has room. Returns `false` on OOM.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 预先增长堆，使接下来的 `bytes` 字节无需增长即可 Bump 分配，例如在对延迟敏感的阶段之前。
+    /// 只推进堆末尾；如果当前区域已有足够空间，则不会增长。OOM 时返回 `false`。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn reserve(&self, bytes: usize) -> bool {
+        unsafe { self.bump_room(bytes, 1) }.is_some()
+    }
+
+    /// Pages that [`reserve`](Self::reserve) would have to grow for `bytes` bytes, given
+    /// the room left in the current bump region, without growing anything: `PageCount(0)`
+    /// if they already fit. A host can check it against its memory budget before
+    /// allocating. The growth policy may still ask for more pages than this minimum.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 根据当前 Bump 区域的剩余空间，计算 [`reserve`](Self::reserve) 为 `bytes` 字节
+    /// 需要增长的页数，且不进行任何增长：已能放下时为 `PageCount(0)`。
+    /// 宿主可以在分配之前用它对照自己的内存预算。增长策略仍可能申请多于此最小值的页数。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn pages_needed_for(&self, bytes: usize) -> PageCount {
+        unsafe { bump_pages_needed(*self.heap_top.get(), *self.heap_end.get(), bytes) }
+    }
+
+    /// Fallible allocation: like `GlobalAlloc::alloc`, but returns `Err(AllocError)`
+    /// instead of a null pointer. The OOM hook still fires on failure.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::alloc`, except that zero-sized layouts are allowed:
+    /// they yield a dangling, aligned pointer that uses no heap memory.
+    ///
+    /// 可失败的分配：与 `GlobalAlloc::alloc` 相同，但失败时返回 `Err(AllocError)`
+    /// 而不是空指针。失败时仍会触发 OOM 回调。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::alloc` 的约定相同，但允许零大小的布局：
+    /// 它们得到一个悬垂但对齐、不占用堆内存的指针。
+    pub unsafe fn try_alloc(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if layout.size() == 0 {
+            return Ok(unsafe { NonNull::new_unchecked(dangling(layout)) });
+        }
+        #[cfg(feature = "quota")]
+        if !self.quota.fits(layout.size()) {
+            self.oom_hook.fire(layout);
+            return Err(AllocError);
+        }
+        let Some(ptr) = NonNull::new(unsafe { self.alloc_block(layout) }) else {
+            self.oom_hook.fire(layout);
+            return Err(AllocError);
+        };
+        #[cfg(feature = "quota")]
+        self.quota.charge(layout.size());
+        #[cfg(feature = "canary")]
+        unsafe {
+            write_canary(ptr.as_ptr(), layout.size());
+        }
+        Ok(ptr)
+    }
+
+    /// Allocate an uninitialized array of `n` values of type `T` in one block. Returns null
+    /// if `Layout::array::<T>(n)` overflows or memory runs out. Free it with
+    /// [`dealloc_array`](Self::dealloc_array) and the same `n`.
+    ///
+    /// # Safety
+    /// Same contract as [`try_alloc`](Self::try_alloc) with `Layout::array::<T>(n)`.
+    ///
+    /// 在一个块中分配 `n` 个 `T` 类型值的未初始化数组。`Layout::array::<T>(n)` 溢出或内存耗尽时
+    /// 返回 null。使用 [`dealloc_array`](Self::dealloc_array) 并传入相同的 `n` 释放。
+    ///
+    /// # Safety
+    /// 与以 `Layout::array::<T>(n)` 调用 [`try_alloc`](Self::try_alloc) 的约定相同。
+    pub unsafe fn alloc_array<T>(&self, n: usize) -> *mut T {
+        match Layout::array::<T>(n) {
+            Ok(layout) => unsafe { self.alloc(layout) }.cast(),
+            Err(_) => null_mut(),
+        }
+    }
+
+    /// Free an array allocated by [`alloc_array`](Self::alloc_array).
+    ///
+    /// # Safety
+    /// `ptr` must have been returned by `alloc_array::<T>(n)` on this allocator, with the
+    /// same `n`, and not freed yet.
+    ///
+    /// 释放由 [`alloc_array`](Self::alloc_array) 分配的数组。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器的 `alloc_array::<T>(n)` 以相同的 `n` 返回，且尚未被释放。
+    pub unsafe fn dealloc_array<T>(&self, ptr: *mut T, n: usize) {
+        unsafe { self.dealloc(ptr.cast(), Layout::array::<T>(n).unwrap_unchecked()) }
+    }
+
+    /// Allocate like `GlobalAlloc::alloc`, also returning the capacity of the block:
+    /// the rounded size that was bumped or reused, always `>= layout.size()`.
+    /// The caller may use all of it, and may later free or resize the block with the
+    /// same `layout`. On failure, returns `(null, 0)`.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::alloc`.
+    ///
+    /// 与 `GlobalAlloc::alloc` 一样分配，并同时返回块的容量：
+    /// Bump 或复用的取整后大小，总是 `>= layout.size()`。
+    /// 调用者可以使用全部容量，之后仍以同一 `layout` 释放或调整该块。失败时返回 `(null, 0)`。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::alloc` 的约定相同。
+    pub unsafe fn alloc_with_capacity(&self, layout: Layout) -> (*mut u8, usize) {
+        let ptr = unsafe { self.alloc(layout) };
+        if ptr.is_null() {
+            return (ptr, 0);
+        }
+        (ptr, unsafe { self.usable_size(ptr, layout) })
+    }
+
+    /// Like `GlobalAlloc::realloc`, but the bytes past the old size, `[layout.size(),
+    /// new_size)`, are zeroed, whether the block grew in place or moved.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::realloc`.
+    ///
+    /// 与 `GlobalAlloc::realloc` 相同，但旧大小之后的字节 `[layout.size(), new_size)` 会被清零，
+    /// 无论块是原地扩容还是被移动。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::realloc` 的约定相同。
+    #[cfg(feature = "realloc")]
+    pub unsafe fn realloc_zeroed(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { realloc_zero_tail(self, ptr, layout, new_size) }
+    }
+
+    /// Try to grow the block at `ptr` to `new_size` bytes without moving it: at the heap
+    /// top (growing the heap if needed), or into a free block right above it. Returns
+    /// whether it grew; on `false` the block is unchanged and the caller may move the data
+    /// itself. Unlike `realloc`, this does not need the `realloc` feature.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`, and `new_size` must
+    /// be at least `layout.size()` and valid for a `Layout` of `layout.align()`. After a
+    /// `true` the block must be freed with a layout of `new_size` bytes.
+    ///
+    /// 尝试在不移动的情况下将 `ptr` 处的块扩容到 `new_size` 字节：在堆顶延伸
+    /// （必要时增长堆），或扩入紧接其上的空闲块。返回是否扩容成功；返回 `false` 时块保持不变，
+    /// 调用者可以自行移动数据。与 `realloc` 不同，它不需要 `realloc` feature。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配，`new_size` 不得小于 `layout.size()`，
+    /// 且对 `layout.align()` 的 `Layout` 有效。返回 `true` 后必须以 `new_size` 字节的布局释放该块。
+    pub unsafe fn grow_in_place(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> bool {
+        debug_assert!(
+            new_size >= layout.size(),
+            "grow_in_place cannot shrink a block"
+        );
+        // Zero-sized blocks own no memory to grow into
+        // 零大小的块不拥有可供扩容的内存
+        if layout.size() == 0 {
+            return false;
+        }
+        #[cfg(feature = "canary")]
+        unsafe {
+            check_canary(ptr, layout.size());
+        }
+        #[cfg(feature = "quota")]
+        if !self.quota.fits(new_size - layout.size()) {
+            return false;
+        }
+        unsafe { self.resize_in_place(ptr, layout, new_size) }
+    }
+
+    /// Shrink the block at `ptr` to `new_size` bytes without moving it, giving the tail
+    /// back. Returns `false` only for `new_size == 0`, which leaves the block unchanged:
+    /// a zero-sized block owns no memory.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`, and `new_size` must
+    /// not exceed `layout.size()`. After a `true` the block must be freed with a layout of
+    /// `new_size` bytes.
+    ///
+    /// 在不移动的情况下将 `ptr` 处的块缩小为 `new_size` 字节，并归还尾部。
+    /// 仅当 `new_size == 0` 时返回 `false` 且块保持不变：零大小的块不拥有内存。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配，且 `new_size` 不得大于 `layout.size()`。
+    /// 返回 `true` 后必须以 `new_size` 字节的布局释放该块。
+    pub unsafe fn shrink_in_place(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> bool {
+        debug_assert!(
+            new_size <= layout.size(),
+            "shrink_in_place cannot grow a block"
+        );
+        if new_size == 0 {
+            return false;
+        }
+        #[cfg(feature = "canary")]
+        unsafe {
+            check_canary(ptr, layout.size());
+        }
+        unsafe { self.resize_in_place(ptr, layout, new_size) }
+    }
+
+    /// Resize the block at `ptr` without moving it: shrinking always succeeds, growing
+    /// extends it at the heap top (growing the heap if needed) or into a free block right
+    /// above it. Returns whether the block was resized.
+    ///
+    /// 在不移动的情况下调整 `ptr` 处块的大小：缩小总能成功；扩容时在堆顶延伸
+    /// （必要时增长堆），或扩入紧接其上的空闲块。返回块是否已被调整。
+    unsafe fn resize_in_place(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> bool {
+        let old_size = Self::block_size_for(layout.size());
+        let req_new_size = Self::block_size_for(new_size);
+
+        // Shrinking keeps the block and gives the freed tail back; a tail too small for a
+        // node is left unused
+        // 缩小时保留块并归还释放出的尾部；太小而放不下节点的尾部则弃置不用
+        if req_new_size <= old_size {
+            let tail = old_size - req_new_size;
+            if tail >= core::mem::size_of::<Node>() {
+                unsafe { self.free_block(ptr.add(req_new_size), tail) };
+            }
+            self.resized_in_place(ptr, layout.size(), new_size);
+            return true;
+        }
+
+        // Optimization: Check if at heap top, if so, extend in place
+        // 优化：检查是否在堆顶，如果是则原地扩容
+        let heap_top = unsafe { &mut *self.heap_top.get() };
+        let heap_end = unsafe { &mut *self.heap_end.get() };
+
+        if ptr as usize + old_size == *heap_top {
+            let diff = req_new_size - old_size;
+
+            // Try to extend heap top
+            // 尝试扩容堆顶
+            if diff <= *heap_end - *heap_top {
+                *heap_top += diff;
+                self.reached_top();
+                self.resized_in_place(ptr, layout.size(), new_size);
+                return true;
+            }
+
+            // Request more pages
+            // 申请更多页面
+            let pages_needed = PageCount::from_bytes(diff - (*heap_end - *heap_top))
+                .as_usize()
+                .max(1);
+            if let Some((memory_start, pages)) = unsafe { self.grow_pages(pages_needed) } {
+                if memory_start == *heap_end {
+                    *heap_end += pages * PAGE_SIZE;
+                    *heap_top += diff;
+                    self.reached_top();
+                    self.resized_in_place(ptr, layout.size(), new_size);
+                    return true;
+                }
+
+                // Not contiguous (the source was grown by someone else): bump from the
+                // new pages instead and let the fallback below move the block there
+                // 不连续（内存源被其他人扩容过）：改为从新页面 Bump，
+                // 由下面的回退路径将块移动过去
+                *heap_top = memory_start;
+                *heap_end = memory_start + pages * PAGE_SIZE;
+                unsafe { *self.heap_start.get() = memory_start };
+            }
+        }
+
+        // A freed block right above the old one (e.g. a neighbour freed just before):
+        // absorb its front part and grow without copying
+        // 紧接在旧块之上的已释放块（例如刚刚释放的相邻块）：吸收其前部，无需复制即可扩容
+        if unsafe { self.take_adjacent(ptr as usize + old_size, req_new_size - old_size) } {
+            self.resized_in_place(ptr, layout.size(), new_size);
+            return true;
+        }
+        false
+    }
+
+    /// Bookkeeping for a block resized in place from `old_size` to `new_size` requested bytes.
+    ///
+    /// 记录一个块的请求大小从 `old_size` 原地调整为 `new_size` 字节。
+    #[inline(always)]
+    fn resized_in_place(&self, ptr: *mut u8, old_size: usize, new_size: usize) {
+        let _ = (ptr, old_size, new_size);
+        #[cfg(feature = "canary")]
+        unsafe {
+            write_canary(ptr, new_size);
+        }
+        #[cfg(feature = "quota")]
+        self.quota.resize(old_size, new_size);
+    }
+
+    /// Unlink the free block starting exactly at `addr` if it holds at least `needed` bytes,
+    /// putting back whatever remains above the first `needed` bytes. O(N).
+    ///
+    /// 如果恰好起始于 `addr` 的空闲块至少有 `needed` 字节，则将其从链表中移除，
+    /// 并把前 `needed` 字节之后的剩余部分放回链表。O(N)。
+    unsafe fn take_adjacent(&self, addr: usize, needed: usize) -> bool {
+        unsafe {
+            let mut link = self.free_list.get();
+            while !(*link).is_null() {
+                let node = *link;
+                if node as usize != addr {
+                    link = ptr::addr_of_mut!((*node).next);
+                    continue;
+                }
+                let size = (*node).size;
+                if size < needed {
+                    return false;
+                }
+                #[cfg(feature = "poison")]
+                poison::check(node as *mut u8, core::mem::size_of::<Node>(), size);
+
+                // A remainder too small for a node stays attached to the grown block
+                // 太小而放不下节点的剩余部分仍附着在扩容后的块上
+                let remaining = size - needed;
+                if remaining >= core::mem::size_of::<Node>() {
+                    let rest = (node as *mut u8).add(needed) as *mut Node;
+                    (*rest).size = remaining;
+                    (*rest).next = (*node).next;
+                    *link = rest;
+                } else {
+                    *link = (*node).next;
+                }
+                return true;
+            }
+            false
+        }
+    }
+
+    /// Allocation logic behind `GlobalAlloc::alloc`, without the OOM hook.
+    ///
+    /// `GlobalAlloc::alloc` 背后的分配逻辑，不包含 OOM 回调。
+    unsafe fn alloc_block(&self, layout: Layout) -> *mut u8 {
+        // 1. Unify alignment to MIN_ALIGN (16 bytes by default).
         // This simplifies all pointer calculations and adapts to Wasm SIMD.
-        // 1. 统一对齐到 16 字节
+        // 1. 统一对齐到 MIN_ALIGN（默认 16 字节）
         // 这简化了所有指针计算，并且适配 Wasm SIMD
-        let align_req = layout.align().max(16);
-        let size = layout.size().max(16);
+        let align_req = layout.align().max(MIN_ALIGN);
 
-        // Ensure size is also a multiple of 16 for easier management
-        // 确保 size 也是 16 的倍数，方便后续管理
-        let size = (size + 15) & !15;
+        // Ensure size is also a multiple of MIN_ALIGN for easier management
+        // 确保 size 也是 MIN_ALIGN 的倍数，方便后续管理
+        let size = Self::block_size_for(layout.size());
 
         // 2. Try to allocate from the free list (First Fit).
         // Iterate through the list to find the first block that is large enough.
@@ -79,14 +835,19 @@ unsafe impl GlobalAlloc for BumpFreeListAllocator {
         // 遍历链表找到第一个足够大的块。
         // 注意：这是 O(N) 操作。但在短生命周期应用中，链表通常很短。
         unsafe {
-            let mut prev = ptr::addr_of_mut!(FREE_LIST);
+            let mut prev = self.free_list.get();
             let mut curr = *prev;
 
             while !curr.is_null() {
-                if (*curr).size >= size {
+                // Blocks are only MIN_ALIGN-aligned: a higher alignment needs a block that
+                // happens to start on it
+                // 块只保证按 MIN_ALIGN 对齐：更高的对齐要求需要恰好从该对齐处开始的块
+                if (*curr).size >= size && (curr as usize).is_multiple_of(align_req) {
                     // Found a suitable block: remove from list
                     // 找到合适的块：从链表中移除
                     *prev = (*curr).next;
+                    #[cfg(feature = "poison")]
+                    poison::check(curr as *mut u8, core::mem::size_of::<Node>(), (*curr).size);
                     return curr as *mut u8;
                 }
                 // Move to next node
@@ -102,117 +863,204 @@ unsafe impl GlobalAlloc for BumpFreeListAllocator {
         unsafe { self.bump_alloc(size, align_req) }
     }
 
-    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-        // 1. Calculate size (must be consistent with calculation in alloc)
-        // 1. 计算大小 (必须与 alloc 中的计算方式一致)
-        let size = layout.size().max(16);
-        let size = (size + 15) & !15;
+    /// `dealloc-check`: the freed pointer must be `MIN_ALIGN`-aligned and lie between the
+    /// first region this allocator grew and the current heap top.
+    ///
+    /// `dealloc-check`：被释放的指针必须按 `MIN_ALIGN` 对齐，
+    /// 并位于此分配器增长的首个区域与当前堆顶之间。
+    #[cfg(feature = "dealloc-check")]
+    fn debug_check_dealloc(&self, ptr: *mut u8) {
+        let addr = ptr as usize;
+        debug_assert!(
+            addr.is_multiple_of(MIN_ALIGN),
+            "dealloc of a misaligned pointer: {ptr:p}"
+        );
+        let (base, top) = unsafe { (*self.heap_base.get(), *self.heap_top.get()) };
+        debug_assert!(
+            base <= addr && addr < top,
+            "dealloc of a pointer outside the heap: {ptr:p}"
+        );
+    }
+
+    /// Give the `size`-byte block at `ptr` back: roll the heap top back over it with
+    /// `coalesce`, otherwise thread it onto the free list.
+    ///
+    /// 归还 `ptr` 处 `size` 字节的块：开启 `coalesce` 时若可能则回退堆顶，
+    /// 否则将其挂入空闲链表。
+    unsafe fn free_block(&self, ptr: *mut u8, size: usize) {
+        // 1. With `coalesce`: if the block is the last one bumped, roll the heap top back instead.
+        // This reclaims the common LIFO pattern while staying O(1).
+        // 1. 开启 `coalesce` 时：如果该块是最后一次 bump 出去的块，则直接回退堆顶。
+        // 这样可以在保持 O(1) 的同时回收常见的 LIFO 模式。
+        #[cfg(feature = "coalesce")]
+        unsafe {
+            let heap_top = self.heap_top.get();
+            if ptr as usize + size == *heap_top {
+                *heap_top = ptr as usize;
+                return;
+            }
+        }
 
         // 2. Insert into free list at head (O(1)).
+        // With `address-ordered`, walk to the first node above `ptr` instead (O(N)),
+        // so first fit picks the lowest suitable address.
         // No merging, simply thread it through.
         // 2. 头插法插入空闲链表 (O(1))
+        // 开启 `address-ordered` 时，改为走到第一个地址高于 `ptr` 的节点之前插入 (O(N))，
+        // 使首次适配选中地址最低的合适块。
         // 不进行合并，直接通过
         unsafe {
+            #[cfg(feature = "poison")]
+            poison::fill(ptr, size);
             let node = ptr as *mut Node;
             (*node).size = size;
-            (*node).next = FREE_LIST;
-            FREE_LIST = node;
+            #[cfg(not(feature = "address-ordered"))]
+            let link = self.free_list.get();
+            #[cfg(feature = "address-ordered")]
+            let link = {
+                let mut link = self.free_list.get();
+                while !(*link).is_null() && (*link as usize) < ptr as usize {
+                    link = ptr::addr_of_mut!((**link).next);
+                }
+                link
+            };
+            (*node).next = *link;
+            *link = node;
         }
     }
 
-    #[cfg(feature = "realloc")]
-    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
-        // Optimization: Check if at heap top, if so, extend in place
-        // 优化：检查是否在堆顶，如果是则原地扩容
-        let old_size = (layout.size().max(16) + 15) & !15;
-        let req_new_size = (new_size.max(16) + 15) & !15;
+    /// Grow the source by at least `needed` pages, or by what the growth policy asks for.
+    /// Returns the start address and the number of pages obtained.
+    ///
+    /// 将内存源增长至少 `needed` 页，或增长策略要求的页数。返回起始地址与实际获得的页数。
+    unsafe fn grow_pages(&self, needed: usize) -> Option<(usize, usize)> {
+        let region_pages = unsafe { (*self.heap_end.get() - *self.heap_start.get()) / PAGE_SIZE };
+        let wanted = self.growth.pages_for(needed, region_pages);
+        let grown = unsafe { grow_amortized(&self.source, needed, wanted) };
+        #[cfg(feature = "stats")]
+        if let Some((_, pages)) = grown {
+            self.high_water.grew(pages * PAGE_SIZE);
+        }
+        grown
+    }
 
-        // Accessing mutable statics requires unsafe block
-        let heap_top = unsafe { HEAP_TOP };
+    /// Raise the `stats` high-water mark after the heap top moved up.
+    ///
+    /// 堆顶上移后提升 `stats` 高水位线。
+    #[inline(always)]
+    fn reached_top(&self) {
+        #[cfg(feature = "stats")]
+        unsafe {
+            self.high_water
+                .reach(*self.heap_end.get() - *self.heap_top.get());
+        }
+    }
 
-        if ptr as usize + old_size == heap_top {
-            let diff = req_new_size.saturating_sub(old_size);
-            if diff == 0 {
-                return ptr;
-            }
+    /// Grow the heap as needed so that `size` bytes at `align` fit in the current bump
+    /// region, and return their `[start, end)` range without claiming it. `None` on OOM.
+    ///
+    /// 按需增长堆，使 `align` 对齐的 `size` 字节能放入当前 Bump 区域，并返回其
+    /// `[start, end)` 范围（不占用它）。OOM 时返回 `None`。
+    unsafe fn bump_room(&self, size: usize, align: usize) -> Option<(usize, usize)> {
+        let heap_top = unsafe { &mut *self.heap_top.get() };
+        let heap_end = unsafe { &mut *self.heap_end.get() };
 
-            // Try to extend heap top
-            // 尝试扩容堆顶
-            unsafe {
-                if HEAP_TOP + diff <= HEAP_END {
-                    HEAP_TOP += diff;
-                    return ptr;
+        // Alignment handling; an address past `usize::MAX` can never be served
+        // 对齐处理；超出 `usize::MAX` 的地址永远无法满足
+        let (mut ptr, mut end) = bump_range(*heap_top, size, align)?;
+
+        if end > *heap_end {
+            let pages_needed = PageCount::from_bytes(end - *heap_end).as_usize().max(1);
+
+            let (memory_start, pages) = unsafe { self.grow_pages(pages_needed) }?;
+
+            // Initial allocation, or pages not following our heap end (another instance
+            // grew the same memory): restart the bump region there
+            // 初次分配，或新页面不紧接在堆末尾之后（其他实例扩容了同一块内存）：
+            // 从新页面重新开始 Bump 区域
+            if memory_start != *heap_end {
+                let restart = bump_range(memory_start, size, align)?;
+                (ptr, end) = restart;
+                *heap_top = memory_start;
+                *heap_end = memory_start;
+                unsafe { *self.heap_start.get() = memory_start };
+                if unsafe { *self.heap_base.get() } == 0 {
+                    unsafe { *self.heap_base.get() = memory_start };
                 }
+            }
+            *heap_end += pages * PAGE_SIZE;
 
-                // Request more pages
-                // 申请更多页面
-                let pages_needed =
-                    ((HEAP_TOP + diff - HEAP_END + PAGE_SIZE - 1) / PAGE_SIZE).max(1);
-                if grow_memory(pages_needed) != usize::MAX {
-                    HEAP_END += pages_needed * PAGE_SIZE;
-                    HEAP_TOP += diff;
-                    return ptr;
+            // After a restart the pages sized for the old region may fall short
+            // 重新开始后，按旧区域计算的页数可能不够
+            if end > *heap_end {
+                let extra = PageCount::from_bytes(end - *heap_end).as_usize();
+                // The extra pages must follow the region, or the block would straddle a gap
+                // 额外的页面必须紧接在区域之后，否则块会跨越空洞
+                let grown = unsafe { grow_region(&self.source, extra) };
+                #[cfg(feature = "stats")]
+                if grown.is_some() {
+                    self.high_water.grew(extra * PAGE_SIZE);
+                }
+                if grown != Some(*heap_end) {
+                    return None;
                 }
+                *heap_end += extra * PAGE_SIZE;
             }
         }
+        Some((ptr, end))
+    }
 
-        // Default fallback
-        // 默认回退
-        unsafe {
-            let new_ptr = self.alloc(Layout::from_size_align_unchecked(new_size, layout.align()));
-            if !new_ptr.is_null() {
-                ptr::copy_nonoverlapping(ptr, new_ptr, layout.size());
-                self.dealloc(ptr, layout);
-            }
-            new_ptr
+    unsafe fn bump_alloc(&self, size: usize, align: usize) -> *mut u8 {
+        let Some((ptr, end)) = (unsafe { self.bump_room(size, align) }) else {
+            return null_mut();
+        };
+
+        unsafe { *self.heap_top.get() = end };
+        self.reached_top();
+        // First touch of this region: zero it if the source does not guarantee it
+        // 首次使用该区域：如果内存来源不保证为零，则将其清零
+        if !S::ZEROED {
+            unsafe { ptr::write_bytes(ptr as *mut u8, 0, size) };
         }
+        ptr as *mut u8
     }
 }
 
-impl BumpFreeListAllocator {
-    unsafe fn bump_alloc(&self, size: usize, align: usize) -> *mut u8 {
+impl<S, const MIN_ALIGN: usize> ResettableAllocator for BumpFreeListAllocator<S, MIN_ALIGN> {
+    unsafe fn reset(&self) {
+        unsafe { BumpFreeListAllocator::reset(self) }
+    }
+}
+
+impl<S, const MIN_ALIGN: usize> sealed::Sealed for BumpFreeListAllocator<S, MIN_ALIGN> {
+    fn mark(&self) -> (usize, usize) {
+        unsafe { (*self.heap_top.get(), *self.heap_start.get()) }
+    }
+
+    unsafe fn rewind(&self, mark: (usize, usize)) {
         unsafe {
-            let mut ptr = HEAP_TOP;
-            // Alignment handling
-            // 对齐处理
-            ptr = (ptr + align - 1) & !(align - 1);
-
-            if ptr + size > HEAP_END || ptr < HEAP_TOP {
-                let bytes_needed = (ptr + size).saturating_sub(HEAP_END);
-                let pages_needed = ((bytes_needed + PAGE_SIZE - 1) / PAGE_SIZE).max(1);
-
-                let prev_page = grow_memory(pages_needed);
-                if prev_page == usize::MAX {
-                    return null_mut();
-                }
+            let top = rewound(*self.heap_top.get(), *self.heap_start.get(), mark);
+            let end = *self.heap_end.get();
+            *self.heap_top.get() = top;
 
-                if HEAP_END == 0 {
-                    let memory_start = prev_page * PAGE_SIZE;
-                    ptr = memory_start;
-                    ptr = (ptr + align - 1) & !(align - 1);
-                    HEAP_END = memory_start + pages_needed * PAGE_SIZE;
+            // Free blocks above the heap top would be handed out twice: unlink them
+            // 位于堆顶之上的空闲块会被重复分配：将其移除
+            let mut link = self.free_list.get();
+            while !(*link).is_null() {
+                let node = *link;
+                if (top..end).contains(&(node as usize)) {
+                    *link = (*node).next;
                 } else {
-                    HEAP_END += pages_needed * PAGE_SIZE;
+                    link = ptr::addr_of_mut!((*node).next);
                 }
             }
-
-            HEAP_TOP = ptr + size;
-            ptr as *mut u8
         }
     }
+}
 
-    /// Testing only: Reset the internal state.
-    /// Safety: usage is inherently unsafe if allocator is in use.
-    ///
-    /// 仅测试用：重置内部状态。
-    /// 安全性：如果分配器正在使用，未定义的行为。
-    pub unsafe fn reset() {
-        unsafe {
-            FREE_LIST = null_mut();
-            HEAP_TOP = 0;
-            HEAP_END = 0;
-        }
-    }
+impl<S: MemorySource, const MIN_ALIGN: usize> ScopedAllocator
+    for BumpFreeListAllocator<S, MIN_ALIGN>
+{
 }
 
 #[cfg(test)]
@@ -220,25 +1068,16 @@ mod tests {
     use super::*;
     use crate::reset_heap;
     use core::alloc::Layout;
-    use std::sync::{Mutex, MutexGuard};
-
-    static TEST_MUTEX: Mutex<()> = Mutex::new(());
 
     struct SafeAllocator {
         inner: BumpFreeListAllocator,
-        _guard: MutexGuard<'static, ()>,
     }
 
     impl SafeAllocator {
         fn new() -> Self {
-            let guard = TEST_MUTEX.lock().unwrap();
-            unsafe {
-                BumpFreeListAllocator::reset(); // Reset allocator state
-                reset_heap(); // Reset memory mock
-                Self {
-                    inner: BumpFreeListAllocator::new(),
-                    _guard: guard,
-                }
+            reset_heap(); // Reset memory mock
+            Self {
+                inner: BumpFreeListAllocator::new(),
             }
         }
 
@@ -253,10 +1092,8 @@ mod tests {
 
     impl Drop for SafeAllocator {
         fn drop(&mut self) {
-            unsafe {
-                BumpFreeListAllocator::reset();
-                reset_heap();
-            }
+            unsafe { self.inner.reset() };
+            reset_heap();
         }
     }
 
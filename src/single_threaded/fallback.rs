@@ -0,0 +1,115 @@
+use crate::ResettableAllocator;
+use crate::layout_math::copy_span;
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::null_mut;
+
+/// Two allocators under a single `GlobalAlloc`: layouts accepted by a predicate go to
+/// `A` (e.g. a fast `SegregatedBumpAllocator` for small objects), everything else to `B`
+/// (e.g. a coalescing `FreeListAllocator` for large ones).
+///
+/// Routing depends only on the layout, so `dealloc` and `realloc` always reach the
+/// allocator that served the block. For the same reason a request routed to `A` is not
+/// retried in `B` when `A` runs out of memory: `dealloc` could not tell where it went.
+///
+/// 将两个分配器组合在一个 `GlobalAlloc` 之下：谓词接受的布局交给 `A`
+/// （例如用于小对象的快速 `SegregatedBumpAllocator`），其余的交给 `B`
+/// （例如用于大对象、支持合并的 `FreeListAllocator`）。
+///
+/// 路由只取决于布局，因此 `dealloc` 和 `realloc` 总能回到分配该块的分配器。
+/// 出于同样的原因，路由到 `A` 的请求在 `A` 内存不足时不会转而在 `B` 中重试：
+/// 否则 `dealloc` 将无法判断块的来源。
+pub struct Fallback<A, B> {
+    primary: A,
+    fallback: B,
+    use_primary: fn(Layout) -> bool,
+}
+
+impl<A, B> Fallback<A, B> {
+    /// Combine `primary` and `fallback`; `use_primary` decides which one serves a layout.
+    ///
+    /// 组合 `primary` 与 `fallback`；由 `use_primary` 决定哪一个服务某个布局。
+    pub const fn new(primary: A, fallback: B, use_primary: fn(Layout) -> bool) -> Self {
+        Fallback {
+            primary,
+            fallback,
+            use_primary,
+        }
+    }
+
+    /// The allocator serving layouts accepted by the predicate.
+    ///
+    /// 服务谓词所接受布局的分配器。
+    pub fn primary(&self) -> &A {
+        &self.primary
+    }
+
+    /// The allocator serving every other layout.
+    ///
+    /// 服务其余所有布局的分配器。
+    pub fn fallback(&self) -> &B {
+        &self.fallback
+    }
+}
+
+unsafe impl<A: GlobalAlloc, B: GlobalAlloc> GlobalAlloc for Fallback<A, B> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if (self.use_primary)(layout) {
+            unsafe { self.primary.alloc(layout) }
+        } else {
+            unsafe { self.fallback.alloc(layout) }
+        }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if (self.use_primary)(layout) {
+            unsafe { self.primary.alloc_zeroed(layout) }
+        } else {
+            unsafe { self.fallback.alloc_zeroed(layout) }
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if (self.use_primary)(layout) {
+            unsafe { self.primary.dealloc(ptr, layout) }
+        } else {
+            unsafe { self.fallback.dealloc(ptr, layout) }
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+        let old_primary = (self.use_primary)(layout);
+        let new_primary = (self.use_primary)(new_layout);
+
+        // 1. Same owner before and after: let it resize in place if it can
+        // 1. 前后归属相同：交给它处理，能原地调整则原地调整
+        if old_primary == new_primary {
+            return if old_primary {
+                unsafe { self.primary.realloc(ptr, layout, new_size) }
+            } else {
+                unsafe { self.fallback.realloc(ptr, layout, new_size) }
+            };
+        }
+
+        // 2. The block changes owner: allocate from the other one, copy, free the old block
+        // 2. 块的归属发生变化：从另一个分配器分配，复制，再释放旧块
+        let new_ptr = unsafe { self.alloc(new_layout) };
+        if new_ptr.is_null() {
+            return null_mut();
+        }
+        unsafe {
+            core::ptr::copy_nonoverlapping(ptr, new_ptr, copy_span(layout, new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
+
+impl<A: ResettableAllocator, B: ResettableAllocator> ResettableAllocator for Fallback<A, B> {
+    unsafe fn reset(&self) {
+        unsafe {
+            self.primary.reset();
+            self.fallback.reset();
+        }
+    }
+}
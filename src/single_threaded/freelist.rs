@@ -1,7 +1,23 @@
-use crate::PAGE_SIZE;
+#[cfg(feature = "stats")]
+use crate::HighWater;
+#[cfg(feature = "quota")]
+use crate::Quota;
+#[cfg(feature = "realloc")]
+use crate::layout_math::copy_span;
+use crate::layout_math::{NODE_SIZE, round_up};
+#[cfg(feature = "poison")]
+use crate::poison;
+use crate::{
+    AllocError, DefaultMemory, GrowthPolicy, MemorySource, OomHook, PAGE_SIZE, PageCount,
+    ResettableAllocator, dangling, grow_amortized,
+};
+#[cfg(feature = "realloc")]
+use crate::{realloc_zero_sized, realloc_zero_tail};
 use core::{
     alloc::{GlobalAlloc, Layout},
-    ptr::{self, null_mut},
+    cell::UnsafeCell,
+    fmt,
+    ptr::{self, NonNull, null_mut},
 };
 
 /// Safety Warning:
@@ -15,7 +31,17 @@ use core::{
 /// 实现了 `Sync` 仅为了满足 `GlobalAlloc` trait 的要求。
 /// 在多线程环境中使用此分配器会导致未定义行为 (UB)。
 /// 请确保只在单线程环境（如 WASM 或单线程嵌入式环境）中使用。
-unsafe impl Sync for FreeListAllocator {}
+///
+/// With `strict-single-thread` this impl is left out: wrap the allocator in
+/// [`GlobalWrapper`](super::GlobalWrapper) at the `#[global_allocator]` site instead.
+///
+/// 开启 `strict-single-thread` 时不提供此实现：
+/// 请改为在 `#[global_allocator]` 处用 [`GlobalWrapper`](super::GlobalWrapper) 包装分配器。
+#[cfg(not(feature = "strict-single-thread"))]
+unsafe impl<S, const MIN_ALIGN: usize, const MIN_BLOCK: usize> Sync
+    for FreeListAllocator<S, MIN_ALIGN, MIN_BLOCK>
+{
+}
 
 /// A non-thread-safe allocator using a free list.
 /// Complexity of allocation and deallocation is O(length of free list).
@@ -26,268 +52,1535 @@ unsafe impl Sync for FreeListAllocator {}
 /// 分配和释放操作的时间复杂度为 O(空闲链表长度)。
 ///
 /// 空闲链表按地址排序，并且在插入新块时会合并相邻的内存块。
-pub struct FreeListAllocator;
+///
+/// Memory is obtained from a [`MemorySource`], which defaults to `grow_memory`.
+/// Each instance owns its own free list, so several heaps can coexist.
+///
+/// 内存从 [`MemorySource`] 获取，默认为 `grow_memory`。
+/// 每个实例拥有自己的空闲链表，因此多个堆可以共存。
+///
+/// Every block is aligned to `MIN_ALIGN` (16 by default, covering u8 to u128/v128) and its
+/// size is a multiple of it; see [`with_min_align`](Self::with_min_align).
+///
+/// 每个块都按 `MIN_ALIGN` 对齐（默认 16，覆盖 u8 到 u128/v128），且大小为其倍数；
+/// 见 [`with_min_align`](Self::with_min_align)。
+///
+/// With the `small-bins` feature, requests of up to 128 bytes (alignment <= 16) are served
+/// from four LIFO size-class bins in O(1); bin misses carve a block from the sorted list.
+/// Binned blocks are never coalesced.
+///
+/// 开启 `small-bins` feature 后，不超过 128 字节（对齐 <= 16）的请求由四个 LIFO
+/// 大小分级的 Bin 以 O(1) 处理；Bin 未命中时从有序链表中切出块。Bin 中的块永远不会被合并。
+///
+/// With the `size-index` feature, every block on the sorted list is also linked into a
+/// second list by ascending size, so the best fit is the first block there that is large
+/// enough. Taking a whole block still walks the address list to unlink it.
+///
+/// 开启 `size-index` feature 后，有序链表上的每个块还按大小升序链接到第二个链表中，
+/// 最佳适配即该链表中第一个足够大的块。整块取出时仍需遍历地址链表以将其摘除。
+///
+/// Blocks are at least `MIN_BLOCK` bytes (a node header, [`NODE_SIZE`], by default). A
+/// smaller `MIN_BLOCK` lets tiny allocations use less memory, e.g. 8-byte objects in 8-byte
+/// blocks with `FreeListAllocator<DefaultMemory, 8, 8>`: blocks too small for a header
+/// are freed into LIFO bins linked through their first word, and are never coalesced.
+///
+/// 块至少为 `MIN_BLOCK` 字节（默认为一个节点头，即 [`NODE_SIZE`]）。更小的 `MIN_BLOCK`
+/// 让极小的分配占用更少的内存，例如使用 `FreeListAllocator<DefaultMemory, 8, 8>` 时，
+/// 8 字节的对象占用 8 字节的块：放不下节点头的块会被释放到通过其首个字链接的 LIFO Bin 中，
+/// 且永远不会被合并。
+///
+/// [`dealloc_nomerge`](Self::dealloc_nomerge) frees in O(1) onto a separate LIFO list that
+/// allocations check first; [`compact`](Self::compact) merges those blocks into the sorted
+/// list, which also happens on its own before the allocator grows memory.
+///
+/// [`dealloc_nomerge`](Self::dealloc_nomerge) 以 O(1) 将块释放到一个独立的 LIFO 链表上，
+/// 分配时会优先检查该链表；[`compact`](Self::compact) 将这些块合并进有序链表，
+/// 分配器在增长内存之前也会自动执行这一步。
+pub struct FreeListAllocator<
+    S = DefaultMemory,
+    const MIN_ALIGN: usize = 16,
+    const MIN_BLOCK: usize = NODE_SIZE,
+> {
+    free_list: UnsafeCell<*mut FreeListNode>,
+    // Number of nodes on `free_list`, and the most it may hold (`with_max_free_nodes`)
+    // `free_list` 上的节点数，以及其最多可容纳的节点数（`with_max_free_nodes`）
+    free_nodes: UnsafeCell<usize>,
+    max_free_nodes: usize,
+    // The same blocks by ascending size, linked through `smaller` / `larger`, null-terminated
+    // 按大小升序排列的同一批块，通过 `smaller` / `larger` 链接，以 null 结尾
+    #[cfg(feature = "size-index")]
+    size_index: UnsafeCell<*mut FreeListNode>,
+    // LIFO bins for small blocks, linked through `next` only
+    // 小块的 LIFO Bin，仅通过 `next` 链接
+    #[cfg(feature = "small-bins")]
+    bins: UnsafeCell<[*mut FreeListNode; SMALL_BINS.len()]>,
+    // LIFO bins for blocks smaller than a node header (see `MIN_BLOCK`), one per size in
+    // words, linked through `next` only
+    // 小于节点头的块（见 `MIN_BLOCK`）的 LIFO Bin，每个以字计的大小一个，仅通过 `next` 链接
+    tiny_bins: UnsafeCell<[*mut FreeListNode; TINY_BINS]>,
+    // Blocks freed by `dealloc_nomerge`, unsorted and unmerged until `compact`, linked
+    // through `next`, null-terminated
+    // 由 `dealloc_nomerge` 释放的块，在 `compact` 之前既不排序也不合并，
+    // 通过 `next` 链接，以 null 结尾
+    deferred: UnsafeCell<*mut FreeListNode>,
+    // Frees that found the sorted list at its cap (`with_max_free_nodes`), kept the same
+    // way as `deferred` until `compact` finds them room
+    // 遇到有序链表已达上限（`with_max_free_nodes`）的释放块，以与 `deferred` 相同的方式保存，
+    // 直到 `compact` 为它们找到空间
+    parked: UnsafeCell<*mut FreeListNode>,
+    // Latest contiguous run of pages obtained from `source`, handed back by `clear`
+    // 从 `source` 获取的最近一段连续页面，由 `clear` 归还
+    heap_start: UnsafeCell<usize>,
+    heap_end: UnsafeCell<usize>,
+    source: S,
+    // How many pages each `grow_memory` call requests
+    // 每次 `grow_memory` 调用申请的页数策略
+    growth: GrowthPolicy,
+    oom_hook: OomHook,
+    #[cfg(feature = "quota")]
+    quota: Quota,
+    #[cfg(feature = "stats")]
+    stats: core::cell::Cell<AllocStats>,
+    #[cfg(feature = "stats")]
+    high_water: HighWater,
+    // Tag and requested size of blocks from `alloc_tagged`, kept on the host heap
+    // `alloc_tagged` 分配的块的标签与请求大小，保存在宿主堆上
+    #[cfg(all(
+        feature = "alloc-tags",
+        debug_assertions,
+        not(any(target_arch = "wasm32", target_arch = "wasm64"))
+    ))]
+    tags: UnsafeCell<Tags>,
+}
 
-// Global State
-static mut FREE_LIST: *mut FreeListNode = EMPTY_FREE_LIST;
+#[cfg(all(
+    feature = "alloc-tags",
+    debug_assertions,
+    not(any(target_arch = "wasm32", target_arch = "wasm64"))
+))]
+type Tags = std::collections::BTreeMap<usize, (u32, usize)>;
+
+/// Runtime counters of a [`FreeListAllocator`] (`stats` feature).
+///
+/// [`FreeListAllocator`] 的运行时计数器（`stats` feature）。
+#[cfg(feature = "stats")]
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Default)]
+pub struct AllocStats {
+    /// Sum of all sizes requested through `alloc` and `realloc`.
+    ///
+    /// 通过 `alloc` 和 `realloc` 请求的大小总和。
+    pub bytes_requested: usize,
+    /// Requested bytes currently allocated and not yet freed.
+    ///
+    /// 当前已分配且尚未释放的请求字节数。
+    pub live_bytes: usize,
+    /// Number of successful calls to the memory source.
+    ///
+    /// 成功调用内存来源的次数。
+    pub grow_calls: usize,
+    /// Number of free blocks, counted when `stats` is called.
+    ///
+    /// 空闲块的数量，在调用 `stats` 时统计。
+    pub free_list_len: usize,
+    /// Number of times a freed block was merged with a neighbour.
+    ///
+    /// 释放的块与相邻块合并的次数。
+    pub merges: usize,
+}
 
 impl FreeListAllocator {
     pub const fn new() -> Self {
-        FreeListAllocator
+        Self::with_source(DefaultMemory)
     }
+}
 
-    /// Testing only: Reset the internal state.
-    /// Safety: usage is inherently unsafe if allocator is in use.
-    pub unsafe fn reset() {
+impl<S> FreeListAllocator<S> {
+    /// Create an allocator that obtains its pages from `source`.
+    ///
+    /// 创建一个从 `source` 获取页面的分配器。
+    pub const fn with_source(source: S) -> Self {
+        Self::with_min_align(source)
+    }
+}
+
+impl<S, const MIN_ALIGN: usize, const MIN_BLOCK: usize> FreeListAllocator<S, MIN_ALIGN, MIN_BLOCK> {
+    /// Create an allocator with a custom minimum alignment, e.g. 8 on memory-tight
+    /// embedded targets or 64 for wide SIMD:
+    /// `FreeListAllocator::<DefaultMemory, 8>::with_min_align(DefaultMemory)`.
+    ///
+    /// The lower bound is the alignment of `usize`, not the two-word size of a node header:
+    /// a header only needs its words aligned, and every block is still at least
+    /// [`NODE_SIZE`](crate::layout_math::NODE_SIZE) bytes, so `MIN_ALIGN = 8` is valid on
+    /// 64-bit targets.
+    ///
+    /// # Panics
+    /// If `MIN_ALIGN` is not a power of two or is smaller than the alignment of `usize`
+    /// (free-list headers are stored inside the blocks).
+    ///
+    /// 创建一个使用自定义最小对齐的分配器，例如在内存紧张的嵌入式目标上使用 8，
+    /// 或为宽 SIMD 使用 64：
+    /// `FreeListAllocator::<DefaultMemory, 8>::with_min_align(DefaultMemory)`。
+    ///
+    /// 下限是 `usize` 的对齐，而不是节点头的两个字大小：节点头只需要其各个字对齐，
+    /// 而每个块仍至少为 [`NODE_SIZE`](crate::layout_math::NODE_SIZE) 字节，
+    /// 因此在 64 位目标上 `MIN_ALIGN = 8` 是有效的。
+    ///
+    /// # Panics
+    /// 如果 `MIN_ALIGN` 不是 2 的幂，或小于 `usize` 的对齐（空闲链表头存储在块内）。
+    pub const fn with_min_align(source: S) -> Self {
+        assert!(
+            MIN_ALIGN.is_power_of_two() && MIN_ALIGN >= core::mem::align_of::<usize>(),
+            "MIN_ALIGN must be a power of two >= align_of::<usize>()"
+        );
+        FreeListAllocator {
+            free_list: UnsafeCell::new(EMPTY_FREE_LIST),
+            free_nodes: UnsafeCell::new(0),
+            max_free_nodes: usize::MAX,
+            #[cfg(feature = "size-index")]
+            size_index: UnsafeCell::new(null_mut()),
+            #[cfg(feature = "small-bins")]
+            bins: UnsafeCell::new([null_mut(); SMALL_BINS.len()]),
+            tiny_bins: UnsafeCell::new([null_mut(); TINY_BINS]),
+            deferred: UnsafeCell::new(null_mut()),
+            parked: UnsafeCell::new(null_mut()),
+            heap_start: UnsafeCell::new(0),
+            heap_end: UnsafeCell::new(0),
+            source,
+            growth: GrowthPolicy::EXACT,
+            oom_hook: OomHook::new(),
+            #[cfg(feature = "quota")]
+            quota: Quota::new(),
+            #[cfg(feature = "stats")]
+            stats: core::cell::Cell::new(AllocStats {
+                bytes_requested: 0,
+                live_bytes: 0,
+                grow_calls: 0,
+                free_list_len: 0,
+                merges: 0,
+            }),
+            #[cfg(feature = "stats")]
+            high_water: HighWater::new(),
+            #[cfg(all(
+                feature = "alloc-tags",
+                debug_assertions,
+                not(any(target_arch = "wasm32", target_arch = "wasm64"))
+            ))]
+            tags: UnsafeCell::new(Tags::new()),
+        }
+    }
+
+    /// Use `policy` to size this allocator's memory growth, e.g. [`GrowthPolicy::DOUBLING`]
+    /// for workloads that keep growing the heap. Pages grown beyond the request join the
+    /// free list.
+    ///
+    /// 使用 `policy` 决定此分配器的内存增长大小，例如对持续扩大堆的负载使用
+    /// [`GrowthPolicy::DOUBLING`]。超出请求的增长页面会加入空闲链表。
+    pub const fn with_growth(mut self, policy: GrowthPolicy) -> Self {
+        self.growth = policy;
+        self
+    }
+
+    /// Cap the sorted free list at `max` nodes, bounding the list walk of every allocation
+    /// and free, e.g. for latency-sensitive WASM handlers. A freed block that merges with a
+    /// neighbour never counts against the cap; a block that would need a node of its own
+    /// while the list is full is parked aside instead, where allocations do not search it
+    /// (see [`parked_blocks`](Self::parked_blocks)). Parked blocks are merged back by
+    /// [`compact`](Self::compact), which also runs on its own before the allocator grows
+    /// memory, as far as merges and free nodes make room. Remainders the allocator carves
+    /// itself (from grown pages, alignment padding or shrinking) are never capped.
+    ///
+    /// # Panics
+    /// If `max` is zero.
+    ///
+    /// 将有序空闲链表限制为最多 `max` 个节点，从而限定每次分配与释放遍历链表的长度，
+    /// 例如用于对延迟敏感的 WASM 处理函数。与相邻块合并的释放块永远不计入上限；
+    /// 链表已满时，需要独立节点的块会被暂存到一旁，分配时不会搜索它们
+    /// （见 [`parked_blocks`](Self::parked_blocks)）。暂存的块由 [`compact`](Self::compact)
+    /// 合并回链表，分配器在增长内存之前也会自动执行这一步，只要合并与空闲节点能腾出空间。
+    /// 分配器自己切出的剩余部分（来自增长的页面、对齐填充或缩小）永远不受上限限制。
+    ///
+    /// # Panics
+    /// 如果 `max` 为零。
+    pub const fn with_max_free_nodes(mut self, max: usize) -> Self {
+        assert!(max > 0, "the free list needs room for at least one node");
+        self.max_free_nodes = max;
+        self
+    }
+
+    /// Snapshot of the allocator counters.
+    ///
+    /// 分配器计数器的快照。
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> AllocStats {
+        let mut stats = self.stats.get();
+        // SAFETY: Only walks the list, which is always well formed between calls
+        // SAFETY: 仅遍历链表，链表在调用之间总是完整的
+        stats.free_list_len = unsafe { self.free_list_len() };
+        stats
+    }
+
+    /// Peak heap usage so far (`stats` feature): the bytes grown from the memory source minus
+    /// the lower part of the latest growth that the block triggering it did not need. Taken
+    /// whenever the heap grows, and kept after the memory is freed or trimmed, so it can
+    /// size the initial memory of a workload. Cleared by [`reset`](Self::reset).
+    ///
+    /// 迄今为止的堆使用峰值（`stats` feature）：从内存来源增长的字节数，减去最近一次增长中
+    /// 触发它的块用不到的低地址部分。在每次堆增长时记录，内存被释放或修剪后仍保留，
+    /// 因此可用于确定某个工作负载的初始内存大小。由 [`reset`](Self::reset) 清零。
+    #[cfg(feature = "stats")]
+    pub fn peak_bytes(&self) -> usize {
+        self.high_water.peak()
+    }
+
+    /// Number of blocks in the free list. Walks the list without allocating.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 空闲链表中块的数量。遍历链表，不进行分配。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn free_list_len(&self) -> usize {
+        unsafe { self.free_blocks() }.count()
+    }
+
+    /// Size in bytes of the largest free block, or 0 if the free list is empty.
+    /// Many free blocks but a small largest block indicates fragmentation.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 最大空闲块的字节大小，空闲链表为空时为 0。
+    /// 空闲块很多但最大块很小说明存在碎片。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn largest_free_block(&self) -> usize {
+        unsafe { self.free_blocks() }
+            .map(|(_, size)| size)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Check that the sorted free list is well-formed: every block is `MIN_ALIGN`-aligned,
+    /// at least a node header in size and a multiple of `MIN_ALIGN`, and lies strictly below
+    /// the previous one without touching it (adjacent blocks must have been merged).
+    /// Blocks waiting in the `small-bins` bins or for [`compact`](Self::compact) are not
+    /// checked.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 检查有序空闲链表是否完好：每个块都按 `MIN_ALIGN` 对齐，大小至少为节点头且为
+    /// `MIN_ALIGN` 的倍数，并严格位于前一个块之下且不与其相接（相邻的块必须已被合并）。
+    /// 不检查在 `small-bins` 的 Bin 中或等待 [`compact`](Self::compact) 的块。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn check_integrity(&self) -> Result<(), IntegrityError> {
+        let mut prev: Option<usize> = None;
+        for (addr, size) in unsafe { self.free_blocks() } {
+            if !addr.is_multiple_of(MIN_ALIGN) {
+                return Err(IntegrityError::Misaligned { addr });
+            }
+            if size < NODE_SIZE || !size.is_multiple_of(MIN_ALIGN) {
+                return Err(IntegrityError::BadSize { addr, size });
+            }
+            if let Some(prev) = prev {
+                if addr >= prev {
+                    return Err(IntegrityError::NotDescending { addr, prev });
+                }
+                match (addr + size).cmp(&prev) {
+                    core::cmp::Ordering::Greater => {
+                        return Err(IntegrityError::Overlap { addr, prev });
+                    }
+                    core::cmp::Ordering::Equal => {
+                        return Err(IntegrityError::Unmerged { addr, prev });
+                    }
+                    core::cmp::Ordering::Less => {}
+                }
+            }
+            prev = Some(addr);
+        }
+        debug_assert_eq!(
+            unsafe { self.free_list_len() },
+            unsafe { *self.free_nodes.get() },
+            "free-list node count is out of sync"
+        );
+        #[cfg(feature = "size-index")]
         unsafe {
-            FREE_LIST = EMPTY_FREE_LIST;
+            self.check_index()?;
         }
+        Ok(())
     }
-}
 
-const EMPTY_FREE_LIST: *mut FreeListNode = usize::MAX as *mut FreeListNode;
+    /// The size index must hold exactly the blocks of the sorted list, by ascending size,
+    /// with consistent back links.
+    ///
+    /// 大小索引必须恰好包含有序链表中的块，按大小升序排列，且反向链接一致。
+    #[cfg(feature = "size-index")]
+    unsafe fn check_index(&self) -> Result<(), IntegrityError> {
+        unsafe {
+            let listed = self.free_list_len();
+            let mut indexed = 0;
+            let mut prev: *mut FreeListNode = null_mut();
+            let mut node = *self.size_index.get();
+            while !node.is_null() {
+                let addr = node as usize;
+                // Bounding the walk by the list length also catches cycles
+                // 以链表长度限制遍历次数，同时可以发现环
+                indexed += 1;
+                if indexed > listed
+                    || (*node).smaller != prev
+                    || (!prev.is_null() && (*node).size < (*prev).size)
+                    || !self.free_blocks().any(|(block, _)| block == addr)
+                {
+                    return Err(IntegrityError::BadIndex { addr });
+                }
+                prev = node;
+                node = (*node).larger;
+            }
+            if indexed < listed {
+                for (addr, _) in self.free_blocks() {
+                    let mut node = *self.size_index.get();
+                    while !node.is_null() && node as usize != addr {
+                        node = (*node).larger;
+                    }
+                    if node.is_null() {
+                        return Err(IntegrityError::Unindexed { addr });
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
 
-/// Stored at the beginning of each free segment.
-/// Note: This could be packed into 1 word (using low bits to mark this case,
-/// and only using the second word when allocation size is larger than 1 word).
-///
-/// 存储在每个空闲段的开头。
-/// 注意：可以将其放入 1 个字中（使用低位标记该情况，
-/// 然后仅在分配大小大于 1 个字时使用第二个字）
-struct FreeListNode {
-    next: *mut FreeListNode,
-    size: usize,
-}
+    /// Debug-only double-free check: the block being freed must not overlap any free block
+    /// (including blocks waiting in the `small-bins` bins or for `compact`).
+    ///
+    /// 仅调试模式下的重复释放检查：被释放的块不得与任何空闲块重叠
+    /// （包括在 `small-bins` 的 Bin 中或等待 `compact` 的块）。
+    #[cfg(debug_assertions)]
+    unsafe fn debug_assert_not_free(&self, ptr: *mut u8, size: usize) {
+        let start = ptr as usize;
+        let end = start + size;
+        unsafe {
+            let mut node = *self.free_list.get();
+            while node != EMPTY_FREE_LIST {
+                let node_start = node as usize;
+                let node_end = node_start + node_size(node);
+                debug_assert!(
+                    end <= node_start || node_end <= start,
+                    "double free or free into a freed region: {ptr:p}"
+                );
+                node = node_next(node);
+            }
+            #[cfg(feature = "small-bins")]
+            for (index, &head) in (*self.bins.get()).iter().enumerate() {
+                let mut node = head;
+                while !node.is_null() {
+                    let node_start = node as usize;
+                    debug_assert!(
+                        end <= node_start || node_start + SMALL_BINS[index] <= start,
+                        "double free or free into a freed region: {ptr:p}"
+                    );
+                    node = (*node).next;
+                }
+            }
+            for (index, &head) in (*self.tiny_bins.get()).iter().enumerate() {
+                let mut node = head;
+                while !node.is_null() {
+                    let node_start = node as usize;
+                    debug_assert!(
+                        end <= node_start || node_start + tiny_size(index) <= start,
+                        "double free or free into a freed region: {ptr:p}"
+                    );
+                    node = (*node).next;
+                }
+            }
+            for head in [*self.deferred.get(), *self.parked.get()] {
+                let mut node = head;
+                while !node.is_null() {
+                    let node_start = node as usize;
+                    debug_assert!(
+                        end <= node_start || node_start + node_size(node) <= start,
+                        "double free or free into a freed region: {ptr:p}"
+                    );
+                    node = node_next(node);
+                }
+            }
+        }
+    }
 
-const NODE_SIZE: usize = core::mem::size_of::<FreeListNode>();
+    /// `(address, size)` of every block on the sorted free list, in list order (descending
+    /// addresses). Blocks waiting in the `small-bins` bins or for [`compact`](Self::compact)
+    /// are not included.
+    ///
+    /// # Safety
+    /// The allocator must not be used while the iterator is alive.
+    ///
+    /// 有序空闲链表上每个块的 `(地址, 大小)`，按链表顺序（地址降序）。
+    /// 不包括在 `small-bins` 的 Bin 中或等待 [`compact`](Self::compact) 的块。
+    ///
+    /// # Safety
+    /// 迭代器存活期间不得使用此分配器。
+    pub unsafe fn free_blocks(&self) -> impl Iterator<Item = (usize, usize)> {
+        let mut node = unsafe { *self.free_list.get() };
+        core::iter::from_fn(move || {
+            if node == EMPTY_FREE_LIST {
+                return None;
+            }
+            // SAFETY: every node reachable from the list head is a valid free block
+            // SAFETY: 从链表头可达的每个节点都是有效的空闲块
+            let block = unsafe { (node as usize, node_size(node)) };
+            node = unsafe { node_next(node) };
+            Some(block)
+        })
+    }
 
-// Safety: No one else owns the raw pointer (conceptually), logic is same.
-unsafe impl Send for FreeListAllocator {}
+    /// `(address, size)` of every block parked because the free list was at its cap (see
+    /// [`with_max_free_nodes`](Self::with_max_free_nodes)), in no particular order.
+    ///
+    /// # Safety
+    /// The allocator must not be used while the iterator is alive.
+    ///
+    /// 因空闲链表已达上限而被暂存的每个块的 `(地址, 大小)`（见
+    /// [`with_max_free_nodes`](Self::with_max_free_nodes)），顺序不定。
+    ///
+    /// # Safety
+    /// 迭代器存活期间不得使用此分配器。
+    pub unsafe fn parked_blocks(&self) -> impl Iterator<Item = (usize, usize)> {
+        let mut node = unsafe { *self.parked.get() };
+        core::iter::from_fn(move || {
+            if node.is_null() {
+                return None;
+            }
+            // SAFETY: every parked node is a valid free block
+            // SAFETY: 每个暂存的节点都是有效的空闲块
+            let block = unsafe { (node as usize, node_size(node)) };
+            node = unsafe { node_next(node) };
+            Some(block)
+        })
+    }
 
-unsafe impl GlobalAlloc for FreeListAllocator {
-    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        // 1. Force fixed alignment to 16 bytes (covering u8 to u128/v128)
-        // This saves you from complex dynamic alignment logic reading layout.align()
-        // 1. 强制固定对齐为 16 字节 (覆盖 u8 到 u128/v128)
-        // 这样你就不用读取 layout.align() 来做复杂的动态对齐逻辑了
-        const MIN_ALIGN: usize = 16;
-
-        // 2. If user requests more aggressive alignment (e.g. 4KB page alignment), must handle or fail
-        // For size, you can choose not to support alignment > 16 (return null or panic)
-        // 2. 如果用户请求了更变态的对齐 (比如 4KB 对齐的页)，必须处理或失败
-        // 为了体积，你可以选择直接不支持超过 16 的对齐（直接返回 null 或 panic）
-        if layout.align() > MIN_ALIGN {
-            return null_mut();
+    /// Live requested bytes per tag among the blocks allocated with
+    /// [`alloc_tagged`](Self::alloc_tagged) and not freed yet, sorted by tag. Calling it at
+    /// shutdown lists what each category leaked. Always empty without the `alloc-tags`
+    /// feature and in release builds.
+    ///
+    /// 以 [`alloc_tagged`](Self::alloc_tagged) 分配且尚未释放的块中，每个标签的存活请求字节数，
+    /// 按标签排序。在程序结束时调用即可列出每个类别泄漏的内存。
+    /// 未开启 `alloc-tags` feature 或在 release 构建中总是为空。
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    pub fn leaked_by_tag(&self) -> impl Iterator<Item = (u32, usize)> {
+        // SAFETY: Only reads the table, which no other call is borrowing
+        #[cfg(all(feature = "alloc-tags", debug_assertions))]
+        let tagged = unsafe { &*self.tags.get() }.values().copied();
+        #[cfg(not(all(feature = "alloc-tags", debug_assertions)))]
+        let tagged = core::iter::empty::<(u32, usize)>();
+
+        let mut leaked = std::collections::BTreeMap::new();
+        for (tag, size) in tagged {
+            *leaked.entry(tag).or_insert(0) += size;
+        }
+        leaked.into_iter()
+    }
+
+    /// Record `tag` and `size` for the block at `ptr`.
+    ///
+    /// 为 `ptr` 处的块记录 `tag` 与 `size`。
+    #[cfg(all(
+        feature = "alloc-tags",
+        debug_assertions,
+        not(any(target_arch = "wasm32", target_arch = "wasm64"))
+    ))]
+    fn retag(&self, ptr: *mut u8, tag: u32, size: usize) {
+        // The table is moved out while it may allocate, so a nested call made by the host
+        // allocator (if it is this allocator) never sees it borrowed
+        // 表可能分配内存时先将其移出，使宿主分配器（若正是此分配器）发起的嵌套调用
+        // 永远不会看到它处于借用状态
+        let mut tags = self.take_tags();
+        tags.insert(ptr as usize, (tag, size));
+        unsafe { *self.tags.get() = tags };
+    }
+
+    /// Forget the tag of the block at `ptr`, returning it with the recorded size.
+    ///
+    /// 忘记 `ptr` 处的块的标签，并将其与记录的大小一起返回。
+    #[cfg(all(
+        feature = "alloc-tags",
+        debug_assertions,
+        not(any(target_arch = "wasm32", target_arch = "wasm64"))
+    ))]
+    fn untag(&self, ptr: *mut u8) -> Option<(u32, usize)> {
+        if unsafe { &*self.tags.get() }.is_empty() {
+            return None;
         }
+        let mut tags = self.take_tags();
+        let entry = tags.remove(&(ptr as usize));
+        unsafe { *self.tags.get() = tags };
+        entry
+    }
 
-        // 3. Calculate size: round up to multiple of 16
-        // Assume NODE_SIZE is also 16 bytes or smaller
-        // 3. 计算大小：向上取整到 16 的倍数
-        // 假设 NODE_SIZE 也是 16 字节或更小
-        let size = layout.size().max(NODE_SIZE);
-        // Fast bitwise round up to 16
-        // 快速位运算取整 (等同于 round_up to 16)
-        let size = (size + 15) & !15;
+    #[cfg(all(
+        feature = "alloc-tags",
+        debug_assertions,
+        not(any(target_arch = "wasm32", target_arch = "wasm64"))
+    ))]
+    fn take_tags(&self) -> Tags {
+        core::mem::take(unsafe { &mut *self.tags.get() })
+    }
 
-        let mut free_list: *mut *mut FreeListNode = ptr::addr_of_mut!(FREE_LIST);
-        // Search the free list
-        // 搜索空闲链表
-        loop {
-            // SAFETY: Dereferencing free_list is safe
-            // SAFETY: 解引用 free_list 是安全的
-            if unsafe { *free_list == EMPTY_FREE_LIST } {
-                break;
+    /// Register a hook called with the failing `Layout` right before `alloc` returns null.
+    /// Pass `None` to remove it.
+    ///
+    /// 注册一个回调，在 `alloc` 返回 null 之前以失败的 `Layout` 调用。
+    /// 传入 `None` 以移除。
+    pub fn set_oom_hook(&self, hook: Option<fn(Layout)>) {
+        self.oom_hook.set(hook);
+    }
+
+    /// Cap the bytes this allocator has handed out and not yet taken back (`quota` feature).
+    /// Once a request would exceed `limit`, `alloc` returns null without growing memory.
+    /// Pass `None` to remove the cap.
+    ///
+    /// 限制此分配器已分配出去且尚未收回的字节数（`quota` feature）。
+    /// 一旦请求会超出 `limit`，`alloc` 将返回 null 且不会增长内存。
+    /// 传入 `None` 以移除限制。
+    #[cfg(feature = "quota")]
+    pub fn set_quota(&self, limit: Option<usize>) {
+        self.quota.set_limit(limit);
+    }
+
+    /// Bytes currently counted against the quota (`quota` feature).
+    ///
+    /// 当前计入配额的字节数（`quota` feature）。
+    #[cfg(feature = "quota")]
+    pub fn quota_used(&self) -> usize {
+        self.quota.live()
+    }
+
+    /// Testing only: Reset the internal state.
+    /// The allocator forgets every page it obtained; use [`clear`](Self::clear) to reuse them.
+    ///
+    /// # Safety
+    /// Usage is inherently unsafe if allocator is in use.
+    ///
+    /// 仅测试用：重置内部状态。
+    /// 分配器会忘记所有已获取的页面；如需复用它们，请使用 [`clear`](Self::clear)。
+    ///
+    /// # Safety
+    /// 如果分配器正在使用，未定义的行为。
+    pub unsafe fn reset(&self) {
+        unsafe {
+            *self.free_list.get() = EMPTY_FREE_LIST;
+            *self.free_nodes.get() = 0;
+            #[cfg(feature = "size-index")]
+            {
+                *self.size_index.get() = null_mut();
             }
+            #[cfg(feature = "small-bins")]
+            {
+                *self.bins.get() = [null_mut(); SMALL_BINS.len()];
+            }
+            *self.tiny_bins.get() = [null_mut(); TINY_BINS];
+            *self.deferred.get() = null_mut();
+            *self.parked.get() = null_mut();
+            *self.heap_start.get() = 0;
+            *self.heap_end.get() = 0;
+        }
+        #[cfg(feature = "stats")]
+        {
+            self.stats.set(AllocStats::default());
+            self.high_water.clear();
+        }
+        #[cfg(feature = "quota")]
+        self.quota.clear();
+        #[cfg(all(
+            feature = "alloc-tags",
+            debug_assertions,
+            not(any(target_arch = "wasm32", target_arch = "wasm64"))
+        ))]
+        self.take_tags();
+    }
 
-            let node = unsafe { *free_list };
-            let node_size = unsafe { (*node).size };
+    /// Deallocate everything at once: the free list is emptied and the pages owned by the
+    /// allocator become a single free block again. Host memory is not touched.
+    ///
+    /// Only the latest contiguous run of pages is tracked; if the memory source ever
+    /// returned pages that do not follow the previous ones, earlier pages are not reclaimed.
+    ///
+    /// # Safety
+    /// All outstanding pointers from this allocator are invalidated.
+    ///
+    /// 一次性释放所有内存：清空空闲链表，分配器拥有的页面重新成为单个空闲块。
+    /// 不会触碰宿主内存。
+    ///
+    /// 仅跟踪最近一段连续页面；如果内存来源曾返回不紧接前一段的页面，
+    /// 更早的页面不会被回收。
+    ///
+    /// # Safety
+    /// 此分配器分配出去的所有指针都将失效。
+    pub unsafe fn clear(&self) {
+        unsafe {
+            *self.free_list.get() = EMPTY_FREE_LIST;
+            *self.free_nodes.get() = 0;
+            #[cfg(feature = "size-index")]
+            {
+                *self.size_index.get() = null_mut();
+            }
+            #[cfg(feature = "small-bins")]
+            {
+                *self.bins.get() = [null_mut(); SMALL_BINS.len()];
+            }
+            *self.tiny_bins.get() = [null_mut(); TINY_BINS];
+            *self.deferred.get() = null_mut();
+            *self.parked.get() = null_mut();
+            let start = *self.heap_start.get();
+            let end = *self.heap_end.get();
+            if end > start {
+                self.free_block(start as *mut u8, end - start);
+            }
+        }
+        record!(self, s => s.live_bytes = 0);
+        #[cfg(feature = "quota")]
+        self.quota.clear();
+        #[cfg(all(
+            feature = "alloc-tags",
+            debug_assertions,
+            not(any(target_arch = "wasm32", target_arch = "wasm64"))
+        ))]
+        self.take_tags();
+    }
 
-            if size <= node_size {
-                let remaining = node_size - size;
-                // If remaining space is large enough, keep it in the list
-                // 如果剩余空间足够大，我们将其保留在链表中
-                if remaining >= NODE_SIZE {
-                    unsafe {
-                        (*node).size = remaining;
-                        return (node as *mut u8).add(remaining);
-                    }
-                } else {
-                    // Otherwise, allocate the whole block
-                    // 否则，整个块都分配出去
-                    unsafe {
-                        *free_list = (*node).next;
-                        return node as *mut u8;
+    /// Number of usable bytes in the block at `ptr`, allocated with `layout`.
+    /// Always `>= layout.size()`; callers may use the slack without reallocating.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`.
+    ///
+    /// `ptr` 处以 `layout` 分配的块中的可用字节数。
+    /// 总是 `>= layout.size()`；调用者可以直接使用多余空间而无需重新分配。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配。
+    pub unsafe fn usable_size(&self, _ptr: *mut u8, layout: Layout) -> usize {
+        if layout.size() == 0 {
+            return 0;
+        }
+        Self::block_size_for(layout)
+    }
+
+    /// Size of the block an allocation with `layout` would carve from the heap, without
+    /// allocating: the rounded-up size, or the bin size for binned requests. `0` for
+    /// zero-sized layouts, which use no heap memory.
+    ///
+    /// 不进行分配，返回以 `layout` 分配时将从堆中切出的块大小：向上取整后的大小，
+    /// 对分箱请求则为 Bin 的大小。零大小的布局不占用堆内存，返回 `0`。
+    pub fn block_size(&self, layout: Layout) -> usize {
+        if layout.size() == 0 {
+            return 0;
+        }
+        Self::block_size_for(layout)
+    }
+
+    /// Address range `[start, end)` of the pages this allocator owns, computed from the
+    /// pages it grew. Every live block lies inside it, so a host can copy this span to
+    /// checkpoint the heap. `(0, 0)` before the first allocation.
+    ///
+    /// Like [`clear`](Self::clear), only the latest contiguous run of pages is tracked.
+    ///
+    /// # Safety
+    /// Must not race with an allocation on this allocator.
+    ///
+    /// 此分配器拥有的页面的地址范围 `[start, end)`，由其增长的页面计算得出。
+    /// 所有存活块都位于其中，因此宿主可以复制这段内存为堆建立检查点。
+    /// 首次分配之前为 `(0, 0)`。
+    ///
+    /// 与 [`clear`](Self::clear) 一样，仅跟踪最近一段连续页面。
+    ///
+    /// # Safety
+    /// 不得与此分配器上的分配同时进行。
+    pub unsafe fn heap_extent(&self) -> (usize, usize) {
+        unsafe { (*self.heap_start.get(), *self.heap_end.get()) }
+    }
+
+    /// Carve `size` bytes out of the node referenced by `link`, which must be large enough.
+    /// The block is taken from the high end of the node so the node itself stays in place.
+    ///
+    /// 从 `link` 指向的节点中切出 `size` 字节，该节点必须足够大。
+    /// 块从节点的高地址端切出，因此节点本身保持原位。
+    unsafe fn take_from_node(&self, link: *mut *mut FreeListNode, size: usize) -> *mut u8 {
+        unsafe {
+            let node = read_link(link);
+            let node_size = node_size(node);
+            let remaining = node_size - size;
+            self.index_remove(node);
+            // If remaining space is large enough, keep it in the list
+            // 如果剩余空间足够大，我们将其保留在链表中
+            if remaining >= NODE_SIZE {
+                set_node_size(node, remaining);
+                self.index_insert(node);
+                let block = (node as *mut u8).add(remaining);
+                // A node shrinking to one word hands out its old `size` word
+                // 缩小为单字的节点会分配出其原来的 `size` 字
+                #[cfg(feature = "poison")]
+                poison::check(block, header_len(node_size).saturating_sub(remaining), size);
+                block
+            } else {
+                // Otherwise, allocate the whole block
+                // 否则，整个块都分配出去
+                write_link(link, node_next(node));
+                *self.free_nodes.get() -= 1;
+                #[cfg(feature = "poison")]
+                poison::check(node as *mut u8, header_len(node_size), size);
+                node as *mut u8
+            }
+        }
+    }
+
+    /// Carve `size` bytes out of the first block on the deferred list that holds them, the
+    /// same way `take_from_node` does; the remainder stays deferred. Null if none fits.
+    ///
+    /// 从延迟链表中第一个能容纳 `size` 字节的块中切出块，方式与 `take_from_node` 相同；
+    /// 剩余部分仍留在延迟链表上。没有合适的块时返回 null。
+    unsafe fn take_deferred(&self, size: usize) -> *mut u8 {
+        unsafe {
+            let mut link = self.deferred.get();
+            while !read_link(link).is_null() {
+                let node = read_link(link);
+                let node_size = node_size(node);
+                if node_size >= size {
+                    let remaining = node_size - size;
+                    if remaining >= NODE_SIZE {
+                        set_node_size(node, remaining);
+                        let block = (node as *mut u8).add(remaining);
+                        #[cfg(feature = "poison")]
+                        poison::check(block, header_len(node_size).saturating_sub(remaining), size);
+                        return block;
                     }
+                    write_link(link, node_next(node));
+                    #[cfg(feature = "poison")]
+                    poison::check(node as *mut u8, header_len(node_size), size);
+                    return node as *mut u8;
                 }
+                link = ptr::addr_of_mut!((*node).next);
             }
-            // SAFETY: Move to next node.
-            // SAFETY: 移动到下一个节点。
-            unsafe {
-                free_list = ptr::addr_of_mut!((*node).next);
+            null_mut()
+        }
+    }
+
+    /// Carve `size` bytes out of `node`, found through the size index. Only a node used up
+    /// whole leaves the sorted list, so only then is its link looked up by walking the list.
+    ///
+    /// 从通过大小索引找到的 `node` 中切出 `size` 字节。只有被整块用尽的节点才会离开有序链表，
+    /// 因此只有这时才需要遍历链表查找它的链接。
+    #[cfg(feature = "size-index")]
+    unsafe fn take_indexed(&self, node: *mut FreeListNode, size: usize) -> *mut u8 {
+        unsafe {
+            let mut node = node;
+            let link = if (*node).size - size >= NODE_SIZE {
+                // The node stays where it is, so a local copy stands in for its link
+                // 节点保持原位，因此用一个局部副本代替它的链接
+                ptr::addr_of_mut!(node)
+            } else {
+                let mut link = self.free_list.get();
+                while *link != node {
+                    link = ptr::addr_of_mut!((**link).next);
+                }
+                link
+            };
+            self.take_from_node(link, size)
+        }
+    }
+
+    /// Link `node` into the size index, in front of the first block at least as large.
+    /// Does nothing without the `size-index` feature.
+    ///
+    /// 将 `node` 链入大小索引，位于第一个不小于它的块之前。
+    /// 未开启 `size-index` feature 时不执行任何操作。
+    #[inline(always)]
+    unsafe fn index_insert(&self, node: *mut FreeListNode) {
+        #[cfg(feature = "size-index")]
+        unsafe {
+            let mut smaller = null_mut();
+            let mut larger = *self.size_index.get();
+            while !larger.is_null() && (*larger).size < (*node).size {
+                smaller = larger;
+                larger = (*larger).larger;
+            }
+            (*node).smaller = smaller;
+            (*node).larger = larger;
+            if smaller.is_null() {
+                *self.size_index.get() = node;
+            } else {
+                (*smaller).larger = node;
+            }
+            if !larger.is_null() {
+                (*larger).smaller = node;
             }
         }
+        #[cfg(not(feature = "size-index"))]
+        let _ = node;
+    }
 
-        // No space found in free list.
-        // 未在空闲链表中找到空间。
-        let requested_bytes = round_up(size, PAGE_SIZE);
-        // SAFETY: Call global grow_memory (shimmed on non-wasm)
-        let previous_page_count = unsafe { crate::grow_memory(requested_bytes / PAGE_SIZE) };
-        if previous_page_count == usize::MAX {
-            return null_mut();
+    /// Unlink `node` from the size index. Must run before its header is overwritten.
+    /// Does nothing without the `size-index` feature.
+    ///
+    /// 将 `node` 从大小索引中摘除。必须在其节点头被覆盖之前执行。
+    /// 未开启 `size-index` feature 时不执行任何操作。
+    #[inline(always)]
+    unsafe fn index_remove(&self, node: *mut FreeListNode) {
+        #[cfg(feature = "size-index")]
+        unsafe {
+            let (smaller, larger) = ((*node).smaller, (*node).larger);
+            if smaller.is_null() {
+                *self.size_index.get() = larger;
+            } else {
+                (*smaller).larger = larger;
+            }
+            if !larger.is_null() {
+                (*larger).smaller = smaller;
+            }
         }
+        #[cfg(not(feature = "size-index"))]
+        let _ = node;
+    }
 
-        let ptr = (previous_page_count * PAGE_SIZE) as *mut u8;
-        // SAFETY: Recursive call to self to add new memory block.
-        // SAFETY: 递归调用自身，添加新的内存块。
+    /// Smallest block in the size index that holds `size` bytes, or null.
+    ///
+    /// 大小索引中能容纳 `size` 字节的最小块，没有则为 null。
+    #[cfg(feature = "size-index")]
+    unsafe fn index_fit(&self, size: usize) -> *mut FreeListNode {
         unsafe {
-            self.dealloc(
-                ptr,
-                Layout::from_size_align_unchecked(requested_bytes, PAGE_SIZE),
-            );
-            self.alloc(layout)
+            let mut node = *self.size_index.get();
+            while !node.is_null() && (*node).size < size {
+                node = (*node).larger;
+            }
+            node
         }
     }
 
-    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-        debug_assert!(ptr.align_offset(NODE_SIZE) == 0);
+    /// Count a new node on the sorted list. A `capped` block finding the list at its cap (see
+    /// [`with_max_free_nodes`](Self::with_max_free_nodes)) is parked instead, and `false`
+    /// tells the caller to leave the list alone.
+    ///
+    /// 为有序链表上的新节点计数。`capped` 的块若遇到链表已达上限（见
+    /// [`with_max_free_nodes`](Self::with_max_free_nodes)），则改为被暂存，
+    /// 返回 `false` 告知调用方不要改动链表。
+    #[inline(always)]
+    unsafe fn claim_node(&self, ptr: *mut FreeListNode, size: usize, capped: bool) -> bool {
+        unsafe {
+            let nodes = self.free_nodes.get();
+            if capped && *nodes >= self.max_free_nodes {
+                write_node(ptr, *self.parked.get(), size);
+                *self.parked.get() = ptr;
+                return false;
+            }
+            *nodes += 1;
+        }
+        true
+    }
+
+    /// Insert the `size`-byte block at `ptr` into the sorted free list, merging neighbours.
+    /// Used for internal remainders, which the node cap never applies to.
+    ///
+    /// 将 `ptr` 处 `size` 字节的块插入有序空闲链表，并合并相邻块。
+    /// 用于内部剩余部分，节点上限永远不作用于它们。
+    unsafe fn free_block(&self, ptr: *mut u8, size: usize) {
+        unsafe { self.free_block_at(self.free_list.get(), ptr, size, false) };
+    }
+
+    /// `free_block`, searching from `free_list`, which must link to a node above `ptr` or
+    /// be the list head, and parking the block if it is `capped` and needs a node while the
+    /// list is full. Returns the link to the node now holding the block, from which a lower
+    /// block can be inserted without walking the list again.
+    ///
+    /// 从 `free_list` 开始搜索的 `free_block`；`free_list` 必须链接到位于 `ptr` 之上的节点
+    /// 或为链表头。若块为 `capped` 且在链表已满时需要新节点，则将其暂存。
+    /// 返回指向现在容纳该块的节点的链接，从该处插入更低的块时无需再次遍历链表。
+    unsafe fn free_block_at(
+        &self,
+        mut free_list: *mut *mut FreeListNode,
+        ptr: *mut u8,
+        size: usize,
+        capped: bool,
+    ) -> *mut *mut FreeListNode {
+        debug_assert!(ptr.align_offset(MIN_ALIGN) == 0);
+        #[cfg(feature = "poison")]
+        unsafe {
+            poison::fill(ptr, size);
+        }
         let ptr = ptr as *mut FreeListNode;
-        let size = full_size(layout);
         // SAFETY: Pointer arithmetic
         // SAFETY: 指针算术
         // Used to merge with the next node if adjacent.
         // 用于在相邻时与下一个节点合并。
         let after_new = unsafe { offset_bytes(ptr, size) };
 
-        // SAFETY: Get static mutable pointer
-        // SAFETY: 获取静态可变指针
-        let mut free_list: *mut *mut FreeListNode = ptr::addr_of_mut!(FREE_LIST);
         // Insert into free list, sorted by pointer descending.
         // 插入到空闲链表中，该链表按指针降序存储。
         loop {
             // SAFETY: Dereference free_list to check if empty or compare address
             // SAFETY: 解引用 free_list 检查是否为空或比较地址
-            if unsafe { *free_list == EMPTY_FREE_LIST } {
+            let node = unsafe { read_link(free_list) };
+            if node == EMPTY_FREE_LIST {
+                if unsafe { !self.claim_node(ptr, size, capped) } {
+                    return free_list;
+                }
                 // SAFETY: Write new node and insert at head
                 // SAFETY: 写入新节点并插入链表头
                 unsafe {
-                    (*ptr).next = EMPTY_FREE_LIST;
-                    (*ptr).size = size;
-                    *free_list = ptr;
+                    write_node(ptr, EMPTY_FREE_LIST, size);
+                    write_link(free_list, ptr);
+                    self.index_insert(ptr);
                 }
-                return;
+                return free_list;
             }
 
             // SAFETY: *free_list is a valid node pointer because we checked EMPTY_FREE_LIST above
             // SAFETY: *free_list 是一个有效的节点指针，因为我们上面检查了 EMPTY_FREE_LIST
-            if unsafe { *free_list == after_new } {
+            if node == after_new {
                 // Merge new node into the node after it.
                 // 将新节点合并到此节点之后的节点中。
 
                 // SAFETY: Access fields
                 // SAFETY: 访问字段
-                let new_size = unsafe { size + (**free_list).size };
-                let next = unsafe { (**free_list).next };
+                let absorbed = unsafe { node_size(after_new) };
+                let new_size = size + absorbed;
+                let next = unsafe { node_next(after_new) };
+                unsafe { self.index_remove(after_new) };
+                // The absorbed node's header is now in the middle of a free block
+                // 被吸收节点的头部现在位于空闲块的中间
+                #[cfg(feature = "poison")]
+                unsafe {
+                    poison::fill(after_new as *mut u8, header_len(absorbed));
+                }
 
                 // SAFETY: Check next continuity
                 // SAFETY: 检查 next 连续性
-                if unsafe { next != EMPTY_FREE_LIST && offset_bytes(next, (*next).size) == ptr } {
+                if unsafe { next != EMPTY_FREE_LIST && offset_bytes(next, node_size(next)) == ptr }
+                {
                     // Merge into the node before this node, and the one after.
                     // 合并到此节点之前的节点，以及之后的节点。
                     // SAFETY: Update next size, remove current node
                     // SAFETY: 更新 next 的大小，移除当前节点
                     unsafe {
-                        (*next).size += new_size;
-                        *free_list = next;
+                        self.index_remove(next);
+                        set_node_size(next, node_size(next) + new_size);
+                        write_link(free_list, next);
+                        self.index_insert(next);
+                        *self.free_nodes.get() -= 1;
                     }
-                    return;
+                    record!(self, s => s.merges += 2);
+                    return free_list;
                 }
                 // Edit node in free list, move its position and update its size.
                 // 编辑空闲链表中的节点，移动其位置并更新其大小。
                 // SAFETY: Pointer operations
                 // SAFETY: 指针操作
                 unsafe {
-                    *free_list = ptr;
-                    (*ptr).size = new_size;
-                    (*ptr).next = next;
+                    write_link(free_list, ptr);
+                    write_node(ptr, next, new_size);
+                    self.index_insert(ptr);
                 }
-                return;
+                record!(self, s => s.merges += 1);
+                return free_list;
             }
 
-            if unsafe { *free_list < ptr } {
+            if node < ptr {
                 // If adjacent, merge to the end of current node
                 // 如果相邻，则合并到当前节点的末尾
                 // SAFETY: ptr comparison and offset_bytes are pointer arithmetic
                 // SAFETY: 这里的 ptr 比较和 offset_bytes 都是指针算术
-                if unsafe { offset_bytes(*free_list, (**free_list).size) == ptr } {
+                if unsafe { offset_bytes(node, node_size(node)) == ptr } {
                     // Merge into the node before this node (and potentially after).
                     // 合并到此节点之前的节点，以及之后的节点。
-                    // SAFETY: Only need to update size
-                    // SAFETY: 只需更新大小
+                    // SAFETY: Only need to update size (a one-word node gains its `size` word)
+                    // SAFETY: 只需更新大小（单字节点会获得其 `size` 字）
                     unsafe {
-                        (**free_list).size += size;
+                        self.index_remove(node);
+                        set_node_size(node, node_size(node) + size);
+                        self.index_insert(node);
                     }
+                    record!(self, s => s.merges += 1);
                     // Since we merged new node to the end of existing node, no need to update pointers, just change size.
                     // 因为我们将新节点合并到现有节点的末尾，所以不需要更新指针，只需更改大小。
-                    return;
+                    return free_list;
+                }
+                if unsafe { !self.claim_node(ptr, size, capped) } {
+                    return free_list;
                 }
                 // Create a new free list node
                 // 创建一个新的空闲链表节点
                 // SAFETY: List insertion
                 // SAFETY: 链表插入
                 unsafe {
-                    (*ptr).next = *free_list;
-                    (*ptr).size = size;
-                    *free_list = ptr;
+                    write_node(ptr, node, size);
+                    write_link(free_list, ptr);
+                    self.index_insert(ptr);
                 }
-                return;
+                return free_list;
             }
             // SAFETY: Move pointer
             // SAFETY: 移动指针
             unsafe {
-                free_list = ptr::addr_of_mut!((**free_list).next);
+                free_list = ptr::addr_of_mut!((*node).next);
+            }
+        }
+    }
+}
+
+impl Default for FreeListAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Summarizes the heap without allocating, so it can be printed from inside the allocator
+/// (e.g. to a WASM console). Walks the free list, so it must not run during another
+/// operation on the allocator, which single-threaded use already rules out.
+///
+/// 不进行分配地概括堆状态，因此可以在分配器内部打印（例如输出到 WASM 控制台）。
+/// 它会遍历空闲链表，因此不得在分配器的其他操作期间运行，单线程使用已保证这一点。
+impl<S, const MIN_ALIGN: usize, const MIN_BLOCK: usize> fmt::Debug
+    for FreeListAllocator<S, MIN_ALIGN, MIN_BLOCK>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (start, end) = unsafe { self.heap_extent() };
+        let (len, largest) = unsafe { (self.free_list_len(), self.largest_free_block()) };
+        f.debug_struct("FreeListAllocator")
+            .field("heap_start", &start)
+            .field("heap_end", &end)
+            .field("free_blocks", &len)
+            .field("largest_free_block", &largest)
+            .finish()
+    }
+}
+
+/// Broken free-list invariant reported by [`FreeListAllocator::check_integrity`].
+/// Addresses are those of the offending block and of the block before it in the list.
+///
+/// [`FreeListAllocator::check_integrity`] 报告的被破坏的空闲链表不变量。
+/// 地址分别为出错块以及链表中位于其前面的块的地址。
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum IntegrityError {
+    /// The block does not start at a multiple of `MIN_ALIGN`.
+    ///
+    /// 块的起始地址不是 `MIN_ALIGN` 的倍数。
+    Misaligned { addr: usize },
+    /// The block is smaller than a node header or not a multiple of `MIN_ALIGN`.
+    ///
+    /// 块小于节点头，或大小不是 `MIN_ALIGN` 的倍数。
+    BadSize { addr: usize, size: usize },
+    /// The block is not below the previous one (the list must be strictly descending).
+    ///
+    /// 块不在前一个块之下（链表必须严格降序）。
+    NotDescending { addr: usize, prev: usize },
+    /// The block runs into the previous one.
+    ///
+    /// 块与前一个块重叠。
+    Overlap { addr: usize, prev: usize },
+    /// The block ends exactly where the previous one starts but was not merged with it.
+    ///
+    /// 块恰好结束于前一个块的起始处，却没有与其合并。
+    Unmerged { addr: usize, prev: usize },
+    /// The block is on the sorted list but missing from the size index (`size-index`).
+    ///
+    /// 块在有序链表上，却不在大小索引中（`size-index`）。
+    #[cfg(feature = "size-index")]
+    Unindexed { addr: usize },
+    /// The size index is out of order at this block, has a broken back link here, or holds
+    /// it although it is not on the sorted list (`size-index`).
+    ///
+    /// 大小索引在此块处顺序错误、反向链接损坏，或包含了不在有序链表上的该块（`size-index`）。
+    #[cfg(feature = "size-index")]
+    BadIndex { addr: usize },
+}
+
+impl core::fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            IntegrityError::Misaligned { addr } => write!(f, "free block {addr:#x} is misaligned"),
+            IntegrityError::BadSize { addr, size } => {
+                write!(f, "free block {addr:#x} has invalid size {size}")
+            }
+            IntegrityError::NotDescending { addr, prev } => {
+                write!(f, "free block {addr:#x} is not below {prev:#x}")
+            }
+            IntegrityError::Overlap { addr, prev } => {
+                write!(f, "free block {addr:#x} overlaps {prev:#x}")
+            }
+            IntegrityError::Unmerged { addr, prev } => {
+                write!(
+                    f,
+                    "free block {addr:#x} is adjacent to {prev:#x} but not merged"
+                )
+            }
+            #[cfg(feature = "size-index")]
+            IntegrityError::Unindexed { addr } => {
+                write!(f, "free block {addr:#x} is missing from the size index")
+            }
+            #[cfg(feature = "size-index")]
+            IntegrityError::BadIndex { addr } => {
+                write!(f, "size index is corrupt at {addr:#x}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for IntegrityError {}
+
+// Keeps the `ONE_WORD` bit clear, so a one-word node at the end of the list still ends it
+// 保持 `ONE_WORD` 位为零，因此链表末尾的单字节点仍能结束链表
+const EMPTY_FREE_LIST: *mut FreeListNode = (usize::MAX & !ONE_WORD) as *mut FreeListNode;
+
+/// Low bit of `next` marking a one-word node with the `compact-header` feature: the block is
+/// exactly one word, so it has no `size` word. Blocks are at least word-aligned, so the bit
+/// is free in every link.
+///
+/// 开启 `compact-header` feature 时，`next` 中标记单字节点的最低位：该块恰好为一个字，
+/// 因此没有 `size` 字。块至少按字对齐，所以每个链接中的该位都是空闲的。
+#[cfg(all(feature = "compact-header", not(feature = "size-index")))]
+const ONE_WORD: usize = 1;
+#[cfg(any(not(feature = "compact-header"), feature = "size-index"))]
+const ONE_WORD: usize = 0;
+
+/// Stored at the beginning of each free segment.
+/// With the `compact-header` feature a one-word block only holds `next`, tagged with
+/// [`ONE_WORD`]; `size` is only present in larger blocks. Go through `node_next`,
+/// `node_size` and `write_node`, and through `read_link` / `write_link` for any link
+/// that may be the `next` word of a node.
+///
+/// 存储在每个空闲段的开头。
+/// 开启 `compact-header` feature 时，单字块只保存带有 [`ONE_WORD`] 标记的 `next`；
+/// `size` 仅存在于更大的块中。请通过 `node_next`、`node_size` 与 `write_node` 访问，
+/// 对可能是某个节点 `next` 字的链接则通过 `read_link` / `write_link` 访问。
+struct FreeListNode {
+    next: *mut FreeListNode,
+    size: usize,
+    // Neighbours in the size index (`size-index` feature)
+    // 大小索引中的相邻节点（`size-index` feature）
+    #[cfg(feature = "size-index")]
+    smaller: *mut FreeListNode,
+    #[cfg(feature = "size-index")]
+    larger: *mut FreeListNode,
+}
+
+// `layout_math::NODE_SIZE` describes this header (only its `next` word with `compact-header`)
+// `layout_math::NODE_SIZE` 描述的就是该节点头（开启 `compact-header` 时仅为其 `next` 字）
+#[cfg(any(not(feature = "compact-header"), feature = "size-index"))]
+const _: () = assert!(core::mem::size_of::<FreeListNode>() == NODE_SIZE);
+#[cfg(all(feature = "compact-header", not(feature = "size-index")))]
+const _: () = assert!(core::mem::size_of::<FreeListNode>() == 2 * NODE_SIZE);
+
+/// The node a link points to, without the [`ONE_WORD`] bit of the node holding the link.
+///
+/// 链接指向的节点，去掉持有该链接的节点的 [`ONE_WORD`] 位。
+#[inline(always)]
+unsafe fn read_link(link: *mut *mut FreeListNode) -> *mut FreeListNode {
+    unsafe { (*link).map_addr(|addr| addr & !ONE_WORD) }
+}
+
+/// Point `link` at `node`, keeping the [`ONE_WORD`] bit of the node holding the link.
+///
+/// 将 `link` 指向 `node`，并保留持有该链接的节点的 [`ONE_WORD`] 位。
+#[inline(always)]
+unsafe fn write_link(link: *mut *mut FreeListNode, node: *mut FreeListNode) {
+    unsafe { *link = node.map_addr(|addr| addr | ((*link).addr() & ONE_WORD)) }
+}
+
+#[inline(always)]
+unsafe fn node_next(node: *mut FreeListNode) -> *mut FreeListNode {
+    unsafe { read_link(ptr::addr_of_mut!((*node).next)) }
+}
+
+#[inline(always)]
+unsafe fn node_size(node: *mut FreeListNode) -> usize {
+    unsafe {
+        #[cfg(all(feature = "compact-header", not(feature = "size-index")))]
+        if (*node).next.addr() & ONE_WORD != 0 {
+            return WORD;
+        }
+        (*node).size
+    }
+}
+
+/// Write the header of a free `size`-byte block, in its one-word form when it fits.
+///
+/// 写入一个 `size` 字节空闲块的节点头，能放下时使用单字形式。
+#[inline(always)]
+unsafe fn write_node(node: *mut FreeListNode, next: *mut FreeListNode, size: usize) {
+    unsafe {
+        if ONE_WORD != 0 && size == WORD {
+            (*node).next = next.map_addr(|addr| addr | ONE_WORD);
+        } else {
+            (*node).next = next;
+            (*node).size = size;
+        }
+    }
+}
+
+/// Resize a free block in place, switching between the one-word and two-word forms.
+///
+/// 原地调整空闲块的大小，并在单字与双字形式之间切换。
+#[inline(always)]
+unsafe fn set_node_size(node: *mut FreeListNode, size: usize) {
+    unsafe { write_node(node, node_next(node), size) }
+}
+
+/// Bytes of a free `size`-byte block covered by its header.
+///
+/// `size` 字节的空闲块中被其节点头占用的字节数。
+#[cfg(feature = "poison")]
+#[inline(always)]
+const fn header_len(size: usize) -> usize {
+    let header = core::mem::size_of::<FreeListNode>();
+    if size < header { size } else { header }
+}
+
+// Safety: No one else owns the raw pointer (conceptually), logic is same.
+unsafe impl<S: Send, const MIN_ALIGN: usize, const MIN_BLOCK: usize> Send
+    for FreeListAllocator<S, MIN_ALIGN, MIN_BLOCK>
+{
+}
+
+unsafe impl<S: MemorySource, const MIN_ALIGN: usize, const MIN_BLOCK: usize> GlobalAlloc
+    for FreeListAllocator<S, MIN_ALIGN, MIN_BLOCK>
+{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.try_alloc(layout) }.map_or(null_mut(), NonNull::as_ptr);
+        trace!(
+            "alloc size={=usize} align={=usize} ptr={=usize:#x}",
+            layout.size(),
+            layout.align(),
+            ptr as usize
+        );
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        // Freshly grown pages are already zeroed (by WASM or the host mock, see
+        // `MemorySource::ZEROED`), so only blocks recycled from the free list need a memset.
+        // 新增长的页面已经是零（由 WASM 或宿主模拟保证，见 `MemorySource::ZEROED`），
+        // 因此只有从空闲链表回收的块才需要清零。
+        if layout.size() == 0 {
+            return dangling(layout);
+        }
+        #[cfg(feature = "quota")]
+        if !self.quota.fits(layout.size()) {
+            self.oom_hook.fire(layout);
+            return null_mut();
+        }
+        let (ptr, fresh) = unsafe { self.alloc_block(layout) };
+        if ptr.is_null() {
+            self.oom_hook.fire(layout);
+        } else {
+            if !fresh || !S::ZEROED {
+                unsafe { ptr::write_bytes(ptr, 0, layout.size()) };
             }
+            #[cfg(feature = "quota")]
+            self.quota.charge(layout.size());
+            record!(self, s => {
+                s.bytes_requested += layout.size();
+                s.live_bytes += layout.size();
+            });
         }
+        trace!(
+            "alloc size={=usize} align={=usize} ptr={=usize:#x}",
+            layout.size(),
+            layout.align(),
+            ptr as usize
+        );
+        ptr
     }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        trace!(
+            "dealloc size={=usize} ptr={=usize:#x}",
+            layout.size(),
+            ptr as usize
+        );
+        if layout.size() == 0 {
+            return;
+        }
+        unsafe { self.release(ptr, layout) };
+        if let Some(index) = Self::tiny_bin(layout) {
+            unsafe { self.push_tiny(ptr, index) };
+            return;
+        }
+        #[cfg(feature = "small-bins")]
+        if let Some(index) = Self::small_bin(layout) {
+            unsafe { self.push_bin(ptr, index) };
+            return;
+        }
+        unsafe { self.free_block_at(self.free_list.get(), ptr, Self::full_size(layout), true) };
+    }
+
     #[cfg(feature = "realloc")]
     unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // A tagged block keeps its tag wherever it moves
+        // 被标记的块无论移动到哪里都保留其标签
+        #[cfg(all(
+            feature = "alloc-tags",
+            debug_assertions,
+            not(any(target_arch = "wasm32", target_arch = "wasm64"))
+        ))]
+        let tag = self.untag(ptr);
+        let new_ptr = unsafe { self.realloc_block(ptr, layout, new_size) };
+        #[cfg(all(
+            feature = "alloc-tags",
+            debug_assertions,
+            not(any(target_arch = "wasm32", target_arch = "wasm64"))
+        ))]
+        match (tag, new_ptr.is_null()) {
+            (Some((tag, size)), true) => self.retag(ptr, tag, size),
+            (Some((tag, _)), false) => self.retag(new_ptr, tag, new_size),
+            (None, _) => {}
+        }
+        new_ptr
+    }
+}
+
+impl<S: MemorySource, const MIN_ALIGN: usize, const MIN_BLOCK: usize>
+    FreeListAllocator<S, MIN_ALIGN, MIN_BLOCK>
+{
+    /// Resizing logic behind `GlobalAlloc::realloc`.
+    ///
+    /// `GlobalAlloc::realloc` 背后的调整大小逻辑。
+    #[cfg(feature = "realloc")]
+    unsafe fn realloc_block(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // Zero-sized blocks own no memory: resizing from or to one allocates or frees
+        // 零大小的块不拥有内存：从零大小调整或调整为零大小即分配或释放
+        if layout.size() == 0 || new_size == 0 {
+            return unsafe { realloc_zero_sized(self, ptr, layout, new_size) };
+        }
+
+        // A size no `Layout` can describe cannot be rounded to a block without overflowing
+        // 任何 `Layout` 都无法描述的大小在取整为块时会溢出
+        let Ok(new_layout) = Layout::from_size_align(new_size, layout.align()) else {
+            return null_mut();
+        };
+
+        // Growing must fit in the quota whether it happens in place or by moving
+        // 无论原地扩容还是移动，增长都必须在配额之内
+        #[cfg(feature = "quota")]
+        if new_size > layout.size() && !self.quota.fits(new_size - layout.size()) {
+            self.oom_hook.fire(new_layout);
+            return null_mut();
+        }
+
+        // case A: Shrinking, or growing into the block's own slack. An over-aligned block
+        // starts at its aligned address with no header in front, so the tail after the new
+        // block size is MIN_ALIGN-aligned and the head keeps its alignment.
+        // case A: 缩小，或在块自身的余量内增长。超对齐的块从其对齐地址开始，前面没有头部，
+        // 因此新块大小之后的尾部按 MIN_ALIGN 对齐，头部也保持其对齐。
+        if Self::block_size_for(new_layout) <= Self::block_size_for(layout) {
+            unsafe { self.shrink(ptr, layout, new_size) };
+            return ptr;
+        }
+
+        // Binned blocks have a fixed class size and cannot grow in place: move them
+        // Bin 中的块大小固定，无法原地扩容：移动它们
+        #[cfg(feature = "small-bins")]
+        if Self::small_bin(layout).is_some() || Self::small_bin(new_layout).is_some() {
+            return unsafe { self.realloc_by_copy(ptr, layout, new_size) };
+        }
+
         // 1. Calculate original block size (consistent with alloc/dealloc)
         // 1. 计算原块大小 (与 alloc/dealloc 一致)
-        let old_size = full_size(layout);
+        let old_size = Self::full_size(layout);
         // 2. Calculate new block size (aligned)
         // 2. 计算新块大小 (对齐)
-        let new_full_size = (new_size.max(NODE_SIZE) + 15) & !15;
-
-        // case A: Shrinking
-        if new_full_size <= old_size {
-            let diff = old_size - new_full_size;
-            // If remaining space is large enough, split and free the remainder
-            // 如果剩余空间足够大，切分并释放剩余部分
-            if diff >= NODE_SIZE {
+        let new_full_size = Self::round_size(new_size);
+        let needed = new_full_size - old_size;
+
+        // case B: Growing into the following free block
+        // case B: 向后面的空闲块扩容
+        let mut prev = match unsafe { self.grow_into_next(ptr, old_size, needed) } {
+            Ok(()) => {
+                self.resized_in_place(layout.size(), new_size);
+                return ptr;
+            }
+            Err(prev) => prev,
+        };
+
+        // case C: Growing into the preceding free block.
+        // The first node below `ptr` is the only one that can end exactly at `ptr`.
+        // Carve the grown block from its high end (like `take_from_node`) and move the data
+        // down. Moving by `needed` keeps MIN_ALIGN alignment only, so skip higher alignments.
+        // case C: 向前面的空闲块扩容。
+        // 位于 `ptr` 之下的第一个节点是唯一可能恰好结束于 `ptr` 的节点。
+        // 从它的高地址端切出扩容后的块（与 `take_from_node` 相同），并将数据向下移动。
+        // 按 `needed` 移动只能保持 MIN_ALIGN 对齐，因此跳过更高的对齐要求。
+        if layout.align() <= MIN_ALIGN {
+            let ptr_node = ptr as *mut FreeListNode;
+            loop {
+                let curr = unsafe { read_link(prev) };
+                if curr == EMPTY_FREE_LIST || curr < ptr_node {
+                    break;
+                }
+                unsafe {
+                    prev = ptr::addr_of_mut!((*curr).next);
+                }
+            }
+
+            let curr = unsafe { read_link(prev) };
+            if curr != EMPTY_FREE_LIST
+                && unsafe {
+                    offset_bytes(curr, node_size(curr)) == ptr_node && node_size(curr) >= needed
+                }
+            {
                 unsafe {
-                    let remainder = ptr.add(new_full_size);
-                    // Construct a Layout for freeing
-                    // align=16 is safe because all our blocks are 16-aligned
-                    // 构造一个 Layout 用于释放
-                    // align=16 是安全的，因为我们所有的块都是 16 对齐
-                    let remainder_layout = Layout::from_size_align_unchecked(diff, 16);
-                    self.dealloc(remainder, remainder_layout);
+                    // A remainder too small for a node header is consumed with the block
+                    // 剩余部分太小放不下节点头时，随块一起被消耗
+                    let remaining = node_size(curr) - needed;
+                    self.index_remove(curr);
+                    let new_ptr = if remaining >= NODE_SIZE {
+                        set_node_size(curr, remaining);
+                        self.index_insert(curr);
+                        ptr.sub(needed)
+                    } else {
+                        write_link(prev, node_next(curr));
+                        *self.free_nodes.get() -= 1;
+                        curr as *mut u8
+                    };
+                    ptr::copy(ptr, new_ptr, copy_span(layout, new_size));
+                    self.resized_in_place(layout.size(), new_size);
+                    return new_ptr;
                 }
             }
-            return ptr;
         }
 
-        // case B: Growing
+        // Default Fallback: Alloc new, Copy, Dealloc old
+        // 默认回退: Alloc new, Copy, Dealloc old
+        unsafe { self.realloc_by_copy(ptr, layout, new_size) }
+    }
+
+    /// Grow the block at `ptr`, `old_size` bytes long, by `needed` bytes taken from the
+    /// free block that directly follows it. On failure, returns the link at which the
+    /// search of the free list stopped: every node from there on lies below `ptr + old_size`.
+    ///
+    /// 从紧跟在 `ptr` 处块（长 `old_size` 字节）之后的空闲块中取出 `needed` 字节，
+    /// 使该块扩容。失败时返回空闲链表搜索停止处的链接：从该处起的每个节点都位于
+    /// `ptr + old_size` 之下。
+    unsafe fn grow_into_next(
+        &self,
+        ptr: *mut u8,
+        old_size: usize,
+        needed: usize,
+    ) -> Result<(), *mut *mut FreeListNode> {
         // Try to merge backwards (In-place grow)
         // Our list is [Sorted Descending by Address]
         // 尝试向后合并 (In-place grow)
         // 我们的链表是【地址降序】 (Descending)
         // Check if `ptr + old_size` is a free node.
-        let needed = new_full_size - old_size;
         let target_addr = unsafe { ptr.add(old_size) as *mut FreeListNode };
 
-        let mut prev = ptr::addr_of_mut!(FREE_LIST);
+        let mut prev = self.free_list.get();
         loop {
-            let curr = unsafe { *prev };
+            let curr = unsafe { read_link(prev) };
             if curr == EMPTY_FREE_LIST {
                 break;
             }
@@ -309,12 +1602,15 @@ unsafe impl GlobalAlloc for FreeListAllocator {
             if curr == target_addr {
                 // Found adjacent free block
                 // Check size
-                let node_size = unsafe { (*curr).size };
+                let node_size = unsafe { node_size(curr) };
                 if node_size >= needed {
                     // Merge!
                     // 1. Remove 'curr' from free list
+                    let next = unsafe { node_next(curr) };
                     unsafe {
-                        *prev = (*curr).next;
+                        write_link(prev, next);
+                        self.index_remove(curr);
+                        *self.free_nodes.get() -= 1;
                     }
 
                     // 2. If 'curr' had extra space, put the remainder back
@@ -323,18 +1619,19 @@ unsafe impl GlobalAlloc for FreeListAllocator {
                         unsafe {
                             // Create remainder node
                             let remainder_addr = (curr as *mut u8).add(needed) as *mut FreeListNode;
-                            (*remainder_addr).size = remaining_in_node;
 
                             // Insert remainder back into list.
                             // Since remainder_addr > curr (sub-part of curr, higher address).
                             // And *prev points to Next (which is < curr).
                             // So Remainder > Next.
                             // Remainder should replace Curr's position.
-                            (*remainder_addr).next = (*curr).next;
-                            *prev = remainder_addr;
+                            write_node(remainder_addr, next, remaining_in_node);
+                            write_link(prev, remainder_addr);
+                            *self.free_nodes.get() += 1;
+                            self.index_insert(remainder_addr);
                         }
                     }
-                    return ptr;
+                    return Ok(());
                 }
                 // Adjacent block exists but too small.
                 break;
@@ -345,61 +1642,972 @@ unsafe impl GlobalAlloc for FreeListAllocator {
                 prev = ptr::addr_of_mut!((*curr).next);
             }
         }
+        Err(prev)
+    }
 
-        // Default Fallback: Alloc new, Copy, Dealloc old
-        // 默认回退: Alloc new, Copy, Dealloc old
+    /// Bookkeeping for a block resized in place from `old_size` to `new_size` requested bytes.
+    ///
+    /// 记录一个块的请求大小从 `old_size` 原地调整为 `new_size` 字节。
+    #[inline(always)]
+    fn resized_in_place(&self, old_size: usize, new_size: usize) {
+        let _ = (old_size, new_size);
+        record!(self, s => {
+            s.bytes_requested += new_size;
+            s.live_bytes = s.live_bytes + new_size - old_size;
+        });
+        #[cfg(feature = "quota")]
+        self.quota.resize(old_size, new_size);
+    }
+
+    /// `realloc` fallback: allocate a new block, copy, and free the old one.
+    /// Uses the effective alignment (at least MIN_ALIGN) so the new block is laid out
+    /// exactly like the original one.
+    ///
+    /// `realloc` 的回退路径：分配新块、复制并释放旧块。
+    /// 使用实际生效的对齐（至少为 MIN_ALIGN），使新块与原块的布局方式完全一致。
+    #[cfg(feature = "realloc")]
+    unsafe fn realloc_by_copy(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
         unsafe {
-            let new_ptr = self.alloc(Layout::from_size_align_unchecked(new_size, layout.align()));
+            let align = layout.align().max(MIN_ALIGN);
+            let new_ptr = self.alloc(Layout::from_size_align_unchecked(new_size, align));
             if !new_ptr.is_null() {
-                ptr::copy_nonoverlapping(ptr, new_ptr, layout.size());
+                ptr::copy_nonoverlapping(ptr, new_ptr, copy_span(layout, new_size));
                 self.dealloc(ptr, layout);
             }
             new_ptr
         }
     }
-}
 
-fn full_size(layout: Layout) -> usize {
-    let grown = layout.size().max(NODE_SIZE);
-    (grown + 15) & !15
+    /// Shrink the block at `ptr` in place to `new_size` bytes; the tail goes back to the
+    /// free list when it can hold a free-list node. Unlike `realloc`, this does not need
+    /// the `realloc` feature.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`, and `new_size` must
+    /// not need a larger block than `layout` (any non-zero `new_size <= layout.size()` is
+    /// fine). Afterwards the block must be freed with a layout of `new_size` bytes.
+    ///
+    /// 将 `ptr` 处的块原地缩小为 `new_size` 字节；尾部能容纳空闲链表节点时归还到空闲链表。
+    /// 与 `realloc` 不同，它不需要 `realloc` feature。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配，且 `new_size` 所需的块不得大于 `layout`
+    /// （任何非零且 `new_size <= layout.size()` 的值均可）。之后必须以 `new_size`
+    /// 字节的布局释放该块。
+    pub unsafe fn shrink(&self, ptr: *mut u8, layout: Layout, new_size: usize) {
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+        let old_block = Self::block_size_for(layout);
+        let new_block = Self::block_size_for(new_layout);
+        debug_assert!(new_block <= old_block, "shrink cannot grow a block");
+
+        // A tail too small for a node header stays attached to the block and is lost
+        // 太小而放不下节点头的尾部仍附着在块上，并会丢失
+        let tail = old_block - new_block;
+        if tail >= NODE_SIZE {
+            unsafe { self.free_block(ptr.add(new_block), tail) };
+        }
+        self.resized_in_place(layout.size(), new_size);
+    }
+
+    /// Try to grow the block at `ptr` to `new_size` bytes without moving it: within its own
+    /// slack, or into the free block that follows it. Returns whether it grew; on `false`
+    /// the block is unchanged and the caller may move the data itself. Unlike `realloc`,
+    /// this does not need the `realloc` feature.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`, and `new_size` must
+    /// be at least `layout.size()`. After a `true` the block must be freed with a layout
+    /// of `new_size` bytes.
+    ///
+    /// 尝试在不移动的情况下将 `ptr` 处的块扩容到 `new_size` 字节：在其自身余量内，
+    /// 或扩入紧随其后的空闲块。返回是否扩容成功；返回 `false` 时块保持不变，
+    /// 调用者可以自行移动数据。与 `realloc` 不同，它不需要 `realloc` feature。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配，且 `new_size` 不得小于 `layout.size()`。
+    /// 返回 `true` 后必须以 `new_size` 字节的布局释放该块。
+    pub unsafe fn grow_in_place(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> bool {
+        debug_assert!(
+            new_size >= layout.size(),
+            "grow_in_place cannot shrink a block"
+        );
+        // Zero-sized blocks own no memory to grow into
+        // 零大小的块不拥有可供扩容的内存
+        if layout.size() == 0 {
+            return false;
+        }
+        let Ok(new_layout) = Layout::from_size_align(new_size, layout.align()) else {
+            return false;
+        };
+        #[cfg(feature = "quota")]
+        if !self.quota.fits(new_size - layout.size()) {
+            return false;
+        }
+
+        if Self::block_size_for(new_layout) <= Self::block_size_for(layout) {
+            unsafe { self.shrink(ptr, layout, new_size) };
+        } else {
+            // Binned blocks have a fixed class size
+            // Bin 中的块大小固定
+            #[cfg(feature = "small-bins")]
+            if Self::small_bin(layout).is_some() || Self::small_bin(new_layout).is_some() {
+                return false;
+            }
+            let old_size = Self::full_size(layout);
+            let needed = Self::round_size(new_size) - old_size;
+            if unsafe { self.grow_into_next(ptr, old_size, needed) }.is_err() {
+                return false;
+            }
+            self.resized_in_place(layout.size(), new_size);
+        }
+        self.resize_tag(ptr, new_size);
+        true
+    }
+
+    /// Shrink the block at `ptr` to `new_size` bytes without moving it, like
+    /// [`shrink`](Self::shrink). Returns `false` only for `new_size == 0`, which leaves the
+    /// block unchanged: a zero-sized block owns no memory.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`, and `new_size` must
+    /// not exceed `layout.size()`. After a `true` the block must be freed with a layout of
+    /// `new_size` bytes.
+    ///
+    /// 与 [`shrink`](Self::shrink) 一样，在不移动的情况下将 `ptr` 处的块缩小为 `new_size`
+    /// 字节。仅当 `new_size == 0` 时返回 `false` 且块保持不变：零大小的块不拥有内存。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配，且 `new_size` 不得大于 `layout.size()`。
+    /// 返回 `true` 后必须以 `new_size` 字节的布局释放该块。
+    pub unsafe fn shrink_in_place(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> bool {
+        debug_assert!(
+            new_size <= layout.size(),
+            "shrink_in_place cannot grow a block"
+        );
+        if new_size == 0 {
+            return false;
+        }
+        unsafe { self.shrink(ptr, layout, new_size) };
+        self.resize_tag(ptr, new_size);
+        true
+    }
+
+    /// Record the new size of a tagged block resized in place.
+    ///
+    /// 记录原地调整大小的被标记块的新大小。
+    #[inline(always)]
+    fn resize_tag(&self, ptr: *mut u8, new_size: usize) {
+        let _ = (ptr, new_size);
+        #[cfg(all(
+            feature = "alloc-tags",
+            debug_assertions,
+            not(any(target_arch = "wasm32", target_arch = "wasm64"))
+        ))]
+        if let Some((tag, _)) = self.untag(ptr) {
+            self.retag(ptr, tag, new_size);
+        }
+    }
+
+    /// Free the block at `ptr` in O(1) without merging it: it goes onto a LIFO list that
+    /// allocations check before the sorted list, so a hot temporary is handed straight back
+    /// out. Blocks that the `small-bins` bins serve go to their bin as usual.
+    ///
+    /// Deferred blocks are merged by [`compact`](Self::compact), or automatically before the
+    /// allocator grows memory. Until then they are not reported by
+    /// [`free_blocks`](Self::free_blocks) and are not given back by [`trim`](Self::trim).
+    ///
+    /// # Safety
+    /// Same contract as [`GlobalAlloc::dealloc`].
+    ///
+    /// 以 O(1) 释放 `ptr` 处的块且不进行合并：它被放入一个 LIFO 链表，分配时会先于有序链表
+    /// 检查该链表，因此频繁使用的临时块会被直接再次分配出去。由 `small-bins` 的 Bin
+    /// 服务的块照常进入其 Bin。
+    ///
+    /// 延迟的块由 [`compact`](Self::compact) 合并，或在分配器增长内存之前自动合并。
+    /// 在此之前，[`free_blocks`](Self::free_blocks) 不会报告它们，
+    /// [`trim`](Self::trim) 也不会归还它们。
+    ///
+    /// # Safety
+    /// 与 [`GlobalAlloc::dealloc`] 的约定相同。
+    pub unsafe fn dealloc_nomerge(&self, ptr: *mut u8, layout: Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+        unsafe { self.release(ptr, layout) };
+        if let Some(index) = Self::tiny_bin(layout) {
+            unsafe { self.push_tiny(ptr, index) };
+            return;
+        }
+        #[cfg(feature = "small-bins")]
+        if let Some(index) = Self::small_bin(layout) {
+            unsafe { self.push_bin(ptr, index) };
+            return;
+        }
+        let size = Self::full_size(layout);
+        unsafe {
+            #[cfg(feature = "poison")]
+            poison::fill(ptr, size);
+            let node = ptr as *mut FreeListNode;
+            write_node(node, *self.deferred.get(), size);
+            *self.deferred.get() = node;
+        }
+    }
+
+    /// Merge every block freed by [`dealloc_nomerge`](Self::dealloc_nomerge) into the
+    /// sorted free list, coalescing it with its neighbours. The deferred blocks are sorted
+    /// by address in place first, so a single walk of the sorted list merges them all.
+    /// Blocks parked by the node cap are merged the same way, and stay parked if they still
+    /// need a node while the list is full.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 将所有由 [`dealloc_nomerge`](Self::dealloc_nomerge) 释放的块并入有序空闲链表，
+    /// 并与相邻块合并。延迟的块会先按地址原地排序，因此只需遍历一次有序链表即可将它们全部合并。
+    /// 因节点上限而被暂存的块以同样方式合并；若链表已满时它们仍需新节点，则继续暂存。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn compact(&self) {
+        unsafe { self.merge_pending() };
+    }
+
+    /// The work of [`compact`](Self::compact). Returns whether any block reached the sorted
+    /// list, i.e. whether a search that just missed may succeed now.
+    ///
+    /// [`compact`](Self::compact) 的实际工作。返回是否有块进入了有序链表，
+    /// 即刚刚未命中的搜索现在是否可能成功。
+    unsafe fn merge_pending(&self) -> bool {
+        unsafe {
+            // Parked blocks join the end of the deferred list and are sorted with it
+            // 暂存的块接到延迟链表的末尾，并与之一起排序
+            let mut tail = self.deferred.get();
+            while !read_link(tail).is_null() {
+                tail = ptr::addr_of_mut!((*read_link(tail)).next);
+            }
+            write_link(tail, *self.parked.get());
+            *self.parked.get() = null_mut();
+
+            let mut node = sort_descending(*self.deferred.get());
+            *self.deferred.get() = null_mut();
+            let mut link = self.free_list.get();
+            let mut merged = false;
+            while !node.is_null() {
+                // Read the link before `free_block_at` overwrites the header
+                // 在 `free_block_at` 覆盖节点头之前读取链接
+                let next = node_next(node);
+                let parked = *self.parked.get();
+                link = self.free_block_at(link, node as *mut u8, node_size(node), true);
+                merged |= *self.parked.get() == parked;
+                node = next;
+            }
+            merged
+        }
+    }
+
+    /// Checks and accounting shared by every way of freeing a block, before it is linked
+    /// into a free list.
+    ///
+    /// 所有释放块的方式共用的检查与计数，在块被链入空闲链表之前执行。
+    unsafe fn release(&self, ptr: *mut u8, layout: Layout) {
+        #[cfg(feature = "dealloc-check")]
+        debug_assert!(
+            (ptr as usize).is_multiple_of(MIN_ALIGN),
+            "dealloc of a misaligned pointer: {ptr:p}"
+        );
+        #[cfg(debug_assertions)]
+        unsafe {
+            self.debug_assert_not_free(ptr, Self::block_size_for(layout));
+        }
+        #[cfg(all(
+            feature = "alloc-tags",
+            debug_assertions,
+            not(any(target_arch = "wasm32", target_arch = "wasm64"))
+        ))]
+        self.untag(ptr);
+        record!(self, s => s.live_bytes = s.live_bytes.saturating_sub(layout.size()));
+        #[cfg(feature = "quota")]
+        self.quota.release(layout.size());
+    }
+
+    /// Fallible allocation: like `GlobalAlloc::alloc`, but returns `Err(AllocError)`
+    /// instead of a null pointer. The OOM hook still fires on failure.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::alloc`, except that zero-sized layouts are allowed:
+    /// they yield a dangling, aligned pointer that uses no heap memory.
+    ///
+    /// 可失败的分配：与 `GlobalAlloc::alloc` 相同，但失败时返回 `Err(AllocError)`
+    /// 而不是空指针。失败时仍会触发 OOM 回调。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::alloc` 的约定相同，但允许零大小的布局：
+    /// 它们得到一个悬垂但对齐、不占用堆内存的指针。
+    pub unsafe fn try_alloc(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if layout.size() == 0 {
+            return Ok(unsafe { NonNull::new_unchecked(dangling(layout)) });
+        }
+        #[cfg(feature = "quota")]
+        if !self.quota.fits(layout.size()) {
+            self.oom_hook.fire(layout);
+            return Err(AllocError);
+        }
+        let Some(ptr) = NonNull::new(unsafe { self.alloc_block(layout) }.0) else {
+            self.oom_hook.fire(layout);
+            return Err(AllocError);
+        };
+        #[cfg(feature = "quota")]
+        self.quota.charge(layout.size());
+        record!(self, s => {
+            s.bytes_requested += layout.size();
+            s.live_bytes += layout.size();
+        });
+        Ok(ptr)
+    }
+
+    /// Allocate an uninitialized array of `n` values of type `T` in one block. Returns null
+    /// if `Layout::array::<T>(n)` overflows or memory runs out. Free it with
+    /// [`dealloc_array`](Self::dealloc_array) and the same `n`.
+    ///
+    /// # Safety
+    /// Same contract as [`try_alloc`](Self::try_alloc) with `Layout::array::<T>(n)`.
+    ///
+    /// 在一个块中分配 `n` 个 `T` 类型值的未初始化数组。`Layout::array::<T>(n)` 溢出或内存耗尽时
+    /// 返回 null。使用 [`dealloc_array`](Self::dealloc_array) 并传入相同的 `n` 释放。
+    ///
+    /// # Safety
+    /// 与以 `Layout::array::<T>(n)` 调用 [`try_alloc`](Self::try_alloc) 的约定相同。
+    pub unsafe fn alloc_array<T>(&self, n: usize) -> *mut T {
+        match Layout::array::<T>(n) {
+            Ok(layout) => unsafe { self.alloc(layout) }.cast(),
+            Err(_) => null_mut(),
+        }
+    }
+
+    /// Free an array allocated by [`alloc_array`](Self::alloc_array).
+    ///
+    /// # Safety
+    /// `ptr` must have been returned by `alloc_array::<T>(n)` on this allocator, with the
+    /// same `n`, and not freed yet.
+    ///
+    /// 释放由 [`alloc_array`](Self::alloc_array) 分配的数组。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器的 `alloc_array::<T>(n)` 以相同的 `n` 返回，且尚未被释放。
+    pub unsafe fn dealloc_array<T>(&self, ptr: *mut T, n: usize) {
+        unsafe { self.dealloc(ptr.cast(), Layout::array::<T>(n).unwrap_unchecked()) }
+    }
+
+    /// Allocate like `GlobalAlloc::alloc`, also returning the capacity of the block:
+    /// the bin size, or the rounded size carved off a free block when it is split, always `>= layout.size()`.
+    /// The caller may use all of it, and may later free or resize the block with the
+    /// same `layout`. On failure, returns `(null, 0)`.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::alloc`.
+    ///
+    /// 与 `GlobalAlloc::alloc` 一样分配，并同时返回块的容量：
+    /// Bin 大小，或拆分空闲块时切出的取整后大小，总是 `>= layout.size()`。
+    /// 调用者可以使用全部容量，之后仍以同一 `layout` 释放或调整该块。失败时返回 `(null, 0)`。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::alloc` 的约定相同。
+    pub unsafe fn alloc_with_capacity(&self, layout: Layout) -> (*mut u8, usize) {
+        let ptr = unsafe { self.alloc(layout) };
+        if ptr.is_null() {
+            return (ptr, 0);
+        }
+        (ptr, unsafe { self.usable_size(ptr, layout) })
+    }
+
+    /// Like `GlobalAlloc::realloc`, but the bytes past the old size, `[layout.size(),
+    /// new_size)`, are zeroed, whether the block grew in place or moved.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::realloc`.
+    ///
+    /// 与 `GlobalAlloc::realloc` 相同，但旧大小之后的字节 `[layout.size(), new_size)` 会被清零，
+    /// 无论块是原地扩容还是被移动。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::realloc` 的约定相同。
+    #[cfg(feature = "realloc")]
+    pub unsafe fn realloc_zeroed(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { realloc_zero_tail(self, ptr, layout, new_size) }
+    }
+
+    /// Allocate like `GlobalAlloc::alloc`, recording `tag` (e.g. a category id) for the
+    /// block in a side table on the host heap until it is freed; see
+    /// [`leaked_by_tag`](Self::leaked_by_tag). Tags are only recorded in debug builds with
+    /// the `alloc-tags` feature, so other builds never call into the host heap for them.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::alloc`, except that zero-sized layouts are allowed:
+    /// they yield a dangling, aligned pointer that uses no heap memory.
+    ///
+    /// 与 `GlobalAlloc::alloc` 相同地分配，并在宿主堆上的旁路表中为该块记录 `tag`
+    /// （例如类别 id），直到其被释放；见 [`leaked_by_tag`](Self::leaked_by_tag)。
+    /// 仅在开启 `alloc-tags` feature 的 debug 构建中记录标签，因此其他构建不会为此调用宿主堆。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::alloc` 的约定相同，但允许零大小的布局：
+    /// 它们得到一个悬垂但对齐、不占用堆内存的指针。
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    pub unsafe fn alloc_tagged(&self, layout: Layout, tag: u32) -> *mut u8 {
+        let ptr = unsafe { self.alloc(layout) };
+        #[cfg(all(feature = "alloc-tags", debug_assertions))]
+        if !ptr.is_null() && layout.size() != 0 {
+            self.retag(ptr, tag, layout.size());
+        }
+        #[cfg(not(all(feature = "alloc-tags", debug_assertions)))]
+        let _ = tag;
+        ptr
+    }
+
+    /// Grow the heap up front so that a free block of at least `bytes` bytes is available,
+    /// e.g. before a latency-sensitive phase. The new pages go onto the free list; nothing
+    /// is grown if such a block is already free. Returns `false` on OOM.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 预先增长堆，使至少有一个 `bytes` 字节的空闲块可用，例如在对延迟敏感的阶段之前。
+    /// 新页面加入空闲链表；如果已有这样的空闲块，则不会增长。OOM 时返回 `false`。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn reserve(&self, bytes: usize) -> bool {
+        if unsafe { self.largest_free_block() } >= bytes {
+            return true;
+        }
+        let Some((ptr, len)) = (unsafe { self.grow_heap(bytes) }) else {
+            return false;
+        };
+        // SAFETY: The region was just grown and belongs to no block
+        // SAFETY: 该区域刚刚增长，不属于任何块
+        unsafe { self.free_block(ptr, len) };
+        true
+    }
+
+    /// Pages that [`reserve`](Self::reserve) would have to grow for `bytes` bytes, without
+    /// growing anything: `PageCount(0)` if a free block of that size already exists.
+    /// A host can check it against its memory budget before allocating. The growth policy
+    /// may still ask for more pages than this minimum.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 计算 [`reserve`](Self::reserve) 为 `bytes` 字节需要增长的页数，且不进行任何增长：
+    /// 已有这么大的空闲块时为 `PageCount(0)`。宿主可以在分配之前用它对照自己的内存预算。
+    /// 增长策略仍可能申请多于此最小值的页数。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn pages_needed_for(&self, bytes: usize) -> PageCount {
+        if unsafe { self.largest_free_block() } >= bytes {
+            return PageCount(0);
+        }
+        PageCount::from_bytes(bytes)
+    }
+
+    /// Hand whole free pages at the top of the heap back to the memory source, returning
+    /// how many pages were reclaimed. Only the free block ending at the heap end is
+    /// considered, and only if the source accepts the pages (see [`MemorySource::shrink`]);
+    /// blocks held in small bins are not trimmed.
+    ///
+    /// # Safety
+    /// No other allocator may be using the same memory source concurrently.
+    ///
+    /// 将堆顶部整页的空闲内存归还给内存来源，返回回收的页数。
+    /// 只考虑结束于堆末尾的空闲块，且仅当内存来源接受这些页面时才会归还
+    /// （见 [`MemorySource::shrink`]）；小块 Bin 中的块不会被修剪。
+    ///
+    /// # Safety
+    /// 不得有其他分配器同时使用同一内存来源。
+    pub unsafe fn trim(&self) -> PageCount {
+        unsafe {
+            // The list is sorted by descending address: the head is the highest block
+            // 链表按地址降序排列：头节点即最高的块
+            let head = *self.free_list.get();
+            let heap_end = self.heap_end.get();
+            if head == EMPTY_FREE_LIST || head as usize + node_size(head) != *heap_end {
+                return PageCount(0);
+            }
+
+            // Keep a lower remainder only if it can still hold a node header
+            // 仅当低地址剩余部分仍能容纳节点头时才保留它
+            let mut start = round_up(head as usize, PAGE_SIZE);
+            let lead = start - head as usize;
+            if lead != 0 && lead < NODE_SIZE {
+                start += PAGE_SIZE;
+            }
+            if start >= *heap_end {
+                return PageCount(0);
+            }
+
+            // Read the links before the source reclaims (and may clear) the pages
+            // 在内存来源回收（并可能清零）这些页面之前读取链接
+            let next = node_next(head);
+            self.index_remove(head);
+            let pages = (*heap_end - start) / PAGE_SIZE;
+            if !self.source.shrink(start / PAGE_SIZE, pages) {
+                self.index_insert(head);
+                return PageCount(0);
+            }
+            *heap_end = start;
+            #[cfg(feature = "stats")]
+            self.high_water.shrank(pages * PAGE_SIZE);
+            if start == head as usize {
+                *self.free_list.get() = next;
+                *self.free_nodes.get() -= 1;
+            } else {
+                set_node_size(head, start - head as usize);
+                self.index_insert(head);
+            }
+            PageCount(pages)
+        }
+    }
+
+    /// Core allocation routine shared by `alloc` and `alloc_zeroed`.
+    /// The returned flag is `true` when the block was carved out of freshly grown pages.
+    ///
+    /// `alloc` 与 `alloc_zeroed` 共用的核心分配逻辑。
+    /// 当块来自新增长的页面时，返回的标志为 `true`。
+    unsafe fn alloc_block(&self, layout: Layout) -> (*mut u8, bool) {
+        // 1. Every block is MIN_ALIGN-aligned (16 bytes by default).
+        // 2. If user requests more aggressive alignment (e.g. 4KB page alignment),
+        // take the slower over-allocate-and-carve path.
+        // 1. 每个块都按 MIN_ALIGN 对齐（默认 16 字节）。
+        // 2. 如果用户请求了更变态的对齐 (比如 4KB 对齐的页)，走较慢的超额分配再切割路径。
+        if layout.align() > MIN_ALIGN {
+            return unsafe { self.alloc_aligned(layout) };
+        }
+
+        if let Some(index) = Self::tiny_bin(layout) {
+            return unsafe { self.alloc_tiny(index) };
+        }
+
+        #[cfg(feature = "small-bins")]
+        if let Some(index) = Self::small_bin(layout) {
+            return unsafe { self.alloc_small(index) };
+        }
+
+        // 3. Calculate size: at least NODE_SIZE, rounded up to a multiple of MIN_ALIGN
+        // 3. 计算大小：至少为 NODE_SIZE，并向上取整到 MIN_ALIGN 的倍数
+        unsafe { self.alloc_list(Self::full_size(layout)) }
+    }
+
+    /// Pop a block from small bin `index`, or carve a class-sized block from the sorted list.
+    ///
+    /// 从第 `index` 个小块 Bin 中弹出一个块，或从有序链表中切出一个该大小等级的块。
+    #[cfg(feature = "small-bins")]
+    unsafe fn alloc_small(&self, index: usize) -> (*mut u8, bool) {
+        unsafe {
+            let bin = &mut (*self.bins.get())[index];
+            let block = *bin;
+            if block.is_null() {
+                return self.alloc_list(SMALL_BINS[index]);
+            }
+            *bin = (*block).next;
+            #[cfg(feature = "poison")]
+            poison::check(
+                block as *mut u8,
+                core::mem::size_of::<*mut FreeListNode>(),
+                SMALL_BINS[index],
+            );
+            (block as *mut u8, false)
+        }
+    }
+
+    /// Push a freed block onto small bin `index` (LIFO, no coalescing).
+    ///
+    /// 将释放的块压入第 `index` 个小块 Bin（LIFO，不合并）。
+    #[cfg(feature = "small-bins")]
+    unsafe fn push_bin(&self, ptr: *mut u8, index: usize) {
+        unsafe {
+            #[cfg(feature = "poison")]
+            poison::fill(ptr, SMALL_BINS[index]);
+            let bin = &mut (*self.bins.get())[index];
+            let node = ptr as *mut FreeListNode;
+            (*node).next = *bin;
+            *bin = node;
+        }
+    }
+
+    /// Pop a block from tiny bin `index`, or carve one of that size from the sorted list.
+    ///
+    /// 从第 `index` 个极小块 Bin 中弹出一个块，或从有序链表中切出一个该大小的块。
+    unsafe fn alloc_tiny(&self, index: usize) -> (*mut u8, bool) {
+        unsafe {
+            let bin = &mut (*self.tiny_bins.get())[index];
+            let block = *bin;
+            if block.is_null() {
+                return self.alloc_list(tiny_size(index));
+            }
+            *bin = (*block).next;
+            #[cfg(feature = "poison")]
+            poison::check(
+                block as *mut u8,
+                core::mem::size_of::<*mut FreeListNode>(),
+                tiny_size(index),
+            );
+            (block as *mut u8, false)
+        }
+    }
+
+    /// Push a freed block onto tiny bin `index` (LIFO, no coalescing).
+    ///
+    /// 将释放的块压入第 `index` 个极小块 Bin（LIFO，不合并）。
+    unsafe fn push_tiny(&self, ptr: *mut u8, index: usize) {
+        unsafe {
+            #[cfg(feature = "poison")]
+            poison::fill(ptr, tiny_size(index));
+            let bin = &mut (*self.tiny_bins.get())[index];
+            let node = ptr as *mut FreeListNode;
+            (*node).next = *bin;
+            *bin = node;
+        }
+    }
+
+    /// Take a `size`-byte block (a multiple of MIN_ALIGN) from the sorted free list,
+    /// growing the memory source on a miss.
+    ///
+    /// 从有序空闲链表中取出 `size` 字节（MIN_ALIGN 的倍数）的块，未命中时扩容内存来源。
+    unsafe fn alloc_list(&self, size: usize) -> (*mut u8, bool) {
+        // Blocks freed by `dealloc_nomerge` are likely hot: try them first
+        // 由 `dealloc_nomerge` 释放的块很可能仍在缓存中：优先尝试它们
+        let block = unsafe { self.take_deferred(size) };
+        if !block.is_null() {
+            return (block, false);
+        }
+
+        // The size index hands out the best fit directly
+        // 大小索引直接给出最佳适配
+        #[cfg(feature = "size-index")]
+        {
+            let node = unsafe { self.index_fit(size) };
+            if !node.is_null() {
+                return unsafe { (self.take_indexed(node, size), false) };
+            }
+        }
+
+        // Search the free list (first fit by default, best fit with the `best-fit` feature)
+        // 搜索空闲链表（默认首次适配，开启 `best-fit` feature 时为最佳适配）
+        #[cfg(not(feature = "size-index"))]
+        {
+            let mut free_list: *mut *mut FreeListNode = self.free_list.get();
+            #[cfg(feature = "best-fit")]
+            let mut best: *mut *mut FreeListNode = null_mut();
+            loop {
+                // SAFETY: Dereferencing free_list is safe
+                // SAFETY: 解引用 free_list 是安全的
+                let node = unsafe { read_link(free_list) };
+                if node == EMPTY_FREE_LIST {
+                    break;
+                }
+
+                let node_size = unsafe { node_size(node) };
+
+                if size <= node_size {
+                    #[cfg(not(feature = "best-fit"))]
+                    return unsafe { (self.take_from_node(free_list, size), false) };
+
+                    // Remember the tightest block; an exact fit cannot be beaten.
+                    // 记录最紧凑的块；完全匹配的块不可能被超越。
+                    #[cfg(feature = "best-fit")]
+                    {
+                        if best.is_null() || unsafe { node_size < self::node_size(read_link(best)) }
+                        {
+                            best = free_list;
+                        }
+                        if node_size == size {
+                            break;
+                        }
+                    }
+                }
+                // SAFETY: Move to next node.
+                // SAFETY: 移动到下一个节点。
+                unsafe {
+                    free_list = ptr::addr_of_mut!((*node).next);
+                }
+            }
+
+            #[cfg(feature = "best-fit")]
+            if !best.is_null() {
+                return unsafe { (self.take_from_node(best, size), false) };
+            }
+        }
+
+        // Merge deferred and parked blocks before growing: next to their neighbours they
+        // may fit
+        // 在增长之前合并延迟与暂存的块：与相邻块合并后它们可能足够容纳请求
+        if unsafe { self.merge_pending() } {
+            return unsafe { self.alloc_list(size) };
+        }
+
+        // No space found in free list: grow the heap
+        // 未在空闲链表中找到空间：增长堆
+        let Some((ptr, requested_bytes)) = (unsafe { self.grow_heap(size) }) else {
+            return (null_mut(), false);
+        };
+        // Carve the block from the top of the new pages (same position the free list
+        // search would pick), and add the lower remainder to the free list.
+        // The returned block never overlaps a node header, so it stays zeroed.
+        // 从新页面的顶部切出块（与空闲链表搜索选择的位置相同），并将低地址的剩余部分加入空闲链表。
+        // 返回的块不会与节点头重叠，因此保持为零。
+        let remaining = requested_bytes - size;
+        #[cfg(feature = "stats")]
+        self.high_water.reach(remaining);
+        if remaining >= NODE_SIZE {
+            // SAFETY: The remainder lies inside the freshly grown region.
+            // SAFETY: 剩余部分位于新增长的区域内。
+            unsafe {
+                self.free_block(ptr, remaining);
+                (ptr.add(remaining), true)
+            }
+        } else {
+            (ptr, true)
+        }
+    }
+
+    /// Grow the memory source by at least `size` bytes (more under the growth policy) and
+    /// track the new pages as part of the heap. Returns the start and length of the new
+    /// region, which is not yet on the free list, or `None` on OOM.
+    ///
+    /// 将内存来源增长至少 `size` 字节（按增长策略可能更多），并将新页面作为堆的一部分跟踪。
+    /// 返回新区域的起始地址和长度（尚未加入空闲链表），OOM 时返回 `None`。
+    unsafe fn grow_heap(&self, size: usize) -> Option<(*mut u8, usize)> {
+        // Page math must not wrap for sizes near `usize::MAX`, which would ask for a
+        // handful of pages and carve past them.
+        // 对于接近 `usize::MAX` 的大小，页数计算不得回绕，否则只会申请寥寥几页，并越界切出块。
+        let pages_needed = PageCount::from_bytes(size).as_usize();
+        let region_pages = unsafe { (*self.heap_end.get() - *self.heap_start.get()) / PAGE_SIZE };
+        let wanted = self.growth.pages_for(pages_needed, region_pages);
+        // SAFETY: Grow the backing memory source (grow_memory by default, shimmed on non-wasm)
+        let (start, pages) = unsafe { grow_amortized(&self.source, pages_needed, wanted) }?;
+        // A region reaching past the address space (e.g. 4 GiB of pages on a 32-bit target)
+        // cannot be tracked: give up on it rather than wrap the heap end
+        // 超出地址空间的区域（例如 32 位目标上 4 GiB 的页面）无法跟踪：
+        // 放弃它，而不是让堆末尾回绕
+        let requested_bytes = PageCount(pages)
+            .checked_size_in_bytes()
+            .filter(|&bytes| start.checked_add(bytes).is_some())?;
+        record!(self, s => s.grow_calls += 1);
+        #[cfg(feature = "stats")]
+        self.high_water.grew(requested_bytes);
+
+        let ptr = start as *mut u8;
+        // Extend the tracked run, or start a new one if the pages are not contiguous
+        // 扩展跟踪的连续区域；如果页面不连续，则开始新的区域
+        unsafe {
+            let heap_end = self.heap_end.get();
+            if *heap_end != ptr as usize {
+                *self.heap_start.get() = ptr as usize;
+            }
+            *heap_end = ptr as usize + requested_bytes;
+        }
+        Some((ptr, requested_bytes))
+    }
+
+    /// Allocation path for alignments greater than `MIN_ALIGN`.
+    /// Over-allocates `size + align - MIN_ALIGN` bytes (rounded up to GRAIN), carves out an
+    /// aligned sub-block, and returns the leading/trailing remainders to the free list.
+    /// All remainders are GRAIN-aligned multiples of GRAIN, so each one can hold a node.
+    ///
+    /// 对齐要求大于 `MIN_ALIGN` 时的分配路径。
+    /// 超额分配 `size + align - MIN_ALIGN` 字节（向上取整到 GRAIN），切出一个对齐的子块，
+    /// 并将前后剩余部分归还到空闲链表。
+    /// 所有剩余部分都按 GRAIN 对齐且为其倍数，因此每一部分都能容纳一个节点。
+    unsafe fn alloc_aligned(&self, layout: Layout) -> (*mut u8, bool) {
+        let size = Self::full_size(layout);
+        // A whole number of grains, so the tail left after the aligned block is one too
+        // 取整数个粒度，使对齐块之后留下的尾部同样是整数个粒度
+        let Some(padded) = size
+            .checked_add(layout.align() - MIN_ALIGN)
+            .and_then(|padded| padded.checked_next_multiple_of(Self::GRAIN))
+        else {
+            return (null_mut(), false);
+        };
+
+        // SAFETY: padded is non-zero and MIN_ALIGN is a valid alignment
+        // SAFETY: padded 非零，且 MIN_ALIGN 是合法的对齐值
+        let (raw, fresh) = unsafe { self.alloc_list(padded) };
+        if raw.is_null() {
+            return (raw, false);
+        }
+
+        let aligned = round_up(raw as usize, layout.align());
+        let lead = aligned - raw as usize;
+        let tail = padded - lead - size;
+        // SAFETY: Both remainders lie inside the block we just allocated.
+        // SAFETY: 两个剩余部分都位于刚刚分配的块内。
+        unsafe {
+            if lead >= NODE_SIZE {
+                self.free_block(raw, lead);
+            }
+            if tail >= NODE_SIZE {
+                self.free_block((aligned + size) as *mut u8, tail);
+            }
+        }
+        (aligned as *mut u8, fresh)
+    }
 }
 
-/// Round up value to the nearest multiple of increment, where increment must be a power of 2.
-/// If `value` is already a multiple of increment, it remains unchanged.
-///
-/// 将值向上取整到增量的最接近倍数，增量必须是 2 的幂。
-/// 如果 `value` 是增量的倍数，则保持不变。
-fn round_up(value: usize, increment: usize) -> usize {
-    debug_assert!(increment.is_power_of_two());
-    // Calculate `value.div_ceil(increment) * increment`,
-    // utilizing the fact that `increment` is always a power of 2 to avoid integer division,
-    // as it is not always optimized away.
-    // 计算 `value.div_ceil(increment) * increment`，
-    // 利用 `increment` 总是 2 的幂这一事实避免使用整数除法，
-    // 因为它并不总是会被优化掉。
-    multiple_below(value + (increment - 1), increment)
+/// Size classes of the `small-bins` feature.
+///
+/// `small-bins` feature 的大小等级。
+#[cfg(feature = "small-bins")]
+const SMALL_BINS: [usize; 4] = [16, 32, 64, 128];
+
+impl<S, const MIN_ALIGN: usize, const MIN_BLOCK: usize> FreeListAllocator<S, MIN_ALIGN, MIN_BLOCK> {
+    /// Small bin serving `layout`, if any: alignment <= MIN_ALIGN and at most 128 bytes,
+    /// unless a tiny bin serves it. With `compact-header`, blocks below the first class
+    /// stay on the sorted list rather than being rounded up to it.
+    ///
+    /// 服务 `layout` 的小块 Bin（如有）：对齐 <= MIN_ALIGN 且不超过 128 字节，
+    /// 除非由极小块 Bin 服务。开启 `compact-header` 时，小于第一个大小等级的块留在有序链表上，
+    /// 而不会被向上取整到该等级。
+    #[cfg(feature = "small-bins")]
+    #[inline(always)]
+    fn small_bin(layout: Layout) -> Option<usize> {
+        let size = Self::full_size(layout);
+        if layout.align() > MIN_ALIGN
+            || size < NODE_SIZE
+            || (ONE_WORD != 0 && size < SMALL_BINS[0])
+            || size > SMALL_BINS[SMALL_BINS.len() - 1]
+        {
+            return None;
+        }
+        // Classes are consecutive powers of two starting at 16
+        // 大小等级是从 16 开始的连续 2 的幂
+        let class = size.next_power_of_two().max(SMALL_BINS[0]);
+        Some((class.trailing_zeros() - SMALL_BINS[0].trailing_zeros()) as usize)
+    }
+
+    /// Tiny bin serving `layout`, if its block is too small for a node header (only when
+    /// `MIN_BLOCK` is below [`NODE_SIZE`]). Larger alignments take the aligned path instead.
+    ///
+    /// 服务 `layout` 的极小块 Bin（如有），即其块小到放不下节点头（仅当 `MIN_BLOCK` 小于
+    /// [`NODE_SIZE`] 时）。更大的对齐要求改走对齐分配路径。
+    #[inline(always)]
+    fn tiny_bin(layout: Layout) -> Option<usize> {
+        let size = Self::full_size(layout);
+        if size >= NODE_SIZE || layout.align() > MIN_ALIGN {
+            return None;
+        }
+        Some(size / WORD - 1)
+    }
+
+    /// Size of the block backing an allocation with `layout`.
+    ///
+    /// 以 `layout` 分配时实际占用的块大小。
+    #[inline(always)]
+    fn block_size_for(layout: Layout) -> usize {
+        #[cfg(feature = "small-bins")]
+        if let Some(index) = Self::small_bin(layout) {
+            return SMALL_BINS[index];
+        }
+        Self::full_size(layout)
+    }
+
+    #[inline(always)]
+    fn full_size(layout: Layout) -> usize {
+        Self::round_size(layout.size())
+    }
+
+    /// Granularity of the blocks on the sorted list: MIN_ALIGN, or the node size when
+    /// `size-index` makes nodes larger. Every split then leaves either nothing or room for
+    /// a node, so no remainder is too small to go back on the list. Tiny-bin blocks below
+    /// NODE_SIZE keep MIN_ALIGN.
+    ///
+    /// 有序链表上块的粒度：MIN_ALIGN；当 `size-index` 使节点更大时则为节点大小。
+    /// 这样每次切分要么不留剩余，要么剩余部分能容纳一个节点，不会有因太小而无法放回链表的剩余部分。
+    /// 小于 NODE_SIZE 的极小块 Bin 块仍按 MIN_ALIGN 取整。
+    const GRAIN: usize = if cfg!(feature = "size-index") && NODE_SIZE > MIN_ALIGN {
+        NODE_SIZE
+    } else {
+        MIN_ALIGN
+    };
+
+    /// Block size for `size` bytes: at least MIN_BLOCK, rounded up to a multiple of
+    /// MIN_ALIGN, and of GRAIN from NODE_SIZE on.
+    ///
+    /// `size` 字节对应的块大小：至少为 MIN_BLOCK，向上取整到 MIN_ALIGN 的倍数；
+    /// 从 NODE_SIZE 起取整到 GRAIN 的倍数。
+    #[inline(always)]
+    fn round_size(size: usize) -> usize {
+        let size = round_up(size.max(MIN_BLOCK), MIN_ALIGN);
+        if size < NODE_SIZE {
+            size
+        } else {
+            round_up(size, Self::GRAIN)
+        }
+    }
 }
 
-/// Round down value to the nearest multiple of increment, where increment must be a power of 2.
-/// If `value` is a multiple of `increment`, it remains unchanged.
-///
-/// 将值向下取整到增量的最接近倍数，增量必须是 2 的幂。
-/// 如果 `value` 是 `increment` 的倍数，则保持不变。
-fn multiple_below(value: usize, increment: usize) -> usize {
-    debug_assert!(increment.is_power_of_two());
-    // Calculate `value / increment * increment`,
-    // utilizing the fact that `increment` is always a power of 2 to avoid integer division,
-    // as it is not always optimized away.
-    // 计算 `value / increment * increment`，
-    // 利用 `increment` 总是 2 的幂这一事实避免使用整数除法，
-    // 因为它并不总是会被优化掉。
-    value & increment.wrapping_neg()
+const WORD: usize = core::mem::size_of::<usize>();
+
+/// Number of tiny bins: one per block size in words below [`NODE_SIZE`].
+///
+/// 极小块 Bin 的数量：低于 [`NODE_SIZE`] 的每个以字计的块大小各一个。
+const TINY_BINS: usize = NODE_SIZE / WORD - 1;
+
+/// Block size held by tiny bin `index`.
+///
+/// 第 `index` 个极小块 Bin 中的块大小。
+#[inline(always)]
+const fn tiny_size(index: usize) -> usize {
+    (index + 1) * WORD
 }
 
 unsafe fn offset_bytes(ptr: *mut FreeListNode, offset: usize) -> *mut FreeListNode {
     unsafe { (ptr as *mut u8).add(offset) as *mut FreeListNode }
 }
 
+/// Sort the null-terminated list at `head` by descending address, in place (merge sort
+/// over the `next` links), and return its new head.
+///
+/// 将 `head` 处以 null 结尾的链表按地址降序原地排序（在 `next` 链接上归并排序），并返回新的头节点。
+unsafe fn sort_descending(head: *mut FreeListNode) -> *mut FreeListNode {
+    unsafe {
+        if head.is_null() || node_next(head).is_null() {
+            return head;
+        }
+        // `slow` stops on the last node of the first half
+        // `slow` 停在前半部分的最后一个节点上
+        let mut slow = head;
+        let mut fast = node_next(head);
+        while !fast.is_null() && !node_next(fast).is_null() {
+            slow = node_next(slow);
+            fast = node_next(node_next(fast));
+        }
+        let second = node_next(slow);
+        write_link(ptr::addr_of_mut!((*slow).next), null_mut());
+        let mut high = sort_descending(head);
+        let mut low = sort_descending(second);
+
+        let mut merged = null_mut();
+        let mut tail: *mut *mut FreeListNode = &mut merged;
+        while !high.is_null() && !low.is_null() {
+            let run = if high > low { &mut high } else { &mut low };
+            let node = *run;
+            *run = node_next(node);
+            write_link(tail, node);
+            tail = ptr::addr_of_mut!((*node).next);
+        }
+        write_link(tail, if high.is_null() { low } else { high });
+        merged
+    }
+}
+
+impl<S, const MIN_ALIGN: usize, const MIN_BLOCK: usize> ResettableAllocator
+    for FreeListAllocator<S, MIN_ALIGN, MIN_BLOCK>
+{
+    unsafe fn reset(&self) {
+        unsafe { FreeListAllocator::reset(self) }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -415,14 +2623,11 @@ mod tests {
 
     impl SafeAllocator {
         fn new() -> Self {
-            let guard = TEST_MUTEX.lock().unwrap();
-            unsafe {
-                FreeListAllocator::reset();
-                reset_heap();
-                Self {
-                    inner: FreeListAllocator::new(),
-                    _guard: guard,
-                }
+            let guard = TEST_MUTEX.lock().unwrap_or_else(|p| p.into_inner());
+            reset_heap();
+            Self {
+                inner: FreeListAllocator::new(),
+                _guard: guard,
             }
         }
 
@@ -438,10 +2643,126 @@ mod tests {
     impl Drop for SafeAllocator {
         fn drop(&mut self) {
             unsafe {
-                FreeListAllocator::reset();
-                reset_heap();
+                self.inner.reset();
+            }
+            reset_heap();
+        }
+    }
+
+    #[repr(C, align(16))]
+    struct Arena([u8; 1024]);
+
+    // Test hook: replace the free list with nodes at the given (offset, size) pairs in `arena`
+    // 测试钩子：用 `arena` 中给定 (偏移, 大小) 处的节点替换空闲链表
+    fn with_list(arena: &mut Arena, blocks: &[(usize, usize)]) -> (FreeListAllocator, usize) {
+        let allocator = FreeListAllocator::new();
+        let base = arena.0.as_mut_ptr() as usize;
+        unsafe {
+            let mut next = EMPTY_FREE_LIST;
+            for &(offset, size) in blocks.iter().rev() {
+                let node = (base + offset) as *mut FreeListNode;
+                node.write_unaligned(FreeListNode {
+                    next,
+                    size,
+                    #[cfg(feature = "size-index")]
+                    smaller: null_mut(),
+                    #[cfg(feature = "size-index")]
+                    larger: null_mut(),
+                });
+                next = node;
+            }
+            *allocator.free_list.get() = next;
+            *allocator.free_nodes.get() = blocks.len();
+            #[cfg(feature = "size-index")]
+            for &(offset, _) in blocks {
+                allocator.index_insert((base + offset) as *mut FreeListNode);
             }
         }
+        (allocator, base)
+    }
+
+    // Broken list and the error it must produce, given the arena base address
+    // 被破坏的链表，以及给定 arena 基地址时它必须产生的错误
+    type BrokenList = (&'static [(usize, usize)], fn(usize) -> IntegrityError);
+
+    #[test]
+    fn test_check_integrity_detects_broken_lists() {
+        let mut arena = Arena([0; 1024]);
+        let cases: [BrokenList; 5] = [
+            (&[(512, 64), (8, 32)], |base| IntegrityError::Misaligned {
+                addr: base + 8,
+            }),
+            (&[(512, 64), (0, 40)], |base| IntegrityError::BadSize {
+                addr: base,
+                size: 40,
+            }),
+            (&[(256, 64), (512, 64)], |base| {
+                IntegrityError::NotDescending {
+                    addr: base + 512,
+                    prev: base + 256,
+                }
+            }),
+            (&[(512, 64), (480, 64)], |base| IntegrityError::Overlap {
+                addr: base + 480,
+                prev: base + 512,
+            }),
+            (&[(512, 64), (448, 64)], |base| IntegrityError::Unmerged {
+                addr: base + 448,
+                prev: base + 512,
+            }),
+        ];
+        for (blocks, expected) in cases {
+            let (allocator, base) = with_list(&mut arena, blocks);
+            assert_eq!(unsafe { allocator.check_integrity() }, Err(expected(base)));
+        }
+
+        let (allocator, _) = with_list(&mut arena, &[(512, 64), (256, 128), (0, 32)]);
+        assert_eq!(unsafe { allocator.check_integrity() }, Ok(()));
+    }
+
+    #[cfg(feature = "size-index")]
+    #[test]
+    fn test_check_integrity_detects_broken_index() {
+        let mut arena = Arena([0; 1024]);
+        let blocks = [(512, 64), (256, 128), (0, 32)];
+
+        // A block dropped from the index
+        // 从索引中丢失的块
+        let (allocator, base) = with_list(&mut arena, &blocks);
+        unsafe { allocator.index_remove((base + 256) as *mut FreeListNode) };
+        assert_eq!(
+            unsafe { allocator.check_integrity() },
+            Err(IntegrityError::Unindexed { addr: base + 256 })
+        );
+
+        // A block whose size changed behind the index's back
+        // 大小在索引不知情时被改变的块
+        let (allocator, base) = with_list(&mut arena, &blocks);
+        unsafe { (*((base + 512) as *mut FreeListNode)).size = 16 };
+        assert_eq!(
+            unsafe { allocator.check_integrity() },
+            Err(IntegrityError::BadSize {
+                addr: base + 512,
+                size: 16
+            })
+        );
+        unsafe { (*((base + 512) as *mut FreeListNode)).size = 256 };
+        assert_eq!(
+            unsafe { allocator.check_integrity() },
+            Err(IntegrityError::BadIndex { addr: base + 256 })
+        );
+    }
+
+    #[test]
+    fn test_check_integrity_after_churn() {
+        let allocator = SafeAllocator::new();
+        let layouts =
+            [16, 200, 48, 1000, 300, 64].map(|size| Layout::from_size_align(size, 8).unwrap());
+        let ptrs = layouts.map(|layout| allocator.alloc(layout));
+        for i in [1, 3, 2, 5, 0, 4] {
+            allocator.dealloc(ptrs[i], layouts[i]);
+            assert_eq!(unsafe { allocator.inner.check_integrity() }, Ok(()));
+        }
     }
 
     #[test]
@@ -483,7 +2804,10 @@ mod tests {
         assert!(ptr1 > ptr2);
 
         // 它们应该是紧挨著的：Block: [... | ptr2 | ptr1 | end]
-        assert_eq!(ptr1 as usize - ptr2 as usize, 16);
+        assert_eq!(
+            ptr1 as usize - ptr2 as usize,
+            allocator.inner.block_size(layout)
+        );
     }
 
     #[test]
@@ -506,6 +2830,9 @@ mod tests {
         assert_eq!(ptr1, ptr2);
     }
 
+    // 128-byte blocks go to the bins with `small-bins`, which never coalesce
+    // 开启 `small-bins` 时 128 字节的块进入 Bin，不会被合并
+    #[cfg(not(feature = "small-bins"))]
     #[test]
     fn test_coalescing_merge() {
         let allocator = SafeAllocator::new();
@@ -577,11 +2904,12 @@ mod tests {
     #[test]
     fn test_alignment_large() {
         let allocator = SafeAllocator::new();
-        // 目前实现只支持 max align 16。
-        // 如果请求 32，应该返回 null。 (代码里写了 check)
-        let layout_bad = Layout::from_size_align(32, 32).unwrap();
-        let ptr = allocator.alloc(layout_bad);
-        assert!(ptr.is_null());
+        // 对齐 > 16 时，超额分配后切出对齐的子块。
+        let layout = Layout::from_size_align(32, 32).unwrap();
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % 32, 0);
+        allocator.dealloc(ptr, layout);
     }
 
     #[test]
@@ -615,7 +2943,9 @@ mod tests {
     #[test]
     fn test_realloc_shrink_in_place() {
         let allocator = SafeAllocator::new();
-        let layout = Layout::from_size_align(128, 16).unwrap();
+        // Sizes stay above the `small-bins` classes so the sorted list is exercised
+        // 大小保持在 `small-bins` 的大小等级之上，以便测试有序链表
+        let layout = Layout::from_size_align(512, 16).unwrap();
         let ptr = allocator.alloc(layout);
         assert!(!ptr.is_null());
 
@@ -623,8 +2953,8 @@ mod tests {
             ptr.write_bytes(0xAA, layout.size());
         }
 
-        // Shrink to 64
-        let new_size = 64;
+        // Shrink to 256
+        let new_size = 256;
         let new_layout = Layout::from_size_align(new_size, 16).unwrap();
 
         // We need to call realloc from GlobalAlloc trait
@@ -647,16 +2977,16 @@ mod tests {
     #[test]
     fn test_realloc_grow_in_place() {
         let allocator = SafeAllocator::new();
-        let layout = Layout::from_size_align(64, 16).unwrap();
+        let layout = Layout::from_size_align(256, 16).unwrap();
 
         let ptr1 = allocator.alloc(layout);
         let ptr2 = allocator.alloc(layout);
 
-        assert_eq!(ptr1 as usize - ptr2 as usize, 64);
+        assert_eq!(ptr1 as usize - ptr2 as usize, 256);
 
         allocator.dealloc(ptr1, layout);
 
-        let new_size = 128;
+        let new_size = 512;
         let ptr2_new = unsafe { allocator.inner.realloc(ptr2, layout, new_size) };
 
         #[cfg(feature = "realloc")]
@@ -667,4 +2997,118 @@ mod tests {
 
         allocator.dealloc(ptr2_new, Layout::from_size_align(new_size, 16).unwrap());
     }
+
+    #[cfg(feature = "realloc")]
+    #[test]
+    fn test_realloc_grow_across_two_freed_blocks() {
+        let allocator = SafeAllocator::new();
+        let layout = Layout::from_size_align(256, 16).unwrap();
+
+        let top = allocator.alloc(layout);
+        let middle = allocator.alloc(layout);
+        let bottom = allocator.alloc(layout);
+        assert_eq!(top as usize - bottom as usize, 512);
+
+        // Freeing both upper neighbours leaves a single merged node right above `bottom`,
+        // so growing over the two of them is one in-place merge
+        // 释放两个高地址邻居后，`bottom` 正上方只剩一个合并后的节点，
+        // 因此跨越二者的增长只需一次原地合并
+        allocator.dealloc(middle, layout);
+        allocator.dealloc(top, layout);
+        unsafe { allocator.inner.check_integrity() }.unwrap();
+
+        let new_size = 768;
+        let grown = unsafe { allocator.inner.realloc(bottom, layout, new_size) };
+        assert_eq!(grown, bottom);
+
+        allocator.dealloc(grown, Layout::from_size_align(new_size, 16).unwrap());
+    }
+
+    #[cfg(feature = "realloc")]
+    #[test]
+    fn test_realloc_grow_into_preceding_block() {
+        let allocator = SafeAllocator::new();
+        let layout = Layout::from_size_align(256, 16).unwrap();
+
+        // Blocks are carved top-down: `lower` sits right below `upper`
+        // 块自顶向下切出：`lower` 紧挨在 `upper` 之下
+        let upper = allocator.alloc(layout);
+        let lower = allocator.alloc(layout);
+        assert_eq!(upper as usize - lower as usize, 256);
+        unsafe { upper.write_bytes(0x7E, 256) };
+
+        // Free the lower neighbour; nothing is free above `upper`
+        // 释放低地址的邻居；`upper` 之上没有空闲空间
+        allocator.dealloc(lower, layout);
+
+        let new_size = 1024;
+        let moved = unsafe { allocator.inner.realloc(upper, layout, new_size) };
+        assert_eq!(moved as usize, upper as usize + 256 - new_size);
+        for i in 0..256 {
+            assert_eq!(unsafe { *moved.add(i) }, 0x7E);
+        }
+
+        // The rest of the coalesced block is still free below the moved block
+        // 合并块的剩余部分仍在被移动的块之下保持空闲
+        let below = allocator.alloc(layout);
+        assert_eq!(below as usize, moved as usize - 256);
+
+        allocator.dealloc(below, layout);
+        allocator.dealloc(moved, Layout::from_size_align(new_size, 16).unwrap());
+    }
+
+    #[test]
+    fn test_realloc_fallback_low_align() {
+        let allocator = SafeAllocator::new();
+        let layout = Layout::from_size_align(100, 8).unwrap();
+
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        unsafe { ptr.write_bytes(0x5C, layout.size()) };
+        // `ptr` sits at the top of the grown pages, so the grow falls back to alloc + copy
+        // `ptr` 位于新增长页面的顶部，因此扩容会回退到分配 + 复制
+        let blocker = allocator.alloc(layout);
+
+        let new_size = 4096;
+        let new_ptr = unsafe { allocator.inner.realloc(ptr, layout, new_size) };
+        assert!(!new_ptr.is_null());
+        assert_eq!(new_ptr as usize % 16, 0);
+        for i in 0..layout.size() {
+            assert_eq!(unsafe { *new_ptr.add(i) }, 0x5C);
+        }
+
+        allocator.dealloc(new_ptr, Layout::from_size_align(new_size, 8).unwrap());
+        allocator.dealloc(blocker, layout);
+    }
+
+    #[test]
+    fn test_alloc_zeroed_recycled_block() {
+        let allocator = SafeAllocator::new();
+        let layout = Layout::from_size_align(4096, 16).unwrap();
+
+        // First allocation is carved from freshly grown pages: no memset needed.
+        // 第一次分配来自新增长的页面：无需清零。
+        let (ptr, fresh) = unsafe { allocator.inner.alloc_block(layout) };
+        assert!(!ptr.is_null());
+        assert!(fresh);
+        unsafe {
+            assert!((0..layout.size()).all(|i| *ptr.add(i) == 0));
+            ptr.write_bytes(0xAA, layout.size());
+        }
+        allocator.dealloc(ptr, layout);
+
+        // The recycled block is dirty, so it must be zeroed explicitly.
+        // 回收的块是脏的，因此必须显式清零。
+        let (recycled, fresh) = unsafe { allocator.inner.alloc_block(layout) };
+        assert_eq!(recycled, ptr);
+        assert!(!fresh);
+        allocator.dealloc(recycled, layout);
+
+        let zeroed = unsafe { allocator.inner.alloc_zeroed(layout) };
+        assert_eq!(zeroed, ptr);
+        unsafe {
+            assert!((0..layout.size()).all(|i| *zeroed.add(i) == 0));
+        }
+        allocator.dealloc(zeroed, layout);
+    }
 }
@@ -0,0 +1,81 @@
+use crate::ResettableAllocator;
+use core::alloc::{GlobalAlloc, Layout};
+use core::ops::Deref;
+
+/// Opt-in `Sync` for a single-threaded allocator, meant for the `#[global_allocator]`
+/// static only.
+///
+/// With the `strict-single-thread` feature the allocators in this module are `!Sync`, so
+/// sharing one across threads by accident fails to compile. A global allocator still has
+/// to be `Sync`; this wrapper provides that promise explicitly, at the one place it is
+/// needed:
+///
+/// ```
+/// use lite_alloc::single_threaded::{FreeListAllocator, GlobalWrapper};
+///
+/// // #[global_allocator] on the target
+/// static ALLOCATOR: GlobalWrapper<FreeListAllocator> =
+///     GlobalWrapper(FreeListAllocator::new());
+/// ```
+///
+/// Without the wrapper, the same static is rejected (a `static` must be `Sync`):
+///
+#[cfg_attr(feature = "strict-single-thread", doc = "```compile_fail")]
+#[cfg_attr(not(feature = "strict-single-thread"), doc = "```ignore")]
+/// use lite_alloc::single_threaded::FreeListAllocator;
+///
+/// static ALLOCATOR: FreeListAllocator = FreeListAllocator::new();
+/// ```
+///
+/// The wrapped allocator stays reachable through `Deref` and the public field.
+///
+/// 为单线程分配器显式提供 `Sync`，仅用于 `#[global_allocator]` 静态变量。
+///
+/// 开启 `strict-single-thread` feature 后，本模块中的分配器都是 `!Sync` 的，
+/// 因此意外地在线程之间共享它们将无法通过编译。全局分配器仍然必须是 `Sync` 的；
+/// 此包装器在唯一需要的地方显式给出这一承诺。不使用包装器时，同样的静态变量会被拒绝
+/// （`static` 必须是 `Sync` 的）。
+///
+/// 被包装的分配器仍可通过 `Deref` 与公开字段访问。
+pub struct GlobalWrapper<A>(pub A);
+
+/// Safety Warning:
+/// The wrapped allocator is still single-threaded. Only use this wrapper where a single
+/// thread ever allocates (e.g., WASM or single-threaded embedded).
+///
+/// 安全性警示 (Safety Warning):
+/// 被包装的分配器仍然是单线程的。仅在只有一个线程进行分配的环境
+/// （如 WASM 或单线程嵌入式环境）中使用此包装器。
+unsafe impl<A> Sync for GlobalWrapper<A> {}
+
+impl<A> Deref for GlobalWrapper<A> {
+    type Target = A;
+
+    fn deref(&self) -> &A {
+        &self.0
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for GlobalWrapper<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { self.0.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        unsafe { self.0.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.0.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { self.0.realloc(ptr, layout, new_size) }
+    }
+}
+
+impl<A: ResettableAllocator> ResettableAllocator for GlobalWrapper<A> {
+    unsafe fn reset(&self) {
+        unsafe { self.0.reset() }
+    }
+}
@@ -0,0 +1,111 @@
+use super::BumpFreeListAllocator;
+use crate::{DefaultMemory, ResettableAllocator};
+use core::cell::Cell;
+
+/// `N` bump allocators used in turn, e.g. one per frame of a game loop: allocate from
+/// [`current`](RingArena::current) during a frame, then [`advance`](RingArena::advance),
+/// which clears the oldest arena and makes it current. A block therefore lives for `N - 1`
+/// further advances, and each arena keeps its pages, so a steady workload stops growing
+/// memory after the first lap.
+///
+/// Arenas that share a memory source interleave their growth, and a cleared arena only
+/// reuses its latest region. Give each arena enough pages up front, e.g. with
+/// [`BumpFreeListAllocator::with_initial_pages`], so it never has to grow again.
+///
+/// `N` 个轮流使用的 Bump 分配器，例如游戏循环中每帧一个：在一帧内从
+/// [`current`](RingArena::current) 分配，然后调用 [`advance`](RingArena::advance)，
+/// 它会清空最旧的分配区并将其设为当前分配区。因此一个块在之后的 `N - 1` 次推进中保持有效；
+/// 每个分配区都保留自己的页面，所以稳定的工作负载在第一轮之后不再增长内存。
+///
+/// 共享同一内存来源的分配区会交替增长，而被清空的分配区只复用其最新的区域。
+/// 请预先为每个分配区提供足够的页面，例如使用
+/// [`BumpFreeListAllocator::with_initial_pages`]，使其无需再次增长。
+pub struct RingArena<const N: usize, S = DefaultMemory> {
+    arenas: [BumpFreeListAllocator<S>; N],
+    current: Cell<usize>,
+}
+
+impl<const N: usize> RingArena<N> {
+    /// Create a ring of `N` arenas that grow page by page from the default memory.
+    ///
+    /// # Panics
+    /// If `N` is zero.
+    ///
+    /// 创建由 `N` 个分配区组成的环，它们从默认内存逐页增长。
+    ///
+    /// # Panics
+    /// 如果 `N` 为零。
+    pub const fn new() -> Self {
+        Self::from_arenas([const { BumpFreeListAllocator::new() }; N])
+    }
+}
+
+impl<const N: usize, S> RingArena<N, S> {
+    /// Create a ring from `arenas`, e.g.
+    /// `RingArena::from_arenas([const { BumpFreeListAllocator::with_initial_pages(PageCount(16)) }; 3])`.
+    /// The first arena is current.
+    ///
+    /// # Panics
+    /// If `N` is zero.
+    ///
+    /// 由 `arenas` 创建一个环，例如
+    /// `RingArena::from_arenas([const { BumpFreeListAllocator::with_initial_pages(PageCount(16)) }; 3])`。
+    /// 第一个分配区为当前分配区。
+    ///
+    /// # Panics
+    /// 如果 `N` 为零。
+    pub const fn from_arenas(arenas: [BumpFreeListAllocator<S>; N]) -> Self {
+        assert!(N > 0, "a RingArena needs at least one arena");
+        RingArena {
+            arenas,
+            current: Cell::new(0),
+        }
+    }
+
+    /// The arena to allocate from until the next [`advance`](RingArena::advance).
+    ///
+    /// 在下一次 [`advance`](RingArena::advance) 之前用于分配的分配区。
+    pub fn current(&self) -> &BumpFreeListAllocator<S> {
+        &self.arenas[self.current.get()]
+    }
+
+    /// Position of the current arena in the ring, from `0` to `N - 1`.
+    ///
+    /// 当前分配区在环中的位置，范围为 `0` 到 `N - 1`。
+    pub fn index(&self) -> usize {
+        self.current.get()
+    }
+
+    /// Move to the next arena, clearing it first, and return it.
+    ///
+    /// # Safety
+    /// Every pointer allocated from that arena since it was last current becomes
+    /// dangling, i.e. everything allocated `N` advances ago.
+    ///
+    /// 移动到下一个分配区（先将其清空）并返回它。
+    ///
+    /// # Safety
+    /// 自该分配区上次成为当前分配区以来从中分配的所有指针都将失效，
+    /// 即 `N` 次推进之前分配的所有内存。
+    pub unsafe fn advance(&self) -> &BumpFreeListAllocator<S> {
+        let next = (self.current.get() + 1) % N;
+        unsafe { self.arenas[next].clear() };
+        self.current.set(next);
+        &self.arenas[next]
+    }
+}
+
+impl<const N: usize> Default for RingArena<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, S> ResettableAllocator for RingArena<N, S> {
+    unsafe fn reset(&self) {
+        for arena in &self.arenas {
+            unsafe { arena.reset() };
+        }
+        self.current.set(0);
+    }
+}
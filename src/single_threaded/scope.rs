@@ -0,0 +1,127 @@
+use crate::AllocError;
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::NonNull;
+
+/// Bump-based allocators whose heap top can be rolled back by a [`Scope`].
+///
+/// 可以通过 [`Scope`] 回退堆顶的基于 Bump 的分配器。
+pub trait ScopedAllocator: GlobalAlloc + sealed::Sealed {}
+
+pub(crate) mod sealed {
+    pub trait Sealed {
+        /// Snapshot of the bump region: `(heap_top, heap_start)`.
+        ///
+        /// Bump 区域的快照：`(heap_top, heap_start)`。
+        fn mark(&self) -> (usize, usize);
+
+        /// Roll the bump region back to `mark` and drop free blocks that now lie above the heap top.
+        ///
+        /// # Safety
+        /// Every block handed out after `mark` was taken must be dead.
+        ///
+        /// 将 Bump 区域回退到 `mark`，并丢弃现在位于堆顶之上的空闲块。
+        ///
+        /// # Safety
+        /// 在获取 `mark` 之后分配出去的所有块都必须已不再使用。
+        unsafe fn rewind(&self, mark: (usize, usize));
+    }
+}
+
+/// RAII guard that frees everything allocated after its creation when dropped,
+/// by restoring the allocator's heap top.
+///
+/// Blocks recycled from free lists inside the scope are not given back; they are
+/// simply leaked when the scope ends.
+///
+/// 一个 RAII 守卫，在被丢弃时通过恢复分配器的堆顶，释放其创建之后分配的所有内存。
+///
+/// 在作用域内从空闲链表回收的块不会被归还；作用域结束时它们只是被泄漏。
+pub struct Scope<'a, A: ScopedAllocator> {
+    allocator: &'a A,
+    mark: (usize, usize),
+}
+
+impl<'a, A: ScopedAllocator> Scope<'a, A> {
+    /// Open a scope on `allocator`.
+    ///
+    /// # Safety
+    /// Every pointer allocated from `allocator` while the scope is alive, through the
+    /// scope or not, must not be used after the scope is dropped.
+    /// Nested scopes must be dropped in reverse order of creation.
+    ///
+    /// 在 `allocator` 上打开一个作用域。
+    ///
+    /// # Safety
+    /// 作用域存活期间从 `allocator` 分配的所有指针（无论是否经由作用域）
+    /// 在作用域被丢弃后都不得再使用。
+    /// 嵌套的作用域必须按照创建的相反顺序丢弃。
+    pub unsafe fn new(allocator: &'a A) -> Self {
+        Scope {
+            allocator,
+            mark: allocator.mark(),
+        }
+    }
+
+    /// Allocate from the inner allocator.
+    ///
+    /// # Safety
+    /// Same contract as [`GlobalAlloc::alloc`].
+    ///
+    /// 从内部分配器分配。
+    ///
+    /// # Safety
+    /// 与 [`GlobalAlloc::alloc`] 的约定相同。
+    pub unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { self.allocator.alloc(layout) }
+    }
+
+    /// Fallible variant of [`Scope::alloc`].
+    ///
+    /// # Safety
+    /// Same contract as [`GlobalAlloc::alloc`].
+    ///
+    /// [`Scope::alloc`] 的可失败版本。
+    ///
+    /// # Safety
+    /// 与 [`GlobalAlloc::alloc`] 的约定相同。
+    pub unsafe fn try_alloc(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        NonNull::new(unsafe { self.alloc(layout) }).ok_or(AllocError)
+    }
+
+    /// Return a block to the inner allocator.
+    ///
+    /// # Safety
+    /// Same contract as [`GlobalAlloc::dealloc`].
+    ///
+    /// 将块归还给内部分配器。
+    ///
+    /// # Safety
+    /// 与 [`GlobalAlloc::dealloc`] 的约定相同。
+    pub unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.allocator.dealloc(ptr, layout) }
+    }
+}
+
+impl<A: ScopedAllocator> Drop for Scope<'_, A> {
+    fn drop(&mut self) {
+        // SAFETY: Guaranteed by the contract of `Scope::new`
+        // SAFETY: 由 `Scope::new` 的约定保证
+        unsafe { self.allocator.rewind(self.mark) }
+    }
+}
+
+/// New heap top after rewinding from `top` in the region starting at `start` to `mark`.
+/// Within the marked region the lower top wins (a `coalesce` rollback may already be
+/// below the mark); if a new region was started inside the scope, everything in it was
+/// allocated inside the scope, so the top goes back to its start.
+///
+/// 从起始于 `start` 的区域中的 `top` 回退到 `mark` 后的新堆顶。
+/// 在标记的区域内取较低的堆顶（`coalesce` 的回退可能已经低于标记）；
+/// 如果在作用域内开始了新区域，其中的所有内存都是在作用域内分配的，因此堆顶回到其起始位置。
+pub(crate) fn rewound(top: usize, start: usize, mark: (usize, usize)) -> usize {
+    if start == mark.1 {
+        top.min(mark.0)
+    } else {
+        start
+    }
+}
@@ -1,4 +1,19 @@
-use crate::{PAGE_SIZE, grow_memory};
+use super::scope::{ScopedAllocator, rewound, sealed};
+#[cfg(feature = "stats")]
+use crate::HighWater;
+#[cfg(feature = "quota")]
+use crate::Quota;
+#[cfg(feature = "realloc")]
+use crate::layout_math::copy_span;
+use crate::layout_math::round_up;
+#[cfg(feature = "poison")]
+use crate::poison;
+use crate::{
+    AllocError, DefaultMemory, GrowthPolicy, MemorySource, OomHook, PAGE_SIZE, PageCount,
+    ResettableAllocator, bump_pages_needed, bump_range, dangling, grow_amortized, grow_region,
+};
+#[cfg(feature = "realloc")]
+use crate::{realloc_zero_sized, realloc_zero_tail};
 
 /// Safety Warning:
 /// Allocators in this module are designed for [Single Threaded] environments.
@@ -11,10 +26,28 @@ use crate::{PAGE_SIZE, grow_memory};
 /// 实现了 `Sync` 仅为了满足 `GlobalAlloc` trait 的要求。
 /// 在多线程环境中使用此分配器会导致未定义行为 (UB)。
 /// 请确保只在单线程环境（如 WASM 或单线程嵌入式环境）中使用。
-unsafe impl Sync for SegregatedBumpAllocator {}
+///
+/// With `strict-single-thread` this impl is left out: wrap the allocator in
+/// [`GlobalWrapper`](super::GlobalWrapper) at the `#[global_allocator]` site instead.
+///
+/// 开启 `strict-single-thread` 时不提供此实现：
+/// 请改为在 `#[global_allocator]` 处用 [`GlobalWrapper`](super::GlobalWrapper) 包装分配器。
+#[cfg(not(feature = "strict-single-thread"))]
+unsafe impl<S, const N: usize, const MIN_ALIGN: usize> Sync
+    for SegregatedBumpAllocator<S, N, MIN_ALIGN>
+{
+}
+
+// Safety: No one else owns the raw pointers (conceptually), logic is same.
+unsafe impl<S: Send, const N: usize, const MIN_ALIGN: usize> Send
+    for SegregatedBumpAllocator<S, N, MIN_ALIGN>
+{
+}
 use core::{
     alloc::{GlobalAlloc, Layout},
-    ptr::null_mut,
+    cell::UnsafeCell,
+    fmt,
+    ptr::{NonNull, null_mut},
 };
 
 /// Minimal Allocator with Fixed Bins + Bump Pointer Fallback.
@@ -36,30 +69,570 @@ use core::{
 /// - **Bin 1**: 32 Bytes
 /// - **Bin 2**: 64 Bytes
 /// - **Bin 3**: 128 Bytes
-/// - **Large**: > 128 Bytes, allocated directly using Bump Pointer, not reused.
+/// - **Large**: > 128 Bytes, allocated directly using Bump Pointer, not reused
+///   (reused first-fit with the `large-reuse` feature).
+///
+/// The `N` bin sizes can be replaced with [`with_bins`](Self::with_bins).
 ///
 /// # 内存布局
 /// - **Bin 0**: 16 Bytes (用于 Box<u8>, small structs)
 /// - **Bin 1**: 32 Bytes
 /// - **Bin 2**: 64 Bytes
 /// - **Bin 3**: 128 Bytes
-/// - **Large**: > 128 Bytes，直接使用 Bump Pointer 分配，不复用。
-pub struct SegregatedBumpAllocator;
+/// - **Large**: > 128 Bytes，直接使用 Bump Pointer 分配，不复用
+///   （启用 `large-reuse` feature 时按首次适配复用）。
+///
+/// 可以通过 [`with_bins`](Self::with_bins) 替换这 `N` 个 Bin 的大小。
+pub struct SegregatedBumpAllocator<
+    S = DefaultMemory,
+    const N: usize = 4,
+    const MIN_ALIGN: usize = 16,
+> {
+    // Block size of each bin, strictly increasing
+    // 每个 Bin 的块大小，严格递增
+    bin_sizes: [usize; N],
+    // Head pointers of the bin free lists, bins[i] holds blocks of bin_sizes[i]
+    // 各 Bin 空闲链表的头指针，bins[i] 存放 bin_sizes[i] 大小的块
+    bins: UnsafeCell<[*mut Node; N]>,
+    // Number of blocks in each bin free list
+    // 各 Bin 空闲链表中的块数
+    bin_depths: UnsafeCell<[usize; N]>,
+    // Blocks freed into a full bin go back to the heap top or the large free list
+    // 释放到已满 Bin 的块回到堆顶或大对象空闲链表
+    bin_depth_cap: usize,
+    // Freed large blocks, unordered, searched first-fit
+    // 已释放的大对象块，无序，首次适配搜索
+    #[cfg(feature = "large-reuse")]
+    large: UnsafeCell<*mut LargeNode>,
+    // Freed blocks aligned beyond MIN_ALIGN, each with its front padding, searched first-fit
+    // 已释放的对齐超过 MIN_ALIGN 的块（各自连同其前部填充），首次适配搜索
+    aligned: UnsafeCell<*mut LargeNode>,
+    // Bump Pointer (Heap Top Pointer)
+    // Bump Pointer (堆顶指针)
+    heap_top: UnsafeCell<usize>,
+    // End of the memory currently owned by this instance
+    // 此实例当前拥有的内存边界
+    heap_end: UnsafeCell<usize>,
+    // Start of the current bump region, used by `Scope` to rewind
+    // 当前 Bump 区域的起始地址，供 `Scope` 回退使用
+    heap_start: UnsafeCell<usize>,
+    // Start of the first region ever grown: the lower bound of `heap_extent` and of the
+    // `dealloc-check` checks
+    // 首个增长区域的起始地址：`heap_extent` 以及 `dealloc-check` 检查的下界
+    heap_base: UnsafeCell<usize>,
+    // Memory below this address may have been handed out before a `Scope` rewind,
+    // so bumping over it again does not yield zeroed blocks
+    // 低于此地址的内存可能在 `Scope` 回退之前被分配过，
+    // 因此再次 Bump 经过它时得到的块不一定为零
+    dirty_end: UnsafeCell<usize>,
+    source: S,
+    // How many pages each `grow_memory` call requests
+    // 每次 `grow_memory` 调用申请的页数策略
+    growth: GrowthPolicy,
+    oom_hook: OomHook,
+    #[cfg(feature = "quota")]
+    quota: Quota,
+    #[cfg(feature = "stats")]
+    high_water: HighWater,
+    #[cfg(feature = "stats")]
+    stats: core::cell::Cell<BinStats<N>>,
+}
+
+/// Per-size-class allocation counters of a [`SegregatedBumpAllocator`] (`stats` feature),
+/// for tuning bin sizes to a workload.
+///
+/// [`SegregatedBumpAllocator`] 按大小分类的分配计数器（`stats` feature），
+/// 用于根据工作负载调整 Bin 大小。
+#[cfg(feature = "stats")]
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct BinStats<const N: usize = 4> {
+    /// Successful allocations served by each bin.
+    ///
+    /// 每个 Bin 成功服务的分配次数。
+    pub bin_allocs: [usize; N],
+    /// Successful allocations that bypassed the bins: larger than the last bin, or
+    /// aligned beyond `MIN_ALIGN`.
+    ///
+    /// 绕过 Bins 的成功分配次数：大于最后一个 Bin，或对齐要求超过 `MIN_ALIGN`。
+    pub large_allocs: usize,
+    /// Bin allocations served by reusing a freed block.
+    ///
+    /// 通过复用已释放块完成的 Bin 分配次数。
+    pub bin_hits: usize,
+    /// Bin allocations that found their bin empty and were bumped.
+    ///
+    /// Bin 为空、转而通过 Bump 完成的 Bin 分配次数。
+    pub bin_misses: usize,
+}
+
+#[cfg(feature = "stats")]
+impl<const N: usize> BinStats<N> {
+    const fn new() -> Self {
+        BinStats {
+            bin_allocs: [0; N],
+            large_allocs: 0,
+            bin_hits: 0,
+            bin_misses: 0,
+        }
+    }
+}
+
+// Default size classes
+// 默认的大小分类
+const DEFAULT_BINS: [usize; 4] = [16, 32, 64, 128];
+
+impl<S> SegregatedBumpAllocator<S> {
+    /// Create an allocator that obtains its pages from `source`.
+    ///
+    /// 创建一个从 `source` 获取页面的分配器。
+    pub const fn with_source(source: S) -> Self {
+        Self::with_bins(source, DEFAULT_BINS)
+    }
+}
+
+impl<S, const N: usize> SegregatedBumpAllocator<S, N> {
+    /// Create an allocator with custom size classes. A request is served from the
+    /// smallest bin that fits it; anything larger than the last bin takes the large path.
+    ///
+    /// # Panics
+    /// If `bin_sizes` is not strictly increasing or a size cannot hold a free-list node.
+    ///
+    /// 创建一个使用自定义大小分类的分配器。请求由能容纳它的最小 Bin 服务；
+    /// 大于最后一个 Bin 的请求走大对象路径。
+    ///
+    /// # Panics
+    /// 如果 `bin_sizes` 不是严格递增，或某个大小无法容纳空闲链表节点。
+    pub const fn with_bins(source: S, bin_sizes: [usize; N]) -> Self {
+        Self::with_min_align(source, bin_sizes)
+    }
+}
+
+impl<S, const N: usize, const MIN_ALIGN: usize> SegregatedBumpAllocator<S, N, MIN_ALIGN> {
+    /// Create an allocator with custom size classes whose blocks are aligned to
+    /// `MIN_ALIGN` (16 by default); requests with a larger alignment bypass the bins.
+    ///
+    /// Bin sizes are checked against the free-list node on their own, so `MIN_ALIGN` only
+    /// needs the alignment of `usize`, e.g. 8-byte bins with `MIN_ALIGN = 8`.
+    ///
+    /// # Panics
+    /// If `MIN_ALIGN` is not a power of two or is smaller than the alignment of `usize`,
+    /// or for the same `bin_sizes` as [`with_bins`](SegregatedBumpAllocator::with_bins).
+    ///
+    /// 创建一个使用自定义大小分类、块按 `MIN_ALIGN`（默认 16）对齐的分配器；
+    /// 对齐要求更大的请求会绕过 Bins。
+    ///
+    /// Bin 大小会单独与空闲链表节点比较，因此 `MIN_ALIGN` 只需要 `usize` 的对齐，
+    /// 例如 `MIN_ALIGN = 8` 时使用 8 字节的 Bin。
+    ///
+    /// # Panics
+    /// 如果 `MIN_ALIGN` 不是 2 的幂或小于 `usize` 的对齐，
+    /// 或 `bin_sizes` 满足 [`with_bins`](SegregatedBumpAllocator::with_bins) 的 panic 条件。
+    pub const fn with_min_align(source: S, bin_sizes: [usize; N]) -> Self {
+        assert!(
+            MIN_ALIGN.is_power_of_two() && MIN_ALIGN >= core::mem::align_of::<usize>(),
+            "MIN_ALIGN must be a power of two >= align_of::<usize>()"
+        );
+        let mut i = 0;
+        while i < N {
+            assert!(
+                bin_sizes[i] >= core::mem::size_of::<Node>(),
+                "bin size too small"
+            );
+            assert!(
+                i == 0 || bin_sizes[i] > bin_sizes[i - 1],
+                "bin sizes must be strictly increasing"
+            );
+            i += 1;
+        }
+
+        SegregatedBumpAllocator {
+            bin_sizes,
+            bins: UnsafeCell::new([null_mut(); N]),
+            bin_depths: UnsafeCell::new([0; N]),
+            bin_depth_cap: usize::MAX,
+            #[cfg(feature = "large-reuse")]
+            large: UnsafeCell::new(null_mut()),
+            aligned: UnsafeCell::new(null_mut()),
+            heap_top: UnsafeCell::new(0),
+            heap_end: UnsafeCell::new(0),
+            heap_start: UnsafeCell::new(0),
+            heap_base: UnsafeCell::new(0),
+            dirty_end: UnsafeCell::new(0),
+            source,
+            growth: GrowthPolicy::EXACT,
+            oom_hook: OomHook::new(),
+            #[cfg(feature = "quota")]
+            quota: Quota::new(),
+            #[cfg(feature = "stats")]
+            high_water: HighWater::new(),
+            #[cfg(feature = "stats")]
+            stats: core::cell::Cell::new(BinStats::new()),
+        }
+    }
+
+    /// Use `policy` to size this allocator's memory growth, e.g.
+    /// `GrowthPolicy { initial_pages: PageCount(64), divisor: 2 }` reserves 4 MiB up front
+    /// and then grows by at least half of the current region.
+    ///
+    /// 使用 `policy` 决定此分配器的内存增长大小，例如
+    /// `GrowthPolicy { initial_pages: PageCount(64), divisor: 2 }` 预先保留 4 MiB，
+    /// 之后每次至少增长当前区域的一半。
+    pub const fn with_growth(mut self, policy: GrowthPolicy) -> Self {
+        self.growth = policy;
+        self
+    }
+
+    /// Keep at most `cap` free blocks in each bin (unbounded by default). A block freed
+    /// into a full bin rolls the bump pointer back if it is the last block bumped; otherwise,
+    /// with the `large-reuse` feature, it joins the large free list, where misses of any bin
+    /// can reuse it. Without `large-reuse` such blocks stay in the bin beyond the cap.
+    ///
+    /// 每个 Bin 最多保留 `cap` 个空闲块（默认无上限）。释放到已满 Bin 的块如果是最后一个
+    /// Bump 出的块，则回退 Bump 指针；否则在启用 `large-reuse` feature 时加入大对象空闲链表，
+    /// 任何 Bin 未命中时都可以复用它。未启用 `large-reuse` 时，这些块超出上限留在 Bin 中。
+    pub const fn with_bin_depth_cap(mut self, cap: usize) -> Self {
+        self.bin_depth_cap = cap;
+        self
+    }
+
+    /// Largest request served by a bin: the last size class, e.g. 128 with the default
+    /// bins. Anything larger takes the large path; 0 when there are no bins.
+    ///
+    /// 由 Bin 服务的最大请求：即最后一个大小分类，默认 Bin 下为 128。
+    /// 更大的请求走大对象路径；没有 Bin 时为 0。
+    pub const fn large_threshold(&self) -> usize {
+        if N == 0 { 0 } else { self.bin_sizes[N - 1] }
+    }
+
+    /// Number of free blocks currently held by each bin.
+    ///
+    /// 每个 Bin 当前持有的空闲块数。
+    pub fn bin_depths(&self) -> [usize; N] {
+        unsafe { *self.bin_depths.get() }
+    }
+
+    /// Register a hook called with the failing `Layout` right before `alloc` returns null.
+    /// Pass `None` to remove it.
+    ///
+    /// 注册一个回调，在 `alloc` 返回 null 之前以失败的 `Layout` 调用。
+    /// 传入 `None` 以移除。
+    pub fn set_oom_hook(&self, hook: Option<fn(Layout)>) {
+        self.oom_hook.set(hook);
+    }
+
+    /// Cap the bytes this allocator has handed out and not yet taken back (`quota` feature).
+    /// Once a request would exceed `limit`, `alloc` returns null without growing memory.
+    /// Pass `None` to remove the cap.
+    /// Blocks that `dealloc` leaks (large blocks without `large-reuse`) keep counting
+    /// against it.
+    ///
+    /// 限制此分配器已分配出去且尚未收回的字节数（`quota` feature）。
+    /// 一旦请求会超出 `limit`，`alloc` 将返回 null 且不会增长内存。
+    /// 传入 `None` 以移除限制。
+    /// `dealloc` 泄漏的块（未启用 `large-reuse` 时的大对象块）仍计入限制。
+    #[cfg(feature = "quota")]
+    pub fn set_quota(&self, limit: Option<usize>) {
+        self.quota.set_limit(limit);
+    }
+
+    /// Snapshot of the per-size-class counters.
+    ///
+    /// 按大小分类的计数器快照。
+    #[cfg(feature = "stats")]
+    pub fn bin_stats(&self) -> BinStats<N> {
+        self.stats.get()
+    }
+
+    /// Bytes currently counted against the quota (`quota` feature).
+    ///
+    /// 当前计入配额的字节数（`quota` feature）。
+    #[cfg(feature = "quota")]
+    pub fn quota_used(&self) -> usize {
+        self.quota.live()
+    }
+
+    /// Peak heap usage so far (`stats` feature): the bytes grown from the memory source
+    /// minus the part of the current region above the highest heap top. Kept after the
+    /// memory is freed, so it can size the initial memory of a workload. Cleared by
+    /// [`reset`](Self::reset).
+    ///
+    /// 迄今为止的堆使用峰值（`stats` feature）：从内存来源增长的字节数，减去当前区域中
+    /// 高于最高堆顶的部分。内存被释放后仍保留，因此可用于确定某个工作负载的初始内存大小。
+    /// 由 [`reset`](Self::reset) 清零。
+    #[cfg(feature = "stats")]
+    pub fn peak_bytes(&self) -> usize {
+        self.high_water.peak()
+    }
+
+    /// ⚠️ Test/Bench only: Forget all bins and the bump region.
+    /// Pages already obtained from the memory source are not returned.
+    ///
+    /// # Safety
+    /// All pointers handed out before the reset become dangling.
+    ///
+    /// ⚠️ 仅用于测试/Bench：清空所有 Bin 与 Bump 区域。
+    /// 已从内存源获取的页面不会被归还。
+    ///
+    /// # Safety
+    /// 重置之前分配出去的所有指针都将失效。
+    pub unsafe fn reset(&self) {
+        unsafe {
+            *self.bins.get() = [null_mut(); N];
+            *self.bin_depths.get() = [0; N];
+            #[cfg(feature = "large-reuse")]
+            {
+                *self.large.get() = null_mut();
+            }
+            *self.aligned.get() = null_mut();
+            *self.heap_top.get() = 0;
+            *self.heap_end.get() = 0;
+            *self.heap_start.get() = 0;
+            *self.heap_base.get() = 0;
+            *self.dirty_end.get() = 0;
+        }
+        #[cfg(feature = "quota")]
+        self.quota.clear();
+        #[cfg(feature = "stats")]
+        {
+            self.stats.set(BinStats::new());
+            self.high_water.clear();
+        }
+    }
+
+    /// Number of usable bytes in the block at `ptr`, allocated with `layout`.
+    /// For binned sizes this is the whole bin size.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`.
+    ///
+    /// `ptr` 处以 `layout` 分配的块中的可用字节数。
+    /// 对于分箱大小，即整个 Bin 的大小。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配。
+    pub unsafe fn usable_size(&self, _ptr: *mut u8, layout: Layout) -> usize {
+        if layout.size() == 0 {
+            return 0;
+        }
+        #[cfg(feature = "track-sizes")]
+        return match Self::tracked_layout(layout) {
+            Some(inner) => self.block_capacity(inner) - Self::header_size(layout.align()),
+            None => 0,
+        };
+        #[cfg(not(feature = "track-sizes"))]
+        self.block_capacity(layout)
+    }
+
+    /// Size of the block an allocation with `layout` would carve from the heap, without
+    /// allocating: the bin size for binned requests, otherwise the request itself (plus
+    /// the size word with `track-sizes`) rounded up to a multiple of MIN_ALIGN. Padding
+    /// in front of a high-align block depends on where it lands and is not included.
+    /// `0` for zero-sized layouts, which use no heap memory.
+    ///
+    /// 不进行分配，返回以 `layout` 分配时将从堆中切出的块大小：分箱请求为 Bin 的大小，
+    /// 否则为请求大小本身（开启 `track-sizes` 时加上大小字）向上取整到 MIN_ALIGN 的倍数。
+    /// 高对齐块前的填充取决于其落点，不计算在内。零大小的布局不占用堆内存，返回 `0`。
+    pub fn block_size(&self, layout: Layout) -> usize {
+        if layout.size() == 0 {
+            return 0;
+        }
+        #[cfg(feature = "track-sizes")]
+        let Some(layout) = Self::tracked_layout(layout) else {
+            return 0;
+        };
+        round_up(self.block_capacity(layout), MIN_ALIGN)
+    }
+
+    /// Address range `[base, end)` this allocator has obtained so far: from the first page
+    /// it grew to the end of its current region. Every live block lies inside it, so a
+    /// host can copy this span to checkpoint the heap. `(0, 0)` before the first allocation.
+    ///
+    /// If the memory source ever returned pages that do not follow the previous ones, the
+    /// span also covers the gap between them.
+    ///
+    /// # Safety
+    /// Must not race with an allocation on this allocator.
+    ///
+    /// 此分配器目前已获取的地址范围 `[base, end)`：从其增长的第一页到当前区域的末尾。
+    /// 所有存活块都位于其中，因此宿主可以复制这段内存为堆建立检查点。
+    /// 首次分配之前为 `(0, 0)`。
+    ///
+    /// 如果内存来源曾返回不紧接前一段的页面，该范围也会覆盖它们之间的空隙。
+    ///
+    /// # Safety
+    /// 不得与此分配器上的分配同时进行。
+    pub unsafe fn heap_extent(&self) -> (usize, usize) {
+        unsafe { (*self.heap_base.get(), *self.heap_end.get()) }
+    }
+
+    /// Walk every bin's free list and check that it is intact: each node is
+    /// `MIN_ALIGN`-aligned, its block lies in `[base, heap top)` of [`heap_extent`], and the
+    /// list ends after exactly as many nodes as the bin counts, which also stops at a cycle.
+    /// Meant for fuzzers and tests to assert after each operation.
+    ///
+    /// # Safety
+    /// Must not race with an allocation on this allocator. Reads the `next` link of every
+    /// node it accepts, so a corrupted link is only followed once it passed the checks.
+    ///
+    /// 遍历每个 Bin 的空闲链表并检查其完整性：每个节点都按 `MIN_ALIGN` 对齐，
+    /// 其块位于 [`heap_extent`] 的 `[base, 堆顶)` 范围内，且链表恰好在 Bin 计数的节点数之后结束，
+    /// 这同时也会在遇到环时停止。供模糊测试和测试在每次操作后断言使用。
+    ///
+    /// # Safety
+    /// 不得与此分配器上的分配同时进行。会读取每个通过检查的节点的 `next` 链接，
+    /// 因此损坏的链接只有在通过检查后才会被跟随。
+    ///
+    /// [`heap_extent`]: Self::heap_extent
+    pub unsafe fn check_bins(&self) -> bool {
+        let (base, top) = unsafe { (*self.heap_base.get(), *self.heap_top.get()) };
+        let bins = unsafe { &*self.bins.get() };
+        let depths = unsafe { &*self.bin_depths.get() };
+        for ((&head, &depth), &size) in bins.iter().zip(depths).zip(&self.bin_sizes) {
+            let mut node = head;
+            // The count bounds the walk, so a cycle shows up as a list that is too long
+            // 计数限定了遍历的长度，因此环表现为过长的链表
+            for _ in 0..depth {
+                let addr = node as usize;
+                if !addr.is_multiple_of(MIN_ALIGN)
+                    || addr < base
+                    || addr.checked_add(size).is_none_or(|end| end > top)
+                {
+                    return false;
+                }
+                node = unsafe { (*node).next };
+            }
+            if !node.is_null() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Bytes reserved in front of each block for its size word (`track-sizes` feature).
+    /// At least `MIN_ALIGN`, and the full alignment for high-align blocks, so the payload
+    /// keeps its alignment.
+    ///
+    /// 每个块前为其大小字预留的字节数（`track-sizes` feature）。
+    /// 至少为 `MIN_ALIGN`，高对齐块则为完整的对齐值，使有效载荷保持对齐。
+    #[cfg(feature = "track-sizes")]
+    #[inline(always)]
+    const fn header_size(align: usize) -> usize {
+        if align > MIN_ALIGN { align } else { MIN_ALIGN }
+    }
+
+    /// Layout of the underlying block for a `layout` request, header included.
+    ///
+    /// `layout` 请求对应的底层块布局，包含头部。
+    #[cfg(feature = "track-sizes")]
+    #[inline(always)]
+    fn tracked_layout(layout: Layout) -> Option<Layout> {
+        let size = layout
+            .size()
+            .checked_add(Self::header_size(layout.align()))?;
+        Layout::from_size_align(size, layout.align()).ok()
+    }
+
+    /// Bytes a block of `layout` counts against the quota: the caller's request, without
+    /// the `track-sizes` size word.
+    ///
+    /// `layout` 的块计入配额的字节数：调用方请求的大小，不含 `track-sizes` 的大小字。
+    #[cfg(feature = "quota")]
+    #[inline(always)]
+    fn charged(layout: Layout) -> usize {
+        #[cfg(feature = "track-sizes")]
+        return layout.size() - Self::header_size(layout.align());
+        #[cfg(not(feature = "track-sizes"))]
+        layout.size()
+    }
+
+    /// Store the size word of the block whose payload starts at `ptr`.
+    ///
+    /// 存储有效载荷起始于 `ptr` 的块的大小字。
+    #[cfg(feature = "track-sizes")]
+    #[inline(always)]
+    unsafe fn set_tracked_size(ptr: *mut u8, inner: Layout) {
+        let word = ptr as *mut usize;
+        // High-align blocks have room for two words: the complement of the alignment, which
+        // no size can equal, then the size
+        // 高对齐块有容纳两个字的空间：对齐值的按位取反（任何大小都不会等于它），然后是大小
+        unsafe {
+            if inner.align() > MIN_ALIGN {
+                word.sub(1).write(!inner.align());
+                word.sub(2).write(inner.size());
+            } else {
+                word.sub(1).write(inner.size());
+            }
+        }
+    }
+
+    /// Actual capacity of a block allocated with `layout`.
+    /// Binned blocks own the whole bin size; high-align and large blocks own exactly their size.
+    ///
+    /// 以 `layout` 分配的块的实际容量。
+    /// 分箱块拥有整个 Bin 的大小；高对齐块和大对象块恰好拥有其大小。
+    #[inline(always)]
+    fn block_capacity(&self, layout: Layout) -> usize {
+        let size = layout.size().max(MIN_ALIGN);
+        if layout.align() > MIN_ALIGN {
+            size
+        } else if let Some(index) = get_index(&self.bin_sizes, size) {
+            self.bin_sizes[index]
+        } else {
+            size
+        }
+    }
+}
 
 impl SegregatedBumpAllocator {
     pub const fn new() -> Self {
-        SegregatedBumpAllocator
+        Self::with_source(DefaultMemory)
     }
 
-    /// ⚠️ Test/Bench only: Reset global state
+    /// Create an allocator whose first allocation grows memory by `pages` pages at once
+    /// (or more if the request needs them), instead of page by page.
     ///
-    /// ⚠️ 仅用于测试/Bench：重置全局状态
-    pub unsafe fn reset() {
-        unsafe {
-            BINS = [null_mut(); 4];
-            HEAP_TOP = 0;
-            HEAP_END = 0;
+    /// 创建一个分配器，其首次分配一次性增长 `pages` 页内存（如果请求需要更多则增长更多），
+    /// 而不是逐页增长。
+    pub const fn with_initial_pages(pages: PageCount) -> Self {
+        Self::new().with_growth(GrowthPolicy {
+            initial_pages: pages,
+            divisor: 0,
+        })
+    }
+}
+
+impl Default for SegregatedBumpAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Prints the bump pointer, the end of the heap and the number of free blocks in each bin
+/// (next to the bin sizes) without allocating. Bin counts are kept up to date, so only the
+/// `large-reuse` list is walked.
+///
+/// 不进行分配地打印 Bump 指针、堆末尾以及每个 Bin 中的空闲块数（与 Bin 大小并列）。
+/// Bin 的计数是实时维护的，因此只会遍历 `large-reuse` 链表。
+impl<S, const N: usize, const MIN_ALIGN: usize> fmt::Debug
+    for SegregatedBumpAllocator<S, N, MIN_ALIGN>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = f.debug_struct("SegregatedBumpAllocator");
+        out.field("heap_top", unsafe { &*self.heap_top.get() })
+            .field("heap_end", unsafe { &*self.heap_end.get() })
+            .field("bin_sizes", &self.bin_sizes)
+            .field("bin_free", unsafe { &*self.bin_depths.get() });
+        #[cfg(feature = "large-reuse")]
+        {
+            let mut large_free = 0;
+            let mut node = unsafe { *self.large.get() };
+            while !node.is_null() {
+                large_free += 1;
+                node = unsafe { (*node).next };
+            }
+            out.field("large_free", &large_free);
         }
+        out.finish()
     }
 }
 
@@ -69,207 +642,1006 @@ struct Node {
     next: *mut Node,
 }
 
-// --------------------------------------------------------------------------
-// Global Static State (Safe in single-threaded Wasm)
-// 全局静态状态 (在单线程 Wasm 中是安全的)
-// --------------------------------------------------------------------------
+// Free large or high-align block header, records the block size since layouts vary
+// 空闲大对象块或高对齐块的头部，由于布局各异，需要记录块大小
+struct LargeNode {
+    next: *mut LargeNode,
+    size: usize,
+}
 
-// 4 bins head pointers. BINS[0] -> 16B, [1] -> 32B, [2] -> 64B, [3] -> 128B
-// 4个桶的头指针。BINS[0] -> 16B, [1] -> 32B, [2] -> 64B, [3] -> 128B
-static mut BINS: [*mut Node; 4] = [null_mut(); 4];
+unsafe impl<S: MemorySource, const N: usize, const MIN_ALIGN: usize> GlobalAlloc
+    for SegregatedBumpAllocator<S, N, MIN_ALIGN>
+{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.try_alloc(layout) }.map_or(null_mut(), NonNull::as_ptr);
+        trace!(
+            "alloc size={=usize} align={=usize} ptr={=usize:#x}",
+            layout.size(),
+            layout.align(),
+            ptr as usize
+        );
+        ptr
+    }
 
-// Bump Pointer (Heap Top Pointer)
-// Bump Pointer (堆顶指针)
-static mut HEAP_TOP: usize = 0;
-// Current Wasm memory boundary
-// 当前已申请的 Wasm 内存边界
-static mut HEAP_END: usize = 0;
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let Ok((ptr, fresh)) = (unsafe { self.try_alloc_payload(layout) }) else {
+            return null_mut();
+        };
+        let ptr = ptr.as_ptr();
+        // Freshly bumped blocks are already zeroed (by the memory source, or by `bump_alloc`
+        // for dirty sources), except where a `Scope` rewind let the bump pointer run over
+        // memory handed out before. Recycled blocks may hold anything.
+        // 新 Bump 出的块已经是零（由内存来源保证，或对脏内存来源由 `bump_alloc` 清零），
+        // 除非 `Scope` 回退使 Bump 指针重新经过之前分配过的内存。回收的块可能包含任意内容。
+        let dirty = if fresh {
+            unsafe { *self.dirty_end.get() }
+                .saturating_sub(ptr as usize)
+                .min(layout.size())
+        } else {
+            layout.size()
+        };
+        unsafe { ptr.write_bytes(0, dirty) };
+        trace!(
+            "alloc size={=usize} align={=usize} ptr={=usize:#x}",
+            layout.size(),
+            layout.align(),
+            ptr as usize
+        );
+        ptr
+    }
 
-unsafe impl GlobalAlloc for SegregatedBumpAllocator {
-    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        // 1. Large alignment handling
-        // Fixed Bins default guarantee 16-byte alignment.
-        // If user requests > 16-byte alignment (very rare), handle directly via Bump allocation.
-        // 1. 大对齐处理
-        // 固定 Bins 默认保证 16 字节对齐。
-        // 如果用户请求 > 16 字节对齐（非常罕见），直接通过 Bump 分配来处理对齐。
-        if layout.align() > 16 {
-            return unsafe { self.bump_alloc(layout.size(), layout.align()) };
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        trace!(
+            "dealloc size={=usize} ptr={=usize:#x}",
+            layout.size(),
+            ptr as usize
+        );
+        if layout.size() == 0 {
+            return;
         }
+        #[cfg(feature = "track-sizes")]
+        let (ptr, layout) = unsafe {
+            (
+                ptr.sub(Self::header_size(layout.align())),
+                Self::tracked_layout(layout).unwrap_unchecked(),
+            )
+        };
+        unsafe { self.dealloc_block(ptr, layout) }
+    }
 
-        // 2. Calculate category
-        // 2. 计算分类
-        let size = layout.size().max(16);
+    #[cfg(feature = "realloc")]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if layout.size() == 0 || new_size == 0 {
+            return unsafe { realloc_zero_sized(self, ptr, layout, new_size) };
+        }
 
-        // 3. Try lookup reuse (Small Alloc)
-        // 3. 尝试查表复用 (Small Alloc)
-        if let Some(index) = get_index(size) {
-            unsafe {
-                let head = BINS[index];
-                if !head.is_null() {
-                    // Hit: Pop from list head (LIFO)
-                    // Hit: 弹出链表头 (LIFO)
-                    let next = (*head).next;
-                    BINS[index] = next;
-                    return head as *mut u8;
-                }
+        // The size word moves with the block and is updated to the new size
+        // 大小字随块一起移动，并更新为新的大小
+        #[cfg(feature = "track-sizes")]
+        unsafe {
+            let header = Self::header_size(layout.align());
+            let Some(new_inner) = new_size.checked_add(header) else {
+                return null_mut();
+            };
+            let inner = Self::tracked_layout(layout).unwrap_unchecked();
+            let base = self.realloc_block(ptr.sub(header), inner, new_inner);
+            if base.is_null() {
+                return base;
             }
+            let ptr = base.add(header);
+            Self::set_tracked_size(
+                ptr,
+                Layout::from_size_align_unchecked(new_inner, layout.align()),
+            );
+            ptr
+        }
+        #[cfg(not(feature = "track-sizes"))]
+        unsafe {
+            self.realloc_block(ptr, layout, new_size)
+        }
+    }
+}
 
-            // Miss: Bin is empty, fallback to Bump allocation
-            // Allocate block of corresponding Bin size directly, instead of layout.size(), so it can be returned correctly later
-            // Miss: Bin 为空，回退到 Bump 分配
-            // 直接分配对应 Bin 大小的块，而不是 layout.size()，以便将来 dealloc 能正确归位
-            let block_size = 16 << index;
-            return unsafe { self.bump_alloc(block_size, 16) };
+impl<S: MemorySource, const N: usize, const MIN_ALIGN: usize>
+    SegregatedBumpAllocator<S, N, MIN_ALIGN>
+{
+    /// Try to grow the block at `ptr` to `new_size` bytes without moving it: within its bin
+    /// or alignment slack, or at the heap top (growing the heap if needed). Returns whether
+    /// it grew; on `false` the block is unchanged and the caller may move the data itself.
+    /// Unlike `realloc`, this does not need the `realloc` feature.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`, and `new_size` must
+    /// be at least `layout.size()` and valid for a `Layout` of `layout.align()`. After a
+    /// `true` the block must be freed with a layout of `new_size` bytes.
+    ///
+    /// 尝试在不移动的情况下将 `ptr` 处的块扩容到 `new_size` 字节：在其 Bin 或对齐余量之内，
+    /// 或在堆顶（必要时增长堆）。返回是否扩容成功；返回 `false` 时块保持不变，
+    /// 调用者可以自行移动数据。与 `realloc` 不同，它不需要 `realloc` feature。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配，`new_size` 不得小于 `layout.size()`，
+    /// 且对 `layout.align()` 的 `Layout` 有效。返回 `true` 后必须以 `new_size` 字节的布局释放该块。
+    pub unsafe fn grow_in_place(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> bool {
+        debug_assert!(
+            new_size >= layout.size(),
+            "grow_in_place cannot shrink a block"
+        );
+        // Zero-sized blocks own no memory to grow into
+        // 零大小的块不拥有可供扩容的内存
+        if layout.size() == 0 {
+            return false;
+        }
+        #[cfg(feature = "quota")]
+        if !self.quota.fits(new_size - layout.size()) {
+            return false;
         }
+        unsafe { self.resize_in_place(ptr, layout, new_size) }
+    }
 
-        // 4. Large object handling (> 128 Bytes)
-        // Alloc via Bump directly, skip Bins
-        // 4. 大对象处理 (> 128 Bytes)
-        // 直接 Bump 分配，不走 Bin
-        unsafe { self.bump_alloc(size, 16) }
+    /// Shrink the block at `ptr` to `new_size` bytes without moving it; the block keeps
+    /// its capacity. Returns `false` only for `new_size == 0`, which leaves the block
+    /// unchanged: a zero-sized block owns no memory.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`, and `new_size` must
+    /// not exceed `layout.size()`. After a `true` the block must be freed with a layout of
+    /// `new_size` bytes.
+    ///
+    /// 在不移动的情况下将 `ptr` 处的块缩小为 `new_size` 字节；块保留其容量。
+    /// 仅当 `new_size == 0` 时返回 `false` 且块保持不变：零大小的块不拥有内存。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配，且 `new_size` 不得大于 `layout.size()`。
+    /// 返回 `true` 后必须以 `new_size` 字节的布局释放该块。
+    pub unsafe fn shrink_in_place(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> bool {
+        debug_assert!(
+            new_size <= layout.size(),
+            "shrink_in_place cannot grow a block"
+        );
+        new_size != 0 && unsafe { self.resize_in_place(ptr, layout, new_size) }
     }
 
-    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-        // 1. If block has high alignment requirement, it must not be from Bins,
-        //    and because we don't have metadata for its size, just discard it (leak).
-        // 1. 如果是对齐要求很高的块，它一定不是来自 Bins，
-        //    且我们没有元数据记录它的大小，所以直接丢弃（泄漏）。
-        if layout.align() > 16 {
+    /// Grow the heap up front so that the next `bytes` bytes can be bumped without growing,
+    /// e.g. before a latency-sensitive phase. Advances the heap end only; nothing is grown
+    /// if the current region already has room. Returns `false` on OOM.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 预先增长堆，使接下来的 `bytes` 字节无需增长即可 Bump 分配，例如在对延迟敏感的阶段之前。
+    /// 只推进堆末尾；如果当前区域已有足够空间，则不会增长。OOM 时返回 `false`。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn reserve(&self, bytes: usize) -> bool {
+        unsafe { self.bump_room(bytes, 1) }.is_some()
+    }
+
+    /// Pages that [`reserve`](Self::reserve) would have to grow for `bytes` bytes, given
+    /// the room left in the current bump region, without growing anything: `PageCount(0)`
+    /// if they already fit. A host can check it against its memory budget before
+    /// allocating. The growth policy may still ask for more pages than this minimum.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 根据当前 Bump 区域的剩余空间，计算 [`reserve`](Self::reserve) 为 `bytes` 字节
+    /// 需要增长的页数，且不进行任何增长：已能放下时为 `PageCount(0)`。
+    /// 宿主可以在分配之前用它对照自己的内存预算。增长策略仍可能申请多于此最小值的页数。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn pages_needed_for(&self, bytes: usize) -> PageCount {
+        unsafe { bump_pages_needed(*self.heap_top.get(), *self.heap_end.get(), bytes) }
+    }
+
+    /// Fallible allocation: like `GlobalAlloc::alloc`, but returns `Err(AllocError)`
+    /// instead of a null pointer. The OOM hook still fires on failure.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::alloc`, except that zero-sized layouts are allowed:
+    /// they yield a dangling, aligned pointer that uses no heap memory.
+    ///
+    /// 可失败的分配：与 `GlobalAlloc::alloc` 相同，但失败时返回 `Err(AllocError)`
+    /// 而不是空指针。失败时仍会触发 OOM 回调。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::alloc` 的约定相同，但允许零大小的布局：
+    /// 它们得到一个悬垂但对齐、不占用堆内存的指针。
+    pub unsafe fn try_alloc(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        unsafe { self.try_alloc_payload(layout) }.map(|(ptr, _)| ptr)
+    }
+
+    /// Allocate an uninitialized array of `n` values of type `T` in one block. Returns null
+    /// if `Layout::array::<T>(n)` overflows or memory runs out. Free it with
+    /// [`dealloc_array`](Self::dealloc_array) and the same `n`.
+    ///
+    /// # Safety
+    /// Same contract as [`try_alloc`](Self::try_alloc) with `Layout::array::<T>(n)`.
+    ///
+    /// 在一个块中分配 `n` 个 `T` 类型值的未初始化数组。`Layout::array::<T>(n)` 溢出或内存耗尽时
+    /// 返回 null。使用 [`dealloc_array`](Self::dealloc_array) 并传入相同的 `n` 释放。
+    ///
+    /// # Safety
+    /// 与以 `Layout::array::<T>(n)` 调用 [`try_alloc`](Self::try_alloc) 的约定相同。
+    pub unsafe fn alloc_array<T>(&self, n: usize) -> *mut T {
+        match Layout::array::<T>(n) {
+            Ok(layout) => unsafe { self.alloc(layout) }.cast(),
+            Err(_) => null_mut(),
+        }
+    }
+
+    /// Free an array allocated by [`alloc_array`](Self::alloc_array).
+    ///
+    /// # Safety
+    /// `ptr` must have been returned by `alloc_array::<T>(n)` on this allocator, with the
+    /// same `n`, and not freed yet.
+    ///
+    /// 释放由 [`alloc_array`](Self::alloc_array) 分配的数组。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器的 `alloc_array::<T>(n)` 以相同的 `n` 返回，且尚未被释放。
+    pub unsafe fn dealloc_array<T>(&self, ptr: *mut T, n: usize) {
+        unsafe { self.dealloc(ptr.cast(), Layout::array::<T>(n).unwrap_unchecked()) }
+    }
+
+    /// Allocate like `GlobalAlloc::alloc`, also returning the capacity of the block:
+    /// the bin size, or the rounded size of a large block, always `>= layout.size()`.
+    /// The caller may use all of it, and may later free or resize the block with the
+    /// same `layout`. On failure, returns `(null, 0)`.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::alloc`.
+    ///
+    /// 与 `GlobalAlloc::alloc` 一样分配，并同时返回块的容量：
+    /// Bin 大小，或大块的取整后大小，总是 `>= layout.size()`。
+    /// 调用者可以使用全部容量，之后仍以同一 `layout` 释放或调整该块。失败时返回 `(null, 0)`。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::alloc` 的约定相同。
+    pub unsafe fn alloc_with_capacity(&self, layout: Layout) -> (*mut u8, usize) {
+        let ptr = unsafe { self.alloc(layout) };
+        if ptr.is_null() {
+            return (ptr, 0);
+        }
+        (ptr, unsafe { self.usable_size(ptr, layout) })
+    }
+
+    /// Like `GlobalAlloc::realloc`, but the bytes past the old size, `[layout.size(),
+    /// new_size)`, are zeroed, whether the block grew in place or moved.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::realloc`.
+    ///
+    /// 与 `GlobalAlloc::realloc` 相同，但旧大小之后的字节 `[layout.size(), new_size)` 会被清零，
+    /// 无论块是原地扩容还是被移动。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::realloc` 的约定相同。
+    #[cfg(feature = "realloc")]
+    pub unsafe fn realloc_zeroed(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { realloc_zero_tail(self, ptr, layout, new_size) }
+    }
+
+    /// Free a block given only its pointer, for callers that no longer know the `Layout`
+    /// (e.g. FFI glue). Needs the `track-sizes` feature, which stores each block's size in
+    /// a word in front of it (and the alignment too for blocks aligned beyond `MIN_ALIGN`).
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator and not freed yet. Zero-sized
+    /// allocations own no block and must not be passed here.
+    ///
+    /// 仅凭指针释放块，供已不知道 `Layout` 的调用方使用（例如 FFI 胶水代码）。
+    /// 需要 `track-sizes` feature，它将每个块的大小存储在块前的一个字中
+    /// （对齐超过 `MIN_ALIGN` 的块还会存储其对齐）。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器分配且尚未被释放。零大小的分配不拥有块，不得传入此处。
+    #[cfg(feature = "track-sizes")]
+    pub unsafe fn free(&self, ptr: *mut u8) {
+        unsafe {
+            let word = (ptr as *mut usize).sub(1).read();
+            let layout = if word > isize::MAX as usize {
+                Layout::from_size_align_unchecked((ptr as *mut usize).sub(2).read(), !word)
+            } else {
+                Layout::from_size_align_unchecked(word, MIN_ALIGN)
+            };
+            self.dealloc_block(ptr.sub(Self::header_size(layout.align())), layout);
+        }
+    }
+
+    /// `try_alloc_block` plus, with `track-sizes`, the size word in front of the payload.
+    ///
+    /// `try_alloc_block`，启用 `track-sizes` 时还会在有效载荷之前写入大小字。
+    unsafe fn try_alloc_payload(&self, layout: Layout) -> Result<(NonNull<u8>, bool), AllocError> {
+        if layout.size() == 0 {
+            return Ok((unsafe { NonNull::new_unchecked(dangling(layout)) }, false));
+        }
+        #[cfg(feature = "track-sizes")]
+        unsafe {
+            let Some(inner) = Self::tracked_layout(layout) else {
+                self.oom_hook.fire(layout);
+                return Err(AllocError);
+            };
+            let (base, fresh) = self.try_alloc_block(inner, layout)?;
+            let ptr = base.add(Self::header_size(layout.align()));
+            Self::set_tracked_size(ptr.as_ptr(), inner);
+            Ok((ptr, fresh))
+        }
+        #[cfg(not(feature = "track-sizes"))]
+        unsafe {
+            self.try_alloc_block(layout, layout)
+        }
+    }
+
+    /// Deallocation logic behind `GlobalAlloc::dealloc`, on the underlying block.
+    ///
+    /// `GlobalAlloc::dealloc` 背后的释放逻辑，作用于底层块。
+    unsafe fn dealloc_block(&self, ptr: *mut u8, layout: Layout) {
+        #[cfg(feature = "dealloc-check")]
+        self.debug_check_dealloc(ptr);
+        // 1. If block has high alignment requirement, it must not be from Bins:
+        //    it goes back with its padding to the high-align free list.
+        // 1. 如果是对齐要求很高的块，它一定不是来自 Bins：
+        //    它连同其填充一起回到高对齐空闲链表。
+        if layout.align() > MIN_ALIGN {
+            unsafe { self.free_aligned(ptr, self.block_capacity(layout)) };
+            #[cfg(feature = "quota")]
+            self.quota.release(Self::charged(layout));
             return;
         }
 
-        let size = layout.size().max(16);
+        let size = layout.size().max(MIN_ALIGN);
+
+        #[cfg(feature = "poison")]
+        unsafe {
+            poison::fill(ptr, self.block_capacity(layout));
+        }
 
         // 2. Try to return to Bins
         // 2. 尝试归还到 Bins
-        if let Some(index) = get_index(size) {
-            let node = ptr as *mut Node;
-            // Insert at head (O(1))
-            // 头插法 (O(1))
-            unsafe {
-                (*node).next = BINS[index];
-                BINS[index] = node;
-            }
+        if let Some(index) = get_index(&self.bin_sizes, size) {
+            unsafe { self.push_bin(ptr, index) };
+            #[cfg(feature = "quota")]
+            self.quota.release(Self::charged(layout));
+            #[cfg(feature = "large-reuse")]
             return;
         }
 
-        // 3. Large Object (> 128 Bytes)
+        // 3. Large Object (> largest bin)
         // Strategy choice: Abandon large object reuse for minimal size.
         // They will be reclaimed when Wasm instance is destroyed.
-        // 3. 大对象 (> 128 Bytes)
+        // With `large-reuse`, they go to the large free list instead.
+        // 3. 大对象 (> 最大 Bin)
         // 策略选择：为了极简体积，放弃大对象复用。
         // 它们会随 Wasm 实例销毁而回收。
+        // 启用 `large-reuse` 时，它们会进入大对象空闲链表。
+        #[cfg(feature = "large-reuse")]
+        unsafe {
+            self.push_large(ptr, size);
+        }
+        #[cfg(all(feature = "large-reuse", feature = "quota"))]
+        self.quota.release(Self::charged(layout));
     }
 
+    /// Resizing logic behind `GlobalAlloc::realloc`, on the underlying block.
+    ///
+    /// `GlobalAlloc::realloc` 背后的调整大小逻辑，作用于底层块。
     #[cfg(feature = "realloc")]
-    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    unsafe fn realloc_block(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
         // 1. Determine actual capacity of old block
         // 1. 确定旧块的实际容量
-        let old_size = layout.size().max(16);
-        let old_capacity = if layout.align() > 16 {
-            old_size
-        } else if let Some(index) = get_index(old_size) {
-            16 << index
-        } else {
-            old_size
-        };
+        let old_capacity = self.block_capacity(layout);
+
+        // Growing must fit in the quota whether it happens in place or by moving
+        // 无论原地扩容还是移动，增长都必须在配额之内
+        #[cfg(feature = "quota")]
+        if new_size > layout.size() && !self.quota.fits(new_size - layout.size()) {
+            self.oom_hook
+                .fire(unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) });
+            return null_mut();
+        }
 
         // 2. If new size <= old capacity, reuse directly (In-place shrink)
         // 2. 如果新大小 <= 旧容量，直接复用 (In-place shrink)
         if new_size <= old_capacity {
+            #[cfg(feature = "quota")]
+            self.quota.resize(layout.size(), new_size);
             return ptr;
         }
 
-        // 3. Try to grow in place (In-place grow at HEAP_TOP)
-        // Only possible if ptr is exactly at heap top.
-        // 3. 尝试原地扩容 (In-place grow at HEAP_TOP)
-        // 只有当 ptr 恰好在堆顶时才可能。
-        let heap_top = unsafe { HEAP_TOP };
-        if ptr as usize + old_capacity == heap_top {
-            let diff = new_size - old_capacity;
-            let heap_end = unsafe { HEAP_END };
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+
+        // 3. Promote to the bin of the new size: a free block there is reused directly,
+        // even over growing at the heap top, so the bump pointer stays put
+        // 3. 提升到新大小对应的 Bin：直接复用其中的空闲块，
+        // 甚至优先于在堆顶扩容，使 Bump 指针保持不动
+        if layout.align() <= MIN_ALIGN
+            && let Some(index) = get_index(&self.bin_sizes, new_size)
+        {
+            let new_ptr = unsafe { self.pop_bin(index) };
+            if !new_ptr.is_null() {
+                #[cfg(feature = "quota")]
+                self.quota.charge(Self::charged(new_layout));
+                unsafe {
+                    core::ptr::copy_nonoverlapping(ptr, new_ptr, copy_span(layout, new_size));
+                    self.dealloc_block(ptr, layout);
+                }
+                return new_ptr;
+            }
+        }
+
+        // 4. Try to grow in place (In-place grow at heap top)
+        // Only possible if ptr is exactly at heap top. The block grows to its new capacity,
+        // which for a binned size is the whole bin, so a later free returns it intact.
+        // 4. 尝试原地扩容 (In-place grow at heap top)
+        // 只有当 ptr 恰好在堆顶时才可能。块增长到其新容量，对分箱大小即整个 Bin，
+        // 使之后的释放能完整归还它。
+        if unsafe { self.grow_at_top(ptr, layout, new_layout) } {
+            return ptr;
+        }
+
+        // 5. Default fallback: Alloc + Copy + Dealloc
+        // 5. 默认回退：Alloc + Copy + Dealloc
+        unsafe {
+            let Ok((new_ptr, _)) = self.try_alloc_block(new_layout, new_layout) else {
+                return null_mut();
+            };
+            let new_ptr = new_ptr.as_ptr();
+            // copy old_size, not old_capacity, because data is only valid up to old_size
+            core::ptr::copy_nonoverlapping(ptr, new_ptr, copy_span(layout, new_size));
+            self.dealloc_block(ptr, layout);
+            new_ptr
+        }
+    }
+
+    /// Grow the block at `ptr` to the capacity of `new_layout` if it ends at the heap top,
+    /// growing the heap if needed. Returns whether it grew.
+    ///
+    /// 如果 `ptr` 处的块结束于堆顶，则将其扩容到 `new_layout` 的容量，必要时增长堆。
+    /// 返回是否扩容成功。
+    unsafe fn grow_at_top(&self, ptr: *mut u8, layout: Layout, new_layout: Layout) -> bool {
+        let heap_top = unsafe { &mut *self.heap_top.get() };
+        let heap_end = unsafe { &mut *self.heap_end.get() };
+        let old_capacity = self.block_capacity(layout);
+        if ptr as usize + old_capacity == *heap_top {
+            let diff = self.block_capacity(new_layout) - old_capacity;
 
             // Check if there is enough remaining space or grow memory
             // 检查是否有足够的剩余空间或扩容
-            unsafe {
-                if HEAP_TOP + diff <= heap_end {
-                    HEAP_TOP += diff;
-                    return ptr;
+            if diff <= *heap_end - *heap_top {
+                *heap_top += diff;
+                self.reached_top();
+                #[cfg(feature = "quota")]
+                self.quota.resize(layout.size(), new_layout.size());
+                return true;
+            }
+
+            let pages_needed = PageCount::from_bytes(diff - (*heap_end - *heap_top))
+                .as_usize()
+                .max(1);
+            if let Some((memory_start, pages)) = unsafe { self.grow_pages(pages_needed) } {
+                if memory_start == *heap_end {
+                    *heap_end += pages * PAGE_SIZE;
+                    *heap_top += diff;
+                    self.reached_top();
+                    #[cfg(feature = "quota")]
+                    self.quota.resize(layout.size(), new_layout.size());
+                    return true;
                 }
 
-                let pages_needed =
-                    ((HEAP_TOP + diff - heap_end + PAGE_SIZE - 1) / PAGE_SIZE).max(1);
-                if grow_memory(pages_needed) != usize::MAX {
-                    HEAP_END += pages_needed * PAGE_SIZE;
-                    HEAP_TOP += diff;
-                    return ptr;
+                // Not contiguous (the source was grown by someone else): bump from the
+                // new pages instead and let the fallback below move the block there
+                // 不连续（内存源被其他人扩容过）：改为从新页面 Bump，
+                // 由下面的回退路径将块移动过去
+                *heap_top = memory_start;
+                *heap_end = memory_start + pages * PAGE_SIZE;
+                unsafe { *self.heap_start.get() = memory_start };
+            }
+        }
+        false
+    }
+
+    /// Resize the underlying block at `ptr` without moving it: within its capacity, or at
+    /// the heap top. Returns whether it was resized.
+    ///
+    /// 在不移动的情况下调整 `ptr` 处底层块的大小：在其容量之内，或在堆顶。返回是否调整成功。
+    unsafe fn resize_block_in_place(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> bool {
+        if new_size <= self.block_capacity(layout) {
+            #[cfg(feature = "quota")]
+            self.quota.resize(layout.size(), new_size);
+            return true;
+        }
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+        unsafe { self.grow_at_top(ptr, layout, new_layout) }
+    }
+
+    /// `resize_block_in_place` for the payload at `ptr`, keeping the `track-sizes` size
+    /// word up to date.
+    ///
+    /// 针对 `ptr` 处有效载荷的 `resize_block_in_place`，并保持 `track-sizes` 的大小字最新。
+    unsafe fn resize_in_place(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> bool {
+        #[cfg(feature = "track-sizes")]
+        unsafe {
+            let header = Self::header_size(layout.align());
+            let Some(new_inner) = new_size.checked_add(header) else {
+                return false;
+            };
+            let inner = Self::tracked_layout(layout).unwrap_unchecked();
+            if !self.resize_block_in_place(ptr.sub(header), inner, new_inner) {
+                return false;
+            }
+            Self::set_tracked_size(
+                ptr,
+                Layout::from_size_align_unchecked(new_inner, layout.align()),
+            );
+            true
+        }
+        #[cfg(not(feature = "track-sizes"))]
+        unsafe {
+            self.resize_block_in_place(ptr, layout, new_size)
+        }
+    }
+
+    /// `try_alloc` shared with `alloc_zeroed`; the flag is `true` when the block was bumped
+    /// rather than recycled from a free list. On failure the OOM hook observes `requested`,
+    /// the layout the caller asked for.
+    ///
+    /// `try_alloc` 与 `alloc_zeroed` 共用的逻辑；当块来自 Bump 而非从空闲链表回收时，标志为 `true`。
+    /// 失败时 OOM 回调看到的是 `requested`，即调用方请求的布局。
+    unsafe fn try_alloc_block(
+        &self,
+        layout: Layout,
+        requested: Layout,
+    ) -> Result<(NonNull<u8>, bool), AllocError> {
+        #[cfg(feature = "quota")]
+        if !self.quota.fits(Self::charged(layout)) {
+            self.oom_hook.fire(requested);
+            return Err(AllocError);
+        }
+        let (ptr, fresh) = unsafe { self.alloc_block(layout) };
+        let Some(ptr) = NonNull::new(ptr) else {
+            self.oom_hook.fire(requested);
+            return Err(AllocError);
+        };
+        #[cfg(feature = "quota")]
+        self.quota.charge(Self::charged(layout));
+        Ok((ptr, fresh))
+    }
+
+    /// Allocation logic behind `GlobalAlloc::alloc`, without the OOM hook.
+    /// The returned flag is `true` when the block was bumped.
+    ///
+    /// `GlobalAlloc::alloc` 背后的分配逻辑，不包含 OOM 回调。
+    /// 当块来自 Bump 时，返回的标志为 `true`。
+    unsafe fn alloc_block(&self, layout: Layout) -> (*mut u8, bool) {
+        // 1. Large alignment handling
+        // Fixed Bins guarantee MIN_ALIGN (16 bytes by default) alignment.
+        // If user requests a larger alignment (very rare), reuse a freed high-align block
+        // or bump a new one, with its padding recorded so it can be freed.
+        // A bumped block ends exactly at its capacity, so `realloc` finds it at the heap top.
+        // 1. 大对齐处理
+        // 固定 Bins 保证 MIN_ALIGN（默认 16 字节）对齐。
+        // 如果用户请求更大的对齐（非常罕见），复用已释放的高对齐块或 Bump 一个新块，
+        // 并记录其填充以便之后释放。Bump 出的块恰好结束于其容量处，使 `realloc` 能在堆顶找到它。
+        if layout.align() > MIN_ALIGN {
+            let (ptr, fresh) =
+                unsafe { self.alloc_aligned(self.block_capacity(layout), layout.align()) };
+            if !ptr.is_null() {
+                record!(self, s => s.large_allocs += 1);
+            }
+            return (ptr, fresh);
+        }
+
+        // 2. Calculate category
+        // 2. 计算分类
+        let size = layout.size().max(MIN_ALIGN);
+
+        // 3. Try lookup reuse (Small Alloc)
+        // 3. 尝试查表复用 (Small Alloc)
+        if let Some(index) = get_index(&self.bin_sizes, size) {
+            let ptr = unsafe { self.pop_bin(index) };
+            if !ptr.is_null() {
+                return (ptr, false);
+            }
+
+            // Miss: Bin is empty, fallback to Bump allocation
+            // Allocate block of corresponding Bin size directly, instead of layout.size(), so it can be returned correctly later
+            // Miss: Bin 为空，回退到 Bump 分配
+            // 直接分配对应 Bin 大小的块，而不是 layout.size()，以便将来 dealloc 能正确归位
+            let block_size = self.bin_sizes[index];
+            // Blocks spilled from full bins (or freed large blocks) come first
+            // 优先使用从已满 Bin 溢出的块（或已释放的大对象块）
+            #[cfg(feature = "large-reuse")]
+            {
+                let ptr = unsafe { self.take_large(block_size) };
+                if !ptr.is_null() {
+                    record!(self, s => {
+                        s.bin_allocs[index] += 1;
+                        s.bin_hits += 1;
+                    });
+                    return (ptr, false);
                 }
             }
+            let ptr = unsafe { self.bump_alloc(block_size, MIN_ALIGN) };
+            if !ptr.is_null() {
+                record!(self, s => {
+                    s.bin_allocs[index] += 1;
+                    s.bin_misses += 1;
+                });
+            }
+            return (ptr, true);
         }
 
-        // 4. Default fallback: Alloc + Copy + Dealloc
-        // 4. 默认回退：Alloc + Copy + Dealloc
+        // 4. Large object handling (> largest bin)
+        // Alloc via Bump directly, skip Bins
+        // 4. 大对象处理 (> 最大 Bin)
+        // 直接 Bump 分配，不走 Bin
+        #[cfg(feature = "large-reuse")]
+        {
+            let ptr = unsafe { self.take_large(size) };
+            if !ptr.is_null() {
+                record!(self, s => s.large_allocs += 1);
+                return (ptr, false);
+            }
+        }
+        let ptr = unsafe { self.bump_alloc(size, MIN_ALIGN) };
+        if !ptr.is_null() {
+            record!(self, s => s.large_allocs += 1);
+        }
+        (ptr, true)
+    }
+
+    /// Pop the most recently freed block of bin `index` (LIFO), or null if the bin is empty.
+    ///
+    /// 弹出第 `index` 个 Bin 中最近释放的块（LIFO），Bin 为空时返回 null。
+    unsafe fn pop_bin(&self, index: usize) -> *mut u8 {
         unsafe {
-            let new_ptr = self.alloc(Layout::from_size_align_unchecked(new_size, layout.align()));
-            if !new_ptr.is_null() {
-                // copy old_size, not old_capacity, because data is only valid up to old_size
-                core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size());
-                self.dealloc(ptr, layout);
+            let bins = &mut *self.bins.get();
+            let head = bins[index];
+            if head.is_null() {
+                return null_mut();
             }
-            new_ptr
+            bins[index] = (*head).next;
+            (*self.bin_depths.get())[index] -= 1;
+            #[cfg(feature = "poison")]
+            poison::check(
+                head as *mut u8,
+                core::mem::size_of::<Node>(),
+                self.bin_sizes[index],
+            );
+            record!(self, s => {
+                s.bin_allocs[index] += 1;
+                s.bin_hits += 1;
+            });
+            head as *mut u8
+        }
+    }
+
+    /// Return a freed block to bin `index`. Past the depth cap, a block at the heap top rolls
+    /// the bump pointer back instead, and with `large-reuse` any other one joins the large
+    /// free list.
+    ///
+    /// 将释放的块归还到第 `index` 个 Bin。超过深度上限时，位于堆顶的块改为回退 Bump 指针，
+    /// 启用 `large-reuse` 时其他块加入大对象空闲链表。
+    unsafe fn push_bin(&self, ptr: *mut u8, index: usize) {
+        unsafe {
+            let depths = &mut *self.bin_depths.get();
+            if depths[index] >= self.bin_depth_cap {
+                let block_size = self.bin_sizes[index];
+                let heap_top = &mut *self.heap_top.get();
+                if ptr as usize + block_size == *heap_top {
+                    // The block was handed out, so bumping over it again is not zeroed
+                    // 该块曾被分配出去，因此再次 Bump 经过它时不保证为零
+                    let dirty_end = &mut *self.dirty_end.get();
+                    *dirty_end = (*dirty_end).max(*heap_top);
+                    *heap_top = ptr as usize;
+                    return;
+                }
+                #[cfg(feature = "large-reuse")]
+                {
+                    self.push_large(ptr, block_size);
+                    return;
+                }
+            }
+            // Insert at head (O(1))
+            // 头插法 (O(1))
+            let bins = &mut *self.bins.get();
+            let node = ptr as *mut Node;
+            (*node).next = bins[index];
+            bins[index] = node;
+            depths[index] += 1;
+        }
+    }
+
+    /// Allocate `size` bytes aligned to `align` (beyond `MIN_ALIGN`): first fit from the
+    /// high-align free list, else bumped. The padding in front of the block, at least
+    /// `MIN_ALIGN` bytes, is stored in the word just before it, so `free_aligned` can give
+    /// back the whole region. The flag is `true` when the block was bumped.
+    ///
+    /// 分配按 `align`（超过 `MIN_ALIGN`）对齐的 `size` 字节：优先从高对齐空闲链表首次适配，
+    /// 否则通过 Bump 分配。块前的填充至少为 `MIN_ALIGN` 字节，其长度存储在紧邻块之前的字中，
+    /// 使 `free_aligned` 能归还整个区域。当块来自 Bump 时，标志为 `true`。
+    unsafe fn alloc_aligned(&self, size: usize, align: usize) -> (*mut u8, bool) {
+        unsafe {
+            let mut link = self.aligned.get();
+            while !(*link).is_null() {
+                let node = *link;
+                let start = node as usize;
+                let region_end = start + (*node).size;
+                let ptr = round_up(start + MIN_ALIGN, align);
+                if ptr <= region_end && size <= region_end - ptr {
+                    *link = (*node).next;
+                    #[cfg(feature = "poison")]
+                    poison::check(
+                        node as *mut u8,
+                        core::mem::size_of::<LargeNode>(),
+                        region_end - start,
+                    );
+                    // A tail that can hold a header of its own stays in the list
+                    // 能容纳自身头部的尾部留在链表中
+                    let end = round_up(ptr + size, MIN_ALIGN);
+                    if end < region_end && region_end - end >= core::mem::size_of::<LargeNode>() {
+                        self.push_aligned(end, region_end - end);
+                    }
+                    (ptr as *mut usize).sub(1).write(ptr - start);
+                    return (ptr as *mut u8, false);
+                }
+                link = &mut (*node).next;
+            }
+
+            // Room for the worst-case padding; the unused part past the block stays free
+            // 为最坏情况的填充预留空间；块之后未使用的部分保持空闲
+            let Some((start, _)) = size
+                .checked_add(align)
+                .and_then(|room| self.bump_room(room, MIN_ALIGN))
+            else {
+                return (null_mut(), true);
+            };
+            let ptr = round_up(start + MIN_ALIGN, align);
+            *self.heap_top.get() = ptr + size;
+            self.reached_top();
+            if !S::ZEROED {
+                core::ptr::write_bytes(start as *mut u8, 0, ptr + size - start);
+            }
+            (ptr as *mut usize).sub(1).write(ptr - start);
+            (ptr as *mut u8, true)
+        }
+    }
+
+    /// Free a block from `alloc_aligned` together with its padding: at the heap top it
+    /// rolls the bump pointer back, otherwise it joins the high-align free list.
+    ///
+    /// 将来自 `alloc_aligned` 的块连同其填充一起释放：位于堆顶时回退 Bump 指针，
+    /// 否则加入高对齐空闲链表。
+    unsafe fn free_aligned(&self, ptr: *mut u8, size: usize) {
+        unsafe {
+            let start = ptr as usize - (ptr as *mut usize).sub(1).read();
+            let end = ptr as usize + size;
+            let heap_top = &mut *self.heap_top.get();
+            if end == *heap_top {
+                // The block was handed out, so bumping over it again is not zeroed
+                // 该块曾被分配出去，因此再次 Bump 经过它时不保证为零
+                let dirty_end = &mut *self.dirty_end.get();
+                *dirty_end = (*dirty_end).max(*heap_top);
+                *heap_top = start;
+                return;
+            }
+            self.push_aligned(start, end - start);
+        }
+    }
+
+    /// Push a freed high-align region onto the high-align free list.
+    ///
+    /// 将释放的高对齐区域压入高对齐空闲链表。
+    unsafe fn push_aligned(&self, start: usize, size: usize) {
+        #[cfg(feature = "poison")]
+        unsafe {
+            poison::fill(start as *mut u8, size);
+        }
+        unsafe {
+            let head = self.aligned.get();
+            let node = start as *mut LargeNode;
+            (*node).next = *head;
+            (*node).size = size;
+            *head = node;
+        }
+    }
+
+    /// Push a freed large block onto the large free list. A free block that starts right
+    /// where this one ends, such as the remainder `take_large` split off it, is merged in,
+    /// so a block reused for a smaller request is whole again once freed.
+    ///
+    /// 将释放的大对象块压入大对象空闲链表。紧接在该块末尾开始的空闲块
+    /// （例如 `take_large` 从它切分出的剩余部分）会被合并进来，
+    /// 因此被较小请求复用的块在释放后重新恢复完整。
+    #[cfg(feature = "large-reuse")]
+    unsafe fn push_large(&self, ptr: *mut u8, mut size: usize) {
+        // Too small for a header (only possible with tiny custom bins): leak it
+        // 太小，放不下头部（仅在自定义极小 Bin 时可能）：直接泄漏
+        if size < core::mem::size_of::<LargeNode>() {
+            return;
+        }
+        unsafe {
+            let head = self.large.get();
+            let mut link = head;
+            while !(*link).is_null() {
+                let next = *link;
+                if next as usize == ptr as usize + size {
+                    *link = (*next).next;
+                    size += (*next).size;
+                    #[cfg(feature = "poison")]
+                    poison::fill(next as *mut u8, core::mem::size_of::<LargeNode>());
+                    break;
+                }
+                link = &mut (*next).next;
+            }
+            let node = ptr as *mut LargeNode;
+            (*node).next = *head;
+            (*node).size = size;
+            *head = node;
+        }
+    }
+
+    /// First-fit search of the large free list. The tail remainder is split off and kept
+    /// in the list, where bin misses can reuse it and `push_large` merges it back when the
+    /// block is freed.
+    ///
+    /// 首次适配搜索大对象空闲链表。尾部剩余部分会被切分出来并保留在链表中，
+    /// Bin 未命中时可以复用它，块被释放时 `push_large` 会将其合并回来。
+    #[cfg(feature = "large-reuse")]
+    unsafe fn take_large(&self, size: usize) -> *mut u8 {
+        unsafe {
+            let mut link = self.large.get();
+            while !(*link).is_null() {
+                let node = *link;
+                let block_size = (*node).size;
+                if block_size >= size {
+                    *link = (*node).next;
+                    #[cfg(feature = "poison")]
+                    poison::check(
+                        node as *mut u8,
+                        core::mem::size_of::<LargeNode>(),
+                        block_size,
+                    );
+
+                    // Keep the remainder MIN_ALIGN-aligned
+                    // 保持剩余部分按 MIN_ALIGN 对齐
+                    let used = (size + MIN_ALIGN - 1) & !(MIN_ALIGN - 1);
+                    if block_size > used {
+                        self.push_large((node as *mut u8).add(used), block_size - used);
+                    }
+                    return node as *mut u8;
+                }
+                link = &mut (*node).next;
+            }
+            null_mut()
         }
     }
-}
 
-impl SegregatedBumpAllocator {
     /// Core Bump Pointer allocation logic
     /// 核心 Bump Pointer 分配逻辑
-    unsafe fn bump_alloc(&self, size: usize, align: usize) -> *mut u8 {
+    /// `dealloc-check`: the freed pointer must be `MIN_ALIGN`-aligned and lie between the
+    /// first region this allocator grew and the current heap top.
+    ///
+    /// `dealloc-check`：被释放的指针必须按 `MIN_ALIGN` 对齐，
+    /// 并位于此分配器增长的首个区域与当前堆顶之间。
+    #[cfg(feature = "dealloc-check")]
+    fn debug_check_dealloc(&self, ptr: *mut u8) {
+        let addr = ptr as usize;
+        debug_assert!(
+            addr.is_multiple_of(MIN_ALIGN),
+            "dealloc of a misaligned pointer: {ptr:p}"
+        );
+        let (base, top) = unsafe { (*self.heap_base.get(), *self.heap_top.get()) };
+        debug_assert!(
+            base <= addr && addr < top,
+            "dealloc of a pointer outside the heap: {ptr:p}"
+        );
+    }
+
+    /// Grow the source by at least `needed` pages, or by what the growth policy asks for.
+    /// Returns the start address and the number of pages obtained.
+    ///
+    /// 将内存源增长至少 `needed` 页，或增长策略要求的页数。返回起始地址与实际获得的页数。
+    unsafe fn grow_pages(&self, needed: usize) -> Option<(usize, usize)> {
+        let region_pages = unsafe { (*self.heap_end.get() - *self.heap_start.get()) / PAGE_SIZE };
+        let wanted = self.growth.pages_for(needed, region_pages);
+        let grown = unsafe { grow_amortized(&self.source, needed, wanted) };
+        #[cfg(feature = "stats")]
+        if let Some((_, pages)) = grown {
+            self.high_water.grew(pages * PAGE_SIZE);
+        }
+        grown
+    }
+
+    /// Raise the `stats` high-water mark after the heap top moved up.
+    ///
+    /// 堆顶上移后提升 `stats` 高水位线。
+    #[inline(always)]
+    fn reached_top(&self) {
+        #[cfg(feature = "stats")]
         unsafe {
-            let mut ptr = HEAP_TOP;
-
-            // Handle alignment: (ptr + align - 1) & !(align - 1)
-            // For align=16, it means (ptr + 15) & !15
-            // 处理对齐: (ptr + align - 1) & !(align - 1)
-            // 对于 align=16，即 (ptr + 15) & !15
-            ptr = (ptr + align - 1) & !(align - 1);
-
-            // Check for overflow or insufficient capacity
-            // 检查溢出或容量不足
-            if ptr + size > HEAP_END || ptr < HEAP_TOP {
-                // How many pages needed?
-                // 需要多少页？
-                let bytes_needed = (ptr + size).saturating_sub(HEAP_END);
-                let pages_needed = ((bytes_needed + PAGE_SIZE - 1) / PAGE_SIZE).max(1);
-
-                let prev_page = grow_memory(pages_needed);
-                if prev_page == usize::MAX {
-                    return null_mut(); // OOM
-                }
+            self.high_water
+                .reach(*self.heap_end.get() - *self.heap_top.get());
+        }
+    }
 
-                // If initial allocation (HEAP_END == 0), need to initialize ptr
-                // 如果是初次分配 (HEAP_END == 0)，需要初始化 ptr
-                if HEAP_END == 0 {
-                    // prev_page should be 0 (or existing memory size)
-                    // Wasm memory_grow returns old page count
-                    // prev_page 应该是 0 (或者现有内存大小)
-                    // Wasm memory_grow 返回旧的页数
-                    let memory_start = prev_page * PAGE_SIZE;
-                    ptr = memory_start;
-                    // Re-align
-                    // 再次对齐
-                    ptr = (ptr + align - 1) & !(align - 1);
-
-                    HEAP_END = memory_start + pages_needed * PAGE_SIZE;
-                } else {
-                    HEAP_END += pages_needed * PAGE_SIZE;
+    /// Grow the heap as needed so that `size` bytes at `align` fit in the current bump
+    /// region, and return their `[start, end)` range without claiming it. `None` on OOM.
+    ///
+    /// 按需增长堆，使 `align` 对齐的 `size` 字节能放入当前 Bump 区域，并返回其
+    /// `[start, end)` 范围（不占用它）。OOM 时返回 `None`。
+    unsafe fn bump_room(&self, size: usize, align: usize) -> Option<(usize, usize)> {
+        let heap_top = unsafe { &mut *self.heap_top.get() };
+        let heap_end = unsafe { &mut *self.heap_end.get() };
+
+        // Handle alignment and check for overflow: a block past `usize::MAX` can never fit
+        // 处理对齐并检查溢出：超出 `usize::MAX` 的块永远无法容纳
+        let (mut ptr, mut end) = bump_range(*heap_top, size, align)?;
+
+        // Check for insufficient capacity
+        // 检查容量不足
+        if end > *heap_end {
+            // How many pages needed?
+            // 需要多少页？
+            let pages_needed = PageCount::from_bytes(end - *heap_end).as_usize().max(1);
+
+            let (memory_start, pages) = unsafe { self.grow_pages(pages_needed) }?;
+
+            // If initial allocation, or the new pages do not follow our heap end
+            // (another instance grew the same memory), restart the bump region there.
+            // The unused tail of the old region is abandoned.
+            // 如果是初次分配，或新页面不紧接在堆末尾之后（其他实例扩容了同一块内存），
+            // 则从新页面重新开始 Bump 区域。旧区域未使用的尾部被放弃。
+            if memory_start != *heap_end {
+                // Wasm memory_grow returns old page count
+                // Wasm memory_grow 返回旧的页数
+                let restart = bump_range(memory_start, size, align)?;
+                (ptr, end) = restart;
+                *heap_top = memory_start;
+                *heap_end = memory_start;
+                unsafe { *self.heap_start.get() = memory_start };
+                if unsafe { *self.heap_base.get() } == 0 {
+                    unsafe { *self.heap_base.get() = memory_start };
                 }
             }
+            *heap_end += pages * PAGE_SIZE;
+
+            // After a restart the pages sized for the old region may fall short
+            // 重新开始后，按旧区域计算的页数可能不够
+            if end > *heap_end {
+                let extra = PageCount::from_bytes(end - *heap_end).as_usize();
+                // The extra pages must follow the region, or the block would straddle a gap
+                // 额外的页面必须紧接在区域之后，否则块会跨越空洞
+                let grown = unsafe { grow_region(&self.source, extra) };
+                #[cfg(feature = "stats")]
+                if grown.is_some() {
+                    self.high_water.grew(extra * PAGE_SIZE);
+                }
+                if grown != Some(*heap_end) {
+                    return None; // OOM
+                }
+                *heap_end += extra * PAGE_SIZE;
+            }
+        }
+        Some((ptr, end))
+    }
 
-            HEAP_TOP = ptr + size;
-            ptr as *mut u8
+    unsafe fn bump_alloc(&self, size: usize, align: usize) -> *mut u8 {
+        let Some((ptr, end)) = (unsafe { self.bump_room(size, align) }) else {
+            return null_mut();
+        };
+
+        unsafe { *self.heap_top.get() = end };
+        self.reached_top();
+        // First touch of this region: zero it if the source does not guarantee it
+        // 首次使用该区域：如果内存来源不保证为零，则将其清零
+        if !S::ZEROED {
+            unsafe { core::ptr::write_bytes(ptr as *mut u8, 0, size) };
         }
+        ptr as *mut u8
     }
 }
 
@@ -278,67 +1650,117 @@ impl SegregatedBumpAllocator {
 // 辅助函数
 // --------------------------------------------------------------------------
 
-/// Get Bin index based on size.
-/// 0 -> 16B, 1 -> 32B, 2 -> 64B, 3 -> 128B
+/// Get the index of the smallest bin in `bin_sizes` that can hold `size`.
 /// Returns None indicating large object.
 ///
-/// 根据大小获取 Bin 索引。
-/// 0 -> 16B, 1 -> 32B, 2 -> 64B, 3 -> 128B
+/// 获取 `bin_sizes` 中能容纳 `size` 的最小 Bin 的索引。
 /// 返回 None 表示是大对象。
 #[inline(always)]
-fn get_index(size: usize) -> Option<usize> {
-    if size > 128 {
-        return None;
-    }
-
-    // Use CLZ (Count Leading Zeros) instruction to quickly calculate log2
-    // size 16 (10000) -> index 0
-    // size 17..32 -> index 1
-    // ...
-    // 利用 CLZ (Count Leading Zeros) 指令快速计算 log2
-    // size 16 (10000) -> index 0
-    // size 17..32 -> index 1
-    // ...
-    let size_val = size as usize;
-
-    // next_power_of_two ensures 17 becomes 32
-    // next_power_of_two 确保 17 变成 32
-    let power_of_two = size_val.next_power_of_two();
-    let zeros = power_of_two.leading_zeros();
-
-    // Calculate base offset.
-    // u32: leading_zeros(16) = 27.  Target index = 0. => 27 - 27 = 0
-    // u64: leading_zeros(16) = 59.  Target index = 0. => 59 - 59 = 0
-    // 计算基准偏移。
-    // u32: leading_zeros(16) = 27.  Target index = 0. => 27 - 27 = 0
-    // u64: leading_zeros(16) = 59.  Target index = 0. => 59 - 59 = 0
-    #[cfg(target_pointer_width = "32")]
-    const BASE: u32 = 27;
-
-    #[cfg(target_pointer_width = "64")]
-    const BASE: u32 = 59;
-
-    Some((BASE - zeros) as usize)
+fn get_index(bin_sizes: &[usize], size: usize) -> Option<usize> {
+    // Tables are short and sorted, a linear scan stops at the first fit
+    // 表很短且有序，线性扫描在第一个合适的位置停止
+    bin_sizes.iter().position(|&bin| size <= bin)
+}
+
+/// Unlink every node of the list at `link` that lies in `reclaimed`, and cut back a node
+/// that a merge stretched into it (unlinking it too if its header no longer fits).
+///
+/// 从 `link` 处的链表中移除所有位于 `reclaimed` 中的节点，
+/// 并截短因合并而延伸进该范围的节点（若其头部已放不下则同样移除）。
+unsafe fn unlink_reclaimed(mut link: *mut *mut LargeNode, reclaimed: &core::ops::Range<usize>) {
+    unsafe {
+        while !(*link).is_null() {
+            let node = *link;
+            let kept = reclaimed.start.saturating_sub(node as usize);
+            if kept < core::mem::size_of::<LargeNode>() {
+                *link = (*node).next;
+            } else {
+                (*node).size = (*node).size.min(kept);
+                link = &mut (*node).next;
+            }
+        }
+    }
+}
+
+impl<S, const N: usize, const MIN_ALIGN: usize> ResettableAllocator
+    for SegregatedBumpAllocator<S, N, MIN_ALIGN>
+{
+    unsafe fn reset(&self) {
+        unsafe { SegregatedBumpAllocator::reset(self) }
+    }
+}
+
+impl<S, const N: usize, const MIN_ALIGN: usize> sealed::Sealed
+    for SegregatedBumpAllocator<S, N, MIN_ALIGN>
+{
+    fn mark(&self) -> (usize, usize) {
+        unsafe { (*self.heap_top.get(), *self.heap_start.get()) }
+    }
+
+    unsafe fn rewind(&self, mark: (usize, usize)) {
+        unsafe {
+            let top = rewound(*self.heap_top.get(), *self.heap_start.get(), mark);
+            let end = *self.heap_end.get();
+            let dirty_end = self.dirty_end.get();
+            *dirty_end = (*dirty_end).max(*self.heap_top.get());
+            *self.heap_top.get() = top;
+            let reclaimed = top..end;
+
+            // Free blocks above the heap top would be handed out twice: unlink them
+            // 位于堆顶之上的空闲块会被重复分配：将其移除
+            let depths = &mut *self.bin_depths.get();
+            for (head, depth) in (*self.bins.get()).iter_mut().zip(depths) {
+                let mut link: *mut *mut Node = head;
+                while !(*link).is_null() {
+                    let node = *link;
+                    if reclaimed.contains(&(node as usize)) {
+                        *link = (*node).next;
+                        *depth -= 1;
+                    } else {
+                        link = &mut (*node).next;
+                    }
+                }
+            }
+
+            #[cfg(feature = "large-reuse")]
+            unlink_reclaimed(self.large.get(), &reclaimed);
+            unlink_reclaimed(self.aligned.get(), &reclaimed);
+        }
+    }
+}
+
+impl<S: MemorySource, const N: usize, const MIN_ALIGN: usize> ScopedAllocator
+    for SegregatedBumpAllocator<S, N, MIN_ALIGN>
+{
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::Mutex;
-
-    // Use a global lock to serialize tests because SegregatedBumpAllocator uses global `static mut` state.
-    static TEST_LOCK: Mutex<()> = Mutex::new(());
 
+    // Allocator state is per-instance and the mock memory is per-thread,
+    // so tests only need a fresh heap.
     fn with_clean_allocator(f: impl FnOnce()) {
-        let _guard = TEST_LOCK.lock().unwrap();
-        unsafe {
-            // 1. Reset internal global state
-            SegregatedBumpAllocator::reset();
+        // Reset host memory simulation (lib.rs)
+        crate::reset_heap();
+        f();
+    }
 
-            // 2. Reset host memory simulation (lib.rs)
-            crate::reset_heap();
+    // The default table must keep the power-of-two mapping of the old CLZ-based lookup
+    // 默认表必须保持旧的基于 CLZ 的查找所使用的 2 的幂映射
+    #[test]
+    fn test_default_bin_index_matches_power_of_two_classes() {
+        for size in 1..=128usize {
+            let expected = (size.max(16).next_power_of_two().trailing_zeros() - 4) as usize;
+            assert_eq!(
+                get_index(&DEFAULT_BINS, size),
+                Some(expected),
+                "size {size}"
+            );
+        }
+        for size in [129, 256, 4096, usize::MAX] {
+            assert_eq!(get_index(&DEFAULT_BINS, size), None, "size {size}");
         }
-        f();
     }
 
     #[test]
@@ -394,6 +1816,7 @@ mod tests {
         });
     }
 
+    #[cfg(not(feature = "large-reuse"))]
     #[test]
     fn test_large_alloc_bypass() {
         with_clean_allocator(|| {
@@ -512,6 +1935,34 @@ mod tests {
         });
     }
 
+    #[cfg(feature = "realloc")]
+    #[test]
+    fn test_realloc_grow_high_align_at_top() {
+        with_clean_allocator(|| {
+            let allocator = SegregatedBumpAllocator::new();
+            unsafe {
+                // Tiny and regular high-alignment blocks both grow in place at the top
+                // 较小与常规大小的高对齐块都可以在堆顶原地扩容
+                for (size, align) in [(256, 128), (8, 64)] {
+                    let layout = Layout::from_size_align(size, align).unwrap();
+                    let ptr = allocator.alloc(layout);
+                    assert_eq!(ptr as usize % align, 0);
+                    ptr.write_bytes(0xAB, size);
+
+                    let new_ptr = allocator.realloc(ptr, layout, 512);
+                    assert_eq!(new_ptr, ptr);
+                    assert!((0..size).all(|i| *new_ptr.add(i) == 0xAB));
+                    new_ptr.write_bytes(0xCD, 512);
+
+                    // The next block starts past the grown one
+                    // 下一个块从扩容后的块之后开始
+                    let next = allocator.alloc(Layout::from_size_align(64, 16).unwrap());
+                    assert!(next as usize >= new_ptr as usize + 512);
+                }
+            }
+        });
+    }
+
     #[test]
     fn test_realloc_move() {
         with_clean_allocator(|| {
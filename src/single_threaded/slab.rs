@@ -0,0 +1,300 @@
+use crate::{
+    DefaultMemory, MemorySource, OomHook, PAGE_SIZE, PageCount, ResettableAllocator, grow_region,
+};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::UnsafeCell,
+    ptr::null_mut,
+};
+
+/// Safety Warning:
+/// Allocators in this module are designed for [Single Threaded] environments.
+/// `Sync` is implemented only to satisfy `GlobalAlloc` trait requirements.
+/// Using this allocator in a multi-threaded environment will lead to Undefined Behavior (UB).
+/// Please ensure it is used only in single-threaded environments (e.g., WASM or single-threaded embedded).
+///
+/// 安全性警示 (Safety Warning):
+/// 本模块中的分配器均为【单线程】设计。
+/// 实现了 `Sync` 仅为了满足 `GlobalAlloc` trait 的要求。
+/// 在多线程环境中使用此分配器会导致未定义行为 (UB)。
+/// 请确保只在单线程环境（如 WASM 或单线程嵌入式环境）中使用。
+///
+/// With `strict-single-thread` this impl is left out: wrap the allocator in
+/// [`GlobalWrapper`](super::GlobalWrapper) at the `#[global_allocator]` site instead.
+///
+/// 开启 `strict-single-thread` 时不提供此实现：
+/// 请改为在 `#[global_allocator]` 处用 [`GlobalWrapper`](super::GlobalWrapper) 包装分配器。
+#[cfg(not(feature = "strict-single-thread"))]
+unsafe impl<const BLOCK: usize, S> Sync for SlabAllocator<BLOCK, S> {}
+
+// Safety: No one else owns the raw pointers (conceptually), logic is same.
+unsafe impl<const BLOCK: usize, S: Send> Send for SlabAllocator<BLOCK, S> {}
+
+/// Object pool allocator handing out fixed `BLOCK`-byte slots.
+///
+/// Pages are carved into consecutive slots on demand; freed slots go to an intrusive LIFO
+/// free list and are reused in O(1), so there is no fragmentation. Requests larger than
+/// `BLOCK`, or aligned beyond what the slot spacing guarantees (the largest power of two
+/// dividing `BLOCK`, at most `PAGE_SIZE`), get a null pointer.
+///
+/// 分配固定 `BLOCK` 字节槽位的对象池分配器。
+///
+/// 页面按需被切分为连续的槽位；释放的槽位进入侵入式 LIFO 空闲链表并以 O(1) 复用，
+/// 因此不存在碎片。大于 `BLOCK` 的请求，或对齐要求超过槽位间距所能保证的对齐
+/// （整除 `BLOCK` 的最大 2 的幂，且不超过 `PAGE_SIZE`）的请求，将得到空指针。
+pub struct SlabAllocator<const BLOCK: usize, S = DefaultMemory> {
+    // Freed slots, LIFO
+    // 已释放的槽位，LIFO
+    free_list: UnsafeCell<*mut Node>,
+    // Next never-used slot and the end of the pages carved so far
+    // 下一个从未使用过的槽位，以及目前已切分页面的末尾
+    heap_top: UnsafeCell<usize>,
+    heap_end: UnsafeCell<usize>,
+    source: S,
+    oom_hook: OomHook,
+}
+
+struct Node {
+    next: *mut Node,
+}
+
+impl<const BLOCK: usize> SlabAllocator<BLOCK> {
+    pub const fn new() -> Self {
+        Self::with_source(DefaultMemory)
+    }
+}
+
+impl<const BLOCK: usize, S> SlabAllocator<BLOCK, S> {
+    /// Alignment every slot is guaranteed to have.
+    ///
+    /// 每个槽位保证具有的对齐。
+    pub const SLOT_ALIGN: usize = {
+        // Lowest set bit of BLOCK
+        // BLOCK 的最低置位
+        let align = BLOCK & BLOCK.wrapping_neg();
+        if align > PAGE_SIZE { PAGE_SIZE } else { align }
+    };
+
+    /// Create an allocator that obtains its pages from `source`.
+    ///
+    /// # Panics
+    /// If `BLOCK` cannot hold a pointer-aligned free-list link.
+    ///
+    /// 创建一个从 `source` 获取页面的分配器。
+    ///
+    /// # Panics
+    /// 如果 `BLOCK` 无法容纳按指针对齐的空闲链表链接。
+    pub const fn with_source(source: S) -> Self {
+        assert!(
+            BLOCK >= core::mem::size_of::<Node>()
+                && Self::SLOT_ALIGN >= core::mem::align_of::<Node>(),
+            "BLOCK must be a non-zero multiple of the pointer size"
+        );
+        SlabAllocator {
+            free_list: UnsafeCell::new(null_mut()),
+            heap_top: UnsafeCell::new(0),
+            heap_end: UnsafeCell::new(0),
+            source,
+            oom_hook: OomHook::new(),
+        }
+    }
+
+    /// Register a hook called with the failing `Layout` right before `alloc` returns null.
+    /// Pass `None` to remove it.
+    ///
+    /// 注册一个回调，在 `alloc` 返回 null 之前以失败的 `Layout` 调用。
+    /// 传入 `None` 以移除。
+    pub fn set_oom_hook(&self, hook: Option<fn(Layout)>) {
+        self.oom_hook.set(hook);
+    }
+
+    /// Testing only: Reset the internal state.
+    /// Pages already obtained from the memory source are not returned.
+    ///
+    /// # Safety
+    /// Usage is inherently unsafe if allocator is in use.
+    ///
+    /// 仅测试用：重置内部状态。
+    /// 已从内存源获取的页面不会被归还。
+    ///
+    /// # Safety
+    /// 如果分配器正在使用，未定义的行为。
+    pub unsafe fn reset(&self) {
+        unsafe {
+            *self.free_list.get() = null_mut();
+            *self.heap_top.get() = 0;
+            *self.heap_end.get() = 0;
+        }
+    }
+
+    /// Number of usable bytes in the block at `ptr`: always the whole slot.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`.
+    ///
+    /// `ptr` 处的块中的可用字节数：总是整个槽位。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配。
+    pub unsafe fn usable_size(&self, _ptr: *mut u8, _layout: Layout) -> usize {
+        BLOCK
+    }
+
+    /// Whether a slot can serve `layout`.
+    ///
+    /// 槽位能否服务 `layout`。
+    #[inline(always)]
+    fn fits(layout: Layout) -> bool {
+        layout.size() <= BLOCK && layout.align() <= Self::SLOT_ALIGN
+    }
+}
+
+impl<const BLOCK: usize> Default for SlabAllocator<BLOCK> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const BLOCK: usize, S: MemorySource> SlabAllocator<BLOCK, S> {
+    /// Allocate like `GlobalAlloc::alloc`, also returning the capacity of the block:
+    /// the whole slot, always `>= layout.size()`.
+    /// The caller may use all of it, and may later free or resize the block with the
+    /// same `layout`. On failure, returns `(null, 0)`.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::alloc`.
+    ///
+    /// 与 `GlobalAlloc::alloc` 一样分配，并同时返回块的容量：
+    /// 整个槽位，总是 `>= layout.size()`。
+    /// 调用者可以使用全部容量，之后仍以同一 `layout` 释放或调整该块。失败时返回 `(null, 0)`。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::alloc` 的约定相同。
+    pub unsafe fn alloc_with_capacity(&self, layout: Layout) -> (*mut u8, usize) {
+        let ptr = unsafe { self.alloc(layout) };
+        if ptr.is_null() {
+            return (ptr, 0);
+        }
+        (ptr, unsafe { self.usable_size(ptr, layout) })
+    }
+
+    /// Grow the heap up front so that the next `bytes` bytes of never-used slots can be
+    /// carved without growing, e.g. before a latency-sensitive phase. Freed slots are not
+    /// counted. Returns `false` on OOM.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 预先增长堆，使接下来 `bytes` 字节从未使用过的槽位无需增长即可切分，
+    /// 例如在对延迟敏感的阶段之前。已释放的槽位不计入。OOM 时返回 `false`。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn reserve(&self, bytes: usize) -> bool {
+        unsafe { self.carve_room(bytes) }
+    }
+
+    /// Pop a freed slot, or carve the next one from the pages (growing if needed).
+    ///
+    /// 弹出一个已释放的槽位，或从页面中切出下一个槽位（必要时增长内存）。
+    unsafe fn alloc_slot(&self) -> *mut u8 {
+        unsafe {
+            let free_list = self.free_list.get();
+            let head = *free_list;
+            if !head.is_null() {
+                *free_list = (*head).next;
+                return head as *mut u8;
+            }
+
+            if !self.carve_room(BLOCK) {
+                return null_mut();
+            }
+            let heap_top = &mut *self.heap_top.get();
+            let slot = *heap_top;
+            *heap_top += BLOCK;
+            slot as *mut u8
+        }
+    }
+
+    /// Grow the pages as needed so that `bytes` bytes can be carved past the next
+    /// never-used slot. Returns `false` on OOM.
+    ///
+    /// 按需增长页面，使下一个从未使用过的槽位之后能切分出 `bytes` 字节。OOM 时返回 `false`。
+    unsafe fn carve_room(&self, bytes: usize) -> bool {
+        unsafe {
+            let heap_top = &mut *self.heap_top.get();
+            let heap_end = &mut *self.heap_end.get();
+            if *heap_end - *heap_top < bytes {
+                let pages = PageCount::from_bytes(bytes - (*heap_end - *heap_top)).as_usize();
+                let Some(start) = grow_region(&self.source, pages) else {
+                    return false;
+                };
+                // Pages not following ours (another instance grew the same memory):
+                // abandon the tail and carve from the new pages
+                // 新页面不紧接在我们的页面之后（其他实例扩容了同一块内存）：
+                // 放弃尾部，从新页面开始切分
+                if start != *heap_end {
+                    *heap_top = start;
+                    *heap_end = start;
+                }
+                *heap_end += pages * PAGE_SIZE;
+
+                // After a restart the pages sized for the old tail may fall short
+                // 重新开始后，按旧尾部计算的页数可能不够
+                if *heap_end - *heap_top < bytes {
+                    let extra = PageCount::from_bytes(bytes - (*heap_end - *heap_top)).as_usize();
+                    // The extra pages must follow the region, or the slots would straddle a gap
+                    // 额外的页面必须紧接在区域之后，否则槽位会跨越空洞
+                    if grow_region(&self.source, extra) != Some(*heap_end) {
+                        return false;
+                    }
+                    *heap_end += extra * PAGE_SIZE;
+                }
+            }
+        }
+        true
+    }
+}
+
+unsafe impl<const BLOCK: usize, S: MemorySource> GlobalAlloc for SlabAllocator<BLOCK, S> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = if Self::fits(layout) {
+            unsafe { self.alloc_slot() }
+        } else {
+            null_mut()
+        };
+        if ptr.is_null() {
+            self.oom_hook.fire(layout);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, _layout: Layout) {
+        // Push at head (O(1))
+        // 头插法 (O(1))
+        unsafe {
+            let node = ptr as *mut Node;
+            (*node).next = *self.free_list.get();
+            *self.free_list.get() = node;
+        }
+    }
+
+    // Every slot already spans BLOCK bytes: resizing within it never moves the block
+    // 每个槽位都已占据 BLOCK 字节：在其范围内调整大小永远不需要移动块
+    #[cfg(feature = "realloc")]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size <= BLOCK {
+            ptr
+        } else {
+            self.oom_hook
+                .fire(unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) });
+            null_mut()
+        }
+    }
+}
+
+impl<const BLOCK: usize, S> ResettableAllocator for SlabAllocator<BLOCK, S> {
+    unsafe fn reset(&self) {
+        unsafe { SlabAllocator::reset(self) }
+    }
+}
@@ -0,0 +1,155 @@
+use super::{
+    AnyAllocator, BitmapAllocator, BuddyAllocator, BumpFreeListAllocator, Fallback,
+    FreeListAllocator, RingArena, SegregatedBumpAllocator, SlabAllocator, TlsfAllocator,
+};
+use crate::{ResettableAllocator, SharedMemorySource};
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::null_mut;
+use std::thread::LocalKey;
+
+/// Allocators whose memory comes from a [`SharedMemorySource`], the only ones
+/// [`ThreadLocalAlloc`] accepts: a block freed on another thread joins that thread's free
+/// lists, so its memory must outlive the thread that allocated it.
+///
+/// # Safety
+/// Every block the allocator hands out must stay valid after the thread owning the
+/// allocator exits, and may be freed into another instance of the same type.
+///
+/// 内存来自 [`SharedMemorySource`] 的分配器，也是 [`ThreadLocalAlloc`] 唯一接受的分配器：
+/// 在其他线程释放的块会加入该线程的空闲链表，因此其内存必须比分配它的线程活得更久。
+///
+/// # Safety
+/// 分配器分配出去的每个块在拥有该分配器的线程退出后都必须保持有效，
+/// 并且可以被释放到同一类型的另一个实例中。
+pub unsafe trait SharedMemoryAllocator {}
+
+unsafe impl<S: SharedMemorySource, const MIN_ALIGN: usize, const MIN_BLOCK: usize>
+    SharedMemoryAllocator for FreeListAllocator<S, MIN_ALIGN, MIN_BLOCK>
+{
+}
+unsafe impl<S: SharedMemorySource, const MIN_ALIGN: usize> SharedMemoryAllocator
+    for BumpFreeListAllocator<S, MIN_ALIGN>
+{
+}
+unsafe impl<S: SharedMemorySource, const N: usize, const MIN_ALIGN: usize> SharedMemoryAllocator
+    for SegregatedBumpAllocator<S, N, MIN_ALIGN>
+{
+}
+unsafe impl<const CELL: usize, S: SharedMemorySource> SharedMemoryAllocator
+    for BitmapAllocator<CELL, S>
+{
+}
+unsafe impl<S: SharedMemorySource> SharedMemoryAllocator for BuddyAllocator<S> {}
+unsafe impl<const BLOCK: usize, S: SharedMemorySource> SharedMemoryAllocator
+    for SlabAllocator<BLOCK, S>
+{
+}
+unsafe impl<S: SharedMemorySource> SharedMemoryAllocator for TlsfAllocator<S> {}
+unsafe impl<const N: usize, S: SharedMemorySource> SharedMemoryAllocator for RingArena<N, S> {}
+unsafe impl<S: SharedMemorySource> SharedMemoryAllocator for AnyAllocator<S> {}
+unsafe impl<A: SharedMemoryAllocator, B: SharedMemoryAllocator> SharedMemoryAllocator
+    for Fallback<A, B>
+{
+}
+
+/// Routes every call to the calling thread's own instance of `A`, kept in a
+/// `thread_local!`, so the single-threaded allocators can back a multi-threaded host
+/// without sharing state between threads (at the cost of one heap per thread).
+///
+/// ```
+/// use lite_alloc::single_threaded::{FreeListAllocator, ThreadLocalAlloc};
+/// use lite_alloc::{MemorySource, SharedMemorySource};
+///
+/// struct SharedPages;
+///
+/// impl MemorySource for SharedPages {
+///     unsafe fn grow(&self, pages: usize) -> usize {
+///         // Hand out `pages` pages of a process-wide region
+///         # let _ = pages;
+///         # usize::MAX
+///     }
+/// }
+///
+/// unsafe impl SharedMemorySource for SharedPages {}
+///
+/// std::thread_local! {
+///     static HEAP: FreeListAllocator<SharedPages> =
+///         const { FreeListAllocator::with_source(SharedPages) };
+/// }
+/// static ALLOC: ThreadLocalAlloc<FreeListAllocator<SharedPages>> = ThreadLocalAlloc::new(&HEAP);
+/// ```
+///
+/// Single-threaded WASM does not need this wrapper: use the bare allocator there for zero
+/// overhead (this type only exists on non-WASM targets).
+///
+/// A block freed on another thread joins that thread's free lists, so `A` must be a
+/// [`SharedMemoryAllocator`]. The host mock behind `DefaultMemory` is per thread and is
+/// released when the thread exits, so allocators on it are rejected at compile time:
+///
+/// ```compile_fail
+/// use lite_alloc::single_threaded::{FreeListAllocator, ThreadLocalAlloc};
+///
+/// std::thread_local! {
+///     static HEAP: FreeListAllocator = const { FreeListAllocator::new() };
+/// }
+/// static ALLOC: ThreadLocalAlloc<FreeListAllocator> = ThreadLocalAlloc::new(&HEAP);
+/// ```
+///
+/// 将每次调用转发给调用线程自己的 `A` 实例（保存在 `thread_local!` 中），
+/// 使单线程分配器可以在多线程宿主上使用而不在线程之间共享状态（代价是每个线程一个堆）。
+///
+/// 单线程 WASM 不需要此包装器：在那里直接使用分配器本身，零开销
+/// （此类型只存在于非 WASM 目标上）。
+///
+/// 在其他线程释放的块会加入该线程的空闲链表，因此 `A` 必须是 [`SharedMemoryAllocator`]。
+/// `DefaultMemory` 背后的宿主模拟是每线程的，并在线程退出时释放，
+/// 因此基于它的分配器会在编译期被拒绝（见上方 `compile_fail` 示例）。
+pub struct ThreadLocalAlloc<A: SharedMemoryAllocator + 'static> {
+    key: &'static LocalKey<A>,
+}
+
+impl<A: SharedMemoryAllocator + 'static> ThreadLocalAlloc<A> {
+    /// Wrap the per-thread allocator declared with `thread_local!`.
+    ///
+    /// 包装通过 `thread_local!` 声明的每线程分配器。
+    pub const fn new(key: &'static LocalKey<A>) -> Self {
+        ThreadLocalAlloc { key }
+    }
+}
+
+unsafe impl<A: GlobalAlloc + SharedMemoryAllocator + 'static> GlobalAlloc for ThreadLocalAlloc<A> {
+    // Once the thread-local is destroyed (thread teardown), allocations fail and frees leak
+    // 线程局部变量被销毁后（线程退出时），分配失败，释放的块被泄漏
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.key
+            .try_with(|a| unsafe { a.alloc(layout) })
+            .unwrap_or(null_mut())
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        self.key
+            .try_with(|a| unsafe { a.alloc_zeroed(layout) })
+            .unwrap_or(null_mut())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = self.key.try_with(|a| unsafe { a.dealloc(ptr, layout) });
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        self.key
+            .try_with(|a| unsafe { a.realloc(ptr, layout, new_size) })
+            .unwrap_or(null_mut())
+    }
+}
+
+impl<A: ResettableAllocator + SharedMemoryAllocator + 'static> ResettableAllocator
+    for ThreadLocalAlloc<A>
+{
+    /// Resets the calling thread's instance only.
+    ///
+    /// 只重置调用线程的实例。
+    unsafe fn reset(&self) {
+        let _ = self.key.try_with(|a| unsafe { a.reset() });
+    }
+}
@@ -0,0 +1,613 @@
+#[cfg(feature = "realloc")]
+use crate::layout_math::copy_span;
+use crate::layout_math::round_up;
+use crate::{
+    DefaultMemory, MemorySource, OomHook, PAGE_SIZE, PageCount, ResettableAllocator, grow_region,
+};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::UnsafeCell,
+    ptr::null_mut,
+};
+
+/// Safety Warning:
+/// Allocators in this module are designed for [Single Threaded] environments.
+/// `Sync` is implemented only to satisfy `GlobalAlloc` trait requirements.
+/// Using this allocator in a multi-threaded environment will lead to Undefined Behavior (UB).
+/// Please ensure it is used only in single-threaded environments (e.g., WASM or single-threaded embedded).
+///
+/// 安全性警示 (Safety Warning):
+/// 本模块中的分配器均为【单线程】设计。
+/// 实现了 `Sync` 仅为了满足 `GlobalAlloc` trait 的要求。
+/// 在多线程环境中使用此分配器会导致未定义行为 (UB)。
+/// 请确保只在单线程环境（如 WASM 或单线程嵌入式环境）中使用。
+///
+/// With `strict-single-thread` this impl is left out: wrap the allocator in
+/// [`GlobalWrapper`](super::GlobalWrapper) at the `#[global_allocator]` site instead.
+///
+/// 开启 `strict-single-thread` 时不提供此实现：
+/// 请改为在 `#[global_allocator]` 处用 [`GlobalWrapper`](super::GlobalWrapper) 包装分配器。
+#[cfg(not(feature = "strict-single-thread"))]
+unsafe impl<S> Sync for TlsfAllocator<S> {}
+
+// Safety: No one else owns the raw pointers (conceptually), logic is same.
+unsafe impl<S: Send> Send for TlsfAllocator<S> {}
+
+// Every payload is 16-aligned and every block size is a multiple of 16
+// 每个有效载荷都按 16 对齐，每个块的大小都是 16 的倍数
+const ALIGN: usize = 16;
+// Block header, padded to ALIGN so payloads stay aligned on 32-bit targets too
+// 块头，填充到 ALIGN，使有效载荷在 32 位目标上同样保持对齐
+const HEADER: usize = ALIGN;
+// Header plus the free-list links stored in a free block's payload
+// 块头加上存放在空闲块有效载荷中的空闲链表链接
+const MIN_BLOCK: usize = 2 * ALIGN;
+// Low bit of `Header::size`, set while the block is free
+// `Header::size` 的最低位，块空闲时置位
+const FREE: usize = 1;
+
+// Second level: each power-of-two range is split into 16 linearly spaced classes
+// 第二级：每个 2 的幂区间被划分为 16 个线性间隔的大小等级
+const SL_LOG: u32 = 4;
+const SL_COUNT: usize = 1 << SL_LOG;
+// Blocks below 256 bytes all live in first-level class 0, in 16-byte steps
+// 小于 256 字节的块全部位于第一级等级 0，以 16 字节为步长
+const FL_SHIFT: u32 = SL_LOG + ALIGN.trailing_zeros();
+const SMALL_BLOCK: usize = 1 << FL_SHIFT;
+const FL_COUNT: usize = (usize::BITS - FL_SHIFT + 1) as usize;
+
+/// Boundary tag at the start of every block. `prev_phys` is null for the first block of
+/// a region; a zero-sized used header (the sentinel) closes each region.
+///
+/// 位于每个块开头的边界标记。区域中第一个块的 `prev_phys` 为 null；
+/// 每个区域以一个大小为零且已使用的块头（哨兵）结尾。
+#[repr(C)]
+struct Header {
+    prev_phys: *mut Header,
+    // Block size including the header, with the FREE flag in bit 0
+    // 包含块头在内的块大小，第 0 位为 FREE 标志
+    size: usize,
+}
+
+/// Free-list links, stored in the payload of free blocks.
+///
+/// 空闲链表链接，存放在空闲块的有效载荷中。
+#[repr(C)]
+struct Links {
+    next: *mut Header,
+    prev: *mut Header,
+}
+
+const _: () = assert!(core::mem::size_of::<Header>() <= HEADER);
+const _: () = assert!(core::mem::size_of::<Links>() <= MIN_BLOCK - HEADER);
+
+/// Two-Level Segregated Fit allocator: O(1) allocation and deallocation with immediate
+/// coalescing.
+///
+/// Free blocks are kept in `FL x 16` size-class lists indexed by two bitmaps, so finding a
+/// block that fits is a couple of bit scans instead of a list walk. Boundary tags let
+/// `dealloc` merge with both physical neighbours in constant time. Every block carries a
+/// 16-byte header; payloads are 16-aligned like the other allocators.
+///
+/// 两级分离适配（TLSF）分配器：O(1) 的分配与释放，并立即合并。
+///
+/// 空闲块保存在 `FL x 16` 个大小等级链表中，由两个位图索引，
+/// 因此找到合适的块只需几次位扫描而无需遍历链表。边界标记使 `dealloc`
+/// 可以在常数时间内与前后两个物理相邻块合并。每个块带有 16 字节的块头；
+/// 与其他分配器一样，有效载荷按 16 对齐。
+pub struct TlsfAllocator<S = DefaultMemory> {
+    // Bit `fl` set when any list of first-level class `fl` is non-empty
+    // 当第一级等级 `fl` 的任一链表非空时，第 `fl` 位置位
+    fl_bitmap: UnsafeCell<usize>,
+    // Bit `sl` of entry `fl` set when list `[fl][sl]` is non-empty
+    // 当链表 `[fl][sl]` 非空时，条目 `fl` 的第 `sl` 位置位
+    sl_bitmap: UnsafeCell<[u32; FL_COUNT]>,
+    heads: UnsafeCell<[[*mut Header; SL_COUNT]; FL_COUNT]>,
+    // End of the latest region, so contiguous growth extends it
+    // 最近一个区域的末尾，使连续增长可以扩展它
+    region_end: UnsafeCell<usize>,
+    source: S,
+    oom_hook: OomHook,
+}
+
+impl TlsfAllocator {
+    pub const fn new() -> Self {
+        Self::with_source(DefaultMemory)
+    }
+}
+
+impl Default for TlsfAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> TlsfAllocator<S> {
+    /// Create an allocator that obtains its pages from `source`.
+    ///
+    /// 创建一个从 `source` 获取页面的分配器。
+    pub const fn with_source(source: S) -> Self {
+        TlsfAllocator {
+            fl_bitmap: UnsafeCell::new(0),
+            sl_bitmap: UnsafeCell::new([0; FL_COUNT]),
+            heads: UnsafeCell::new([[null_mut(); SL_COUNT]; FL_COUNT]),
+            region_end: UnsafeCell::new(0),
+            source,
+            oom_hook: OomHook::new(),
+        }
+    }
+
+    /// Register a hook called with the failing `Layout` right before `alloc` returns null.
+    /// Pass `None` to remove it.
+    ///
+    /// 注册一个回调，在 `alloc` 返回 null 之前以失败的 `Layout` 调用。
+    /// 传入 `None` 以移除。
+    pub fn set_oom_hook(&self, hook: Option<fn(Layout)>) {
+        self.oom_hook.set(hook);
+    }
+
+    /// ⚠️ Test/Bench only: Forget all free lists and regions.
+    /// Pages already obtained from the memory source are not returned.
+    ///
+    /// # Safety
+    /// All pointers handed out before the reset become dangling.
+    ///
+    /// ⚠️ 仅用于测试/Bench：清空所有空闲链表与区域。
+    /// 已从内存源获取的页面不会被归还。
+    ///
+    /// # Safety
+    /// 重置之前分配出去的所有指针都将失效。
+    pub unsafe fn reset(&self) {
+        unsafe {
+            *self.fl_bitmap.get() = 0;
+            *self.sl_bitmap.get() = [0; FL_COUNT];
+            *self.heads.get() = [[null_mut(); SL_COUNT]; FL_COUNT];
+            *self.region_end.get() = 0;
+        }
+    }
+
+    /// Number of usable bytes in the block at `ptr`: the block size minus its header.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`.
+    ///
+    /// `ptr` 处的块中的可用字节数：块大小减去块头。
+    ///
+    /// # Safety
+    /// `ptr` 必须由此分配器以 `layout` 分配。
+    pub unsafe fn usable_size(&self, ptr: *mut u8, _layout: Layout) -> usize {
+        unsafe { size_of_block(header_of(ptr)) - HEADER }
+    }
+
+    /// Link the free block `h` into the list of its size class.
+    ///
+    /// 将空闲块 `h` 链入其大小等级的链表。
+    unsafe fn insert(&self, h: *mut Header) {
+        let (fl, sl) = mapping_insert(unsafe { size_of_block(h) });
+        unsafe {
+            let heads = &mut *self.heads.get();
+            let next = heads[fl][sl];
+            *links(h) = Links {
+                next,
+                prev: null_mut(),
+            };
+            if !next.is_null() {
+                (*links(next)).prev = h;
+            }
+            heads[fl][sl] = h;
+            (*self.sl_bitmap.get())[fl] |= 1 << sl;
+            *self.fl_bitmap.get() |= 1 << fl;
+        }
+    }
+
+    /// Unlink the free block `h` from the list of its size class.
+    ///
+    /// 将空闲块 `h` 从其大小等级的链表中移除。
+    unsafe fn remove(&self, h: *mut Header) {
+        let (fl, sl) = mapping_insert(unsafe { size_of_block(h) });
+        unsafe {
+            let heads = &mut *self.heads.get();
+            let Links { next, prev } = *links(h);
+            if prev.is_null() {
+                heads[fl][sl] = next;
+            } else {
+                (*links(prev)).next = next;
+            }
+            if !next.is_null() {
+                (*links(next)).prev = prev;
+            }
+            if heads[fl][sl].is_null() {
+                let sl_bitmap = &mut (*self.sl_bitmap.get())[fl];
+                *sl_bitmap &= !(1 << sl);
+                if *sl_bitmap == 0 {
+                    *self.fl_bitmap.get() &= !(1 << fl);
+                }
+            }
+        }
+    }
+
+    /// A free block of at least `size` bytes (O(1)), or null.
+    ///
+    /// 一个至少 `size` 字节的空闲块（O(1)），或 null。
+    unsafe fn find(&self, size: usize) -> *mut Header {
+        let (mut fl, sl) = mapping_insert(search_size(size));
+        if fl >= FL_COUNT {
+            return null_mut();
+        }
+        unsafe {
+            let sl_bitmap = &*self.sl_bitmap.get();
+            // A larger class in the same range, or else the first non-empty larger range
+            // 同一区间内更大的等级，否则为第一个非空的更大区间
+            let mut sl_map = sl_bitmap[fl] & (!0u32 << sl);
+            if sl_map == 0 {
+                let fl_map =
+                    *self.fl_bitmap.get() & (!0usize).checked_shl(fl as u32 + 1).unwrap_or(0);
+                if fl_map == 0 {
+                    return null_mut();
+                }
+                fl = fl_map.trailing_zeros() as usize;
+                sl_map = sl_bitmap[fl];
+            }
+            (*self.heads.get())[fl][sl_map.trailing_zeros() as usize]
+        }
+    }
+
+    /// Mark the used block `h` free, merge it with free physical neighbours and list it.
+    ///
+    /// 将已使用的块 `h` 标记为空闲，与空闲的物理相邻块合并，并将其加入链表。
+    unsafe fn release(&self, mut h: *mut Header) {
+        unsafe {
+            let mut size = size_of_block(h);
+            let next = next_phys(h);
+            if is_free(next) {
+                self.remove(next);
+                size += size_of_block(next);
+            }
+            let prev = (*h).prev_phys;
+            if !prev.is_null() && is_free(prev) {
+                self.remove(prev);
+                size += size_of_block(prev);
+                h = prev;
+            }
+            (*h).size = size | FREE;
+            (*next_phys(h)).prev_phys = h;
+            self.insert(h);
+        }
+    }
+
+    /// Trim the used block `h` to `size` bytes, releasing the tail if it can form a block.
+    ///
+    /// 将已使用的块 `h` 裁剪为 `size` 字节，如果尾部能构成一个块则将其释放。
+    unsafe fn split(&self, h: *mut Header, size: usize) {
+        unsafe {
+            let total = size_of_block(h);
+            if total - size < MIN_BLOCK {
+                return;
+            }
+            (*h).size = size;
+            let tail = next_phys(h);
+            (*tail).prev_phys = h;
+            (*tail).size = total - size;
+            (*next_phys(tail)).prev_phys = tail;
+            self.release(tail);
+        }
+    }
+}
+
+impl<S: MemorySource> TlsfAllocator<S> {
+    /// Allocate like `GlobalAlloc::alloc`, also returning the capacity of the block:
+    /// the size of the block taken from its class list, always `>= layout.size()`.
+    /// The caller may use all of it, and may later free or resize the block with the
+    /// same `layout`. On failure, returns `(null, 0)`.
+    ///
+    /// # Safety
+    /// Same contract as `GlobalAlloc::alloc`.
+    ///
+    /// 与 `GlobalAlloc::alloc` 一样分配，并同时返回块的容量：
+    /// 从其大小等级链表中取出的块的大小，总是 `>= layout.size()`。
+    /// 调用者可以使用全部容量，之后仍以同一 `layout` 释放或调整该块。失败时返回 `(null, 0)`。
+    ///
+    /// # Safety
+    /// 与 `GlobalAlloc::alloc` 的约定相同。
+    pub unsafe fn alloc_with_capacity(&self, layout: Layout) -> (*mut u8, usize) {
+        let ptr = unsafe { self.alloc(layout) };
+        if ptr.is_null() {
+            return (ptr, 0);
+        }
+        (ptr, unsafe { self.usable_size(ptr, layout) })
+    }
+
+    /// Grow the heap up front so that a free block of at least `bytes` bytes is available,
+    /// e.g. before a latency-sensitive phase. Nothing is grown if such a block is already
+    /// free. Returns `false` on OOM.
+    ///
+    /// # Safety
+    /// Must not be called while another operation on this allocator is in progress.
+    ///
+    /// 预先增长堆，使至少有一个 `bytes` 字节的空闲块可用，例如在对延迟敏感的阶段之前。
+    /// 如果已有这样的空闲块，则不会增长。OOM 时返回 `false`。
+    ///
+    /// # Safety
+    /// 不得在此分配器的其他操作进行期间调用。
+    pub unsafe fn reserve(&self, bytes: usize) -> bool {
+        let Some(size) = block_size(bytes) else {
+            return false;
+        };
+        unsafe { !self.find(size).is_null() || self.grow(size) }
+    }
+
+    /// Grow the source so that a block of at least `size` bytes becomes free.
+    /// Pages following the previous region extend it; others start a new region.
+    ///
+    /// 增长内存源，使一个至少 `size` 字节的块变为空闲。
+    /// 紧接在前一个区域之后的页面会扩展该区域；否则开始一个新区域。
+    unsafe fn grow(&self, size: usize) -> bool {
+        let Some(bytes) = search_size(size).checked_add(HEADER) else {
+            return false;
+        };
+        let pages = PageCount::from_bytes(bytes).as_usize();
+        let Some(start) = (unsafe { grow_region(&self.source, pages) }) else {
+            return false;
+        };
+        let end = start + pages * PAGE_SIZE;
+        unsafe {
+            let region_end = &mut *self.region_end.get();
+            let block = if *region_end != 0 && start == *region_end {
+                // The old sentinel becomes the header of the new block
+                // 旧的哨兵成为新块的块头
+                let block = (start - HEADER) as *mut Header;
+                (*block).size = pages * PAGE_SIZE;
+                block
+            } else {
+                let block = start as *mut Header;
+                (*block).prev_phys = null_mut();
+                (*block).size = pages * PAGE_SIZE - HEADER;
+                block
+            };
+            let sentinel = (end - HEADER) as *mut Header;
+            (*sentinel).prev_phys = block;
+            (*sentinel).size = 0;
+            *region_end = end;
+            self.release(block);
+        }
+        true
+    }
+
+    /// Take a free block of at least `size` bytes off its list, growing if needed.
+    /// The returned block is marked used but not yet split.
+    ///
+    /// 从链表中取出一个至少 `size` 字节的空闲块，必要时增长内存。
+    /// 返回的块已标记为已使用，但尚未切分。
+    unsafe fn take(&self, size: usize) -> *mut Header {
+        unsafe {
+            let mut h = self.find(size);
+            if h.is_null() {
+                if !self.grow(size) {
+                    return null_mut();
+                }
+                h = self.find(size);
+                debug_assert!(!h.is_null());
+            }
+            self.remove(h);
+            (*h).size = size_of_block(h);
+            h
+        }
+    }
+
+    /// Allocation logic behind `GlobalAlloc::alloc`, without the OOM hook.
+    ///
+    /// `GlobalAlloc::alloc` 背后的分配逻辑，不包含 OOM 回调。
+    unsafe fn alloc_block(&self, layout: Layout) -> *mut u8 {
+        let Some(size) = block_size(layout.size()) else {
+            return null_mut();
+        };
+        if layout.align() <= ALIGN {
+            unsafe {
+                let h = self.take(size);
+                if h.is_null() {
+                    return null_mut();
+                }
+                self.split(h, size);
+                return payload(h);
+            }
+        }
+
+        // Over-allocate so the payload can move up to the alignment, leaving a front gap
+        // of either nothing or a whole free block
+        // 超额分配，使有效载荷可以上移到对齐位置，且前部空隙要么为空，要么是一个完整的空闲块
+        let Some(padded) = size.checked_add(layout.align() + MIN_BLOCK) else {
+            return null_mut();
+        };
+        unsafe {
+            let mut h = self.take(padded);
+            if h.is_null() {
+                return null_mut();
+            }
+            let start = payload(h) as usize;
+            let mut aligned = round_up(start, layout.align());
+            if aligned != start && aligned - start < MIN_BLOCK {
+                aligned += layout.align();
+            }
+            let gap = aligned - start;
+            if gap > 0 {
+                let total = size_of_block(h);
+                let front = h;
+                h = (front as *mut u8).add(gap) as *mut Header;
+                (*h).prev_phys = front;
+                (*h).size = total - gap;
+                (*next_phys(h)).prev_phys = h;
+                (*front).size = gap;
+                self.release(front);
+            }
+            self.split(h, size);
+            payload(h)
+        }
+    }
+}
+
+unsafe impl<S: MemorySource> GlobalAlloc for TlsfAllocator<S> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.alloc_block(layout) };
+        if ptr.is_null() {
+            self.oom_hook.fire(layout);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, _layout: Layout) {
+        #[cfg(feature = "dealloc-check")]
+        debug_assert!(
+            (ptr as usize).is_multiple_of(ALIGN),
+            "dealloc of a misaligned pointer: {ptr:p}"
+        );
+        unsafe {
+            let h = header_of(ptr);
+            debug_assert!(!is_free(h), "double free: {ptr:p}");
+            self.release(h);
+        }
+    }
+
+    #[cfg(feature = "realloc")]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let Some(size) = block_size(new_size) else {
+            return null_mut();
+        };
+        unsafe {
+            let h = header_of(ptr);
+            // 1. Shrinking (or within the slack): keep the block, release the tail
+            // 1. 缩小（或在余量之内）：保留块，释放尾部
+            if size <= size_of_block(h) {
+                self.split(h, size);
+                return ptr;
+            }
+
+            // 2. Growing into a free next neighbour: absorb it, then trim
+            // 2. 向空闲的后一个相邻块扩容：吸收它，然后裁剪
+            let next = next_phys(h);
+            if is_free(next) && size_of_block(h) + size_of_block(next) >= size {
+                self.remove(next);
+                (*h).size = size_of_block(h) + size_of_block(next);
+                (*next_phys(h)).prev_phys = h;
+                self.split(h, size);
+                return ptr;
+            }
+
+            // 3. Fallback: Alloc + Copy + Dealloc
+            // 3. 回退：Alloc + Copy + Dealloc
+            let new_ptr = self.alloc(Layout::from_size_align_unchecked(new_size, layout.align()));
+            if !new_ptr.is_null() {
+                core::ptr::copy_nonoverlapping(ptr, new_ptr, copy_span(layout, new_size));
+                self.dealloc(ptr, layout);
+            }
+            new_ptr
+        }
+    }
+}
+
+impl<S> ResettableAllocator for TlsfAllocator<S> {
+    unsafe fn reset(&self) {
+        unsafe { TlsfAllocator::reset(self) }
+    }
+}
+
+// --------------------------------------------------------------------------
+// Helper Functions
+// 辅助函数
+// --------------------------------------------------------------------------
+
+/// Block size for a `size`-byte payload: header included, at least MIN_BLOCK,
+/// a multiple of ALIGN. `None` on overflow.
+///
+/// `size` 字节有效载荷对应的块大小：包含块头，至少为 MIN_BLOCK，且为 ALIGN 的倍数。
+/// 溢出时返回 `None`。
+#[inline(always)]
+fn block_size(size: usize) -> Option<usize> {
+    let size = size.checked_add(HEADER + ALIGN - 1)? & !(ALIGN - 1);
+    Some(size.max(MIN_BLOCK))
+}
+
+/// Size class `(fl, sl)` a free block of `size` bytes is listed under.
+///
+/// `size` 字节的空闲块所属的大小等级 `(fl, sl)`。
+#[inline(always)]
+fn mapping_insert(size: usize) -> (usize, usize) {
+    if size < SMALL_BLOCK {
+        return (0, size / ALIGN);
+    }
+    let log = usize::BITS - 1 - size.leading_zeros();
+    let sl = (size >> (log - SL_LOG)) ^ SL_COUNT;
+    ((log - FL_SHIFT + 1) as usize, sl)
+}
+
+/// Round `size` up to the next class boundary, so every block in its class fits it.
+///
+/// 将 `size` 向上取整到下一个等级边界，使其等级中的每个块都能容纳它。
+#[inline(always)]
+fn search_size(size: usize) -> usize {
+    if size < SMALL_BLOCK {
+        return size;
+    }
+    let log = usize::BITS - 1 - size.leading_zeros();
+    size.saturating_add((1 << (log - SL_LOG)) - 1)
+}
+
+#[inline(always)]
+unsafe fn size_of_block(h: *mut Header) -> usize {
+    unsafe { (*h).size & !FREE }
+}
+
+#[inline(always)]
+unsafe fn is_free(h: *mut Header) -> bool {
+    unsafe { (*h).size & FREE != 0 }
+}
+
+#[inline(always)]
+unsafe fn next_phys(h: *mut Header) -> *mut Header {
+    unsafe { (h as *mut u8).add(size_of_block(h)) as *mut Header }
+}
+
+#[inline(always)]
+unsafe fn links(h: *mut Header) -> *mut Links {
+    unsafe { (h as *mut u8).add(HEADER) as *mut Links }
+}
+
+#[inline(always)]
+unsafe fn payload(h: *mut Header) -> *mut u8 {
+    unsafe { (h as *mut u8).add(HEADER) }
+}
+
+#[inline(always)]
+unsafe fn header_of(ptr: *mut u8) -> *mut Header {
+    unsafe { ptr.sub(HEADER) as *mut Header }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapping_classes() {
+        // Small blocks: one class per 16 bytes
+        assert_eq!(mapping_insert(32), (0, 2));
+        assert_eq!(mapping_insert(240), (0, 15));
+        // 256..512 is split into 16 classes of 16 bytes, 512..1024 into 32-byte classes
+        assert_eq!(mapping_insert(256), (1, 0));
+        assert_eq!(mapping_insert(272), (1, 1));
+        assert_eq!(mapping_insert(511), (1, 15));
+        assert_eq!(mapping_insert(512), (2, 0));
+        assert_eq!(mapping_insert(544), (2, 1));
+        assert_eq!(mapping_insert(usize::MAX).0, FL_COUNT - 1);
+
+        // Searching rounds up, so any block in the found class is large enough
+        for size in (32..4096).step_by(16) {
+            let (fl, sl) = mapping_insert(search_size(size));
+            let class_start = if fl == 0 {
+                sl * ALIGN
+            } else {
+                let log = fl as u32 + FL_SHIFT - 1;
+                (1 << log) + (sl << (log - SL_LOG))
+            };
+            assert!(class_start >= size, "size {size}");
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! `defmt` global logger for the tests of `lite-alloc`.
+//!
+//! Every binary linking `defmt` needs exactly one global logger, so each test target
+//! links this crate when the `defmt` feature is on. Frames are kept per thread, and
+//! `take_frames` hands them over to the test that logged them.
+//!
+//! `lite-alloc` 测试使用的 `defmt` 全局日志器。
+//!
+//! 每个链接 `defmt` 的二进制都需要且只能有一个全局日志器，因此开启 `defmt` feature 时，
+//! 每个测试目标都会链接此 crate。帧按线程保存，`take_frames` 将其交给记录它们的测试。
+
+use std::cell::RefCell;
+
+// Raw frames written by the logger on this thread; each test runs on its own thread,
+// so the frames never interleave
+// 此线程上日志器写入的原始帧；每个测试运行在各自的线程上，因此帧不会交错
+thread_local! {
+    static FRAMES: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
+
+#[defmt::global_logger]
+struct CaptureLogger;
+
+unsafe impl defmt::Logger for CaptureLogger {
+    fn acquire() {
+        FRAMES.with_borrow_mut(|frames| frames.push(Vec::new()));
+    }
+
+    unsafe fn flush() {}
+
+    unsafe fn release() {}
+
+    unsafe fn write(bytes: &[u8]) {
+        FRAMES.with_borrow_mut(|frames| frames.last_mut().unwrap().extend_from_slice(bytes));
+    }
+}
+
+// No timestamp, so a frame is the format string index followed by the arguments
+// 没有时间戳，因此一帧由格式字符串索引及其后的参数组成
+defmt::timestamp!("");
+
+/// Take the raw frames logged on the current thread so far.
+///
+/// 取出当前线程迄今记录的原始帧。
+pub fn take_frames() -> Vec<Vec<u8>> {
+    FRAMES.with_borrow_mut(std::mem::take)
+}
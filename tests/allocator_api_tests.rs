@@ -0,0 +1,128 @@
+#![cfg(feature = "allocator_api")]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::reset_heap;
+use lite_alloc::single_threaded::{
+    BitmapAllocator, BuddyAllocator, BumpFreeListAllocator, FreeListAllocator,
+    SegregatedBumpAllocator, SlabAllocator, TlsfAllocator,
+};
+use std::alloc::{Allocator, Layout};
+
+// Allocator state is per-instance and the mock heap is per-thread, so tests only need a clean heap
+// 分配器状态属于各实例，模拟堆属于各线程，因此测试只需要一个干净的堆
+fn clean_heap() {
+    reset_heap();
+}
+
+#[test]
+fn test_vec_in_free_list() {
+    clean_heap();
+    let allocator = FreeListAllocator::new();
+    let mut v: Vec<u32, &FreeListAllocator> = Vec::new_in(&allocator);
+    for i in 0..1000 {
+        v.push(i);
+    }
+    assert_eq!(v.len(), 1000);
+    for i in (0..1000).rev() {
+        assert_eq!(v.pop(), Some(i));
+    }
+    assert!(v.is_empty());
+}
+
+#[test]
+fn test_box_in_bump_allocators() {
+    clean_heap();
+    let bump = BumpFreeListAllocator::new();
+    let b = Box::new_in([7u64; 8], &bump);
+    assert_eq!(b.iter().sum::<u64>(), 56);
+    drop(b);
+
+    let segregated = SegregatedBumpAllocator::new();
+    let mut v: Vec<u8, &SegregatedBumpAllocator> = Vec::with_capacity_in(10, &segregated);
+    v.extend_from_slice(b"lite-alloc");
+    v.shrink_to_fit();
+    assert_eq!(&v[..], b"lite-alloc");
+}
+
+#[cfg(not(feature = "track-sizes"))]
+#[test]
+fn test_allocate_reports_usable_length() {
+    clean_heap();
+    let allocator = SegregatedBumpAllocator::new();
+    let block = allocator
+        .allocate(Layout::from_size_align(20, 4).unwrap())
+        .unwrap();
+    // 20 bytes land in the 32-byte bin
+    assert_eq!(block.len(), 32);
+}
+
+#[test]
+fn test_boxes_in_slab() {
+    clean_heap();
+    let slab = SlabAllocator::<32>::new();
+    let a = Box::new_in([1u64; 4], &slab);
+    let b = Box::new_in(2u32, &slab);
+    assert_eq!(a.iter().sum::<u64>() + *b as u64, 6);
+    let a_addr = &*a as *const _ as usize;
+    drop(a);
+    // The freed slot is reused first
+    let c = Box::new_in(3u16, &slab);
+    assert_eq!(&*c as *const _ as usize, a_addr);
+}
+
+#[test]
+fn test_boxes_in_bitmap() {
+    clean_heap();
+    let bitmap = BitmapAllocator::<32>::new();
+    let a = Box::new_in([1u64; 4], &bitmap);
+    let b = Box::new_in(2u32, &bitmap);
+    assert_eq!(a.iter().sum::<u64>() + *b as u64, 6);
+    let a_addr = &*a as *const _ as usize;
+    drop(a);
+    // The lowest free cell is handed out again
+    let c = Box::new_in(3u16, &bitmap);
+    assert_eq!(&*c as *const _ as usize, a_addr);
+}
+
+#[test]
+fn test_vec_in_tlsf() {
+    clean_heap();
+    let tlsf = TlsfAllocator::new();
+    let mut v = Vec::new_in(&tlsf);
+    for i in 0..1000u32 {
+        v.push(i);
+    }
+    assert_eq!(v.iter().sum::<u32>(), 499_500);
+}
+
+#[test]
+fn test_aligned_box_in_buddy() {
+    clean_heap();
+    let buddy = BuddyAllocator::new();
+    #[repr(align(4096))]
+    struct Page([u8; 4096]);
+    let page = Box::new_in(Page([7; 4096]), &buddy);
+    assert_eq!(&*page as *const Page as usize % 4096, 0);
+    assert_eq!(page.0[4095], 7);
+}
+
+#[test]
+fn test_zero_sized_allocate() {
+    clean_heap();
+    let allocator = FreeListAllocator::new();
+    let layout = Layout::from_size_align(0, 32).unwrap();
+    let block = allocator.allocate(layout).unwrap();
+    assert_eq!(block.len(), 0);
+    assert_eq!(block.cast::<u8>().as_ptr() as usize % 32, 0);
+
+    // Shrinking a real block to nothing frees it
+    let real = Layout::from_size_align(64, 32).unwrap();
+    let ptr = allocator.allocate(real).unwrap().cast::<u8>();
+    let empty = unsafe { Allocator::shrink(&allocator, ptr, real, layout) }.unwrap();
+    assert_eq!(empty.len(), 0);
+    unsafe { allocator.deallocate(empty.cast(), layout) };
+    unsafe { allocator.deallocate(block.cast(), layout) };
+    assert_eq!(allocator.allocate(real).unwrap().cast::<u8>(), ptr);
+}
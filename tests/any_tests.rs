@@ -0,0 +1,101 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{
+    AnyAllocator, BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator,
+};
+use lite_alloc::{ResettableAllocator, heap_pages_grown, reset_heap};
+use std::alloc::{GlobalAlloc, Layout};
+
+// Stand-in for a host import choosing the allocator for the workload
+fn select(kind: u32) -> AnyAllocator {
+    match kind {
+        0 => AnyAllocator::FreeList(FreeListAllocator::new()),
+        1 => AnyAllocator::BumpFreeList(BumpFreeListAllocator::new()),
+        _ => AnyAllocator::SegregatedBump(SegregatedBumpAllocator::new()),
+    }
+}
+
+// Allocate, fill, grow, free and reuse through the trait object only
+fn exercise(alloc: &dyn GlobalAlloc) {
+    let mut blocks = Vec::new();
+    for (i, size) in [8, 24, 100, 512, 4096].into_iter().enumerate() {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let ptr = unsafe { alloc.alloc(layout) };
+        assert!(!ptr.is_null());
+        unsafe { ptr.write_bytes(i as u8 + 1, size) };
+        blocks.push((ptr, layout));
+    }
+    for (i, &(ptr, layout)) in blocks.iter().enumerate() {
+        assert!((0..layout.size()).all(|j| unsafe { *ptr.add(j) } == i as u8 + 1));
+    }
+
+    let zeroed = Layout::from_size_align(256, 16).unwrap();
+    let ptr = unsafe { alloc.alloc_zeroed(zeroed) };
+    assert!((0..256).all(|j| unsafe { *ptr.add(j) } == 0));
+    unsafe { alloc.dealloc(ptr, zeroed) };
+
+    // Growing keeps the contents
+    let (ptr, layout) = blocks.pop().unwrap();
+    let grown = unsafe { alloc.realloc(ptr, layout, 8192) };
+    assert!(!grown.is_null());
+    assert!((0..layout.size()).all(|j| unsafe { *grown.add(j) } == 5));
+    blocks.push((grown, Layout::from_size_align(8192, 8).unwrap()));
+
+    for (ptr, layout) in blocks {
+        unsafe { alloc.dealloc(ptr, layout) };
+    }
+
+    // Freed blocks are reused instead of growing the heap
+    let pages = heap_pages_grown();
+    let layout = Layout::from_size_align(100, 8).unwrap();
+    let ptr = unsafe { alloc.alloc(layout) };
+    assert!(!ptr.is_null());
+    assert_eq!(heap_pages_grown(), pages);
+    unsafe { alloc.dealloc(ptr, layout) };
+}
+
+#[test]
+fn test_each_variant_runs_common_sequence() {
+    for kind in 0..3 {
+        reset_heap();
+        let allocator = select(kind);
+        exercise(&allocator);
+        unsafe { allocator.reset() };
+    }
+    reset_heap();
+}
+
+#[test]
+fn test_dispatch_reaches_selected_variant() {
+    reset_heap();
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    let allocator = select(0);
+    let ptr = unsafe { allocator.alloc(layout) };
+    match &allocator {
+        AnyAllocator::FreeList(inner) => {
+            let (start, end) = unsafe { inner.heap_extent() };
+            assert!(start <= ptr as usize && (ptr as usize) < end);
+        }
+        _ => panic!("wrong variant selected"),
+    }
+    unsafe { allocator.dealloc(ptr, layout) };
+    reset_heap();
+}
+
+#[test]
+fn test_reset_rewinds_active_variant() {
+    reset_heap();
+    let allocator = select(1);
+    let layout = Layout::from_size_align(128, 16).unwrap();
+    let first = unsafe { allocator.alloc(layout) };
+    assert!(!first.is_null());
+    unsafe { allocator.alloc(layout) };
+
+    // Resetting through the trait starts the bump region over
+    let resettable: &dyn ResettableAllocator = &allocator;
+    unsafe { resettable.reset() };
+    reset_heap();
+    assert_eq!(unsafe { allocator.alloc(layout) }, first);
+    unsafe { allocator.reset() };
+    reset_heap();
+}
@@ -0,0 +1,165 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::BitmapAllocator;
+use lite_alloc::{PAGE_SIZE, PageCount, heap_pages_grown, reset_heap};
+use std::alloc::{GlobalAlloc, Layout};
+
+const CELL: usize = 32;
+type Bitmap = BitmapAllocator<CELL>;
+
+struct SafeAllocator {
+    inner: Bitmap,
+}
+
+impl SafeAllocator {
+    fn new() -> Self {
+        // State is per-instance and the mock heap is per-thread: no lock needed
+        reset_heap();
+        Self {
+            inner: BitmapAllocator::new(),
+        }
+    }
+
+    fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { self.inner.alloc(layout) }
+    }
+
+    fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.inner.dealloc(ptr, layout) }
+    }
+}
+
+impl Drop for SafeAllocator {
+    fn drop(&mut self) {
+        unsafe { self.inner.reset() };
+        reset_heap();
+    }
+}
+
+#[test]
+fn test_exhaust_page_then_grow() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(CELL, 16).unwrap();
+
+    // One page holds exactly CELLS_PER_PAGE consecutive cells
+    let first = allocator.alloc(layout);
+    assert!(!first.is_null());
+    assert_eq!(first as usize % CELL, 0);
+    for i in 1..Bitmap::CELLS_PER_PAGE {
+        let ptr = allocator.alloc(layout);
+        assert_eq!(ptr as usize, first as usize + i * CELL);
+    }
+    assert_eq!(heap_pages_grown(), PageCount(1));
+    assert_eq!(
+        unsafe { allocator.inner.live_cells() },
+        Bitmap::CELLS_PER_PAGE
+    );
+
+    // The next cell needs a second page, past the header at its start
+    let next = allocator.alloc(layout);
+    assert!(!next.is_null());
+    assert_eq!(heap_pages_grown(), PageCount(2));
+    assert_eq!(next as usize / PAGE_SIZE, first as usize / PAGE_SIZE + 1);
+    assert_eq!(next as usize % PAGE_SIZE, first as usize % PAGE_SIZE);
+}
+
+#[test]
+fn test_free_clears_bit_and_reuses_cell() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(24, 8).unwrap();
+    let ptrs: Vec<_> = (0..10).map(|_| allocator.alloc(layout)).collect();
+
+    // Freed cells keep their contents: nothing is written into a free cell
+    unsafe { ptrs[3].write_bytes(0x77, CELL) };
+    allocator.dealloc(ptrs[3], layout);
+    allocator.dealloc(ptrs[7], layout);
+    assert_eq!(unsafe { allocator.inner.live_cells() }, 8);
+    assert!((0..CELL).all(|i| unsafe { *ptrs[3].add(i) } == 0x77));
+
+    // The lowest free cell is handed out first, then the next one
+    assert_eq!(allocator.alloc(layout), ptrs[3]);
+    assert_eq!(allocator.alloc(layout), ptrs[7]);
+    assert_eq!(allocator.alloc(layout) as usize, ptrs[9] as usize + CELL);
+    assert_eq!(heap_pages_grown(), PageCount(1));
+}
+
+#[test]
+fn test_reuse_across_pages() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(CELL, 8).unwrap();
+    let per_page = Bitmap::CELLS_PER_PAGE;
+    let ptrs: Vec<_> = (0..3 * per_page).map(|_| allocator.alloc(layout)).collect();
+    assert!(ptrs.iter().all(|p| !p.is_null()));
+    assert_eq!(heap_pages_grown(), PageCount(3));
+
+    // The cursor follows the last free; once its page is full again, the other pages
+    // are searched before growing
+    allocator.dealloc(ptrs[10], layout);
+    allocator.dealloc(ptrs[per_page + 3], layout);
+    assert_eq!(allocator.alloc(layout), ptrs[per_page + 3]);
+    assert_eq!(allocator.alloc(layout), ptrs[10]);
+    assert_eq!(heap_pages_grown(), PageCount(3));
+}
+
+#[test]
+fn test_full_cell_is_usable() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(1, 1).unwrap();
+    let (a, capacity) = unsafe { allocator.inner.alloc_with_capacity(layout) };
+    assert_eq!(capacity, CELL);
+    let b = allocator.alloc(layout);
+    unsafe { a.write_bytes(0xAA, capacity) };
+    unsafe { b.write_bytes(0xBB, capacity) };
+    assert!((0..CELL).all(|i| unsafe { *a.add(i) } == 0xAA));
+}
+
+#[cfg(not(feature = "panic-on-oom"))]
+#[test]
+fn test_rejects_oversized_and_overaligned() {
+    let allocator = SafeAllocator::new();
+    assert!(
+        allocator
+            .alloc(Layout::from_size_align(CELL + 1, 8).unwrap())
+            .is_null()
+    );
+    assert!(
+        allocator
+            .alloc(Layout::from_size_align(16, 64).unwrap())
+            .is_null()
+    );
+    assert!(
+        !allocator
+            .alloc(Layout::from_size_align(16, CELL).unwrap())
+            .is_null()
+    );
+}
+
+#[cfg(all(feature = "dealloc-check", debug_assertions))]
+#[test]
+#[should_panic(expected = "double free")]
+fn test_dealloc_check_double_free() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(CELL, 8).unwrap();
+    let ptr = allocator.alloc(layout);
+    allocator.dealloc(ptr, layout);
+    allocator.dealloc(ptr, layout);
+}
+
+// After a 1MB reservation, cells totalling less than that never grow the heap
+#[test]
+fn test_reserve_prevents_growth() {
+    let allocator = SafeAllocator::new();
+    assert!(unsafe { allocator.inner.reserve(1024 * 1024) });
+    let pages = heap_pages_grown();
+
+    let layout = Layout::from_size_align(CELL, 16).unwrap();
+    for _ in 0..512 * 1024 / CELL {
+        assert!(!allocator.alloc(layout).is_null());
+    }
+    assert_eq!(
+        heap_pages_grown(),
+        pages,
+        "reserved memory must cover the allocations"
+    );
+    assert!(!unsafe { allocator.inner.reserve(usize::MAX / 2) });
+}
@@ -0,0 +1,198 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::BuddyAllocator;
+use lite_alloc::{PAGE_SIZE, PageCount, heap_pages_grown, reset_heap};
+use std::alloc::{GlobalAlloc, Layout};
+
+struct SafeAllocator {
+    inner: BuddyAllocator,
+}
+
+impl SafeAllocator {
+    fn new() -> Self {
+        // State is per-instance and the mock heap is per-thread: no lock needed
+        reset_heap();
+        Self {
+            inner: BuddyAllocator::new(),
+        }
+    }
+
+    fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { self.inner.alloc(layout) }
+    }
+
+    fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.inner.dealloc(ptr, layout) }
+    }
+}
+
+impl Drop for SafeAllocator {
+    fn drop(&mut self) {
+        unsafe { self.inner.reset() };
+        reset_heap();
+    }
+}
+
+#[test]
+fn test_split_on_alloc() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(16, 16).unwrap();
+    let a = allocator.alloc(layout);
+    assert!(!a.is_null());
+    // The page was split in halves down to 16 bytes: the next block is a's buddy
+    let b = allocator.alloc(layout);
+    assert_eq!(b as usize, a as usize + 16);
+    // Then the buddy of their 32-byte parent, split again
+    let c = allocator.alloc(layout);
+    assert_eq!(c as usize, a as usize + 32);
+    assert_eq!(heap_pages_grown(), PageCount(1));
+}
+
+#[test]
+fn test_buddies_coalesce_on_free() {
+    let allocator = SafeAllocator::new();
+    let small = Layout::from_size_align(64, 16).unwrap();
+    let ptrs: Vec<_> = (0..4).map(|_| allocator.alloc(small)).collect();
+    for (i, ptr) in ptrs.iter().enumerate() {
+        assert_eq!(*ptr as usize, ptrs[0] as usize + i * 64);
+    }
+
+    for ptr in ptrs.iter().rev() {
+        allocator.dealloc(*ptr, small);
+    }
+
+    // All four merged back, up to the whole page
+    let page = Layout::from_size_align(PAGE_SIZE, 16).unwrap();
+    assert_eq!(allocator.alloc(page), ptrs[0]);
+    assert_eq!(heap_pages_grown(), PageCount(1));
+}
+
+#[test]
+fn test_used_buddy_blocks_merge() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(32, 16).unwrap();
+    let a = allocator.alloc(layout);
+    let b = allocator.alloc(layout);
+    allocator.dealloc(a, layout);
+
+    // a's buddy b is still in use: a 64-byte block must come from elsewhere
+    let big = Layout::from_size_align(64, 16).unwrap();
+    let c = allocator.alloc(big);
+    assert_ne!(c, a);
+
+    allocator.dealloc(b, layout);
+    // Now a and b merged into a 64-byte block
+    assert_eq!(allocator.alloc(big), a);
+}
+
+#[test]
+fn test_non_power_of_two_rounds_up() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(100, 8).unwrap();
+    let a = allocator.alloc(layout);
+    assert_eq!(unsafe { allocator.inner.usable_size(a, layout) }, 128);
+    unsafe { a.write_bytes(0xAA, 128) };
+
+    // The next 128-byte block is a's buddy
+    let b = allocator.alloc(Layout::from_size_align(128, 8).unwrap());
+    assert_eq!(b as usize, a as usize + 128);
+}
+
+#[test]
+fn test_alignment_up_to_block_size() {
+    let allocator = SafeAllocator::new();
+    // Offsets the heap so alignment is not accidental
+    let _first = allocator.alloc(Layout::from_size_align(16, 16).unwrap());
+
+    for align in [32, 256, 4096, PAGE_SIZE, 4 * PAGE_SIZE] {
+        let layout = Layout::from_size_align(align, align).unwrap();
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % align, 0, "align {align}");
+        unsafe { ptr.write_bytes(0xCD, align) };
+        allocator.dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn test_large_block_spans_pages() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(3 * PAGE_SIZE, 16).unwrap();
+    let ptr = allocator.alloc(layout);
+    assert!(!ptr.is_null());
+    assert_eq!(ptr as usize % (4 * PAGE_SIZE), 0);
+    unsafe { ptr.write_bytes(0xEE, 4 * PAGE_SIZE) };
+    allocator.dealloc(ptr, layout);
+
+    // The freed block serves the same request without growing again
+    let grown = heap_pages_grown();
+    assert_eq!(allocator.alloc(layout), ptr);
+    assert_eq!(heap_pages_grown(), grown);
+}
+
+#[cfg(feature = "realloc")]
+#[test]
+fn test_realloc_shrinks_in_place() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(256, 16).unwrap();
+    let a = allocator.alloc(layout);
+    unsafe { a.write_bytes(0x5A, 256) };
+
+    // Shrinking to 64 keeps the block and frees the upper 64 + 128 bytes
+    let shrunk = unsafe { allocator.inner.realloc(a, layout, 64) };
+    assert_eq!(shrunk, a);
+    let b = allocator.alloc(Layout::from_size_align(64, 16).unwrap());
+    assert_eq!(b as usize, a as usize + 64);
+
+    // Growing moves and keeps the contents
+    let small = Layout::from_size_align(64, 16).unwrap();
+    let grown = unsafe { allocator.inner.realloc(shrunk, small, 1024) };
+    assert!(!grown.is_null());
+    let data = unsafe { std::slice::from_raw_parts(grown, 64) };
+    assert!(data.iter().all(|&x| x == 0x5A));
+}
+
+#[test]
+fn test_alloc_with_capacity() {
+    let allocator = SafeAllocator::new();
+    let mut blocks = Vec::new();
+    for size in [1, 24, 100, 200, 3000] {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let (ptr, capacity) = unsafe { allocator.inner.alloc_with_capacity(layout) };
+        assert!(!ptr.is_null());
+        assert!(capacity >= size);
+        assert!(capacity.is_power_of_two());
+        // The whole capacity belongs to the block: filling it leaves the others intact
+        unsafe { ptr.write_bytes(blocks.len() as u8 + 1, capacity) };
+        blocks.push((ptr, capacity, layout));
+    }
+    for (i, &(ptr, capacity, layout)) in blocks.iter().enumerate() {
+        assert!((0..capacity).all(|j| unsafe { *ptr.add(j) } == i as u8 + 1));
+        allocator.dealloc(ptr, layout);
+    }
+}
+
+// After a 1MB reservation, allocations totalling less than that never grow the heap
+#[test]
+fn test_reserve_prevents_growth() {
+    let allocator = SafeAllocator::new();
+    assert!(unsafe { allocator.inner.reserve(1024 * 1024) });
+    let pages = heap_pages_grown();
+
+    // Powers of two, so the blocks are exactly the sizes asked for
+    let mut total = 0;
+    for size in [32, 128, 512, 4096, 32768].iter().cycle() {
+        if total + size > 512 * 1024 {
+            break;
+        }
+        let ptr = allocator.alloc(Layout::from_size_align(*size, 8).unwrap());
+        assert!(!ptr.is_null());
+        total += size;
+    }
+    assert_eq!(
+        heap_pages_grown(),
+        pages,
+        "reserved memory must cover the allocations"
+    );
+    assert!(!unsafe { allocator.inner.reserve(usize::MAX / 2) });
+}
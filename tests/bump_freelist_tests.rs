@@ -1,25 +1,20 @@
-use lite_alloc::reset_heap;
-use lite_alloc::single_threaded::BumpFreeListAllocator;
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{BumpFreeListAllocator, Scope};
+use lite_alloc::{DefaultMemory, MemorySource, PAGE_SIZE, PageCount, heap_pages_grown, reset_heap};
 use std::alloc::{GlobalAlloc, Layout};
-use std::sync::{Mutex, MutexGuard};
-
-static TEST_MUTEX: Mutex<()> = Mutex::new(());
+use std::cell::Cell;
 
 struct SafeAllocator {
     inner: BumpFreeListAllocator,
-    _guard: MutexGuard<'static, ()>,
 }
 
 impl SafeAllocator {
     fn new() -> Self {
-        let guard = TEST_MUTEX.lock().unwrap();
-        unsafe {
-            BumpFreeListAllocator::reset();
-            reset_heap();
-            Self {
-                inner: BumpFreeListAllocator::new(),
-                _guard: guard,
-            }
+        // State is per-instance and the mock heap is per-thread: no lock needed
+        reset_heap();
+        Self {
+            inner: BumpFreeListAllocator::new(),
         }
     }
 
@@ -39,10 +34,8 @@ impl SafeAllocator {
 
 impl Drop for SafeAllocator {
     fn drop(&mut self) {
-        unsafe {
-            BumpFreeListAllocator::reset();
-            reset_heap();
-        }
+        unsafe { self.inner.reset() };
+        reset_heap();
     }
 }
 
@@ -70,6 +63,7 @@ fn test_reuse_lifo() {
     assert_eq!(ptr1, ptr2);
 }
 
+#[cfg(not(feature = "coalesce"))]
 #[test]
 fn test_no_coalescing() {
     let allocator = SafeAllocator::new();
@@ -92,9 +86,41 @@ fn test_no_coalescing() {
     assert_ne!(ptr3, ptr1);
     assert_ne!(ptr3, ptr2);
 
-    // However, if we alloc 32B, we get ptr2 (LIFO head)
+    // However, if we alloc 32B, we get ptr2 (LIFO head),
+    // or ptr1 (lowest address) with `address-ordered`
     let ptr4 = allocator.alloc(layout);
+    #[cfg(not(feature = "address-ordered"))]
     assert_eq!(ptr4, ptr2);
+    #[cfg(feature = "address-ordered")]
+    assert_eq!(ptr4, ptr1);
+}
+
+#[cfg(feature = "address-ordered")]
+#[test]
+fn test_address_ordered_reuse() {
+    let allocator = SafeAllocator::new();
+    let small = Layout::from_size_align(32, 8).unwrap();
+    let large = Layout::from_size_align(64, 8).unwrap();
+
+    let a = allocator.alloc(small);
+    let b = allocator.alloc(large);
+    let c = allocator.alloc(large);
+    // Keep an obstacle so `coalesce` does not roll the heap top back over `c`
+    // 保留一个障碍块，避免 `coalesce` 将堆顶回退到 `c` 之下
+    let _obstacle = allocator.alloc(small);
+    assert!(a < b && b < c);
+
+    // Free out of order: the list is still sorted by address
+    // 乱序释放：链表仍按地址排序
+    allocator.dealloc(c, large);
+    allocator.dealloc(a, small);
+    allocator.dealloc(b, large);
+
+    // `a` is too small for 64 bytes; `b` is the lowest suitable block
+    // `a` 放不下 64 字节；`b` 是地址最低的合适块
+    assert_eq!(allocator.alloc(large), b);
+    assert_eq!(allocator.alloc(small), a);
+    assert_eq!(allocator.alloc(small), c);
 }
 
 #[test]
@@ -112,6 +138,21 @@ fn test_alignment_padding() {
     assert_eq!(ptr2 as usize - ptr1 as usize, 16);
 }
 
+// With `canary` the slack belongs to the canary, see `test_canary_intact_when_writing_exact_size`
+#[cfg(not(feature = "canary"))]
+#[test]
+fn test_usable_size_rounding() {
+    let allocator = SafeAllocator::new();
+    for (size, expected) in [(1, 16), (16, 16), (17, 32), (100, 112)] {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let ptr = allocator.alloc(layout);
+        let usable = unsafe { allocator.inner.usable_size(ptr, layout) };
+        assert_eq!(usable, expected);
+        unsafe { ptr.write_bytes(0xEE, usable) };
+        allocator.dealloc(ptr, layout);
+    }
+}
+
 #[test]
 fn test_grow_memory() {
     let allocator = SafeAllocator::new();
@@ -121,6 +162,81 @@ fn test_grow_memory() {
     unsafe { ptr.write_bytes(1, 100 * 1024) };
 }
 
+#[cfg(feature = "coalesce")]
+#[test]
+fn test_coalesce_top_rolls_back() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(32, 8).unwrap();
+
+    let ptr1 = allocator.alloc(layout);
+    let ptr2 = allocator.alloc(layout);
+
+    // ptr2 is adjacent to the heap top: freeing it returns the space to the bump pointer
+    allocator.dealloc(ptr2, layout);
+
+    // A larger request can now be bumped from ptr2's old address
+    let layout_large = Layout::from_size_align(64, 8).unwrap();
+    let ptr3 = allocator.alloc(layout_large);
+    assert_eq!(ptr3, ptr2);
+
+    allocator.dealloc(ptr3, layout_large);
+    allocator.dealloc(ptr1, layout);
+}
+
+#[cfg(feature = "coalesce")]
+#[test]
+fn test_coalesce_non_adjacent_uses_free_list() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(32, 8).unwrap();
+
+    let ptr1 = allocator.alloc(layout);
+    let ptr2 = allocator.alloc(layout);
+
+    // ptr1 is not at the heap top, so it goes to the free list
+    allocator.dealloc(ptr1, layout);
+
+    // A larger request cannot use ptr1 and must bump past ptr2
+    let layout_large = Layout::from_size_align(64, 8).unwrap();
+    let ptr3 = allocator.alloc(layout_large);
+    assert!(ptr3 > ptr2);
+
+    // The free list still hands out ptr1
+    let ptr4 = allocator.alloc(layout);
+    assert_eq!(ptr4, ptr1);
+}
+
+#[cfg(feature = "coalesce")]
+#[test]
+fn test_coalesce_mixed_interleaving() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(32, 8).unwrap();
+
+    let a = allocator.alloc(layout);
+    let b = allocator.alloc(layout);
+    let c = allocator.alloc(layout);
+
+    // b goes to the free list, c rolls the top back to c
+    allocator.dealloc(b, layout);
+    allocator.dealloc(c, layout);
+
+    // Reuse b from the free list first, then bump from c's address
+    assert_eq!(allocator.alloc(layout), b);
+    let d = allocator.alloc(layout);
+    assert_eq!(d, c);
+
+    // Free in LIFO order: d rolls the top back to c, then b (now adjacent) rolls it back to b
+    allocator.dealloc(d, layout);
+    allocator.dealloc(b, layout);
+    let e = allocator.alloc(layout);
+    assert_eq!(e, b);
+    let f = allocator.alloc(layout);
+    assert_eq!(f, c);
+
+    allocator.dealloc(f, layout);
+    allocator.dealloc(e, layout);
+    allocator.dealloc(a, layout);
+}
+
 #[cfg(feature = "realloc")]
 #[test]
 fn test_realloc_extend() {
@@ -132,3 +248,744 @@ fn test_realloc_extend() {
     let new_ptr = allocator.realloc(ptr, layout, 64);
     assert_eq!(ptr, new_ptr);
 }
+
+// The tail given up by a shrink is served to the next request, whether the block sits
+// at the heap top or below a live neighbour (the canary would shift the block sizes)
+#[cfg(all(feature = "realloc", not(feature = "canary")))]
+#[test]
+fn test_realloc_shrink_reuses_tail() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(128, 16).unwrap();
+    let tail = Layout::from_size_align(96, 16).unwrap();
+
+    // Top of heap
+    let top = allocator.alloc(layout);
+    unsafe { top.write_bytes(0x5A, 128) };
+    assert_eq!(allocator.realloc(top, layout, 32), top);
+    let reused = allocator.alloc(tail);
+    assert_eq!(reused as usize, top as usize + 32);
+    assert_eq!(unsafe { *top.add(31) }, 0x5A);
+
+    // Below a live block
+    let below = allocator.alloc(layout);
+    let _neighbour = allocator.alloc(Layout::from_size_align(16, 16).unwrap());
+    assert_eq!(allocator.realloc(below, layout, 32), below);
+    let reused = allocator.alloc(tail);
+    assert_eq!(reused as usize, below as usize + 32);
+}
+
+#[cfg(feature = "realloc")]
+#[test]
+fn test_realloc_keeps_high_alignment() {
+    for align in [32, 64, 256, 4096] {
+        let allocator = SafeAllocator::new();
+        // Offsets the heap top so alignment is not accidental
+        let _first = allocator.alloc(Layout::from_size_align(8, 8).unwrap());
+        let layout = Layout::from_size_align(100, align).unwrap();
+        let ptr = allocator.alloc(layout);
+        assert_eq!(ptr as usize % align, 0);
+        unsafe { ptr.write_bytes(0x6B, layout.size()) };
+
+        // At the heap top: grows in place
+        let grown = allocator.realloc(ptr, layout, 300);
+        assert_eq!(grown, ptr);
+        let layout = Layout::from_size_align(300, align).unwrap();
+
+        // Off the heap top: moves, and the new block is aligned too
+        let _obstacle = allocator.alloc(Layout::from_size_align(8, 8).unwrap());
+        let moved = allocator.realloc(grown, layout, 5000);
+        assert!(!moved.is_null());
+        assert_eq!(moved as usize % align, 0, "align {align}");
+        let data = unsafe { std::slice::from_raw_parts(moved, 100) };
+        assert!(data.iter().all(|&x| x == 0x6B));
+    }
+}
+
+// Freeing the higher neighbour lets the block below grow into it without moving
+#[cfg(feature = "realloc")]
+#[test]
+fn test_realloc_grows_into_freed_neighbour() {
+    let allocator = SafeAllocator::new();
+    let small = Layout::from_size_align(64, 16).unwrap();
+    let big = Layout::from_size_align(256, 16).unwrap();
+    let ptr = allocator.alloc(small);
+    let neighbour = allocator.alloc(big);
+    // Keep the neighbour off the heap top so it goes to the free list
+    let obstacle = allocator.alloc(small);
+    unsafe { ptr.write_bytes(0x3C, 64) };
+
+    allocator.dealloc(neighbour, big);
+    let grown = allocator.realloc(ptr, small, 128);
+    assert_eq!(grown, ptr, "must grow in place");
+    for i in 0..64 {
+        assert_eq!(unsafe { *grown.add(i) }, 0x3C);
+    }
+    unsafe { grown.write_bytes(0x3D, 128) };
+
+    // The rest of the neighbour is still free and is reused before the heap top
+    let reused = allocator.alloc(small);
+    assert!(reused as usize >= grown as usize + 128);
+    assert!((reused as usize) < obstacle as usize);
+    for i in 0..128 {
+        assert_eq!(unsafe { *grown.add(i) }, 0x3D);
+    }
+
+    allocator.dealloc(reused, small);
+    allocator.dealloc(grown, Layout::from_size_align(128, 16).unwrap());
+    allocator.dealloc(obstacle, small);
+}
+
+const REGION_PAGES: usize = 2;
+
+#[repr(C, align(65536))]
+struct Region([u8; REGION_PAGES * PAGE_SIZE]);
+
+// Memory source handing out pages from a fixed, page-aligned array
+// 从固定的页对齐数组中分配页面的内存来源
+struct ArraySource {
+    base: usize,
+    used_pages: Cell<usize>,
+}
+
+impl ArraySource {
+    fn new(region: &mut Region) -> Self {
+        Self {
+            base: region.0.as_mut_ptr() as usize,
+            used_pages: Cell::new(0),
+        }
+    }
+}
+
+impl MemorySource for ArraySource {
+    unsafe fn grow(&self, pages: usize) -> usize {
+        let used = self.used_pages.get();
+        if used + pages > REGION_PAGES {
+            return usize::MAX;
+        }
+        self.used_pages.set(used + pages);
+        self.base / PAGE_SIZE + used
+    }
+}
+
+#[test]
+fn test_independent_instances() {
+    let mut region_a = Box::new(Region([0; REGION_PAGES * PAGE_SIZE]));
+    let mut region_b = Box::new(Region([0; REGION_PAGES * PAGE_SIZE]));
+    let range = |region: &Region| {
+        let start = region.0.as_ptr() as usize;
+        start..start + REGION_PAGES * PAGE_SIZE
+    };
+    let (range_a, range_b) = (range(&region_a), range(&region_b));
+    let a = BumpFreeListAllocator::with_source(ArraySource::new(&mut region_a));
+    let b = BumpFreeListAllocator::with_source(ArraySource::new(&mut region_b));
+    let layout = Layout::from_size_align(64, 16).unwrap();
+
+    unsafe {
+        let pa = a.alloc(layout);
+        let pb = b.alloc(layout);
+        // Block in the middle so `coalesce` cannot roll the heap top back
+        let _pa2 = a.alloc(layout);
+
+        // Free a block into `a`'s free list
+        a.dealloc(pa, layout);
+
+        // `b` never hands out `a`'s freed block, and stays inside its own region
+        for _ in 0..8 {
+            let p = b.alloc(layout);
+            assert!(!p.is_null());
+            assert_ne!(p, pa);
+            assert!(range_b.contains(&(p as usize)));
+        }
+        assert!(range_a.contains(&(pa as usize)));
+        assert!(range_b.contains(&(pb as usize)));
+
+        // `a` still reuses it
+        assert_eq!(a.alloc(layout), pa);
+    }
+}
+
+#[test]
+fn test_scope_reclaims_on_drop() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let before = allocator.alloc(layout);
+
+    let first = unsafe {
+        let scope = Scope::new(&allocator.inner);
+        let a = scope.alloc(layout);
+        let b = scope.alloc(layout);
+        assert!(!a.is_null() && !b.is_null());
+        a.write_bytes(0x11, layout.size());
+        b.write_bytes(0x22, layout.size());
+        a
+    };
+
+    // The scope's memory is handed out again
+    assert_eq!(allocator.alloc(layout), first);
+    assert_ne!(before, first);
+}
+
+#[test]
+fn test_scope_nested() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+
+    unsafe {
+        let outer = Scope::new(&allocator.inner);
+        let x = outer.alloc(layout);
+        let y = {
+            let inner = Scope::new(&allocator.inner);
+            let y = inner.alloc(layout);
+            let _z = inner.alloc(layout);
+            y
+        };
+        // Inner scope released y and z, x is still live
+        assert_eq!(outer.alloc(layout), y);
+        drop(outer);
+
+        // Outer scope released everything
+        assert_eq!(allocator.alloc(layout), x);
+    }
+}
+
+#[test]
+fn test_scope_drops_blocks_freed_inside() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+
+    let freed = unsafe {
+        let scope = Scope::new(&allocator.inner);
+        let a = scope.alloc(layout);
+        let _b = scope.alloc(layout);
+        scope.dealloc(a, layout);
+        a
+    };
+
+    // `a` must not be both in a free list and below the bump pointer
+    let p1 = allocator.alloc(layout);
+    let p2 = allocator.alloc(layout);
+    assert_eq!(p1, freed);
+    assert_ne!(p1, p2);
+}
+
+#[cfg(feature = "poison")]
+#[test]
+fn test_poison_fills_freed_block() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let a = allocator.alloc(layout);
+    let b = allocator.alloc(layout);
+    unsafe { a.write_bytes(0xAA, 64) };
+    allocator.dealloc(a, layout);
+    for i in 16..64 {
+        assert_eq!(unsafe { *a.add(i) }, 0xDE);
+    }
+    assert_eq!(allocator.alloc(layout), a);
+    allocator.dealloc(a, layout);
+    allocator.dealloc(b, layout);
+}
+
+#[cfg(all(feature = "poison", debug_assertions))]
+#[test]
+#[should_panic(expected = "use after free")]
+fn test_poison_detects_write_after_free() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let a = allocator.alloc(layout);
+    // Keep `a` off the bump top so it goes to the free list
+    let _b = allocator.alloc(layout);
+    allocator.dealloc(a, layout);
+    unsafe { *a.add(40) = 0x11 };
+    allocator.alloc(layout);
+}
+
+#[cfg(feature = "canary")]
+#[test]
+fn test_canary_intact_when_writing_exact_size() {
+    let allocator = SafeAllocator::new();
+    for size in [1, 15, 16, 24, 100] {
+        let layout = Layout::from_size_align(size, 1).unwrap();
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % 16, 0);
+        unsafe { ptr.write_bytes(0xAA, size) };
+        allocator.dealloc(ptr, layout);
+    }
+    // Freed blocks are still reused with the canary folded into the block size
+    let layout = Layout::from_size_align(24, 8).unwrap();
+    let a = allocator.alloc(layout);
+    let _b = allocator.alloc(layout);
+    allocator.dealloc(a, layout);
+    assert_eq!(allocator.alloc(layout), a);
+}
+
+#[cfg(all(feature = "canary", debug_assertions))]
+#[test]
+#[should_panic(expected = "canary overwritten")]
+fn test_canary_detects_one_past_the_end_write() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    let ptr = allocator.alloc(layout);
+    unsafe { ptr.write_bytes(0xAA, 33) };
+    allocator.dealloc(ptr, layout);
+}
+
+#[cfg(not(feature = "panic-on-oom"))]
+#[test]
+fn test_try_alloc_result() {
+    let allocator = SafeAllocator::new();
+    lite_alloc::set_mock_memory_size(1024 * 1024);
+
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let ptr = unsafe { allocator.inner.try_alloc(layout) }.expect("small request fits");
+    assert_eq!(ptr.as_ptr() as usize % 16, 0);
+    unsafe { ptr.as_ptr().write_bytes(0x5A, 64) };
+    allocator.dealloc(ptr.as_ptr(), layout);
+
+    // 2MB cannot fit in a 1MB mock heap
+    let too_big = Layout::from_size_align(2 * 1024 * 1024, 16).unwrap();
+    assert_eq!(
+        unsafe { allocator.inner.try_alloc(too_big) },
+        Err(lite_alloc::AllocError)
+    );
+
+    unsafe { allocator.inner.reset() };
+    lite_alloc::set_mock_memory_size(lite_alloc::MOCK_MEMORY_SIZE);
+}
+
+#[test]
+fn test_scope_try_alloc() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let scope = unsafe { Scope::new(&allocator.inner) };
+    let ptr = unsafe { scope.try_alloc(layout) }.unwrap();
+    unsafe { ptr.as_ptr().write_bytes(0x11, 64) };
+}
+
+fn check_min_align<const MIN_ALIGN: usize>() {
+    let allocator =
+        BumpFreeListAllocator::<DefaultMemory, MIN_ALIGN>::with_min_align(DefaultMemory);
+    let mut ptrs = Vec::new();
+    for size in 1..=80 {
+        let layout = Layout::from_size_align(size, 1).unwrap();
+        let ptr = unsafe { allocator.alloc(layout) };
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % MIN_ALIGN, 0, "size {size}");
+        unsafe { ptr.write_bytes(0xAB, size) };
+        ptrs.push((ptr, layout));
+    }
+    // Consecutive blocks are spaced by the size rounded up to MIN_ALIGN
+    // 相邻块的间距为大小向上取整到 MIN_ALIGN
+    #[cfg(not(feature = "canary"))]
+    for pair in ptrs.windows(2) {
+        let block = (pair[0].1.size().max(16) + MIN_ALIGN - 1) & !(MIN_ALIGN - 1);
+        assert_eq!(pair[1].0 as usize - pair[0].0 as usize, block);
+    }
+    for (ptr, layout) in ptrs {
+        unsafe { allocator.dealloc(ptr, layout) };
+    }
+
+    // Freed blocks are reused
+    // 释放的块会被复用
+    let layout = Layout::from_size_align(48, 8).unwrap();
+    let p1 = unsafe { allocator.alloc(layout) };
+    unsafe { allocator.dealloc(p1, layout) };
+    assert_eq!(unsafe { allocator.alloc(layout) }, p1);
+    unsafe { allocator.reset() };
+}
+
+#[test]
+fn test_custom_min_align() {
+    reset_heap();
+    check_min_align::<8>();
+    reset_heap();
+    check_min_align::<32>();
+    reset_heap();
+    // Both ends of the accepted range: a word, and the two-word node
+    // 可接受范围的两端：一个字，以及两个字的节点
+    check_min_align::<{ core::mem::align_of::<usize>() }>();
+    reset_heap();
+    check_min_align::<{ 2 * core::mem::size_of::<usize>() }>();
+    reset_heap();
+}
+
+#[test]
+#[should_panic(expected = "MIN_ALIGN must be a power of two >= align_of::<usize>()")]
+fn test_min_align_below_word_rejected() {
+    BumpFreeListAllocator::<DefaultMemory, 2>::with_min_align(DefaultMemory);
+}
+
+#[cfg(all(feature = "quota", not(feature = "panic-on-oom")))]
+#[test]
+fn test_quota_limits_live_bytes() {
+    let allocator = SafeAllocator::new();
+    allocator.inner.set_quota(Some(64 * 1024));
+    let layout = Layout::from_size_align(1024, 16).unwrap();
+
+    let ptrs: Vec<_> = (0..64).map(|_| allocator.alloc(layout)).collect();
+    assert!(ptrs.iter().all(|p| !p.is_null()));
+    assert_eq!(allocator.inner.quota_used(), 64 * 1024);
+
+    // The 65th allocation exceeds the quota and must not grow memory
+    // 第 65 次分配超出配额，且不得增长内存
+    let pages = lite_alloc::heap_pages_grown();
+    assert!(allocator.alloc(layout).is_null());
+    assert!(
+        allocator
+            .alloc(Layout::from_size_align(PAGE_SIZE, 16).unwrap())
+            .is_null()
+    );
+    assert_eq!(lite_alloc::heap_pages_grown(), pages);
+
+    // Freeing restores headroom
+    // 释放后恢复可用额度
+    allocator.dealloc(ptrs[10], layout);
+    assert_eq!(allocator.inner.quota_used(), 63 * 1024);
+    assert!(!allocator.alloc(layout).is_null());
+    assert!(allocator.alloc(layout).is_null());
+
+    allocator.inner.set_quota(None);
+    assert!(!allocator.alloc(layout).is_null());
+}
+
+#[cfg(all(feature = "quota", feature = "realloc", not(feature = "panic-on-oom")))]
+#[test]
+fn test_quota_limits_realloc_growth() {
+    let allocator = SafeAllocator::new();
+    allocator.inner.set_quota(Some(4096));
+    let layout = Layout::from_size_align(1024, 16).unwrap();
+    let ptr = allocator.alloc(layout);
+
+    // Growing at the heap top stays within the quota
+    // 在堆顶原地扩容，仍在配额之内
+    let ptr = allocator.realloc(ptr, layout, 4096);
+    assert!(!ptr.is_null());
+    assert_eq!(allocator.inner.quota_used(), 4096);
+
+    let layout = Layout::from_size_align(4096, 16).unwrap();
+    assert!(allocator.realloc(ptr, layout, 4097).is_null());
+    let ptr = allocator.realloc(ptr, layout, 1024);
+    assert_eq!(allocator.inner.quota_used(), 1024);
+    allocator.dealloc(ptr, Layout::from_size_align(1024, 16).unwrap());
+    assert_eq!(allocator.inner.quota_used(), 0);
+}
+
+#[cfg(all(feature = "dealloc-check", debug_assertions))]
+#[test]
+#[should_panic(expected = "misaligned pointer")]
+fn test_dealloc_check_misaligned() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let ptr = allocator.alloc(layout);
+    allocator.dealloc(unsafe { ptr.add(1) }, layout);
+}
+
+#[cfg(all(feature = "dealloc-check", debug_assertions))]
+#[test]
+#[should_panic(expected = "outside the heap")]
+fn test_dealloc_check_out_of_range() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let ptr = allocator.alloc(layout);
+    // Above the heap top, though still inside the grown page
+    // 位于堆顶之上，但仍在已增长的页面内
+    allocator.dealloc(unsafe { ptr.add(4096) }, layout);
+}
+
+#[test]
+fn test_zero_sized_alloc() {
+    let allocator = SafeAllocator::new();
+    for align in [1, 8, 64, 4096] {
+        let layout = Layout::from_size_align(0, align).unwrap();
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % align, 0);
+        assert_eq!(unsafe { allocator.inner.usable_size(ptr, layout) }, 0);
+        allocator.dealloc(ptr, layout);
+    }
+    assert_eq!(heap_pages_grown(), PageCount(0));
+}
+
+#[cfg(feature = "realloc")]
+#[test]
+fn test_realloc_from_zero_size() {
+    let allocator = SafeAllocator::new();
+    let zst = Layout::from_size_align(0, 8).unwrap();
+    let ptr = allocator.alloc(zst);
+    let grown = allocator.realloc(ptr, zst, 40);
+    assert_ne!(grown, ptr);
+    unsafe { grown.write_bytes(0x11, 40) };
+    allocator.dealloc(grown, Layout::from_size_align(40, 8).unwrap());
+}
+
+#[test]
+fn test_alloc_array() {
+    let allocator = SafeAllocator::new();
+    let array = unsafe { allocator.inner.alloc_array::<u64>(1000) };
+    assert!(!array.is_null());
+    assert!(array.is_aligned());
+    let values = unsafe { std::slice::from_raw_parts_mut(array, 1000) };
+    for (i, v) in values.iter_mut().enumerate() {
+        *v = i as u64;
+    }
+    assert_eq!(values.iter().sum::<u64>(), 999 * 1000 / 2);
+    unsafe { allocator.inner.dealloc_array(array, 1000) };
+
+    // The byte size overflows: null without touching the heap
+    let grown = heap_pages_grown();
+    assert!(unsafe { allocator.inner.alloc_array::<u64>(usize::MAX / 4) }.is_null());
+    assert_eq!(heap_pages_grown(), grown);
+}
+
+#[test]
+fn test_alloc_with_capacity() {
+    let allocator = SafeAllocator::new();
+    let mut blocks = Vec::new();
+    for size in [1, 24, 100, 200, 3000] {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let (ptr, capacity) = unsafe { allocator.inner.alloc_with_capacity(layout) };
+        assert!(!ptr.is_null());
+        assert!(capacity >= size);
+        // The whole capacity belongs to the block: filling it leaves the others intact
+        unsafe { ptr.write_bytes(blocks.len() as u8 + 1, capacity) };
+        blocks.push((ptr, capacity, layout));
+    }
+    for (i, &(ptr, capacity, layout)) in blocks.iter().enumerate() {
+        assert!((0..capacity).all(|j| unsafe { *ptr.add(j) } == i as u8 + 1));
+        allocator.dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn test_heap_extent_covers_allocations() {
+    let allocator = SafeAllocator::new();
+    assert_eq!(unsafe { allocator.inner.heap_extent() }, (0, 0));
+
+    let layouts = [
+        Layout::from_size_align(8, 8).unwrap(),
+        Layout::from_size_align(100, 16).unwrap(),
+        Layout::from_size_align(3 * PAGE_SIZE, 16).unwrap(),
+        Layout::from_size_align(64, 4096).unwrap(),
+        Layout::from_size_align(24, 8).unwrap(),
+    ];
+    let ptrs: Vec<_> = layouts.iter().map(|&l| allocator.alloc(l)).collect();
+
+    let (base, end) = unsafe { allocator.inner.heap_extent() };
+    assert!(base < end);
+    assert_eq!(base % PAGE_SIZE, 0);
+    assert_eq!(end % PAGE_SIZE, 0);
+    for (&ptr, layout) in ptrs.iter().zip(&layouts) {
+        assert!(!ptr.is_null());
+        assert!(base <= ptr as usize && ptr as usize + layout.size() <= end);
+    }
+    for (&ptr, &layout) in ptrs.iter().zip(&layouts) {
+        allocator.dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn test_block_size_matches_consumption() {
+    let allocator = SafeAllocator::new();
+    assert_eq!(
+        allocator
+            .inner
+            .block_size(Layout::from_size_align(0, 8).unwrap()),
+        0
+    );
+
+    // Back-to-back blocks of one layout sit exactly `block_size` apart
+    // 同一布局的相邻块之间恰好相距 `block_size`
+    for size in [1, 24, 100, 300, 1000] {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let block = allocator.inner.block_size(layout);
+        assert!(block >= size);
+
+        let first = allocator.alloc(layout);
+        let second = allocator.alloc(layout);
+        assert_eq!(second as usize - first as usize, block, "size {size}");
+
+        allocator.dealloc(second, layout);
+        allocator.dealloc(first, layout);
+    }
+}
+
+#[test]
+fn test_debug_reports_heap_state() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let first = allocator.alloc(layout);
+    let second = allocator.alloc(layout);
+    allocator.dealloc(first, layout);
+
+    let top = second as usize + allocator.inner.block_size(layout);
+    let (_, end) = unsafe { allocator.inner.heap_extent() };
+    assert_eq!(
+        format!("{:?}", allocator.inner),
+        format!("BumpFreeListAllocator {{ heap_top: {top}, heap_end: {end}, free_blocks: 1 }}")
+    );
+    allocator.dealloc(second, layout);
+}
+
+// The grown tail is zero even when the memory it lands on was used before
+#[cfg(feature = "realloc")]
+#[test]
+fn test_realloc_zeroed() {
+    let allocator = SafeAllocator::new();
+    let small = Layout::from_size_align(64, 16).unwrap();
+    let a = allocator.alloc(small);
+    unsafe { a.write_bytes(0xAA, 64) };
+    let dirty = Layout::from_size_align(256, 16).unwrap();
+    let b = allocator.alloc(dirty);
+    unsafe { b.write_bytes(0xBB, 256) };
+    allocator.dealloc(b, dirty);
+
+    let mut ptr = a;
+    let mut old = small;
+    for new_size in [256, 4096] {
+        ptr = unsafe { allocator.inner.realloc_zeroed(ptr, old, new_size) };
+        assert!(!ptr.is_null());
+        let data = unsafe { std::slice::from_raw_parts(ptr, new_size) };
+        assert!(data[..64].iter().all(|&x| x == 0xAA));
+        assert!(data[64..].iter().all(|&x| x == 0), "size {new_size}");
+        old = Layout::from_size_align(new_size, 16).unwrap();
+    }
+}
+
+// After a 1MB reservation, allocations totalling less than that never grow the heap
+#[test]
+fn test_reserve_prevents_growth() {
+    let allocator = SafeAllocator::new();
+    assert!(unsafe { allocator.inner.reserve(1024 * 1024) });
+    let pages = heap_pages_grown();
+
+    let mut total = 0;
+    for size in [24, 100, 512, 4096, 30000].iter().cycle() {
+        if total + size > 512 * 1024 {
+            break;
+        }
+        let ptr = allocator.alloc(Layout::from_size_align(*size, 8).unwrap());
+        assert!(!ptr.is_null());
+        total += size;
+    }
+    assert_eq!(
+        heap_pages_grown(),
+        pages,
+        "reserved memory must cover the allocations"
+    );
+    assert!(!unsafe { allocator.inner.reserve(usize::MAX / 2) });
+}
+
+#[test]
+fn test_grow_in_place_at_heap_top() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let ptr = allocator.alloc(layout);
+    unsafe { ptr.write_bytes(0x6B, 64) };
+
+    // At the heap top: grows, across a page boundary too
+    assert!(unsafe { allocator.inner.grow_in_place(ptr, layout, 256) });
+    let grown = Layout::from_size_align(2 * PAGE_SIZE, 16).unwrap();
+    let mid = Layout::from_size_align(256, 16).unwrap();
+    assert!(unsafe { allocator.inner.grow_in_place(ptr, mid, grown.size()) });
+    assert!((0..64).all(|i| unsafe { *ptr.add(i) } == 0x6B));
+    unsafe { ptr.write_bytes(0x6C, grown.size()) };
+
+    // Not at the top any more: the caller has to move the data
+    let other = allocator.alloc(layout);
+    assert!(!other.is_null());
+    let bigger = grown.size() + 64;
+    assert!(!unsafe { allocator.inner.grow_in_place(ptr, grown, bigger) });
+
+    assert!(unsafe { allocator.inner.shrink_in_place(ptr, grown, 128) });
+    assert!(!unsafe { allocator.inner.shrink_in_place(ptr, mid, 0) });
+    allocator.dealloc(ptr, Layout::from_size_align(128, 16).unwrap());
+    allocator.dealloc(other, layout);
+}
+
+#[test]
+fn test_pages_needed_for_reports_growth() {
+    let allocator = SafeAllocator::new();
+    // No bump region yet: every byte needs new pages
+    assert_eq!(
+        unsafe { allocator.inner.pages_needed_for(100) },
+        PageCount(1)
+    );
+    // Asking grows nothing
+    assert_eq!(heap_pages_grown(), PageCount(0));
+
+    let ptr = allocator.alloc(Layout::from_size_align(100, 16).unwrap());
+    assert!(!ptr.is_null());
+    let pages = heap_pages_grown();
+    assert_eq!(pages, PageCount(1));
+
+    // The rest of the first page is still free
+    assert_eq!(
+        unsafe { allocator.inner.pages_needed_for(PAGE_SIZE / 2) },
+        PageCount(0)
+    );
+    assert_eq!(
+        unsafe { allocator.inner.pages_needed_for(PAGE_SIZE) },
+        PageCount(1)
+    );
+
+    // Past it: `reserve` grows exactly the reported pages
+    let needed = unsafe { allocator.inner.pages_needed_for(2 * PAGE_SIZE) };
+    assert_eq!(needed, PageCount(2));
+    assert!(unsafe { allocator.inner.reserve(2 * PAGE_SIZE) });
+    assert_eq!(
+        heap_pages_grown().as_usize() - pages.as_usize(),
+        needed.as_usize()
+    );
+    assert_eq!(
+        unsafe { allocator.inner.pages_needed_for(2 * PAGE_SIZE) },
+        PageCount(0)
+    );
+}
+
+#[test]
+fn test_free_list_reuse_respects_high_alignment() {
+    let allocator = SafeAllocator::new();
+    let small = Layout::from_size_align(16, 16).unwrap();
+    let block = Layout::from_size_align(128, 16).unwrap();
+    let _pad = allocator.alloc(small);
+    let freed = allocator.alloc(block);
+    let _guard = allocator.alloc(small);
+    assert_ne!(
+        freed as usize % 64,
+        0,
+        "the freed block must be misaligned for 64"
+    );
+    allocator.dealloc(freed, block);
+
+    // Big enough, but not on a 64-byte boundary: it must not be handed out
+    let aligned = Layout::from_size_align(128, 64).unwrap();
+    let ptr = allocator.alloc(aligned);
+    assert_eq!(ptr as usize % 64, 0);
+    assert_eq!(allocator.alloc(block), freed);
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn test_peak_bytes_survives_free() {
+    let allocator = SafeAllocator::new();
+    assert_eq!(allocator.inner.peak_bytes(), 0);
+    let small = Layout::from_size_align(100, 16).unwrap();
+    let _a = allocator.alloc(small);
+    let low = allocator.inner.peak_bytes();
+    assert!((100..PAGE_SIZE).contains(&low), "{low}");
+
+    // A large block raises the mark to about its size, within the grown pages
+    let large = Layout::from_size_align(3 * PAGE_SIZE, 16).unwrap();
+    let ptr = allocator.alloc(large);
+    assert!(!ptr.is_null());
+    let peak = allocator.inner.peak_bytes();
+    assert!(peak >= low + large.size(), "{peak}");
+    assert!(peak <= heap_pages_grown().as_usize() * PAGE_SIZE);
+
+    // Freeing it keeps the mark
+    allocator.dealloc(ptr, large);
+    assert_eq!(allocator.inner.peak_bytes(), peak);
+
+    unsafe { allocator.inner.reset() };
+    assert_eq!(allocator.inner.peak_bytes(), 0);
+}
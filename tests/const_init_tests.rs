@@ -0,0 +1,83 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{
+    BitmapAllocator, BuddyAllocator, BumpFreeListAllocator, FreeListAllocator, GlobalWrapper,
+    SegregatedBumpAllocator, SlabAllocator, TlsfAllocator,
+};
+use lite_alloc::{ResettableAllocator, reset_heap};
+use std::alloc::{GlobalAlloc, Layout};
+
+// Every constructor is usable in a `static`, as a `#[global_allocator]` needs; the bare
+// types are only `Sync` without `strict-single-thread`
+// 每个构造函数都可用于 `static`，正如 `#[global_allocator]` 所需；
+// 裸类型仅在未开启 `strict-single-thread` 时是 `Sync` 的
+#[cfg(not(feature = "strict-single-thread"))]
+mod bare {
+    use super::*;
+
+    pub static FREE_LIST: FreeListAllocator = FreeListAllocator::new();
+    pub static BUMP: BumpFreeListAllocator = BumpFreeListAllocator::new();
+    pub static SEGREGATED: SegregatedBumpAllocator = SegregatedBumpAllocator::new();
+    pub static SLAB: SlabAllocator<64> = SlabAllocator::new();
+    pub static BITMAP: BitmapAllocator<32> = BitmapAllocator::new();
+    pub static TLSF: TlsfAllocator = TlsfAllocator::new();
+    pub static BUDDY: BuddyAllocator = BuddyAllocator::new();
+}
+
+static WRAPPED: GlobalWrapper<FreeListAllocator> = GlobalWrapper(FreeListAllocator::new());
+
+fn round_trip(allocator: &dyn GlobalAlloc) {
+    let layout = Layout::from_size_align(24, 8).unwrap();
+    let ptr = unsafe { allocator.alloc(layout) };
+    assert!(!ptr.is_null());
+    unsafe { ptr.write_bytes(0x3C, layout.size()) };
+    unsafe { allocator.dealloc(ptr, layout) };
+}
+
+#[cfg(not(feature = "strict-single-thread"))]
+#[test]
+fn test_statics_allocate() {
+    reset_heap();
+    let all: [&dyn GlobalAlloc; 7] = [
+        &bare::FREE_LIST,
+        &bare::BUMP,
+        &bare::SEGREGATED,
+        &bare::SLAB,
+        &bare::BITMAP,
+        &bare::TLSF,
+        &bare::BUDDY,
+    ];
+    for allocator in all {
+        round_trip(allocator);
+    }
+    reset_heap();
+}
+
+#[test]
+fn test_wrapped_static_allocates() {
+    reset_heap();
+    round_trip(&WRAPPED);
+    unsafe { WRAPPED.reset() };
+    reset_heap();
+}
+
+// Generic code bounded by `Default` builds any of the allocators
+// 以 `Default` 为约束的泛型代码可以构造任一分配器
+fn default_round_trip<A: GlobalAlloc + ResettableAllocator + Default>() {
+    reset_heap();
+    let allocator = A::default();
+    round_trip(&allocator);
+    unsafe { allocator.reset() };
+    reset_heap();
+}
+
+#[test]
+fn test_default_in_generic_code() {
+    default_round_trip::<FreeListAllocator>();
+    default_round_trip::<BumpFreeListAllocator>();
+    default_round_trip::<SegregatedBumpAllocator>();
+    default_round_trip::<SlabAllocator<64>>();
+    default_round_trip::<BitmapAllocator<32>>();
+    default_round_trip::<TlsfAllocator>();
+    default_round_trip::<BuddyAllocator>();
+}
@@ -0,0 +1,121 @@
+// Trace events emitted through `defmt`, captured by the `defmt-capture` test logger.
+// Trace-level events are filtered out at compile time unless `DEFMT_LOG` allows them;
+// `.cargo/config.toml` sets it to `trace`, so `cargo test --features defmt` runs these.
+// 通过 `defmt` 发出的跟踪事件，由 `defmt-capture` 测试日志器捕获。
+// 除非 `DEFMT_LOG` 允许，trace 级别的事件会在编译期被过滤；
+// `.cargo/config.toml` 将其设为 `trace`，因此 `cargo test --features defmt` 会运行这些测试。
+#![cfg(feature = "defmt")]
+
+use lite_alloc::single_threaded::{
+    BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator,
+};
+use lite_alloc::{PAGE_SIZE, ResettableAllocator, heap_pages_grown, reset_heap};
+use std::alloc::{GlobalAlloc, Layout};
+// One decoded frame: the interned format string index and the `usize` arguments
+#[derive(Debug)]
+struct Event {
+    index: u16,
+    args: Vec<usize>,
+}
+
+fn take_events() -> Vec<Event> {
+    defmt_capture::take_frames()
+        .into_iter()
+        .map(|frame| {
+            let (index, args) = frame.split_at(2);
+            assert_eq!(args.len() % 4, 0, "unexpected frame {frame:?}");
+            Event {
+                index: u16::from_le_bytes([index[0], index[1]]),
+                args: args
+                    .chunks_exact(4)
+                    .map(|word| u32::from_le_bytes(word.try_into().unwrap()) as usize)
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+// `usize` goes over the wire as a 32-bit integer, so host addresses are truncated
+fn wire(value: usize) -> usize {
+    value as u32 as usize
+}
+
+// Allocate twice (the first growing the heap), then free both, checking each event
+fn check_known_sequence<A: GlobalAlloc + ResettableAllocator>(alloc: &A) {
+    reset_heap();
+    take_events();
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    let other = Layout::from_size_align(24, 16).unwrap();
+
+    let pages = heap_pages_grown().as_usize();
+    let a = unsafe { alloc.alloc(layout) };
+    let grown = heap_pages_grown().as_usize() - pages;
+    let b = unsafe { alloc.alloc(other) };
+    assert!(!a.is_null() && !b.is_null());
+    unsafe {
+        alloc.dealloc(b, other);
+        alloc.dealloc(a, layout);
+    }
+
+    let events = take_events();
+    assert_eq!(events.len(), 5, "{events:?}");
+    let [grow, first, second, free_b, free_a] = &events[..] else {
+        unreachable!()
+    };
+    // The first block sits in the first new page
+    assert_eq!(grow.args, [grown, wire(a as usize / PAGE_SIZE)]);
+    assert_eq!(first.args, [64, 8, wire(a as usize)]);
+    assert_eq!(second.args, [24, 16, wire(b as usize)]);
+    assert_eq!(free_b.args, [24, wire(b as usize)]);
+    assert_eq!(free_a.args, [64, wire(a as usize)]);
+
+    // The same call site always sends the same format string
+    assert_eq!(first.index, second.index);
+    assert_eq!(free_a.index, free_b.index);
+    assert_ne!(first.index, free_a.index);
+    assert_ne!(grow.index, first.index);
+
+    unsafe { alloc.reset() };
+    reset_heap();
+}
+
+#[test]
+fn test_freelist_events() {
+    check_known_sequence(&FreeListAllocator::new());
+}
+
+#[test]
+fn test_bump_freelist_events() {
+    check_known_sequence(&BumpFreeListAllocator::new());
+}
+
+#[test]
+fn test_segregated_bump_events() {
+    check_known_sequence(&SegregatedBumpAllocator::new());
+}
+
+// Memory source that never grows
+#[cfg(not(feature = "panic-on-oom"))]
+struct NoMemory;
+
+#[cfg(not(feature = "panic-on-oom"))]
+impl lite_alloc::MemorySource for NoMemory {
+    unsafe fn grow(&self, _pages: usize) -> usize {
+        usize::MAX
+    }
+}
+
+#[cfg(not(feature = "panic-on-oom"))]
+#[test]
+fn test_oom_event() {
+    take_events();
+    let allocator = FreeListAllocator::with_source(NoMemory);
+    let layout = Layout::from_size_align(100, 32).unwrap();
+    assert!(unsafe { allocator.alloc(layout) }.is_null());
+
+    // The error comes first, then the failed allocation with a null pointer
+    let events = take_events();
+    assert_eq!(events.len(), 2, "{events:?}");
+    assert_eq!(events[0].args, [100, 32]);
+    assert_eq!(events[1].args, [100, 32, 0]);
+}
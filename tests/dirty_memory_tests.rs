@@ -0,0 +1,68 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{
+    BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator,
+};
+use lite_alloc::{DefaultMemory, MemorySource, PAGE_SIZE, reset_heap};
+use std::alloc::{GlobalAlloc, Layout};
+
+// Memory source handing out recycled pages full of garbage
+// 分配出充满垃圾数据的回收页面的内存来源
+struct DirtySource;
+
+impl MemorySource for DirtySource {
+    const ZEROED: bool = false;
+
+    unsafe fn grow(&self, pages: usize) -> usize {
+        let index = unsafe { DefaultMemory.grow(pages) };
+        if index != usize::MAX {
+            unsafe { ((index * PAGE_SIZE) as *mut u8).write_bytes(0xFF, pages * PAGE_SIZE) };
+        }
+        index
+    }
+}
+
+fn assert_zeroed(ptr: *mut u8, size: usize) {
+    assert!(!ptr.is_null());
+    for i in 0..size {
+        assert_eq!(unsafe { *ptr.add(i) }, 0, "byte {i} is not zeroed");
+    }
+}
+
+fn check_bumped_memory_is_zeroed(allocator: &dyn GlobalAlloc) {
+    for (size, align) in [
+        (16, 8),
+        (100, 16),
+        (200, 16),
+        (PAGE_SIZE + 100, 16),
+        (64, 4096),
+    ] {
+        let layout = Layout::from_size_align(size, align).unwrap();
+        assert_zeroed(unsafe { allocator.alloc(layout) }, size);
+    }
+}
+
+#[test]
+fn test_bump_freelist_zeroes_dirty_pages() {
+    reset_heap();
+    check_bumped_memory_is_zeroed(&BumpFreeListAllocator::with_source(DirtySource));
+    reset_heap();
+}
+
+#[test]
+fn test_segregated_bump_zeroes_dirty_pages() {
+    reset_heap();
+    check_bumped_memory_is_zeroed(&SegregatedBumpAllocator::with_source(DirtySource));
+    reset_heap();
+}
+
+#[test]
+fn test_freelist_alloc_zeroed_on_dirty_pages() {
+    reset_heap();
+    let allocator = FreeListAllocator::with_source(DirtySource);
+    for size in [16, 100, PAGE_SIZE + 100] {
+        let layout = Layout::from_size_align(size, 16).unwrap();
+        assert_zeroed(unsafe { allocator.alloc_zeroed(layout) }, size);
+    }
+    reset_heap();
+}
@@ -0,0 +1,221 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{Fallback, FreeListAllocator, SegregatedBumpAllocator};
+use lite_alloc::{DefaultMemory, MemorySource, PAGE_SIZE, ResettableAllocator, reset_heap};
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::RefCell;
+
+// Wrapper recording which pointers an inner allocator handed out and took back
+// 记录内部分配器分配与回收了哪些指针的包装器
+struct Recording<A> {
+    inner: A,
+    allocated: RefCell<Vec<usize>>,
+    freed: RefCell<Vec<usize>>,
+}
+
+impl<A> Recording<A> {
+    const fn new(inner: A) -> Self {
+        Recording {
+            inner,
+            allocated: RefCell::new(Vec::new()),
+            freed: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn allocated(&self, ptr: *mut u8) -> bool {
+        self.allocated.borrow().contains(&(ptr as usize))
+    }
+
+    fn freed(&self, ptr: *mut u8) -> bool {
+        self.freed.borrow().contains(&(ptr as usize))
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for Recording<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.inner.alloc(layout) };
+        self.allocated.borrow_mut().push(ptr as usize);
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.freed.borrow_mut().push(ptr as usize);
+        unsafe { self.inner.dealloc(ptr, layout) }
+    }
+}
+
+impl<A: ResettableAllocator> ResettableAllocator for Recording<A> {
+    unsafe fn reset(&self) {
+        unsafe { self.inner.reset() };
+    }
+}
+
+fn is_small(layout: Layout) -> bool {
+    layout.size() <= 128 && layout.align() <= 16
+}
+
+type Combined = Fallback<Recording<SegregatedBumpAllocator>, Recording<FreeListAllocator>>;
+
+fn combined() -> Combined {
+    reset_heap();
+    Fallback::new(
+        Recording::new(SegregatedBumpAllocator::new()),
+        Recording::new(FreeListAllocator::new()),
+        is_small,
+    )
+}
+
+#[test]
+fn test_routes_by_layout() {
+    let allocator = combined();
+    let small = Layout::from_size_align(64, 8).unwrap();
+    let large = Layout::from_size_align(4096, 16).unwrap();
+    let aligned = Layout::from_size_align(64, 64).unwrap();
+
+    let p_small = unsafe { allocator.alloc(small) };
+    let p_large = unsafe { allocator.alloc(large) };
+    let p_aligned = unsafe { allocator.alloc(aligned) };
+
+    assert!(allocator.primary().allocated(p_small));
+    assert!(allocator.fallback().allocated(p_large));
+    assert!(allocator.fallback().allocated(p_aligned));
+    assert!(!allocator.primary().allocated(p_large));
+    assert_eq!(p_aligned as usize % 64, 0);
+
+    unsafe { allocator.reset() };
+    reset_heap();
+}
+
+#[test]
+fn test_dealloc_round_trips() {
+    let allocator = combined();
+    let small = Layout::from_size_align(24, 8).unwrap();
+    let large = Layout::from_size_align(1000, 8).unwrap();
+
+    let p_small = unsafe { allocator.alloc(small) };
+    let p_large = unsafe { allocator.alloc(large) };
+    unsafe {
+        allocator.dealloc(p_small, small);
+        allocator.dealloc(p_large, large);
+    }
+
+    assert!(allocator.primary().freed(p_small));
+    assert!(!allocator.fallback().freed(p_small));
+    assert!(allocator.fallback().freed(p_large));
+    assert!(!allocator.primary().freed(p_large));
+
+    // Each inner allocator reuses its own freed block
+    // 每个内部分配器复用自己释放的块
+    assert_eq!(unsafe { allocator.alloc(small) }, p_small);
+    assert_eq!(unsafe { allocator.alloc(large) }, p_large);
+
+    unsafe { allocator.reset() };
+    reset_heap();
+}
+
+#[test]
+fn test_realloc_moves_between_allocators() {
+    let allocator = combined();
+    let small = Layout::from_size_align(100, 8).unwrap();
+
+    let ptr = unsafe { allocator.alloc(small) };
+    for i in 0..100 {
+        unsafe { *ptr.add(i) = i as u8 };
+    }
+
+    // Growing past the predicate moves the block to the fallback allocator
+    // 增长超过谓词范围后，块被移动到后备分配器
+    let grown = unsafe { allocator.realloc(ptr, small, 2000) };
+    assert!(allocator.primary().freed(ptr));
+    assert!(allocator.fallback().allocated(grown));
+    for i in 0..100 {
+        assert_eq!(unsafe { *grown.add(i) }, i as u8);
+    }
+
+    // Shrinking back moves it to the primary allocator, keeping the prefix
+    // 缩小回来后块被移回主分配器，并保留前缀
+    let large = Layout::from_size_align(2000, 8).unwrap();
+    let shrunk = unsafe { allocator.realloc(grown, large, 50) };
+    assert!(allocator.fallback().freed(grown));
+    assert!(allocator.primary().allocated(shrunk));
+    for i in 0..50 {
+        assert_eq!(unsafe { *shrunk.add(i) }, i as u8);
+    }
+
+    unsafe { allocator.reset() };
+    reset_heap();
+}
+
+// Memory source whose fresh pages are filled with a marker: any byte a moving `realloc`
+// writes past the end of the new block, or copies from past the end of the old one,
+// shows up as a missing marker
+// 新页面填满标记字节的内存来源：移动式 `realloc` 在新块末尾之后写入的任何字节，
+// 或从旧块末尾之后复制的任何字节，都会表现为标记缺失
+struct MarkedSource;
+
+const MARKER: u8 = 0xEE;
+
+impl MemorySource for MarkedSource {
+    const ZEROED: bool = false;
+
+    unsafe fn grow(&self, pages: usize) -> usize {
+        let index = unsafe { DefaultMemory.grow(pages) };
+        if index != usize::MAX {
+            unsafe { ((index * PAGE_SIZE) as *mut u8).write_bytes(MARKER, pages * PAGE_SIZE) };
+        }
+        index
+    }
+}
+
+#[test]
+fn test_shrinking_move_copies_only_new_size() {
+    reset_heap();
+    let allocator = Fallback::new(
+        SegregatedBumpAllocator::with_source(MarkedSource),
+        FreeListAllocator::with_source(MarkedSource),
+        is_small,
+    );
+    let large = Layout::from_size_align(1000, 8).unwrap();
+    let ptr = unsafe { allocator.alloc(large) };
+    unsafe { ptr.write_bytes(0x11, 1000) };
+
+    // The 64-byte replacement is bumped from a fresh page of the primary allocator, which
+    // zeroes the block itself but nothing past it. This only covers the write side: reading
+    // past 64 bytes of the 1000-byte source stays inside it and cannot be observed here
+    let small = Layout::from_size_align(64, 8).unwrap();
+    let shrunk = unsafe { allocator.realloc(ptr, large, 64) };
+    let end = unsafe { allocator.primary().usable_size(shrunk, small) };
+    assert!((0..64).all(|i| unsafe { *shrunk.add(i) } == 0x11));
+    assert!((end..1000).all(|i| unsafe { *shrunk.add(i) } == MARKER));
+
+    unsafe { allocator.reset() };
+    reset_heap();
+}
+
+#[test]
+fn test_growing_move_reads_only_old_size() {
+    reset_heap();
+    let allocator = Fallback::new(
+        SegregatedBumpAllocator::with_source(MarkedSource),
+        FreeListAllocator::with_source(MarkedSource),
+        is_small,
+    );
+    let small = Layout::from_size_align(64, 8).unwrap();
+    let ptr = unsafe { allocator.alloc(small) };
+    unsafe { ptr.write_bytes(0x11, 64) };
+    // Bumped just after the source, so reading past its 64 bytes picks up this pattern
+    // 紧随源块之后 Bump 出来，因此读取超过其 64 字节时会读到这一模式
+    let neighbour = unsafe { allocator.alloc(small) };
+    assert!((ptr as usize + 64..ptr as usize + 1000).contains(&(neighbour as usize)));
+    unsafe { neighbour.write_bytes(0x22, 64) };
+
+    // The 1000-byte replacement comes from fresh pages of the secondary allocator, so
+    // everything past the copied 64 bytes must still be the marker
+    let grown = unsafe { allocator.realloc(ptr, small, 1000) };
+    assert!(!grown.is_null());
+    assert!((0..64).all(|i| unsafe { *grown.add(i) } == 0x11));
+    assert!((64..1000).all(|i| unsafe { *grown.add(i) } == MARKER));
+
+    unsafe { allocator.reset() };
+    reset_heap();
+}
@@ -0,0 +1,66 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::{JS_BUFFER_ALIGN, alloc_js_buffer, free_js_buffer};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// Global allocator recording the last layout it served on this thread, to check that
+// the buffers really come from the configured global allocator
+// 记录本线程最近一次所服务布局的全局分配器，用于检查缓冲区确实来自所配置的全局分配器
+struct Recording;
+
+std::thread_local! {
+    static LAST_ALLOC: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+    static LAST_FREE: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+unsafe impl GlobalAlloc for Recording {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        let _ = LAST_ALLOC.try_with(|last| last.set(Some((ptr as usize, layout.size()))));
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = LAST_FREE.try_with(|last| last.set(Some((ptr as usize, layout.size()))));
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Recording = Recording;
+
+#[test]
+fn test_round_trip() {
+    let len = 1000;
+    unsafe {
+        let ptr = alloc_js_buffer(len);
+        assert!(!ptr.is_null());
+        assert!((ptr as usize).is_multiple_of(JS_BUFFER_ALIGN));
+        assert_eq!(LAST_ALLOC.get(), Some((ptr as usize, len)));
+
+        // What JS would write into a `Uint8Array` view, read back from Rust
+        for i in 0..len {
+            ptr.add(i).write(i as u8);
+        }
+        let data = std::slice::from_raw_parts(ptr, len);
+        assert!(data.iter().enumerate().all(|(i, &byte)| byte == i as u8));
+
+        free_js_buffer(ptr, len);
+        assert_eq!(LAST_FREE.get(), Some((ptr as usize, len)));
+    }
+}
+
+#[test]
+fn test_empty_and_oversized() {
+    unsafe {
+        let empty = alloc_js_buffer(0);
+        assert!(!empty.is_null());
+        assert!((empty as usize).is_multiple_of(JS_BUFFER_ALIGN));
+        LAST_FREE.set(None);
+        free_js_buffer(empty, 0);
+        assert_eq!(LAST_FREE.get(), None, "an empty buffer owns no memory");
+
+        assert!(alloc_js_buffer(usize::MAX).is_null());
+    }
+}
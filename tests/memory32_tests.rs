@@ -0,0 +1,132 @@
+// Page arithmetic on 32-bit targets, where 65536 pages already cover the whole address
+// space. Run with `cargo miri test --target i686-unknown-linux-gnu --test memory32_tests`,
+// or with `cross test` on the same target.
+// 32 位目标上的页面计算，65536 页即已覆盖整个地址空间。运行方式为
+// `cargo miri test --target i686-unknown-linux-gnu --test memory32_tests`，
+// 或在同一目标上使用 `cross test`。
+#![cfg(target_pointer_width = "32")]
+
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{FreeListAllocator, SegregatedBumpAllocator};
+use lite_alloc::{
+    DefaultMemory, GrowthPolicy, MemorySource, PAGE_SIZE, PageCount, heap_pages_grown, reset_heap,
+};
+use std::alloc::{GlobalAlloc, Layout};
+
+// Pages in the 4 GiB address space
+// 4 GiB 地址空间中的页数
+const PAGES_4GB: usize = usize::MAX / PAGE_SIZE + 1;
+
+#[test]
+fn test_page_count_at_4gb() {
+    assert_eq!(PAGES_4GB, 65536);
+    assert_eq!(
+        PageCount(PAGES_4GB - 1).checked_size_in_bytes(),
+        Some(usize::MAX - PAGE_SIZE + 1)
+    );
+    assert_eq!(PageCount(PAGES_4GB).checked_size_in_bytes(), None);
+    assert_eq!(PageCount::from_bytes(usize::MAX), PageCount(PAGES_4GB));
+    assert_eq!(
+        PageCount(PAGES_4GB - 1).checked_add(PageCount(1)),
+        Some(PageCount(PAGES_4GB))
+    );
+}
+
+#[test]
+fn test_freelist_multi_page_growth() {
+    reset_heap();
+    let allocator = FreeListAllocator::new();
+    let mut total = 0;
+    for pages in [1, 3, 7] {
+        let layout = Layout::from_size_align(pages * PAGE_SIZE - 64, 16).unwrap();
+        let before = heap_pages_grown().as_usize();
+        let ptr = unsafe { allocator.alloc(layout) };
+        assert!(!ptr.is_null());
+        assert_eq!(heap_pages_grown().as_usize() - before, pages);
+        total += pages;
+
+        // The whole block lies inside the tracked region
+        // 整个块都位于跟踪的区域内
+        let (start, end) = unsafe { allocator.heap_extent() };
+        assert!(start <= ptr as usize && ptr as usize + layout.size() <= end);
+        assert_eq!(end - start, total * PAGE_SIZE);
+        unsafe { ptr.write_bytes(0x32, layout.size()) };
+    }
+    unsafe { allocator.reset() };
+    reset_heap();
+}
+
+// Memory source granting the upper half of the address space in one growth: the region
+// ends exactly at 4 GiB, which a 32-bit heap end cannot hold
+// 一次增长即授予地址空间上半部分的内存来源：该区域恰好结束于 4 GiB，32 位的堆末尾无法表示
+struct UpperHalfSource;
+
+impl MemorySource for UpperHalfSource {
+    unsafe fn grow(&self, pages: usize) -> usize {
+        if pages == PAGES_4GB / 2 {
+            PAGES_4GB / 2
+        } else {
+            usize::MAX
+        }
+    }
+}
+
+#[test]
+fn test_freelist_region_ending_at_4gb_is_oom() {
+    let policy = GrowthPolicy {
+        initial_pages: PageCount(PAGES_4GB / 2),
+        divisor: 0,
+    };
+    let allocator = FreeListAllocator::with_source(UpperHalfSource).with_growth(policy);
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    assert!(unsafe { allocator.alloc(layout) }.is_null());
+    assert_eq!(unsafe { allocator.heap_extent() }, (0, 0));
+}
+
+// Requests near `isize::MAX` fail cleanly instead of wrapping the page count
+// 接近 `isize::MAX` 的请求干净地失败，而不会使页数回绕
+#[test]
+fn test_huge_requests_near_limit_are_null() {
+    reset_heap();
+    let freelist = FreeListAllocator::new();
+    let segregated = SegregatedBumpAllocator::new();
+    for size in [isize::MAX as usize - PAGE_SIZE, isize::MAX as usize - 15] {
+        let layout = Layout::from_size_align(size, 16).unwrap();
+        assert!(
+            unsafe { freelist.alloc(layout) }.is_null(),
+            "size {size:#x}"
+        );
+        assert!(
+            unsafe { segregated.alloc(layout) }.is_null(),
+            "size {size:#x}"
+        );
+    }
+    reset_heap();
+}
+
+// Bin lookup picks the same classes on 32-bit targets, up to the largest bin, and sends
+// everything above it to the large-object path
+// 32 位目标上的 Bin 查找选出相同的大小等级（直到最大的 Bin），更大的请求走大对象路径
+#[cfg(not(feature = "track-sizes"))]
+#[test]
+fn test_segregated_bin_indexing() {
+    reset_heap();
+    let allocator =
+        SegregatedBumpAllocator::<DefaultMemory>::with_bins(DefaultMemory, [16, 32, 64, 128]);
+    for (size, block) in [
+        (1, 16),
+        (16, 16),
+        (17, 32),
+        (64, 64),
+        (65, 128),
+        (128, 128),
+        (129, 144),
+    ] {
+        let layout = Layout::from_size_align(size, 1).unwrap();
+        assert_eq!(allocator.block_size(layout), block, "size {size}");
+    }
+    let near_limit = Layout::from_size_align(isize::MAX as usize - 40, 1).unwrap();
+    assert_eq!(allocator.block_size(near_limit), isize::MAX as usize - 31);
+    reset_heap();
+}
@@ -0,0 +1,148 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+#[cfg(not(feature = "panic-on-oom"))]
+use lite_alloc::GrowthPolicy;
+#[cfg(not(feature = "panic-on-oom"))]
+use lite_alloc::single_threaded::{
+    BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator,
+};
+use lite_alloc::{MemorySource, PAGE_SIZE, PageCount};
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::Cell;
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn test_page_count_beyond_4gb() {
+    // First page past the 32-bit address space
+    // 32 位地址空间之后的第一页
+    let pages_4gb = (1usize << 32) / PAGE_SIZE;
+    assert_eq!(
+        PageCount(pages_4gb + 1).checked_size_in_bytes(),
+        Some((1usize << 32) + PAGE_SIZE)
+    );
+    assert_eq!(
+        PageCount(pages_4gb + 1).size_in_bytes(),
+        (1usize << 32) + PAGE_SIZE
+    );
+}
+
+#[test]
+fn test_page_count_overflow() {
+    let max_pages = usize::MAX / PAGE_SIZE;
+    assert_eq!(
+        PageCount(max_pages).checked_size_in_bytes(),
+        Some(max_pages * PAGE_SIZE)
+    );
+    assert_eq!(PageCount(max_pages + 1).checked_size_in_bytes(), None);
+}
+
+// Memory source reporting a page index whose address does not fit in `usize`
+// 返回地址超出 `usize` 范围的页索引的内存来源
+#[cfg(not(feature = "panic-on-oom"))]
+struct OverflowSource;
+
+#[cfg(not(feature = "panic-on-oom"))]
+impl MemorySource for OverflowSource {
+    unsafe fn grow(&self, _pages: usize) -> usize {
+        usize::MAX / PAGE_SIZE + 1
+    }
+}
+
+#[cfg(not(feature = "panic-on-oom"))]
+fn check_overflow_is_oom(allocator: &dyn GlobalAlloc) {
+    for (size, align) in [(16, 8), (200, 16), (PAGE_SIZE * 2, 16), (64, 4096)] {
+        let layout = Layout::from_size_align(size, align).unwrap();
+        assert!(unsafe { allocator.alloc(layout) }.is_null());
+    }
+}
+
+#[cfg(not(feature = "panic-on-oom"))]
+#[test]
+fn test_overflowing_page_index_is_oom() {
+    check_overflow_is_oom(&FreeListAllocator::with_source(OverflowSource));
+    check_overflow_is_oom(&BumpFreeListAllocator::with_source(OverflowSource));
+    check_overflow_is_oom(&SegregatedBumpAllocator::with_source(OverflowSource));
+}
+
+// Memory source granting every page of the address space in one growth, starting at page 0
+// 一次增长即授予地址空间中所有页面（从第 0 页开始）的内存来源
+#[cfg(not(feature = "panic-on-oom"))]
+struct WholeSpaceSource;
+
+#[cfg(not(feature = "panic-on-oom"))]
+impl MemorySource for WholeSpaceSource {
+    unsafe fn grow(&self, pages: usize) -> usize {
+        if pages == usize::MAX / PAGE_SIZE + 1 {
+            0
+        } else {
+            usize::MAX
+        }
+    }
+}
+
+// The granted region's size does not fit in `usize`, so the allocator must give up before
+// touching it (page 0 is not backed by anything)
+#[cfg(not(feature = "panic-on-oom"))]
+#[test]
+fn test_freelist_region_past_address_space_is_oom() {
+    let policy = GrowthPolicy {
+        initial_pages: PageCount(usize::MAX / PAGE_SIZE + 1),
+        divisor: 0,
+    };
+    let allocator = FreeListAllocator::with_source(WholeSpaceSource).with_growth(policy);
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    assert!(unsafe { allocator.alloc(layout) }.is_null());
+    assert_eq!(unsafe { allocator.heap_extent() }, (0, 0));
+}
+
+// Memory source whose only page sits right below the top of the address space
+// 唯一的页位于地址空间顶端之下的内存来源
+#[allow(dead_code)]
+struct TopPageSource(Cell<bool>);
+
+impl MemorySource for TopPageSource {
+    unsafe fn grow(&self, pages: usize) -> usize {
+        if pages == 1 && !self.0.replace(true) {
+            usize::MAX / PAGE_SIZE - 1
+        } else {
+            usize::MAX
+        }
+    }
+}
+
+// The first block comes from the top page and must not be written (the source is
+// `ZEROED`, but `canary` and `track-sizes` write into blocks); the next ones would end
+// past `usize::MAX`
+#[allow(dead_code)]
+fn check_bump_overflow_is_null(allocator: &dyn GlobalAlloc) {
+    let small = Layout::from_size_align(16, 16).unwrap();
+    assert!(!unsafe { allocator.alloc(small) }.is_null());
+
+    let huge = Layout::from_size_align(isize::MAX as usize - 15, 16).unwrap();
+    assert!(unsafe { allocator.alloc(huge) }.is_null());
+    // Rounding the heap top up to this alignment alone passes `usize::MAX`
+    let aligned = Layout::from_size_align(16, 1 << 20).unwrap();
+    assert!(unsafe { allocator.alloc(aligned) }.is_null());
+}
+
+#[cfg(all(
+    target_pointer_width = "64",
+    not(feature = "canary"),
+    not(feature = "panic-on-oom")
+))]
+#[test]
+fn test_bump_freelist_address_overflow_is_null() {
+    let source = TopPageSource(Cell::new(false));
+    check_bump_overflow_is_null(&BumpFreeListAllocator::with_source(source));
+}
+
+#[cfg(all(
+    target_pointer_width = "64",
+    not(feature = "track-sizes"),
+    not(feature = "panic-on-oom")
+))]
+#[test]
+fn test_segregated_address_overflow_is_null() {
+    let source = TopPageSource(Cell::new(false));
+    check_bump_overflow_is_null(&SegregatedBumpAllocator::with_source(source));
+}
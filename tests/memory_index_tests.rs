@@ -0,0 +1,20 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::HEAP_MEMORY_INDEX;
+
+#[test]
+fn test_heap_memory_index_default() {
+    // Only the default build is checked; an override is validated by the const parser
+    if option_env!("LITE_ALLOC_HEAP_MEMORY_INDEX").is_none() {
+        assert_eq!(HEAP_MEMORY_INDEX, 0);
+    }
+}
+
+// The index is a const generic of the memory intrinsics: this only compiles if it is a
+// usable memory index, and the allocator's view of the heap must match that memory
+#[cfg(target_arch = "wasm32")]
+#[test]
+fn test_heap_memory_index_is_used() {
+    let pages = core::arch::wasm32::memory_size::<HEAP_MEMORY_INDEX>();
+    assert_eq!(lite_alloc::heap_pages_grown().as_usize(), pages);
+}
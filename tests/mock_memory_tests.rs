@@ -0,0 +1,81 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::FreeListAllocator;
+use lite_alloc::{
+    MOCK_MEMORY_SIZE, PAGE_SIZE, PageCount, heap_pages_grown, mock_heap_footprint,
+    mock_heap_offset, mock_memory_size, reset_heap, set_mock_memory_size,
+};
+use std::alloc::{GlobalAlloc, Layout};
+
+// Resident memory of the whole process, as the host sees it
+#[cfg(target_os = "linux")]
+fn resident_bytes() -> usize {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap();
+    let line = status.lines().find(|l| l.starts_with("VmRSS:")).unwrap();
+    let kib: usize = line.split_whitespace().nth(1).unwrap().parse().unwrap();
+    kib * 1024
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "reserves gigabytes of address space")]
+fn test_large_capacity_small_footprint() {
+    #[cfg(target_os = "linux")]
+    let resident = resident_bytes();
+
+    // 4 GiB of logical capacity on 64-bit hosts; only the pages grown are ever written.
+    // Hosts that cannot reserve that much back off to a smaller capacity
+    let requested = if usize::BITS >= 64 { 1 << 32 } else { 1 << 30 };
+    set_mock_memory_size(requested);
+    let capacity = mock_memory_size();
+    assert!(capacity <= requested && capacity.is_multiple_of(PAGE_SIZE));
+    assert_eq!(mock_heap_footprint(), PageCount(0));
+
+    let allocator = FreeListAllocator::new();
+    let layout = Layout::from_size_align(PAGE_SIZE, 16).unwrap();
+    unsafe {
+        let mut ptrs = Vec::new();
+        for _ in 0..3 {
+            let ptr = allocator.alloc(layout);
+            assert!(!ptr.is_null());
+            assert!(mock_heap_offset(ptr).unwrap() < 4 * PAGE_SIZE);
+            ptr.write_bytes(0x5A, layout.size());
+            ptrs.push(ptr);
+        }
+
+        let grown = heap_pages_grown();
+        assert!(grown.as_usize() <= 4, "grew {grown:?}");
+        assert_eq!(mock_heap_footprint(), grown);
+
+        // Writing or zeroing the reservation up front would make the whole capacity resident
+        #[cfg(target_os = "linux")]
+        {
+            let added = resident_bytes().saturating_sub(resident);
+            assert!(added < capacity / 8, "{added} bytes resident");
+        }
+
+        for ptr in ptrs {
+            allocator.dealloc(ptr, layout);
+        }
+        allocator.reset();
+    }
+
+    // Resetting hands the pages out again without touching new ones
+    reset_heap();
+    assert_eq!(heap_pages_grown(), PageCount(0));
+    unsafe {
+        let fresh = allocator.alloc(layout);
+        assert!(!fresh.is_null());
+        assert_eq!(*fresh, 0, "reset pages must be zeroed again");
+        allocator.reset();
+    }
+    assert!(mock_heap_footprint().as_usize() <= 4);
+
+    set_mock_memory_size(MOCK_MEMORY_SIZE);
+}
+
+#[test]
+fn test_default_capacity() {
+    reset_heap();
+    let size = mock_memory_size();
+    assert!(size <= MOCK_MEMORY_SIZE && size.is_multiple_of(PAGE_SIZE));
+}
@@ -0,0 +1,60 @@
+#![cfg(not(feature = "panic-on-oom"))]
+
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::reset_heap;
+use lite_alloc::single_threaded::{
+    BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator,
+};
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::Cell;
+
+thread_local! {
+    static OOM_LAYOUT: Cell<Option<Layout>> = const { Cell::new(None) };
+}
+
+fn record_oom(layout: Layout) {
+    OOM_LAYOUT.with(|l| l.set(Some(layout)));
+}
+
+// The allocator has `record_oom` installed as its hook
+fn check_oom_hook_observes_layout(allocator: &dyn GlobalAlloc) {
+    OOM_LAYOUT.with(|l| l.set(None));
+
+    // Successful allocations do not fire the hook
+    let small = Layout::from_size_align(64, 16).unwrap();
+    let ptr = unsafe { allocator.alloc(small) };
+    assert!(!ptr.is_null());
+    assert_eq!(OOM_LAYOUT.with(Cell::get), None);
+
+    // Larger than the whole 128MB mock heap
+    let huge = Layout::from_size_align(256 * 1024 * 1024, 16).unwrap();
+    assert!(unsafe { allocator.alloc(huge) }.is_null());
+    assert_eq!(OOM_LAYOUT.with(Cell::get), Some(huge));
+
+    unsafe { allocator.dealloc(ptr, small) };
+}
+
+#[test]
+fn test_free_list_oom_hook_observes_layout() {
+    reset_heap();
+    let allocator = FreeListAllocator::new();
+    allocator.set_oom_hook(Some(record_oom));
+    check_oom_hook_observes_layout(&allocator);
+}
+
+#[test]
+fn test_bump_free_list_oom_hook_observes_layout() {
+    reset_heap();
+    let allocator = BumpFreeListAllocator::new();
+    allocator.set_oom_hook(Some(record_oom));
+    check_oom_hook_observes_layout(&allocator);
+}
+
+#[test]
+fn test_segregated_bump_oom_hook_observes_layout() {
+    reset_heap();
+    let allocator = SegregatedBumpAllocator::new();
+    allocator.set_oom_hook(Some(record_oom));
+    check_oom_hook_observes_layout(&allocator);
+}
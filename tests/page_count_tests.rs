@@ -0,0 +1,32 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::{PAGE_SIZE, PageCount};
+
+#[test]
+fn test_from_bytes_rounds_up() {
+    assert_eq!(PageCount::from_bytes(0), PageCount(0));
+    assert_eq!(PageCount::from_bytes(1), PageCount(1));
+    assert_eq!(PageCount::from_bytes(PAGE_SIZE - 1), PageCount(1));
+    assert_eq!(PageCount::from_bytes(PAGE_SIZE), PageCount(1));
+    assert_eq!(PageCount::from_bytes(PAGE_SIZE + 1), PageCount(2));
+    assert_eq!(
+        PageCount::from_bytes(usize::MAX),
+        PageCount(usize::MAX / PAGE_SIZE + 1)
+    );
+    // Round trip for whole pages
+    assert_eq!(
+        PageCount::from_bytes(PageCount(7).size_in_bytes()),
+        PageCount(7)
+    );
+}
+
+#[test]
+fn test_page_count_arithmetic() {
+    assert_eq!(PageCount(3) + PageCount(2), PageCount(5));
+    assert_eq!(PageCount(3) - PageCount(2), PageCount(1));
+    assert_eq!(PageCount(3).checked_add(PageCount(2)), Some(PageCount(5)));
+    assert_eq!(PageCount(usize::MAX).checked_add(PageCount(1)), None);
+
+    const PAGES: usize = PageCount::from_bytes(3 * PAGE_SIZE / 2).as_usize();
+    assert_eq!(PAGES, 2);
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "panic-on-oom")]
+
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{
+    BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator,
+};
+use lite_alloc::{PAGE_SIZE, set_mock_memory_size};
+use std::alloc::{GlobalAlloc, Layout};
+
+// A four-page mock heap cannot serve eight pages: the allocation panics instead of
+// returning null
+fn exhaust(allocator: &dyn GlobalAlloc) {
+    set_mock_memory_size(4 * PAGE_SIZE);
+    let layout = Layout::from_size_align(8 * PAGE_SIZE, 16).unwrap();
+    unsafe { allocator.alloc(layout) };
+}
+
+#[test]
+#[should_panic(expected = "lite-alloc OOM")]
+fn test_free_list_panics_on_oom() {
+    exhaust(&FreeListAllocator::new());
+}
+
+#[test]
+#[should_panic(expected = "lite-alloc OOM")]
+fn test_bump_free_list_panics_on_oom() {
+    exhaust(&BumpFreeListAllocator::new());
+}
+
+#[test]
+#[should_panic(expected = "lite-alloc OOM")]
+fn test_segregated_bump_panics_on_oom() {
+    exhaust(&SegregatedBumpAllocator::new());
+}
@@ -0,0 +1,120 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{
+    BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator,
+};
+use lite_alloc::{ResettableAllocator, reset_heap};
+use std::alloc::{GlobalAlloc, Layout};
+
+const ALIGNS: [usize; 4] = [1, 8, 16, 64];
+
+// Grow and shrink across bins, the large path and the heap top
+const SIZES: [usize; 7] = [8, 24, 100, 64, 300, 2000, 40];
+
+fn pattern(i: usize) -> u8 {
+    (i % 251) as u8
+}
+
+// Realloc one block through every size in `SIZES`, with a neighbour allocated in
+// between so both the in-place and the moving paths are taken
+fn check_realloc_alignment<A: GlobalAlloc + ResettableAllocator>(name: &str, alloc: &A) {
+    for align in ALIGNS {
+        reset_heap();
+        let mut layout = Layout::from_size_align(SIZES[0], align).unwrap();
+        let mut ptr = unsafe { alloc.alloc(layout) };
+        assert!(!ptr.is_null());
+        for i in 0..layout.size() {
+            unsafe { *ptr.add(i) = pattern(i) };
+        }
+
+        for (step, &new_size) in SIZES.iter().enumerate().skip(1) {
+            let neighbour = Layout::from_size_align(16, align).unwrap();
+            let obstacle = if step % 2 == 0 {
+                unsafe { alloc.alloc(neighbour) }
+            } else {
+                std::ptr::null_mut()
+            };
+
+            let new_ptr = unsafe { alloc.realloc(ptr, layout, new_size) };
+            assert!(!new_ptr.is_null(), "{name}: align {align}, size {new_size}");
+            assert_eq!(
+                new_ptr as usize % align,
+                0,
+                "{name}: realloc to {new_size} lost align {align}"
+            );
+            let kept = layout.size().min(new_size);
+            assert!(
+                (0..kept).all(|i| unsafe { *new_ptr.add(i) } == pattern(i)),
+                "{name}: align {align}, size {} -> {new_size} lost its contents",
+                layout.size()
+            );
+
+            // Refill the whole block for the next step
+            for i in 0..new_size {
+                unsafe { *new_ptr.add(i) = pattern(i) };
+            }
+            ptr = new_ptr;
+            layout = Layout::from_size_align(new_size, align).unwrap();
+            if !obstacle.is_null() {
+                unsafe { alloc.dealloc(obstacle, neighbour) };
+            }
+        }
+        unsafe {
+            alloc.dealloc(ptr, layout);
+            alloc.reset();
+        }
+    }
+    reset_heap();
+}
+
+#[test]
+fn test_freelist_realloc_keeps_alignment() {
+    check_realloc_alignment("FreeListAllocator", &FreeListAllocator::new());
+}
+
+#[test]
+fn test_bump_freelist_realloc_keeps_alignment() {
+    check_realloc_alignment("BumpFreeListAllocator", &BumpFreeListAllocator::new());
+}
+
+#[test]
+fn test_segregated_bump_realloc_keeps_alignment() {
+    check_realloc_alignment("SegregatedBumpAllocator", &SegregatedBumpAllocator::new());
+}
+
+// The `realloc_growth` benchmark workload: Vec-style doubling from 16 bytes to 64KB on a
+// fresh heap; returns how many of the 12 reallocs kept the pointer
+#[cfg(feature = "realloc")]
+fn realloc_growth_in_place<A: GlobalAlloc + ResettableAllocator>(alloc: &A) -> usize {
+    reset_heap();
+    let mut layout = Layout::from_size_align(16, 8).unwrap();
+    let mut ptr = unsafe { alloc.alloc(layout) };
+    assert!(!ptr.is_null());
+    let mut in_place = 0;
+    while layout.size() < 64 * 1024 {
+        let new_size = layout.size() * 2;
+        let new_ptr = unsafe { alloc.realloc(ptr, layout, new_size) };
+        assert!(!new_ptr.is_null());
+        if new_ptr == ptr {
+            in_place += 1;
+        }
+        ptr = new_ptr;
+        layout = Layout::from_size_align(new_size, 8).unwrap();
+    }
+    unsafe {
+        alloc.dealloc(ptr, layout);
+        alloc.reset();
+    }
+    reset_heap();
+    in_place
+}
+
+// The bump allocators extend the block at the heap top every time; FreeList cannot grow a
+// block into fresh memory, so with nothing free around it the block moves
+#[cfg(feature = "realloc")]
+#[test]
+fn test_realloc_growth_bench_counts() {
+    assert_eq!(realloc_growth_in_place(&BumpFreeListAllocator::new()), 12);
+    assert_eq!(realloc_growth_in_place(&SegregatedBumpAllocator::new()), 12);
+    assert!(realloc_growth_in_place(&FreeListAllocator::new()) <= 1);
+}
@@ -0,0 +1,145 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::replay::{AllocOp, replay, replay_with};
+use lite_alloc::reset_heap;
+use lite_alloc::single_threaded::{
+    BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator,
+};
+
+#[test]
+fn test_freelist_coalescing_and_realloc_regression() {
+    use AllocOp::*;
+    reset_heap();
+    let allocator = FreeListAllocator::new();
+    let ops = [
+        Alloc {
+            size: 100,
+            align: 8,
+        },
+        Alloc {
+            size: 200,
+            align: 8,
+        },
+        Alloc {
+            size: 300,
+            align: 16,
+        },
+        // Guard, so the freed blocks below are not at the heap end
+        Alloc { size: 64, align: 8 },
+        // Free the 200-byte block, then its lower neighbour: the two merge
+        Dealloc { index: 1 },
+        Dealloc { index: 0 },
+        // Grow the 300-byte block into the merged hole's upper neighbour or move it
+        Realloc {
+            index: 0,
+            new_size: 600,
+        },
+        Alloc {
+            size: 250,
+            align: 64,
+        },
+        // Shrink in place, splitting the tail off into the free list
+        Realloc {
+            index: 0,
+            new_size: 40,
+        },
+        Dealloc { index: 1 },
+        Realloc {
+            index: 0,
+            new_size: 4000,
+        },
+        Alloc { size: 8, align: 8 },
+    ];
+    let mut steps = 0;
+    unsafe {
+        replay_with(&allocator, &ops, |a| {
+            a.check_integrity().expect("free list corrupted");
+            steps += 1;
+        })
+    };
+    assert_eq!(steps, ops.len() + 1);
+
+    // Everything was freed and merged back into a single block (small bins keep theirs)
+    #[cfg(not(feature = "small-bins"))]
+    {
+        assert_eq!(unsafe { allocator.free_list_len() }, 1);
+        let (start, end) = unsafe { allocator.heap_extent() };
+        assert_eq!(unsafe { allocator.largest_free_block() }, end - start);
+    }
+    reset_heap();
+}
+
+#[test]
+fn test_seeded_sequences_are_deterministic() {
+    assert_eq!(AllocOp::seeded(7, 200), AllocOp::seeded(7, 200));
+    assert_ne!(AllocOp::seeded(7, 200), AllocOp::seeded(8, 200));
+}
+
+#[test]
+fn test_seeded_replay_on_general_purpose_allocators() {
+    for seed in 0..8 {
+        let ops = AllocOp::seeded(seed, 400);
+
+        reset_heap();
+        let freelist = FreeListAllocator::new();
+        unsafe {
+            replay_with(&freelist, &ops, |a| {
+                a.check_integrity().expect("free list corrupted");
+            })
+        };
+
+        reset_heap();
+        unsafe { replay(&BumpFreeListAllocator::new(), &ops) };
+
+        reset_heap();
+        let segregated = SegregatedBumpAllocator::new();
+        unsafe { replay_with(&segregated, &ops, |a| assert!(a.check_bins())) };
+    }
+    reset_heap();
+}
+
+#[test]
+fn test_decode_reads_fuzz_input() {
+    let data = [
+        0, 0x10, 0x01, // alloc 0x110 bytes
+        4, 3, // dealloc index 3
+        5, 1, 0x00, 0x02, // realloc index 1 to 0x200 bytes
+        3, 0x01, // truncated alloc
+    ];
+    assert_eq!(
+        AllocOp::decode(&data),
+        [
+            AllocOp::Alloc {
+                size: 0x110,
+                align: 8
+            },
+            AllocOp::Dealloc { index: 3 },
+            AllocOp::Realloc {
+                index: 1,
+                new_size: 0x200
+            },
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "lost its contents")]
+fn test_replay_catches_corruption() {
+    // Stomp on every live block from the check: the next free notices
+    reset_heap();
+    let allocator = FreeListAllocator::new();
+    let ops = [
+        AllocOp::Alloc { size: 32, align: 8 },
+        AllocOp::Dealloc { index: 0 },
+    ];
+    let mut first = true;
+    unsafe {
+        replay_with(&allocator, &ops, |a| {
+            if first {
+                first = false;
+                let (start, end) = a.heap_extent();
+                std::ptr::write_bytes(start as *mut u8, 0, end - start);
+            }
+        })
+    };
+}
@@ -0,0 +1,260 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{
+    BumpFreeListAllocator, FreeListAllocator, SegregatedBumpAllocator,
+};
+use lite_alloc::{
+    GrowthPolicy, MOCK_MEMORY_SIZE, PAGE_SIZE, PageCount, ResettableAllocator, grow_memory,
+    heap_pages_grown, mock_heap_offset, pin_mock_heap, reset_heap, set_mock_memory_size,
+    try_grow_memory,
+};
+use std::alloc::{GlobalAlloc, Layout};
+
+// Allocate, free and reset through the trait object; afterwards the freed block
+// must be forgotten, so the next allocation is served from fresh memory.
+// 分配、释放后通过 trait 对象重置；之后被释放的块必须被遗忘，
+// 因此下一次分配来自新的内存。
+fn check_reset(allocator: &dyn GlobalAlloc, resettable: &dyn ResettableAllocator) {
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        // Keep an obstacle so the freed block is not at the top of a bump heap
+        let _obstacle = allocator.alloc(layout);
+        allocator.dealloc(ptr, layout);
+
+        resettable.reset();
+
+        let fresh = allocator.alloc(layout);
+        assert!(!fresh.is_null());
+        assert_ne!(fresh, ptr, "reset must drop the free list");
+        fresh.write_bytes(0x42, layout.size());
+
+        resettable.reset();
+    }
+}
+
+#[test]
+fn test_reset_through_trait_object() {
+    reset_heap();
+    let free_list = FreeListAllocator::new();
+    let bump = BumpFreeListAllocator::new();
+    let segregated = SegregatedBumpAllocator::new();
+
+    check_reset(&free_list, &free_list);
+    check_reset(&bump, &bump);
+    check_reset(&segregated, &segregated);
+
+    let all: [&dyn ResettableAllocator; 3] = [&free_list, &bump, &segregated];
+    for allocator in all {
+        unsafe { allocator.reset() };
+    }
+    reset_heap();
+}
+
+// A fresh heap reports no grown pages; a multi-page allocation grows exactly the pages it needs
+// 新的堆报告未增长任何页；跨多页的分配恰好增长其所需的页数
+fn check_pages_grown(allocator: &dyn GlobalAlloc) {
+    reset_heap();
+    assert_eq!(heap_pages_grown(), PageCount(0));
+    let layout = Layout::from_size_align(2 * PAGE_SIZE + 100, 16).unwrap();
+    assert!(!unsafe { allocator.alloc(layout) }.is_null());
+    assert_eq!(heap_pages_grown(), PageCount(3));
+    reset_heap();
+    assert_eq!(heap_pages_grown(), PageCount(0));
+}
+
+#[test]
+fn test_heap_pages_grown() {
+    check_pages_grown(&FreeListAllocator::new());
+    check_pages_grown(&BumpFreeListAllocator::new());
+    check_pages_grown(&SegregatedBumpAllocator::new());
+}
+
+// Offsets into the mock heap do not depend on where the system allocator placed it:
+// after every reset the first block sits at the same offset, on page 0
+// 模拟堆中的偏移不取决于系统分配器将其放在何处：每次重置后第一个块都位于相同的偏移，即第 0 页
+fn check_first_block_offset(allocator: &dyn GlobalAlloc, resettable: &dyn ResettableAllocator) {
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let mut offsets = [0; 2];
+    for offset in &mut offsets {
+        unsafe { resettable.reset() };
+        reset_heap();
+        let ptr = unsafe { allocator.alloc(layout) };
+        *offset = mock_heap_offset(ptr).expect("block lies in the mock heap");
+    }
+    assert_eq!(offsets[0], offsets[1]);
+    assert_eq!(offsets[0] / PAGE_SIZE, 0);
+
+    // Memory outside the mock heap has no offset
+    let local = 0u8;
+    assert_eq!(mock_heap_offset(&local), None);
+}
+
+#[test]
+fn test_mock_heap_offset_is_deterministic() {
+    let freelist = FreeListAllocator::new();
+    check_first_block_offset(&freelist, &freelist);
+    let bump = BumpFreeListAllocator::new();
+    check_first_block_offset(&bump, &bump);
+    let segregated = SegregatedBumpAllocator::new();
+    check_first_block_offset(&segregated, &segregated);
+
+    // A bump allocator hands out the very first bytes of the heap
+    unsafe { bump.reset() };
+    reset_heap();
+    let ptr = unsafe { bump.alloc(Layout::from_size_align(64, 16).unwrap()) };
+    assert_eq!(mock_heap_offset(ptr), Some(0));
+}
+
+// A pinned heap gives the same first address after every reset, and its base is aligned
+// to the heap size, so the low address bits match those of any other run
+// 固定的堆在每次重置后给出相同的首个地址，且其基址按堆大小对齐，
+// 因此地址的低位与任何其他运行一致
+#[test]
+fn test_pinned_mock_heap_is_reproducible() {
+    let allocator = FreeListAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let mut first = [0; 2];
+    for addr in &mut first {
+        unsafe { allocator.reset() };
+        pin_mock_heap();
+        *addr = unsafe { allocator.alloc(layout) } as usize;
+    }
+    assert_eq!(first[0], first[1]);
+    let base = first[0] - mock_heap_offset(first[0] as *const u8).unwrap();
+    assert_eq!(base % MOCK_MEMORY_SIZE.next_power_of_two(), 0);
+
+    // Resizing keeps the pin
+    set_mock_memory_size(4 * PAGE_SIZE);
+    let ptr = unsafe { grow_memory(1) } * PAGE_SIZE;
+    assert_eq!(ptr % (4 * PAGE_SIZE), 0);
+    set_mock_memory_size(MOCK_MEMORY_SIZE);
+}
+
+#[test]
+fn test_try_grow_memory_exhaustion() {
+    set_mock_memory_size(4 * PAGE_SIZE);
+    let first = unsafe { try_grow_memory(3) }.expect("3 of 4 pages fit");
+    assert_eq!(unsafe { try_grow_memory(2) }, None);
+    assert_eq!(unsafe { try_grow_memory(1) }, Some(first + 3));
+    assert_eq!(unsafe { try_grow_memory(1) }, None);
+    assert_eq!(unsafe { grow_memory(1) }, usize::MAX);
+    set_mock_memory_size(MOCK_MEMORY_SIZE);
+}
+
+// The first allocation reserves all initial pages in one growth; filling them grows nothing
+// 首次分配一次性保留全部初始页；填满这些页不再增长内存
+fn check_initial_pages(allocator: &dyn GlobalAlloc) {
+    reset_heap();
+    let layout = Layout::from_size_align(PAGE_SIZE / 2, 16).unwrap();
+    assert!(!unsafe { allocator.alloc(layout) }.is_null());
+    assert_eq!(heap_pages_grown(), PageCount(16));
+    for _ in 0..20 {
+        assert!(!unsafe { allocator.alloc(layout) }.is_null());
+    }
+    assert_eq!(heap_pages_grown(), PageCount(16));
+    reset_heap();
+}
+
+#[test]
+fn test_with_initial_pages() {
+    check_initial_pages(&BumpFreeListAllocator::with_initial_pages(PageCount(16)));
+    check_initial_pages(&SegregatedBumpAllocator::with_initial_pages(PageCount(16)));
+}
+
+// Growing by half of the region makes 64 pages of single-page requests take a handful of
+// growths instead of 64
+// 按区域的一半增长时，64 页的单页请求只需少数几次增长，而不是 64 次
+fn check_amortized_growth(allocator: &dyn GlobalAlloc) {
+    reset_heap();
+    let layout = Layout::from_size_align(PAGE_SIZE, 16).unwrap();
+    let mut growths = 0;
+    let mut grown = heap_pages_grown();
+    for _ in 0..64 {
+        assert!(!unsafe { allocator.alloc(layout) }.is_null());
+        let now = heap_pages_grown();
+        if now != grown {
+            growths += 1;
+            grown = now;
+        }
+    }
+    assert!(grown.0 >= 64);
+    assert!(growths <= 10, "{growths} growths");
+    reset_heap();
+}
+
+#[test]
+fn test_amortized_growth() {
+    let policy = GrowthPolicy {
+        initial_pages: PageCount(4),
+        divisor: 2,
+    };
+    check_amortized_growth(&BumpFreeListAllocator::new().with_growth(policy));
+    check_amortized_growth(&SegregatedBumpAllocator::new().with_growth(policy));
+}
+
+#[test]
+fn test_initial_pages_fall_back_when_memory_is_short() {
+    // 8 pages cannot be reserved, but the single page the request needs can
+    // 无法保留 8 页，但请求所需的单页可以
+    set_mock_memory_size(4 * PAGE_SIZE);
+    let allocator = BumpFreeListAllocator::with_initial_pages(PageCount(8));
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    assert!(!unsafe { allocator.alloc(layout) }.is_null());
+    assert_eq!(heap_pages_grown(), PageCount(1));
+    set_mock_memory_size(MOCK_MEMORY_SIZE);
+}
+
+// Pages added by each memory growth while allocating `count` almost page-sized blocks
+// 分配 `count` 个接近一页大小的块时，每次内存增长所增加的页数
+fn growth_jumps(allocator: &dyn GlobalAlloc, count: usize) -> Vec<usize> {
+    reset_heap();
+    let layout = Layout::from_size_align(PAGE_SIZE - 256, 16).unwrap();
+    let mut jumps = Vec::new();
+    let mut grown = heap_pages_grown();
+    for _ in 0..count {
+        assert!(!unsafe { allocator.alloc(layout) }.is_null());
+        let now = heap_pages_grown();
+        if now != grown {
+            jumps.push(now.0 - grown.0);
+            grown = now;
+        }
+    }
+    reset_heap();
+    jumps
+}
+
+// Doubling: once the heap spans a few pages, each growth requests more than the single page
+// the request needs, and 64 pages take far fewer growths than 64
+// 翻倍增长：堆跨越若干页后，每次增长申请的页数多于请求所需的单页，64 页远少于 64 次增长
+fn check_doubling_growth(allocator: &dyn GlobalAlloc) {
+    let jumps = growth_jumps(allocator, 64);
+    assert!(jumps.iter().skip(1).any(|&pages| pages > 1), "{jumps:?}");
+    assert!(jumps.len() <= 8, "{jumps:?}");
+}
+
+#[test]
+fn test_doubling_growth() {
+    check_doubling_growth(&FreeListAllocator::new().with_growth(GrowthPolicy::DOUBLING));
+    check_doubling_growth(&BumpFreeListAllocator::new().with_growth(GrowthPolicy::DOUBLING));
+    check_doubling_growth(&SegregatedBumpAllocator::new().with_growth(GrowthPolicy::DOUBLING));
+}
+
+// The `heap_growth` benchmark workload: exact growth calls `grow_memory` for every page,
+// doubling only a handful of times
+// `heap_growth` 基准测试的负载：精确增长每一页都调用 `grow_memory`，翻倍增长只调用寥寥几次
+#[test]
+fn test_heap_growth_bench_counts() {
+    let exact = growth_jumps(
+        &FreeListAllocator::new().with_growth(GrowthPolicy::EXACT),
+        256,
+    );
+    let doubling = growth_jumps(
+        &FreeListAllocator::new().with_growth(GrowthPolicy::DOUBLING),
+        256,
+    );
+    assert_eq!(exact, vec![1; 256]);
+    assert!(doubling.len() <= 9, "{doubling:?}");
+    assert_eq!(doubling.iter().sum::<usize>(), 256, "{doubling:?}");
+}
@@ -0,0 +1,76 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{BumpFreeListAllocator, RingArena};
+use lite_alloc::{PageCount, ResettableAllocator, heap_pages_grown, reset_heap};
+use std::alloc::{GlobalAlloc, Layout};
+
+#[test]
+fn test_region_reused_after_full_lap() {
+    reset_heap();
+    let ring: RingArena<3> = RingArena::from_arenas(
+        [const { BumpFreeListAllocator::with_initial_pages(PageCount(1)) }; 3],
+    );
+    let layout = Layout::from_size_align(256, 16).unwrap();
+
+    unsafe {
+        assert_eq!(ring.index(), 0);
+        let first = ring.current().alloc(layout);
+        assert!(!first.is_null());
+        first.write_bytes(0xAA, layout.size());
+        let second = ring.current().alloc(layout);
+        assert!(!second.is_null());
+
+        // Fill the other regions; region 0 stays untouched meanwhile
+        for index in 1..3 {
+            let arena = ring.advance();
+            assert_eq!(ring.index(), index);
+            let ptr = arena.alloc(layout);
+            assert!(!ptr.is_null());
+            assert_ne!(ptr, first);
+            assert_eq!(
+                *first, 0xAA,
+                "region 0 must survive until it comes back around"
+            );
+        }
+        let pages = heap_pages_grown();
+
+        // Back to region 0: it was reset, so the bump pointer starts over
+        let arena = ring.advance();
+        assert_eq!(ring.index(), 0);
+        let reused = arena.alloc(layout);
+        assert_eq!(reused, first, "region 0 must be reset on the way back");
+        assert_eq!(arena.alloc(layout), second);
+        assert_eq!(heap_pages_grown(), pages, "reuse must not grow memory");
+
+        ring.reset();
+    }
+    reset_heap();
+}
+
+#[test]
+fn test_advance_drops_free_blocks() {
+    reset_heap();
+    let ring: RingArena<2> = RingArena::new();
+    let small = Layout::from_size_align(32, 16).unwrap();
+    let large = Layout::from_size_align(512, 16).unwrap();
+
+    unsafe {
+        let base = ring.current().alloc(large);
+        assert!(!base.is_null());
+        let _obstacle = ring.current().alloc(small);
+        ring.current().dealloc(base, large);
+
+        ring.advance();
+        let arena = ring.advance();
+
+        // The freed block was dropped with the rest of the region, so the heap is
+        // carved from its start again instead of handing the block out a second time
+        let a = arena.alloc(small);
+        let b = arena.alloc(small);
+        assert_eq!(a, base);
+        assert_eq!(b as usize, base as usize + arena.block_size(small));
+
+        ring.reset();
+    }
+    reset_heap();
+}
@@ -1,25 +1,25 @@
-use lite_alloc::reset_heap;
-use lite_alloc::single_threaded::SegregatedBumpAllocator;
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{Scope, SegregatedBumpAllocator};
+use lite_alloc::{DefaultMemory, PAGE_SIZE, PageCount, heap_pages_grown, reset_heap};
 use std::alloc::{GlobalAlloc, Layout};
-use std::sync::{Mutex, MutexGuard};
 
-static TEST_MUTEX: Mutex<()> = Mutex::new(());
-
-struct SafeAllocator {
-    inner: SegregatedBumpAllocator,
-    _guard: MutexGuard<'static, ()>,
+struct SafeAllocator<const N: usize = 4> {
+    inner: SegregatedBumpAllocator<DefaultMemory, N>,
 }
 
 impl SafeAllocator {
     fn new() -> Self {
-        let guard = TEST_MUTEX.lock().unwrap_or_else(|p| p.into_inner());
-        unsafe {
-            SegregatedBumpAllocator::reset();
-            reset_heap();
-            Self {
-                inner: SegregatedBumpAllocator::new(),
-                _guard: guard,
-            }
+        SafeAllocator::with_bins([16, 32, 64, 128])
+    }
+}
+
+impl<const N: usize> SafeAllocator<N> {
+    fn with_bins(bin_sizes: [usize; N]) -> Self {
+        // State is per-instance and the mock heap is per-thread: no lock needed
+        reset_heap();
+        Self {
+            inner: SegregatedBumpAllocator::with_bins(DefaultMemory, bin_sizes),
         }
     }
 
@@ -37,12 +37,10 @@ impl SafeAllocator {
     }
 }
 
-impl Drop for SafeAllocator {
+impl<const N: usize> Drop for SafeAllocator<N> {
     fn drop(&mut self) {
-        unsafe {
-            SegregatedBumpAllocator::reset();
-            reset_heap();
-        }
+        unsafe { self.inner.reset() };
+        reset_heap();
     }
 }
 
@@ -69,6 +67,7 @@ fn test_small_bins() {
     assert_ne!(ptr3, ptr2);
 }
 
+#[cfg(not(feature = "large-reuse"))]
 #[test]
 fn test_large_bypass() {
     let allocator = SafeAllocator::new();
@@ -84,6 +83,7 @@ fn test_large_bypass() {
     assert_ne!(ptr1, ptr2);
 }
 
+#[cfg(not(feature = "track-sizes"))]
 #[test]
 fn test_mixed_bins() {
     let allocator = SafeAllocator::new();
@@ -128,8 +128,259 @@ fn test_high_alignment_bypass() {
     assert_eq!(ptr as usize % 128, 0);
 
     allocator.dealloc(ptr, layout);
-    // Cannot reuse because it didn't go to Bin 0 (because of align)
-    // nor can it solve align requirement from Bin 0 easily.
+    // It did not go to Bin 0, but the high-align path takes it back
+    assert_eq!(allocator.alloc(layout), ptr);
+}
+
+#[cfg(not(feature = "track-sizes"))]
+#[test]
+fn test_usable_size_bins() {
+    let allocator = SafeAllocator::new();
+    for (size, expected) in [
+        (1, 16),
+        (16, 16),
+        (17, 32),
+        (48, 64),
+        (100, 128),
+        (200, 200),
+    ] {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let ptr = allocator.alloc(layout);
+        let usable = unsafe { allocator.inner.usable_size(ptr, layout) };
+        assert_eq!(usable, expected);
+        assert!(usable >= size);
+        unsafe { ptr.write_bytes(0xEE, usable) };
+        allocator.dealloc(ptr, layout);
+    }
+}
+
+#[cfg(not(feature = "track-sizes"))]
+#[test]
+fn test_custom_bins_reuse_within_class() {
+    let allocator = SafeAllocator::with_bins([24, 48, 96, 192]);
+
+    // (smallest size, largest size) of each class
+    for (lo, hi) in [(1, 24), (25, 48), (49, 96), (97, 192)] {
+        let l_hi = Layout::from_size_align(hi, 8).unwrap();
+        let l_lo = Layout::from_size_align(lo, 8).unwrap();
+
+        let ptr = allocator.alloc(l_hi);
+        assert!(!ptr.is_null());
+        assert_eq!(unsafe { allocator.inner.usable_size(ptr, l_hi) }, hi);
+        unsafe { ptr.write_bytes(0x5A, hi) };
+        allocator.dealloc(ptr, l_hi);
+
+        // Any size of the same class pops the freed block
+        let reused = allocator.alloc(l_lo);
+        assert_eq!(ptr, reused);
+        allocator.dealloc(reused, l_lo);
+    }
+}
+
+#[cfg(not(feature = "track-sizes"))]
+#[test]
+fn test_custom_bins_isolation() {
+    let allocator = SafeAllocator::with_bins([24, 48, 96, 192]);
+    let l24 = Layout::from_size_align(20, 8).unwrap();
+    let l48 = Layout::from_size_align(40, 8).unwrap();
+
+    let p24 = allocator.alloc(l24);
+    allocator.dealloc(p24, l24);
+
+    // A 48-byte class request must not take the 24-byte block
+    let p48 = allocator.alloc(l48);
+    assert_ne!(p24, p48);
+
+    // Fresh blocks are carved from the bump pointer at the class size
+    let a = allocator.alloc(l48);
+    let b = allocator.alloc(l48);
+    assert_eq!(b as usize - a as usize, 48);
+}
+
+#[cfg(not(feature = "track-sizes"))]
+#[test]
+fn test_custom_bins_large_bypass() {
+    let allocator = SafeAllocator::with_bins([24, 48, 96, 192]);
+    let l192 = Layout::from_size_align(192, 8).unwrap();
+    let l193 = Layout::from_size_align(193, 8).unwrap();
+
+    // Park a block in the largest bin
+    let binned = allocator.alloc(l192);
+    allocator.dealloc(binned, l192);
+
+    // Larger than the biggest class: bypasses the bins
+    let large = allocator.alloc(l193);
+    assert!(!large.is_null());
+    assert_ne!(binned, large);
+    assert_eq!(unsafe { allocator.inner.usable_size(large, l193) }, 193);
+
+    // The binned block is still there
+    assert_eq!(allocator.alloc(l192), binned);
+}
+
+// Two extra bins move the large threshold to 512 bytes: everything up to it is recycled
+#[cfg(not(feature = "track-sizes"))]
+#[test]
+fn test_large_threshold_512_five_bins() {
+    let allocator = SafeAllocator::with_bins([32, 64, 128, 256, 512]);
+    assert_eq!(allocator.inner.large_threshold(), 512);
+
+    for (lo, hi) in [(129, 256), (257, 512)] {
+        let l_hi = Layout::from_size_align(hi, 8).unwrap();
+        let l_lo = Layout::from_size_align(lo, 8).unwrap();
+        let ptr = allocator.alloc(l_hi);
+        assert!(!ptr.is_null());
+        assert_eq!(unsafe { allocator.inner.usable_size(ptr, l_lo) }, hi);
+        allocator.dealloc(ptr, l_hi);
+        assert_eq!(
+            allocator.alloc(l_lo),
+            ptr,
+            "size {lo} must reuse the {hi}-byte bin"
+        );
+    }
+
+    // One byte past the threshold takes the large path and leaves the last bin alone
+    let l512 = Layout::from_size_align(512, 8).unwrap();
+    let l513 = Layout::from_size_align(513, 8).unwrap();
+    let binned = allocator.alloc(l512);
+    allocator.dealloc(binned, l512);
+    let large = allocator.alloc(l513);
+    assert_ne!(large, binned);
+    assert_eq!(unsafe { allocator.inner.usable_size(large, l513) }, 513);
+    assert_eq!(allocator.alloc(l512), binned);
+    assert_eq!(allocator.inner.bin_depths(), [0; 5]);
+}
+
+#[cfg(feature = "large-reuse")]
+#[test]
+fn test_large_reuse_smaller_request() {
+    let allocator = SafeAllocator::new();
+    let l512 = Layout::from_size_align(512, 16).unwrap();
+    let l400 = Layout::from_size_align(400, 16).unwrap();
+
+    let p512 = allocator.alloc(l512);
+    // Obstacle so the freed block is not at the heap top
+    let _obstacle = allocator.alloc(l512);
+    allocator.dealloc(p512, l512);
+
+    let p400 = allocator.alloc(l400);
+    assert_eq!(p512, p400, "freed 512B block should be reused");
+    unsafe { p400.write_bytes(0x77, 400) };
+}
+
+#[cfg(feature = "large-reuse")]
+#[test]
+fn test_large_reuse_too_small() {
+    let allocator = SafeAllocator::new();
+    let l512 = Layout::from_size_align(512, 16).unwrap();
+    let l600 = Layout::from_size_align(600, 16).unwrap();
+
+    let p512 = allocator.alloc(l512);
+    allocator.dealloc(p512, l512);
+
+    let p600 = allocator.alloc(l600);
+    assert!(!p600.is_null());
+    assert_ne!(p512, p600, "600B request must not take the 512B block");
+
+    // The 512B block is still available
+    assert_eq!(allocator.alloc(l512), p512);
+}
+
+#[cfg(all(feature = "large-reuse", not(feature = "track-sizes")))]
+#[test]
+fn test_large_reuse_splits_remainder() {
+    let allocator = SafeAllocator::new();
+    let l1024 = Layout::from_size_align(1024, 16).unwrap();
+    let l400 = Layout::from_size_align(400, 16).unwrap();
+
+    let p = allocator.alloc(l1024);
+    allocator.dealloc(p, l1024);
+
+    // 1024 = 400 + 624 remainder, which stays reusable
+    let a = allocator.alloc(l400);
+    let b = allocator.alloc(l400);
+    assert_eq!(a, p);
+    assert_eq!(b as usize, p as usize + 400);
+}
+
+#[cfg(feature = "large-reuse")]
+#[test]
+fn test_large_reuse_smaller_request_does_not_leak() {
+    let allocator = SafeAllocator::new();
+    let l512 = Layout::from_size_align(512, 16).unwrap();
+    let l400 = Layout::from_size_align(400, 16).unwrap();
+
+    // The 400B request splits the freed 512B block; freeing it merges the 112B tail back,
+    // so the next 512B request reuses the block instead of bumping a new one
+    // 400B 请求会切分已释放的 512B 块；释放时 112B 的尾部被合并回来，
+    // 因此下一个 512B 请求复用该块，而不是 Bump 一个新块
+    let mut grown = None;
+    for _ in 0..1000 {
+        let p = allocator.alloc(l512);
+        assert!(!p.is_null());
+        allocator.dealloc(p, l512);
+        let q = allocator.alloc(l400);
+        assert_eq!(q, p);
+        allocator.dealloc(q, l400);
+        assert_eq!(*grown.get_or_insert(heap_pages_grown()), heap_pages_grown());
+    }
+}
+
+#[test]
+fn test_independent_instances() {
+    reset_heap();
+    let a = SegregatedBumpAllocator::new();
+    let b = SegregatedBumpAllocator::new();
+    let layout = Layout::from_size_align(32, 8).unwrap();
+
+    unsafe {
+        let pa = a.alloc(layout);
+        let pb = b.alloc(layout);
+        assert!(!pa.is_null() && !pb.is_null());
+        assert_ne!(pa, pb);
+        pa.write_bytes(0xAA, 32);
+        pb.write_bytes(0xBB, 32);
+
+        // A block freed into `a` is only visible to `a`
+        a.dealloc(pa, layout);
+        let pb2 = b.alloc(layout);
+        assert_ne!(pb2, pa);
+        assert_eq!(a.alloc(layout), pa);
+
+        // Interleaved growth keeps both heaps usable and disjoint
+        let big = Layout::from_size_align(48 * 1024, 16).unwrap();
+        let mut blocks = Vec::new();
+        for _ in 0..4 {
+            let x = a.alloc(big);
+            let y = b.alloc(big);
+            x.write_bytes(0x11, big.size());
+            y.write_bytes(0x22, big.size());
+            blocks.push((x, 0x11));
+            blocks.push((y, 0x22));
+        }
+        for &(p, byte) in &blocks {
+            assert!((0..big.size()).all(|i| *p.add(i) == byte));
+        }
+        assert_eq!(*pb, 0xBB);
+
+        a.reset();
+        b.reset();
+    }
+    reset_heap();
+}
+
+#[cfg(all(feature = "realloc", not(feature = "track-sizes")))]
+#[test]
+fn test_realloc_capacity_follows_custom_threshold() {
+    let allocator = SafeAllocator::with_bins([32, 64, 128, 256, 512]);
+    let layout = Layout::from_size_align(300, 8).unwrap();
+    let ptr = allocator.alloc(layout);
+    let _obstacle = allocator.alloc(layout);
+
+    // 300 bytes live in the 512-byte bin, so growing within it stays in place
+    assert_eq!(allocator.realloc(ptr, layout, 512), ptr);
+    let grown = Layout::from_size_align(512, 8).unwrap();
+    assert_ne!(allocator.realloc(ptr, grown, 513), ptr);
 }
 
 #[cfg(feature = "realloc")]
@@ -151,3 +402,877 @@ fn test_realloc_bin_growth() {
     assert_ne!(ptr, ptr_new); // Must move to new bin/block
     unsafe { assert_eq!(*ptr_new, 0x11) };
 }
+
+// The size word of `track-sizes` shifts blocks into other bins, so these use the plain layout
+// `track-sizes` 的大小字会使块落入其他 Bin，因此这些测试使用普通布局
+#[cfg(all(feature = "realloc", not(feature = "track-sizes")))]
+#[test]
+fn test_realloc_promotes_into_free_bin_block() {
+    let allocator = SafeAllocator::new();
+    let l16 = Layout::from_size_align(16, 16).unwrap();
+    let l32 = Layout::from_size_align(32, 16).unwrap();
+
+    // Leave a free 32-byte block behind a 16-byte one sitting at the heap top
+    // 在位于堆顶的 16 字节块之后留下一个空闲的 32 字节块
+    let spare = allocator.alloc(l32);
+    let ptr = allocator.alloc(l16);
+    allocator.dealloc(spare, l32);
+    unsafe { ptr.write_bytes(0x5A, 16) };
+
+    // Growing to 24 bytes takes the free bin block instead of bumping at the heap top
+    // 增长到 24 字节时取用空闲的 Bin 块，而不是在堆顶 Bump
+    let grown = allocator.realloc(ptr, l16, 24);
+    assert_eq!(grown, spare);
+    for i in 0..16 {
+        assert_eq!(unsafe { *grown.add(i) }, 0x5A);
+    }
+
+    // The heap top did not move: the old block is reused, then bumping resumes right after it
+    // 堆顶没有移动：旧块被复用，之后的 Bump 紧接其后继续
+    assert_eq!(allocator.alloc(l16), ptr);
+    assert_eq!(allocator.alloc(l16) as usize, ptr as usize + 16);
+}
+
+#[cfg(all(feature = "realloc", not(feature = "track-sizes")))]
+#[test]
+fn test_realloc_at_heap_top_grows_to_bin_size() {
+    let allocator = SafeAllocator::new();
+    let l16 = Layout::from_size_align(16, 16).unwrap();
+    let l24 = Layout::from_size_align(24, 16).unwrap();
+    let l32 = Layout::from_size_align(32, 16).unwrap();
+
+    let ptr = allocator.alloc(l16);
+    let grown = allocator.realloc(ptr, l16, 24);
+    assert_eq!(grown, ptr);
+
+    // The grown block spans the whole 32-byte bin, so reusing it cannot overlap the next one
+    // 扩容后的块占据整个 32 字节的 Bin，因此复用它不会与下一个块重叠
+    let next = allocator.alloc(l16);
+    assert_eq!(next as usize, ptr as usize + 32);
+    allocator.dealloc(grown, l24);
+    assert_eq!(allocator.alloc(l32), ptr);
+}
+
+#[cfg(feature = "realloc")]
+#[test]
+fn test_realloc_keeps_high_alignment() {
+    for align in [32, 64, 256, 4096] {
+        let allocator = SafeAllocator::new();
+        let _first = allocator.alloc(Layout::from_size_align(8, 8).unwrap());
+        let layout = Layout::from_size_align(200, align).unwrap();
+        let ptr = allocator.alloc(layout);
+        assert_eq!(ptr as usize % align, 0);
+        unsafe { ptr.write_bytes(0x4D, layout.size()) };
+
+        let grown = allocator.realloc(ptr, layout, 600);
+        assert_eq!(grown as usize % align, 0, "heap top, align {align}");
+        let layout = Layout::from_size_align(600, align).unwrap();
+
+        let _obstacle = allocator.alloc(Layout::from_size_align(8, 8).unwrap());
+        let moved = allocator.realloc(grown, layout, 5000);
+        assert!(!moved.is_null());
+        assert_eq!(moved as usize % align, 0, "moved, align {align}");
+        let data = unsafe { std::slice::from_raw_parts(moved, 200) };
+        assert!(data.iter().all(|&x| x == 0x4D));
+    }
+}
+
+#[test]
+fn test_scope_reclaims_on_drop() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(256, 16).unwrap();
+    let before = allocator.alloc(layout);
+
+    let first = unsafe {
+        let scope = Scope::new(&allocator.inner);
+        let a = scope.alloc(layout);
+        let b = scope.alloc(layout);
+        assert!(!a.is_null() && !b.is_null());
+        a.write_bytes(0x11, layout.size());
+        b.write_bytes(0x22, layout.size());
+        a
+    };
+
+    // The scope's memory is handed out again
+    assert_eq!(allocator.alloc(layout), first);
+    assert_ne!(before, first);
+}
+
+#[test]
+fn test_scope_nested() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(256, 16).unwrap();
+
+    unsafe {
+        let outer = Scope::new(&allocator.inner);
+        let x = outer.alloc(layout);
+        let y = {
+            let inner = Scope::new(&allocator.inner);
+            let y = inner.alloc(layout);
+            let _z = inner.alloc(layout);
+            y
+        };
+        // Inner scope released y and z, x is still live
+        assert_eq!(outer.alloc(layout), y);
+        drop(outer);
+
+        // Outer scope released everything
+        assert_eq!(allocator.alloc(layout), x);
+    }
+}
+
+#[test]
+fn test_scope_drops_blocks_freed_inside() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(256, 16).unwrap();
+
+    let freed = unsafe {
+        let scope = Scope::new(&allocator.inner);
+        let a = scope.alloc(layout);
+        let _b = scope.alloc(layout);
+        scope.dealloc(a, layout);
+        a
+    };
+
+    // `a` must not be both in a free list and below the bump pointer
+    let p1 = allocator.alloc(layout);
+    let p2 = allocator.alloc(layout);
+    assert_eq!(p1, freed);
+    assert_ne!(p1, p2);
+}
+
+#[cfg(feature = "poison")]
+#[test]
+fn test_poison_fills_freed_block() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let a = allocator.alloc(layout);
+    unsafe { a.write_bytes(0xAA, 64) };
+    allocator.dealloc(a, layout);
+    // The bin link occupies the first word only
+    for i in std::mem::size_of::<usize>()..64 {
+        assert_eq!(unsafe { *a.add(i) }, 0xDE);
+    }
+    assert_eq!(allocator.alloc(layout), a);
+    allocator.dealloc(a, layout);
+}
+
+#[cfg(all(feature = "poison", debug_assertions))]
+#[test]
+#[should_panic(expected = "use after free")]
+fn test_poison_detects_write_after_free() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let a = allocator.alloc(layout);
+    allocator.dealloc(a, layout);
+    unsafe { *a.add(40) = 0x11 };
+    allocator.alloc(layout);
+}
+
+#[cfg(not(feature = "panic-on-oom"))]
+#[test]
+fn test_try_alloc_result() {
+    let allocator = SafeAllocator::new();
+    lite_alloc::set_mock_memory_size(1024 * 1024);
+
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let ptr = unsafe { allocator.inner.try_alloc(layout) }.expect("small request fits");
+    assert_eq!(ptr.as_ptr() as usize % 16, 0);
+    unsafe { ptr.as_ptr().write_bytes(0x5A, 64) };
+    allocator.dealloc(ptr.as_ptr(), layout);
+
+    // 2MB cannot fit in a 1MB mock heap
+    let too_big = Layout::from_size_align(2 * 1024 * 1024, 16).unwrap();
+    assert_eq!(
+        unsafe { allocator.inner.try_alloc(too_big) },
+        Err(lite_alloc::AllocError)
+    );
+
+    unsafe { allocator.inner.reset() };
+    lite_alloc::set_mock_memory_size(lite_alloc::MOCK_MEMORY_SIZE);
+}
+
+#[cfg(not(feature = "track-sizes"))]
+#[test]
+fn test_custom_min_align() {
+    reset_heap();
+    let allocator =
+        SegregatedBumpAllocator::<DefaultMemory, 3, 8>::with_min_align(DefaultMemory, [8, 24, 40]);
+    let l8 = Layout::from_size_align(8, 8).unwrap();
+    let p1 = unsafe { allocator.alloc(l8) };
+    let p2 = unsafe { allocator.alloc(l8) };
+    assert_eq!(p1 as usize % 8, 0);
+    // 8-byte bins are packed back to back
+    // 8 字节的 Bin 块紧密相邻
+    assert_eq!(p2 as usize - p1 as usize, 8);
+    unsafe { allocator.dealloc(p1, l8) };
+    assert_eq!(unsafe { allocator.alloc(l8) }, p1);
+    // 16-byte alignment exceeds MIN_ALIGN and bypasses the bins
+    // 16 字节对齐超过 MIN_ALIGN，绕过 Bins
+    let l16 = Layout::from_size_align(8, 16).unwrap();
+    assert_eq!(unsafe { allocator.alloc(l16) } as usize % 16, 0);
+    unsafe { allocator.reset() };
+
+    reset_heap();
+    let allocator = SegregatedBumpAllocator::<DefaultMemory, 4, 32>::with_min_align(
+        DefaultMemory,
+        [32, 64, 96, 128],
+    );
+    for size in [1, 32, 33, 100, 200, 300] {
+        let layout = Layout::from_size_align(size, 32).unwrap();
+        let ptr = unsafe { allocator.alloc(layout) };
+        assert_eq!(ptr as usize % 32, 0, "size {size}");
+        assert!(unsafe { allocator.usable_size(ptr, layout) } >= size);
+    }
+    unsafe { allocator.reset() };
+    reset_heap();
+}
+
+#[test]
+#[should_panic(expected = "MIN_ALIGN must be a power of two >= align_of::<usize>()")]
+fn test_min_align_below_word_rejected() {
+    SegregatedBumpAllocator::<DefaultMemory, 1, 2>::with_min_align(DefaultMemory, [16]);
+}
+
+#[cfg(all(feature = "quota", not(feature = "panic-on-oom")))]
+#[test]
+fn test_quota_limits_live_bytes() {
+    let allocator = SafeAllocator::new();
+    allocator.inner.set_quota(Some(512 * 112));
+    // Stays in the 128 bin even with the `track-sizes` size word, which is not charged
+    // 即使加上 `track-sizes` 的大小字也仍在 128 Bin 中，且大小字不计入配额
+    let layout = Layout::from_size_align(112, 16).unwrap();
+
+    let ptrs: Vec<_> = (0..512).map(|_| allocator.alloc(layout)).collect();
+    assert!(ptrs.iter().all(|p| !p.is_null()));
+    assert_eq!(allocator.inner.quota_used(), 512 * 112);
+
+    // The 513th allocation exceeds the quota and must not grow memory
+    // 第 513 次分配超出配额，且不得增长内存
+    let pages = lite_alloc::heap_pages_grown();
+    assert!(allocator.alloc(layout).is_null());
+    assert_eq!(lite_alloc::heap_pages_grown(), pages);
+
+    // Freeing a binned block restores headroom
+    // 释放 Bin 中的块后恢复可用额度
+    allocator.dealloc(ptrs[10], layout);
+    assert!(!allocator.alloc(layout).is_null());
+    assert!(allocator.alloc(layout).is_null());
+}
+
+#[cfg(all(
+    feature = "quota",
+    not(feature = "large-reuse"),
+    not(feature = "panic-on-oom")
+))]
+#[test]
+fn test_quota_counts_leaked_large_blocks() {
+    let allocator = SafeAllocator::new();
+    allocator.inner.set_quota(Some(4096));
+    let layout = Layout::from_size_align(4096, 16).unwrap();
+
+    // Large blocks are leaked on dealloc, so they keep their bytes counted
+    // 大对象块在 dealloc 时被泄漏，因此其字节仍被计入
+    let ptr = allocator.alloc(layout);
+    assert!(!ptr.is_null());
+    allocator.dealloc(ptr, layout);
+    assert_eq!(allocator.inner.quota_used(), 4096);
+    assert!(allocator.alloc(layout).is_null());
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn test_bin_stats_histogram() {
+    let allocator = SafeAllocator::new();
+    let l16 = Layout::from_size_align(10, 8).unwrap();
+    let l64 = Layout::from_size_align(64, 8).unwrap();
+    let l128 = Layout::from_size_align(100, 16).unwrap();
+    let large = Layout::from_size_align(1000, 16).unwrap();
+    let aligned = Layout::from_size_align(16, 64).unwrap();
+
+    let p16: Vec<_> = (0..3).map(|_| allocator.alloc(l16)).collect();
+    let p64 = allocator.alloc(l64);
+    allocator.alloc(l128);
+    allocator.alloc(large);
+    allocator.alloc(aligned);
+
+    // Freed blocks are reused: hits, not misses
+    // 已释放的块被复用：计为命中而非未命中
+    allocator.dealloc(p16[0], l16);
+    allocator.dealloc(p64, l64);
+    allocator.alloc(l16);
+    allocator.alloc(l64);
+
+    let stats = allocator.inner.bin_stats();
+    #[cfg(not(feature = "track-sizes"))]
+    assert_eq!(stats.bin_allocs, [4, 0, 2, 1]);
+    // The 16-byte size word moves every request up a bin
+    // 16 字节的大小字使每个请求上移一个 Bin
+    #[cfg(feature = "track-sizes")]
+    assert_eq!(stats.bin_allocs, [0, 4, 0, 3]);
+    assert_eq!(stats.large_allocs, 2);
+    assert_eq!(stats.bin_hits, 2);
+    assert_eq!(stats.bin_misses, 5);
+}
+
+fn assert_zeroed(ptr: *mut u8, size: usize) {
+    assert!(!ptr.is_null());
+    assert!((0..size).all(|i| unsafe { *ptr.add(i) } == 0));
+}
+
+#[test]
+fn test_alloc_zeroed_bin_reuse_and_bump() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 8).unwrap();
+
+    // Bump-fresh block
+    // 新 Bump 出的块
+    let ptr = unsafe { allocator.inner.alloc_zeroed(layout) };
+    assert_zeroed(ptr, 64);
+
+    // Recycled block: both the list header and the user data must be cleared
+    // 回收的块：链表头和用户数据都必须被清除
+    unsafe { ptr.write_bytes(0xAB, 64) };
+    allocator.dealloc(ptr, layout);
+    let reused = unsafe { allocator.inner.alloc_zeroed(layout) };
+    assert_eq!(reused, ptr);
+    assert_zeroed(reused, 64);
+
+    let large = Layout::from_size_align(1000, 16).unwrap();
+    let ptr = unsafe { allocator.inner.alloc_zeroed(large) };
+    assert_zeroed(ptr, 1000);
+    #[cfg(feature = "large-reuse")]
+    {
+        let _obstacle = allocator.alloc(layout);
+        unsafe { ptr.write_bytes(0xCD, 1000) };
+        allocator.dealloc(ptr, large);
+        let reused = unsafe { allocator.inner.alloc_zeroed(large) };
+        assert_eq!(reused, ptr);
+        assert_zeroed(reused, 1000);
+    }
+}
+
+#[test]
+fn test_alloc_zeroed_after_scope_rewind() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(256, 16).unwrap();
+    allocator.alloc(layout);
+
+    let first = unsafe {
+        let scope = Scope::new(&allocator.inner);
+        let a = scope.alloc(layout);
+        a.write_bytes(0x11, 256);
+        scope.alloc(layout).write_bytes(0x22, 256);
+        a
+    };
+
+    // Bumping again over the scope's memory must still hand out zeroes
+    // 再次 Bump 经过作用域的内存时，仍必须返回零
+    let ptr = unsafe {
+        allocator
+            .inner
+            .alloc_zeroed(Layout::from_size_align(400, 16).unwrap())
+    };
+    assert_eq!(ptr, first);
+    assert_zeroed(ptr, 400);
+}
+
+#[cfg(all(feature = "dealloc-check", debug_assertions))]
+#[test]
+#[should_panic(expected = "misaligned pointer")]
+fn test_dealloc_check_misaligned() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let ptr = allocator.alloc(layout);
+    allocator.dealloc(unsafe { ptr.add(1) }, layout);
+}
+
+#[cfg(all(feature = "dealloc-check", debug_assertions))]
+#[test]
+#[should_panic(expected = "outside the heap")]
+fn test_dealloc_check_out_of_range() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let ptr = allocator.alloc(layout);
+    // Above the heap top, though still inside the grown page
+    // 位于堆顶之上，但仍在已增长的页面内
+    allocator.dealloc(unsafe { ptr.add(4096) }, layout);
+}
+
+#[cfg(feature = "track-sizes")]
+#[test]
+fn test_free_without_layout() {
+    let allocator = SafeAllocator::new();
+    // With the 16-byte size word these land in the 32, 32, 64 and 128 bins, then large
+    let sizes = [1, 16, 48, 112, 1000];
+    let layouts = sizes.map(|size| Layout::from_size_align(size, 8).unwrap());
+
+    let ptrs = layouts.map(|layout| {
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % 16, 0);
+        unsafe { ptr.write_bytes(0xAB, layout.size()) };
+        ptr
+    });
+    for ptr in ptrs.iter().rev() {
+        unsafe { allocator.inner.free(*ptr) };
+    }
+
+    // Every binned block went back to its bin: the same sizes pop them again
+    for (layout, ptr) in layouts.iter().zip(ptrs).take(4) {
+        assert_eq!(allocator.alloc(*layout), ptr);
+    }
+    // The large block is reused too when large blocks are recycled
+    #[cfg(feature = "large-reuse")]
+    assert_eq!(allocator.alloc(layouts[4]), ptrs[4]);
+
+    // High-align blocks also record their alignment, so they are recycled as well
+    let aligned = Layout::from_size_align(64, 256).unwrap();
+    let ptr = allocator.alloc(aligned);
+    assert_eq!(ptr as usize % 256, 0);
+    unsafe { allocator.inner.free(ptr) };
+    assert_eq!(allocator.alloc(aligned), ptr);
+}
+
+#[cfg(all(feature = "track-sizes", feature = "quota"))]
+#[test]
+fn test_free_releases_quota() {
+    let allocator = SafeAllocator::new();
+    allocator.inner.set_quota(Some(4096));
+    let small = Layout::from_size_align(24, 8).unwrap();
+    let aligned = Layout::from_size_align(64, 256).unwrap();
+
+    // Only the requested bytes are charged, not the size word in front of them
+    let ptrs = [allocator.alloc(small), allocator.alloc(aligned)];
+    assert_eq!(allocator.inner.quota_used(), 24 + 64);
+    for ptr in ptrs {
+        unsafe { allocator.inner.free(ptr) };
+    }
+    assert_eq!(allocator.inner.quota_used(), 0);
+}
+
+#[cfg(all(feature = "track-sizes", feature = "realloc"))]
+#[test]
+fn test_free_after_realloc() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(24, 8).unwrap();
+    let ptr = allocator.alloc(layout);
+    unsafe { ptr.write_bytes(0x11, 24) };
+    // Keeps the block off the heap top, so growing it has to move it
+    let _guard = allocator.alloc(layout);
+
+    // Moves the block into the large range; the size word follows it
+    let grown = allocator.realloc(ptr, layout, 500);
+    assert!(!grown.is_null());
+    assert_ne!(grown, ptr);
+    assert_eq!(unsafe { *grown.add(23) }, 0x11);
+    unsafe { allocator.inner.free(grown) };
+
+    // The original 64-byte bin block was freed by the move and is reused
+    assert_eq!(allocator.alloc(layout), ptr);
+}
+
+#[test]
+fn test_zero_sized_alloc() {
+    let allocator = SafeAllocator::new();
+    for align in [1, 8, 64, 4096] {
+        let layout = Layout::from_size_align(0, align).unwrap();
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % align, 0);
+        assert_eq!(unsafe { allocator.inner.usable_size(ptr, layout) }, 0);
+        allocator.dealloc(ptr, layout);
+    }
+    assert_eq!(heap_pages_grown(), PageCount(0));
+
+    // Zeroed ZSTs do not touch memory either
+    let zst = Layout::from_size_align(0, 16).unwrap();
+    assert!(!unsafe { allocator.inner.alloc_zeroed(zst) }.is_null());
+    assert_eq!(heap_pages_grown(), PageCount(0));
+}
+
+#[cfg(feature = "realloc")]
+#[test]
+fn test_realloc_from_zero_size() {
+    let allocator = SafeAllocator::new();
+    let zst = Layout::from_size_align(0, 8).unwrap();
+    let ptr = allocator.alloc(zst);
+    let grown = allocator.realloc(ptr, zst, 40);
+    assert_ne!(grown, ptr);
+    unsafe { grown.write_bytes(0x11, 40) };
+    allocator.dealloc(grown, Layout::from_size_align(40, 8).unwrap());
+}
+
+#[test]
+fn test_alloc_array() {
+    let allocator = SafeAllocator::new();
+    let array = unsafe { allocator.inner.alloc_array::<u64>(1000) };
+    assert!(!array.is_null());
+    assert!(array.is_aligned());
+    let values = unsafe { std::slice::from_raw_parts_mut(array, 1000) };
+    for (i, v) in values.iter_mut().enumerate() {
+        *v = i as u64;
+    }
+    assert_eq!(values.iter().sum::<u64>(), 999 * 1000 / 2);
+    unsafe { allocator.inner.dealloc_array(array, 1000) };
+
+    // The byte size overflows: null without touching the heap
+    let grown = heap_pages_grown();
+    assert!(unsafe { allocator.inner.alloc_array::<u64>(usize::MAX / 4) }.is_null());
+    assert_eq!(heap_pages_grown(), grown);
+}
+
+#[test]
+fn test_alloc_with_capacity() {
+    let allocator = SafeAllocator::new();
+    let mut blocks = Vec::new();
+    for size in [1, 24, 100, 200, 3000] {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let (ptr, capacity) = unsafe { allocator.inner.alloc_with_capacity(layout) };
+        assert!(!ptr.is_null());
+        assert!(capacity >= size);
+        // The whole capacity belongs to the block: filling it leaves the others intact
+        unsafe { ptr.write_bytes(blocks.len() as u8 + 1, capacity) };
+        blocks.push((ptr, capacity, layout));
+    }
+    for (i, &(ptr, capacity, layout)) in blocks.iter().enumerate() {
+        assert!((0..capacity).all(|j| unsafe { *ptr.add(j) } == i as u8 + 1));
+        allocator.dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn test_heap_extent_covers_allocations() {
+    let allocator = SafeAllocator::new();
+    assert_eq!(unsafe { allocator.inner.heap_extent() }, (0, 0));
+
+    let layouts = [
+        Layout::from_size_align(8, 8).unwrap(),
+        Layout::from_size_align(100, 16).unwrap(),
+        Layout::from_size_align(3 * PAGE_SIZE, 16).unwrap(),
+        Layout::from_size_align(64, 4096).unwrap(),
+        Layout::from_size_align(24, 8).unwrap(),
+    ];
+    let ptrs: Vec<_> = layouts.iter().map(|&l| allocator.alloc(l)).collect();
+
+    let (base, end) = unsafe { allocator.inner.heap_extent() };
+    assert!(base < end);
+    assert_eq!(base % PAGE_SIZE, 0);
+    assert_eq!(end % PAGE_SIZE, 0);
+    for (&ptr, layout) in ptrs.iter().zip(&layouts) {
+        assert!(!ptr.is_null());
+        assert!(base <= ptr as usize && ptr as usize + layout.size() <= end);
+    }
+    for (&ptr, &layout) in ptrs.iter().zip(&layouts) {
+        allocator.dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn test_block_size_matches_consumption() {
+    let allocator = SafeAllocator::new();
+    assert_eq!(
+        allocator
+            .inner
+            .block_size(Layout::from_size_align(0, 8).unwrap()),
+        0
+    );
+
+    // Back-to-back blocks of one layout sit exactly `block_size` apart
+    // 同一布局的相邻块之间恰好相距 `block_size`
+    for size in [1, 24, 100, 300, 1000] {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let block = allocator.inner.block_size(layout);
+        assert!(block >= size);
+
+        let first = allocator.alloc(layout);
+        let second = allocator.alloc(layout);
+        assert_eq!(second as usize - first as usize, block, "size {size}");
+
+        allocator.dealloc(second, layout);
+        allocator.dealloc(first, layout);
+    }
+}
+
+#[cfg(not(feature = "track-sizes"))]
+#[test]
+fn test_debug_reports_heap_state() {
+    let allocator = SafeAllocator::new();
+    let l16 = Layout::from_size_align(16, 16).unwrap();
+    let l64 = Layout::from_size_align(64, 16).unwrap();
+    let ptrs = [
+        allocator.alloc(l16),
+        allocator.alloc(l16),
+        allocator.alloc(l64),
+    ];
+    allocator.dealloc(ptrs[0], l16);
+    allocator.dealloc(ptrs[1], l16);
+    allocator.dealloc(ptrs[2], l64);
+
+    let top = ptrs[2] as usize + 64;
+    let (_, end) = unsafe { allocator.inner.heap_extent() };
+    let debug = format!("{:?}", allocator.inner);
+    assert!(
+        debug.starts_with(&format!(
+            "SegregatedBumpAllocator {{ heap_top: {top}, heap_end: {end}, \
+             bin_sizes: [16, 32, 64, 128], bin_free: [2, 0, 1, 0]"
+        )),
+        "{debug}"
+    );
+}
+
+#[cfg(feature = "large-reuse")]
+#[test]
+fn test_bin_depth_cap_spills_to_large_list() {
+    reset_heap();
+    let allocator = SegregatedBumpAllocator::new().with_bin_depth_cap(64);
+    let layout = Layout::from_size_align(16, 16).unwrap();
+    let ptrs: Vec<_> = (0..10000)
+        .map(|_| unsafe { allocator.alloc(layout) })
+        .collect();
+    for &ptr in &ptrs {
+        unsafe { allocator.dealloc(ptr, layout) };
+    }
+    assert!(allocator.bin_depths().iter().all(|&depth| depth <= 64));
+
+    // The spilled blocks are still reused: no new memory for the same workload
+    let grown = heap_pages_grown();
+    for _ in 0..10000 {
+        assert!(!unsafe { allocator.alloc(layout) }.is_null());
+    }
+    assert_eq!(heap_pages_grown(), grown);
+    reset_heap();
+}
+
+#[test]
+fn test_bin_depth_cap_rolls_back_heap_top() {
+    reset_heap();
+    let allocator = SegregatedBumpAllocator::new().with_bin_depth_cap(0);
+    let layout = Layout::from_size_align(16, 16).unwrap();
+    let ptrs: Vec<_> = (0..100)
+        .map(|_| unsafe { allocator.alloc(layout) })
+        .collect();
+    // Freed from the top down, every block is the last one bumped
+    for &ptr in ptrs.iter().rev() {
+        unsafe { allocator.dealloc(ptr, layout) };
+    }
+    assert_eq!(allocator.bin_depths(), [0; 4]);
+    assert_eq!(unsafe { allocator.alloc(layout) }, ptrs[0]);
+    reset_heap();
+}
+
+// The grown tail is zero even when the memory it lands on was used before
+#[cfg(feature = "realloc")]
+#[test]
+fn test_realloc_zeroed() {
+    let allocator = SafeAllocator::new();
+    let small = Layout::from_size_align(64, 16).unwrap();
+    let a = allocator.alloc(small);
+    unsafe { a.write_bytes(0xAA, 64) };
+    let dirty = Layout::from_size_align(256, 16).unwrap();
+    let b = allocator.alloc(dirty);
+    unsafe { b.write_bytes(0xBB, 256) };
+    allocator.dealloc(b, dirty);
+
+    let mut ptr = a;
+    let mut old = small;
+    for new_size in [256, 4096] {
+        ptr = unsafe { allocator.inner.realloc_zeroed(ptr, old, new_size) };
+        assert!(!ptr.is_null());
+        let data = unsafe { std::slice::from_raw_parts(ptr, new_size) };
+        assert!(data[..64].iter().all(|&x| x == 0xAA));
+        assert!(data[64..].iter().all(|&x| x == 0), "size {new_size}");
+        old = Layout::from_size_align(new_size, 16).unwrap();
+    }
+}
+
+// After a 1MB reservation, allocations totalling less than that never grow the heap
+#[test]
+fn test_reserve_prevents_growth() {
+    let allocator = SafeAllocator::new();
+    assert!(unsafe { allocator.inner.reserve(1024 * 1024) });
+    let pages = heap_pages_grown();
+
+    let mut total = 0;
+    for size in [24, 100, 512, 4096, 30000].iter().cycle() {
+        if total + size > 512 * 1024 {
+            break;
+        }
+        let ptr = allocator.alloc(Layout::from_size_align(*size, 8).unwrap());
+        assert!(!ptr.is_null());
+        total += size;
+    }
+    assert_eq!(
+        heap_pages_grown(),
+        pages,
+        "reserved memory must cover the allocations"
+    );
+    assert!(!unsafe { allocator.inner.reserve(usize::MAX / 2) });
+}
+
+#[test]
+fn test_grow_in_place_at_heap_top() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let ptr = allocator.alloc(layout);
+    unsafe { ptr.write_bytes(0x6B, 64) };
+
+    // At the heap top: grows, into the large range too
+    assert!(unsafe { allocator.inner.grow_in_place(ptr, layout, 100) });
+    let mid = Layout::from_size_align(100, 16).unwrap();
+    assert!(unsafe { allocator.inner.grow_in_place(ptr, mid, 1000) });
+    assert!((0..64).all(|i| unsafe { *ptr.add(i) } == 0x6B));
+    let grown = Layout::from_size_align(1000, 16).unwrap();
+    unsafe { ptr.write_bytes(0x6C, 1000) };
+
+    // Not at the top any more: the caller has to move the data
+    let other = allocator.alloc(layout);
+    assert!(!other.is_null());
+    assert!(!unsafe { allocator.inner.grow_in_place(ptr, grown, 2000) });
+
+    // Within its bin a block grows anywhere
+    let small = Layout::from_size_align(17, 8).unwrap();
+    let binned = allocator.alloc(small);
+    let _top = allocator.alloc(small);
+    assert!(unsafe { allocator.inner.grow_in_place(binned, small, 32) });
+
+    assert!(unsafe { allocator.inner.shrink_in_place(ptr, grown, 300) });
+    assert!(!unsafe { allocator.inner.shrink_in_place(ptr, grown, 0) });
+    allocator.dealloc(ptr, Layout::from_size_align(300, 16).unwrap());
+    allocator.dealloc(other, layout);
+}
+
+#[cfg(not(feature = "track-sizes"))]
+#[test]
+fn test_check_bins_detects_corruption() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(32, 16).unwrap();
+    let a = allocator.alloc(layout);
+    let b = allocator.alloc(layout);
+    let top = allocator.alloc(layout);
+    allocator.dealloc(a, layout);
+    allocator.dealloc(b, layout);
+    assert!(unsafe { allocator.inner.check_bins() });
+
+    // The freed block `b` heads the 32-byte bin and links to `a`
+    let link = b as *mut usize;
+    assert_eq!(unsafe { link.read() }, a as usize);
+    let corruptions = [
+        ("cycle", b as usize),
+        ("misaligned", a as usize + 8),
+        ("past the heap top", top as usize + 4 * PAGE_SIZE),
+        ("below the heap", 16),
+        ("truncated list", 0),
+    ];
+    for (what, next) in corruptions {
+        unsafe { link.write(next) };
+        assert!(!unsafe { allocator.inner.check_bins() }, "{what}");
+    }
+
+    unsafe { link.write(a as usize) };
+    assert!(unsafe { allocator.inner.check_bins() });
+    allocator.dealloc(top, layout);
+    assert!(unsafe { allocator.inner.check_bins() });
+}
+
+#[test]
+fn test_high_align_blocks_are_reused() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(32, 32).unwrap();
+    let small = Layout::from_size_align(8, 8).unwrap();
+
+    // Warm up, then every round must fit in the memory the first one grew
+    let mut pages = 0;
+    for round in 0..1000 {
+        let ptrs: Vec<_> = (0..8)
+            .map(|i| {
+                // Small blocks in between keep most of them off the heap top
+                let ptr = allocator.alloc(layout);
+                assert_eq!(ptr as usize % 32, 0);
+                unsafe { ptr.write_bytes(i, 32) };
+                ptr
+            })
+            .collect();
+        let guard = allocator.alloc(small);
+        for (i, &ptr) in ptrs.iter().enumerate() {
+            assert!((0..32).all(|j| unsafe { *ptr.add(j) } == i as u8));
+            allocator.dealloc(ptr, layout);
+        }
+        allocator.dealloc(guard, small);
+        if round == 0 {
+            pages = heap_pages_grown().as_usize();
+        }
+    }
+    assert_eq!(heap_pages_grown().as_usize(), pages);
+
+    // Freed at the heap top, the padding goes back too
+    let top = allocator.alloc(layout);
+    allocator.dealloc(top, layout);
+    assert_eq!(allocator.alloc(layout), top);
+}
+
+#[test]
+fn test_pages_needed_for_reports_growth() {
+    let allocator = SafeAllocator::new();
+    // No bump region yet: every byte needs new pages
+    assert_eq!(
+        unsafe { allocator.inner.pages_needed_for(100) },
+        PageCount(1)
+    );
+    // Asking grows nothing
+    assert_eq!(heap_pages_grown(), PageCount(0));
+
+    let ptr = allocator.alloc(Layout::from_size_align(100, 16).unwrap());
+    assert!(!ptr.is_null());
+    let pages = heap_pages_grown();
+    assert_eq!(pages, PageCount(1));
+
+    // The rest of the first page is still free
+    assert_eq!(
+        unsafe { allocator.inner.pages_needed_for(PAGE_SIZE / 2) },
+        PageCount(0)
+    );
+    assert_eq!(
+        unsafe { allocator.inner.pages_needed_for(PAGE_SIZE) },
+        PageCount(1)
+    );
+
+    // Past it: `reserve` grows exactly the reported pages
+    let needed = unsafe { allocator.inner.pages_needed_for(2 * PAGE_SIZE) };
+    assert_eq!(needed, PageCount(2));
+    assert!(unsafe { allocator.inner.reserve(2 * PAGE_SIZE) });
+    assert_eq!(
+        heap_pages_grown().as_usize() - pages.as_usize(),
+        needed.as_usize()
+    );
+    assert_eq!(
+        unsafe { allocator.inner.pages_needed_for(2 * PAGE_SIZE) },
+        PageCount(0)
+    );
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn test_peak_bytes_survives_free() {
+    let allocator = SafeAllocator::new();
+    assert_eq!(allocator.inner.peak_bytes(), 0);
+    let small = Layout::from_size_align(100, 16).unwrap();
+    let _a = allocator.alloc(small);
+    let low = allocator.inner.peak_bytes();
+    assert!((100..PAGE_SIZE).contains(&low), "{low}");
+
+    // A large block raises the mark to about its size, within the grown pages
+    let large = Layout::from_size_align(3 * PAGE_SIZE, 16).unwrap();
+    let ptr = allocator.alloc(large);
+    assert!(!ptr.is_null());
+    let peak = allocator.inner.peak_bytes();
+    assert!(peak >= low + large.size(), "{peak}");
+    assert!(peak <= heap_pages_grown().as_usize() * PAGE_SIZE);
+
+    // Freeing it keeps the mark
+    allocator.dealloc(ptr, large);
+    assert_eq!(allocator.inner.peak_bytes(), peak);
+
+    unsafe { allocator.inner.reset() };
+    assert_eq!(allocator.inner.peak_bytes(), 0);
+}
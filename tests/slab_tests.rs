@@ -0,0 +1,161 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::SlabAllocator;
+use lite_alloc::{PAGE_SIZE, PageCount, heap_pages_grown, reset_heap};
+use std::alloc::{GlobalAlloc, Layout};
+
+const BLOCK: usize = 256;
+const SLOTS_PER_PAGE: usize = PAGE_SIZE / BLOCK;
+
+struct SafeAllocator {
+    inner: SlabAllocator<BLOCK>,
+}
+
+impl SafeAllocator {
+    fn new() -> Self {
+        // State is per-instance and the mock heap is per-thread: no lock needed
+        reset_heap();
+        Self {
+            inner: SlabAllocator::new(),
+        }
+    }
+
+    fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { self.inner.alloc(layout) }
+    }
+
+    fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.inner.dealloc(ptr, layout) }
+    }
+}
+
+impl Drop for SafeAllocator {
+    fn drop(&mut self) {
+        unsafe { self.inner.reset() };
+        reset_heap();
+    }
+}
+
+#[test]
+fn test_exhaust_page_then_grow() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(200, 8).unwrap();
+
+    // One page holds exactly SLOTS_PER_PAGE consecutive slots
+    let first = allocator.alloc(layout);
+    for i in 1..SLOTS_PER_PAGE {
+        let ptr = allocator.alloc(layout);
+        assert_eq!(ptr as usize, first as usize + i * BLOCK);
+    }
+    assert_eq!(heap_pages_grown(), PageCount(1));
+
+    // The next slot needs a second page
+    let next = allocator.alloc(layout);
+    assert_eq!(next as usize, first as usize + PAGE_SIZE);
+    assert_eq!(heap_pages_grown(), PageCount(2));
+}
+
+#[test]
+fn test_lifo_reuse() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(BLOCK, 16).unwrap();
+    let a = allocator.alloc(layout);
+    let b = allocator.alloc(layout);
+    let c = allocator.alloc(layout);
+
+    allocator.dealloc(a, layout);
+    allocator.dealloc(c, layout);
+    // Smaller requests share the same slots
+    let small = Layout::from_size_align(8, 8).unwrap();
+    assert_eq!(allocator.alloc(small), c);
+    assert_eq!(allocator.alloc(small), a);
+    // Free list empty again: carve a fresh slot after `c`
+    assert_eq!(allocator.alloc(small) as usize, c as usize + BLOCK);
+    allocator.dealloc(b, layout);
+}
+
+#[cfg(not(feature = "panic-on-oom"))]
+#[test]
+fn test_rejects_oversized_and_overaligned() {
+    let allocator = SafeAllocator::new();
+    assert!(
+        allocator
+            .alloc(Layout::from_size_align(BLOCK + 1, 8).unwrap())
+            .is_null()
+    );
+    assert_eq!(SlabAllocator::<BLOCK>::SLOT_ALIGN, 256);
+    assert!(
+        !allocator
+            .alloc(Layout::from_size_align(16, 256).unwrap())
+            .is_null()
+    );
+    assert!(
+        allocator
+            .alloc(Layout::from_size_align(16, 512).unwrap())
+            .is_null()
+    );
+    // 48-byte slots are only 16-byte aligned
+    assert_eq!(SlabAllocator::<48>::SLOT_ALIGN, 16);
+}
+
+#[test]
+fn test_blocks_spanning_pages() {
+    let allocator = SlabAllocator::<{ PAGE_SIZE + PAGE_SIZE / 2 }>::new();
+    reset_heap();
+    let layout = Layout::from_size_align(PAGE_SIZE, 8).unwrap();
+    let a = unsafe { allocator.alloc(layout) };
+    let b = unsafe { allocator.alloc(layout) };
+    assert_eq!(b as usize, a as usize + PAGE_SIZE + PAGE_SIZE / 2);
+    assert_eq!(heap_pages_grown(), PageCount(3));
+    unsafe { b.write_bytes(0xEE, PAGE_SIZE) };
+    reset_heap();
+}
+
+#[cfg(all(feature = "realloc", not(feature = "panic-on-oom")))]
+#[test]
+fn test_realloc_stays_in_slot() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(16, 8).unwrap();
+    let ptr = allocator.alloc(layout);
+    assert_eq!(unsafe { allocator.inner.realloc(ptr, layout, BLOCK) }, ptr);
+    assert!(unsafe { allocator.inner.realloc(ptr, layout, BLOCK + 1) }.is_null());
+}
+
+#[test]
+fn test_alloc_with_capacity() {
+    let allocator = SafeAllocator::new();
+    let mut blocks = Vec::new();
+    for size in [1, 24, 100, 200, BLOCK] {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let (ptr, capacity) = unsafe { allocator.inner.alloc_with_capacity(layout) };
+        assert!(!ptr.is_null());
+        assert!(capacity >= size);
+        assert_eq!(capacity, BLOCK);
+        // The whole capacity belongs to the block: filling it leaves the others intact
+        unsafe { ptr.write_bytes(blocks.len() as u8 + 1, capacity) };
+        blocks.push((ptr, capacity, layout));
+    }
+    for (i, &(ptr, capacity, layout)) in blocks.iter().enumerate() {
+        assert!((0..capacity).all(|j| unsafe { *ptr.add(j) } == i as u8 + 1));
+        allocator.dealloc(ptr, layout);
+    }
+}
+
+// After a 1MB reservation, slots totalling less than that never grow the heap
+#[test]
+fn test_reserve_prevents_growth() {
+    let allocator = SafeAllocator::new();
+    assert!(unsafe { allocator.inner.reserve(1024 * 1024) });
+    let pages = heap_pages_grown();
+
+    let layout = Layout::from_size_align(BLOCK, 8).unwrap();
+    for _ in 0..512 * 1024 / BLOCK {
+        assert!(!allocator.alloc(layout).is_null());
+    }
+    assert_eq!(
+        heap_pages_grown(),
+        pages,
+        "reserved memory must cover the allocations"
+    );
+    assert!(!unsafe { allocator.inner.reserve(usize::MAX / 2) });
+}
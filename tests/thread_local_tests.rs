@@ -0,0 +1,106 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::{FreeListAllocator, SegregatedBumpAllocator, ThreadLocalAlloc};
+use lite_alloc::{MemorySource, PAGE_SIZE, SharedMemorySource};
+use std::alloc::{GlobalAlloc, Layout};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const SHARED_PAGES: usize = 128;
+
+// Pages carved from one process-wide region that is never freed, so blocks outlive the
+// thread that allocated them
+// 从一块永不释放的进程级区域中切分的页面，因此块的寿命长于分配它们的线程
+struct SharedPages;
+
+static BASE: OnceLock<usize> = OnceLock::new();
+static USED_PAGES: AtomicUsize = AtomicUsize::new(0);
+
+impl MemorySource for SharedPages {
+    unsafe fn grow(&self, pages: usize) -> usize {
+        let base = *BASE.get_or_init(|| {
+            let layout = Layout::from_size_align(SHARED_PAGES * PAGE_SIZE, PAGE_SIZE).unwrap();
+            unsafe { std::alloc::alloc_zeroed(layout) as usize }
+        });
+        let first = USED_PAGES.fetch_add(pages, Ordering::Relaxed);
+        if first + pages > SHARED_PAGES {
+            return usize::MAX;
+        }
+        base / PAGE_SIZE + first
+    }
+}
+
+unsafe impl SharedMemorySource for SharedPages {}
+
+std::thread_local! {
+    static FREE_LIST: FreeListAllocator<SharedPages> =
+        const { FreeListAllocator::with_source(SharedPages) };
+    static SEGREGATED: SegregatedBumpAllocator<SharedPages> =
+        const { SegregatedBumpAllocator::with_source(SharedPages) };
+}
+
+static FREE_LIST_ALLOC: ThreadLocalAlloc<FreeListAllocator<SharedPages>> =
+    ThreadLocalAlloc::new(&FREE_LIST);
+static SEGREGATED_ALLOC: ThreadLocalAlloc<SegregatedBumpAllocator<SharedPages>> =
+    ThreadLocalAlloc::new(&SEGREGATED);
+
+// Each thread fills its blocks with its own byte; a shared heap would let another thread's
+// allocation overwrite them
+// 每个线程用自己的字节填充其块；如果堆被共享，其他线程的分配会覆盖它们
+fn churn(allocator: &'static dyn GlobalAlloc, tag: u8) {
+    let mut live = Vec::new();
+    for round in 0..200 {
+        let layout = Layout::from_size_align(16 + (round * 7) % 300, 8).unwrap();
+        let ptr = unsafe { allocator.alloc(layout) };
+        assert!(!ptr.is_null());
+        unsafe { ptr.write_bytes(tag, layout.size()) };
+        live.push((ptr, layout));
+
+        if round % 3 == 0 {
+            let (ptr, layout) = live.swap_remove(live.len() / 2);
+            unsafe { allocator.dealloc(ptr, layout) };
+        }
+        std::thread::yield_now();
+    }
+    for (ptr, layout) in live {
+        for i in 0..layout.size() {
+            assert_eq!(unsafe { *ptr.add(i) }, tag);
+        }
+        unsafe { allocator.dealloc(ptr, layout) };
+    }
+}
+
+#[test]
+fn test_threads_use_separate_heaps() {
+    let handles: Vec<_> = (0..8u8)
+        .map(|tag| {
+            std::thread::spawn(move || {
+                churn(&FREE_LIST_ALLOC, tag);
+                churn(&SEGREGATED_ALLOC, tag);
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+// A block allocated on a thread that has since exited is still readable, and freeing it
+// here hands it to this thread's heap
+// 在已退出线程上分配的块仍然可读，在此处释放它会将其交给本线程的堆
+#[test]
+fn test_free_after_allocating_thread_exits() {
+    let layout = Layout::from_size_align(200, 8).unwrap();
+    let ptr = std::thread::spawn(move || {
+        let ptr = unsafe { FREE_LIST_ALLOC.alloc(layout) };
+        assert!(!ptr.is_null());
+        unsafe { ptr.write_bytes(0x5A, layout.size()) };
+        ptr as usize
+    })
+    .join()
+    .unwrap() as *mut u8;
+
+    assert!((0..layout.size()).all(|i| unsafe { *ptr.add(i) } == 0x5A));
+    unsafe { FREE_LIST_ALLOC.dealloc(ptr, layout) };
+    assert_eq!(unsafe { FREE_LIST_ALLOC.alloc(layout) }, ptr);
+}
@@ -0,0 +1,211 @@
+#[cfg(feature = "defmt")]
+use defmt_capture as _;
+use lite_alloc::single_threaded::TlsfAllocator;
+use lite_alloc::{PAGE_SIZE, PageCount, heap_pages_grown, reset_heap};
+use std::alloc::{GlobalAlloc, Layout};
+
+struct SafeAllocator {
+    inner: TlsfAllocator,
+}
+
+impl SafeAllocator {
+    fn new() -> Self {
+        // State is per-instance and the mock heap is per-thread: no lock needed
+        reset_heap();
+        Self {
+            inner: TlsfAllocator::new(),
+        }
+    }
+
+    fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { self.inner.alloc(layout) }
+    }
+
+    fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.inner.dealloc(ptr, layout) }
+    }
+
+    #[cfg(feature = "realloc")]
+    fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { self.inner.realloc(ptr, layout, new_size) }
+    }
+}
+
+impl Drop for SafeAllocator {
+    fn drop(&mut self) {
+        unsafe { self.inner.reset() };
+        reset_heap();
+    }
+}
+
+#[test]
+fn test_reuse_freed_block() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let a = allocator.alloc(layout);
+    assert!(!a.is_null());
+    assert_eq!(a as usize % 16, 0);
+    allocator.dealloc(a, layout);
+    // The freed block merged back into the region and is handed out again
+    assert_eq!(allocator.alloc(layout), a);
+}
+
+#[test]
+fn test_coalesce_with_both_neighbours() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let a = allocator.alloc(layout);
+    let b = allocator.alloc(layout);
+    let c = allocator.alloc(layout);
+    // Keeps c's successor used, so the merged hole is bounded
+    let guard = allocator.alloc(layout);
+    assert!(a < b && b < c && c < guard);
+
+    allocator.dealloc(a, layout);
+    allocator.dealloc(c, layout);
+    // Freeing b merges it with the free a before it and the free c after it
+    allocator.dealloc(b, layout);
+
+    let big = Layout::from_size_align(3 * 64, 16).unwrap();
+    assert_eq!(allocator.alloc(big), a);
+    assert_eq!(heap_pages_grown(), PageCount(1));
+}
+
+#[test]
+fn test_coalescing_fragmentation_random_free() {
+    let allocator = SafeAllocator::new();
+    let count = 10;
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let ptrs: Vec<_> = (0..count).map(|_| allocator.alloc(layout)).collect();
+    assert!(ptrs.iter().all(|p| !p.is_null()));
+
+    // Evens first to create holes, then odds to fill them and coalesce
+    for i in (0..count).step_by(2) {
+        allocator.dealloc(ptrs[i], layout);
+    }
+    for i in (1..count).step_by(2) {
+        allocator.dealloc(ptrs[i], layout);
+    }
+
+    // Everything merged back into one block spanning most of the page
+    let whole = Layout::from_size_align(PAGE_SIZE / 2, 16).unwrap();
+    assert_eq!(allocator.alloc(whole), ptrs[0]);
+    assert_eq!(heap_pages_grown(), PageCount(1));
+}
+
+#[test]
+fn test_large_alloc_extends_region() {
+    let allocator = SafeAllocator::new();
+    let small = Layout::from_size_align(64, 16).unwrap();
+    let a = allocator.alloc(small);
+
+    // Larger than what is left of the first page: the region grows in place
+    let large = Layout::from_size_align(100 * 1024, 16).unwrap();
+    let b = allocator.alloc(large);
+    assert!(!b.is_null());
+    assert!(b > a);
+    unsafe { b.write_bytes(0xAB, large.size()) };
+    let grown = heap_pages_grown();
+
+    allocator.dealloc(b, large);
+    allocator.dealloc(a, small);
+    // The first page and the new ones coalesced into a single block
+    let all = Layout::from_size_align(PAGE_SIZE + PAGE_SIZE / 2, 16).unwrap();
+    assert_eq!(allocator.alloc(all), a);
+    assert_eq!(heap_pages_grown(), grown);
+}
+
+#[test]
+fn test_high_alignment() {
+    let allocator = SafeAllocator::new();
+    let small = Layout::from_size_align(16, 16).unwrap();
+    let a = allocator.alloc(small);
+
+    for align in [32, 64, 256, 4096] {
+        let layout = Layout::from_size_align(100, align).unwrap();
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % align, 0, "align {align}");
+        unsafe { ptr.write_bytes(0xCD, layout.size()) };
+        allocator.dealloc(ptr, layout);
+    }
+
+    // The alignment gaps were given back: everything merges again
+    allocator.dealloc(a, small);
+    let whole = Layout::from_size_align(PAGE_SIZE / 2, 16).unwrap();
+    assert_eq!(allocator.alloc(whole), a);
+}
+
+#[cfg(feature = "realloc")]
+#[test]
+fn test_realloc_in_place() {
+    let allocator = SafeAllocator::new();
+    let layout = Layout::from_size_align(64, 16).unwrap();
+    let a = allocator.alloc(layout);
+    unsafe { a.write_bytes(0x5A, 64) };
+
+    // Growing into the free space after it keeps the address
+    let grown = allocator.realloc(a, layout, 1024);
+    assert_eq!(grown, a);
+    let layout = Layout::from_size_align(1024, 16).unwrap();
+
+    // A block placed right after it forces the next growth to move
+    let b = allocator.alloc(Layout::from_size_align(64, 16).unwrap());
+    assert_eq!(b as usize, a as usize + 1024 + 16);
+
+    // Shrinking stays in place and the tail can be reused
+    let shrunk = allocator.realloc(grown, layout, 128);
+    assert_eq!(shrunk, a);
+    let layout = Layout::from_size_align(128, 16).unwrap();
+    let c = allocator.alloc(Layout::from_size_align(256, 16).unwrap());
+    assert_eq!(c as usize, a as usize + 128 + 16);
+
+    let moved = allocator.realloc(shrunk, layout, 4096);
+    assert!(!moved.is_null());
+    assert_ne!(moved, a);
+    let data = unsafe { std::slice::from_raw_parts(moved, 64) };
+    assert!(data.iter().all(|&x| x == 0x5A));
+}
+
+#[test]
+fn test_alloc_with_capacity() {
+    let allocator = SafeAllocator::new();
+    let mut blocks = Vec::new();
+    for size in [1, 24, 100, 200, 3000] {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let (ptr, capacity) = unsafe { allocator.inner.alloc_with_capacity(layout) };
+        assert!(!ptr.is_null());
+        assert!(capacity >= size);
+        // The whole capacity belongs to the block: filling it leaves the others intact
+        unsafe { ptr.write_bytes(blocks.len() as u8 + 1, capacity) };
+        blocks.push((ptr, capacity, layout));
+    }
+    for (i, &(ptr, capacity, layout)) in blocks.iter().enumerate() {
+        assert!((0..capacity).all(|j| unsafe { *ptr.add(j) } == i as u8 + 1));
+        allocator.dealloc(ptr, layout);
+    }
+}
+
+// After a 1MB reservation, allocations totalling less than that never grow the heap
+#[test]
+fn test_reserve_prevents_growth() {
+    let allocator = SafeAllocator::new();
+    assert!(unsafe { allocator.inner.reserve(1024 * 1024) });
+    let pages = heap_pages_grown();
+
+    let mut total = 0;
+    for size in [24, 100, 512, 4096, 30000].iter().cycle() {
+        if total + size > 512 * 1024 {
+            break;
+        }
+        let ptr = allocator.alloc(Layout::from_size_align(*size, 8).unwrap());
+        assert!(!ptr.is_null());
+        total += size;
+    }
+    assert_eq!(
+        heap_pages_grown(),
+        pages,
+        "reserved memory must cover the allocations"
+    );
+    assert!(!unsafe { allocator.inner.reserve(usize::MAX / 2) });
+}